@@ -0,0 +1,182 @@
+// src/analysis.rs
+//
+// Analysis: Token-virran frekvenssijakauma ja Zipf-sovitus.
+//
+// Luonnollisen kielen sanasto noudattaa Zipfin lakia: frekvenssi ~ 1 / rank^s,
+// missä s on lähellä yhtä. Jos opittu sanasto poikkeaa tästä selvästi
+// (esim. yksi token hallitsee tai kaikki ovat yhtä harvinaisia), pankki on
+// todennäköisesti rappeutunut.
+
+use crate::engine::Engine;
+use crate::term::{say, warning};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+/// Zipf-eksponentin "terve" vaihteluväli
+const HEALTHY_EXPONENT: (f64, f64) = (0.6, 1.6);
+
+/// Yhden tokenin rivi frekvenssitaulukossa
+#[derive(Debug, Clone, Serialize)]
+pub struct TokenFrequency {
+    pub rank: usize,
+    pub token: u32,
+    pub count: usize,
+    /// Osuus koko virrasta (0.0 - 1.0)
+    pub frequency: f64,
+    /// Tokenin pituus alkuperäisinä tavuina
+    pub length: usize,
+}
+
+/// Zipf-sovituksen yhteenveto
+#[derive(Debug, Clone, Serialize)]
+pub struct ZipfSummary {
+    pub total_tokens: usize,
+    pub distinct_tokens: usize,
+    /// Sovitettu eksponentti s (frekvenssi ~ rank^-s)
+    pub exponent: f64,
+    /// Log-log-regression selitysaste
+    pub r_squared: f64,
+}
+
+impl ZipfSummary {
+    /// Onko jakauma odotetun kaltainen
+    pub fn is_healthy(&self) -> bool {
+        self.distinct_tokens < 2
+            || (self.exponent >= HEALTHY_EXPONENT.0 && self.exponent <= HEALTHY_EXPONENT.1)
+    }
+}
+
+/// Laske virran tokenien frekvenssit laskevassa järjestyksessä
+pub fn token_histogram(engine: &dyn Engine) -> Vec<TokenFrequency> {
+    let stream = engine.token_stream();
+    let mut counts: HashMap<u32, usize> = HashMap::new();
+    for &token in stream {
+        *counts.entry(token).or_default() += 1;
+    }
+
+    let mut entries: Vec<(u32, usize)> = counts.into_iter().collect();
+    entries.sort_by_key(|&(token, count)| (std::cmp::Reverse(count), token));
+
+    let total = stream.len().max(1) as f64;
+    entries
+        .into_iter()
+        .enumerate()
+        .map(|(i, (token, count))| TokenFrequency {
+            rank: i + 1,
+            token,
+            count,
+            frequency: count as f64 / total,
+            length: engine.decode_token(token).len(),
+        })
+        .collect()
+}
+
+/// Sovita Zipfin laki pienimmän neliösumman menetelmällä log-log-asteikolla
+pub fn zipf_fit(histogram: &[TokenFrequency]) -> ZipfSummary {
+    let total_tokens = histogram.iter().map(|t| t.count).sum();
+    let points: Vec<(f64, f64)> = histogram
+        .iter()
+        .map(|t| ((t.rank as f64).ln(), (t.count as f64).ln()))
+        .collect();
+
+    let n = points.len() as f64;
+    let (mut exponent, mut r_squared) = (0.0, 0.0);
+    if points.len() >= 2 {
+        let mean_x = points.iter().map(|p| p.0).sum::<f64>() / n;
+        let mean_y = points.iter().map(|p| p.1).sum::<f64>() / n;
+        let sxx: f64 = points.iter().map(|p| (p.0 - mean_x).powi(2)).sum();
+        let syy: f64 = points.iter().map(|p| (p.1 - mean_y).powi(2)).sum();
+        let sxy: f64 = points.iter().map(|p| (p.0 - mean_x) * (p.1 - mean_y)).sum();
+
+        exponent = -sxy / sxx;
+        r_squared = if syy > 0.0 {
+            (sxy * sxy) / (sxx * syy)
+        } else {
+            1.0
+        };
+    }
+
+    ZipfSummary {
+        total_tokens,
+        distinct_tokens: histogram.len(),
+        exponent,
+        r_squared,
+    }
+}
+
+/// Kirjoita frekvenssitaulukko CSV:ksi ja yhteenveto + TOP-100 JSON:ksi
+/// (`<prefix>.csv` ja `<prefix>.json`)
+pub fn export(prefix: &str, histogram: &[TokenFrequency], summary: &ZipfSummary) -> io::Result<()> {
+    let mut csv = BufWriter::new(File::create(Path::new(&format!("{}.csv", prefix)))?);
+    writeln!(csv, "rank,token,count,frequency,length")?;
+    for t in histogram {
+        writeln!(
+            csv,
+            "{},{},{},{:.6},{}",
+            t.rank, t.token, t.count, t.frequency, t.length
+        )?;
+    }
+    csv.flush()?;
+
+    #[derive(Serialize)]
+    struct Report<'a> {
+        summary: &'a ZipfSummary,
+        top: &'a [TokenFrequency],
+    }
+    let report = Report {
+        summary,
+        top: &histogram[..histogram.len().min(100)],
+    };
+    let json = serde_json::to_string_pretty(&report).map_err(io::Error::other)?;
+    std::fs::write(format!("{}.json", prefix), json)
+}
+
+/// Tulosta lyhyt Zipf-yhteenveto raporttiin
+pub fn print_summary(summary: &ZipfSummary) {
+    say!(
+        "  📈 Zipf: {} eri tokenia / {} yhteensä, eksponentti {:.2} (R² {:.2})",
+        "  📈 Zipf: {} distinct tokens / {} total, exponent {:.2} (R² {:.2})",
+        summary.distinct_tokens,
+        summary.total_tokens,
+        summary.exponent,
+        summary.r_squared
+    );
+    if !summary.is_healthy() {
+        warning!(
+            "     ⚠️  Jakauma poikkeaa Zipfin laista: pankki voi olla rappeutunut.",
+            "     ⚠️  Distribution deviates from Zipf's law: the bank may be degenerate."
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(rank: usize, count: usize) -> TokenFrequency {
+        TokenFrequency {
+            rank,
+            token: rank as u32,
+            count,
+            frequency: 0.0,
+            length: 1,
+        }
+    }
+
+    #[test]
+    fn test_zipf_fit_recovers_exponent() {
+        // Täydellinen Zipf: count = 1000 / rank
+        let histogram: Vec<_> = (1..=50).map(|r| entry(r, 1000 / r)).collect();
+        let summary = zipf_fit(&histogram);
+        assert!((summary.exponent - 1.0).abs() < 0.05);
+        assert!(summary.r_squared > 0.99);
+        assert!(summary.is_healthy());
+
+        // Tasajakauma ei ole Zipf
+        let flat: Vec<_> = (1..=50).map(|r| entry(r, 10)).collect();
+        assert!(!zipf_fit(&flat).is_healthy());
+    }
+}
@@ -0,0 +1,451 @@
+// src/archive.rs
+//
+// Archive (Arkisto): Koko hakemiston pakkaus yhteen tiedostoon.
+//
+// Jokainen tiedosto koodataan samalla (jäädytetyllä) PatternBankilla
+// token-virraksi ja edelleen entropiakooderilla. Aivoja ei tallenneta
+// arkistoon: purkaminen vaatii samat aivot, mikä tarkistetaan
+// rakenteen sormenjäljellä.
+//
+// Yksittäinen tiedosto (encode-file) on yhden tiedoston arkisto, joka
+// varmistetaan heti kirjoittamisen jälkeen purkamalla. Oppimisajon oma
+// token-virta (`--save-stream`) tallennetaan samoin: virtaa ei koodata
+// uudelleen, vaan se kirjoitetaan sellaisenaan, jolloin `decode` todistaa,
+// että opittu esitys palautuu häviöttä alkuperäisiksi tavuiksi. Jos ajo
+// käytti esikäsittelyä (delta, BWT), muunnos tallennetaan hakemistoon ja
+// purku kääntää sen; lohkojen pituudet ja stridet ovat lohko-otsikoissa.
+//
+// Tiedostomuoto:
+//   [MAGIC: 8 tavua]
+//   [hakemiston pituus: varint][hakemisto: JSON]
+//   [tiedostojen koodatut virrat peräkkäin]
+
+use crate::bitio::{read_varint, write_varint};
+use crate::builder::{BOUNDARY_TOKEN, PatternBank};
+use crate::checksum::crc32;
+use crate::entropy::Coder;
+use crate::i18n::tr;
+use crate::transform::Transform;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Arkistotiedoston tunniste ja versio
+const MAGIC: &[u8; 8] = b"PETRIAR1";
+
+/// Purun esivarauksen yläraja: hakemiston pituuteen ei luoteta sokeasti
+const MAX_PREALLOC: u64 = 1 << 24;
+
+/// Yhden tiedoston tiedot arkiston hakemistossa
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchiveEntry {
+    /// Polku suhteessa arkistoituun hakemistoon ('/'-erottimin)
+    pub path: String,
+    /// Koodatun datan alku payload-osassa
+    pub offset: u64,
+    /// Koodatun datan pituus
+    pub length: u64,
+    /// Alkuperäinen koko tavuina
+    pub original_len: u64,
+    /// Alkuperäisen datan CRC-32
+    pub checksum: u32,
+}
+
+/// Arkiston hakemisto (tallennetaan JSON-muodossa)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchiveIndex {
+    /// Entropiakooderin nimi
+    pub coder: String,
+    /// Koodaukseen käytettyjen aivojen sormenjälki
+    pub brain_fingerprint: u64,
+    /// Virran esikäsittely, joka puretaan ennen tarkistusta
+    /// (vanhoissa arkistoissa kenttää ei ole)
+    #[serde(default)]
+    pub transform: Transform,
+    pub entries: Vec<ArchiveEntry>,
+}
+
+/// Yhteenveto luodusta arkistosta
+pub struct ArchiveSummary {
+    pub files: usize,
+    pub original_bytes: u64,
+    pub archive_bytes: u64,
+}
+
+/// Pakkaa hakemiston kaikki tiedostot yhteen arkistoon
+pub fn create(
+    dir: &Path,
+    out_path: &Path,
+    bank: &PatternBank,
+    coder: Coder,
+) -> io::Result<ArchiveSummary> {
+    let mut files = Vec::new();
+    collect_files(dir, &mut files)?;
+    files.sort();
+
+    let named: Vec<(PathBuf, String)> = files
+        .into_iter()
+        .map(|file| {
+            let relative = file.strip_prefix(dir).unwrap_or(&file);
+            let path = relative
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            (file, path)
+        })
+        .collect();
+    write_archive(&named, out_path, bank, coder)
+}
+
+/// Pakkaa yksi tiedosto arkistoksi ja varmista se heti purkamalla.
+/// Palauttaa yhteenvedon vasta, kun purettu data vastaa alkuperäistä.
+pub fn encode_file(
+    input: &Path,
+    out_path: &Path,
+    bank: &PatternBank,
+    coder: Coder,
+) -> io::Result<ArchiveSummary> {
+    let name = input
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .ok_or_else(|| invalid(&tr!("ei tiedostonimeä", "no file name")))?;
+    let summary = write_archive(&[(input.to_path_buf(), name)], out_path, bank, coder)?;
+
+    let archive = Archive::open(out_path)?;
+    let decoded = archive.read_entry(&archive.index.entries[0], bank)?;
+    if decoded != fs::read(input)? {
+        return Err(invalid(&tr!(
+            "tarkistus epäonnistui: purettu data eroaa alkuperäisestä",
+            "verification failed: decoded data differs from the original"
+        )));
+    }
+    Ok(summary)
+}
+
+/// Tallenna valmis token-virta yhden tiedoston arkistoksi nimellä `name`
+/// ja varmista se purkamalla. Tietuerajat eivät tuota tavuja, joten ne
+/// jätetään pois. `transform` on virran syötössä käytetty esikäsittely:
+/// tiedoston pituus ja CRC lasketaan käännetystä, alkuperäisestä datasta.
+pub fn save_stream(
+    tokens: &[u32],
+    name: &str,
+    out_path: &Path,
+    bank: &PatternBank,
+    coder: Coder,
+    transform: Transform,
+) -> io::Result<ArchiveSummary> {
+    let tokens: Vec<u32> = tokens
+        .iter()
+        .copied()
+        .filter(|&t| t != BOUNDARY_TOKEN)
+        .collect();
+    let mut data = Vec::new();
+    for &id in &tokens {
+        data.extend(bank.decode(id));
+    }
+    let data = transform.invert(&data)?;
+    let coded = coder.encode(&tokens);
+    let entry = ArchiveEntry {
+        path: name.to_string(),
+        offset: 0,
+        length: coded.len() as u64,
+        original_len: data.len() as u64,
+        checksum: crc32(&data),
+    };
+    let summary = write_index(vec![entry], coded, out_path, bank, coder, transform)?;
+
+    let archive = Archive::open(out_path)?;
+    archive.read_entry(&archive.index.entries[0], bank)?;
+    Ok(summary)
+}
+
+/// Kirjoita arkisto: (tiedosto, polku arkistossa) -parit
+fn write_archive(
+    files: &[(PathBuf, String)],
+    out_path: &Path,
+    bank: &PatternBank,
+    coder: Coder,
+) -> io::Result<ArchiveSummary> {
+    let mut entries = Vec::with_capacity(files.len());
+    let mut payload = Vec::new();
+
+    for (file, path) in files {
+        let data = fs::read(file)?;
+        let coded = coder.encode(&bank.encode(&data));
+
+        entries.push(ArchiveEntry {
+            path: path.clone(),
+            offset: payload.len() as u64,
+            length: coded.len() as u64,
+            original_len: data.len() as u64,
+            checksum: crc32(&data),
+        });
+        payload.extend(coded);
+    }
+    write_index(entries, payload, out_path, bank, coder, Transform::None)
+}
+
+/// Kirjoita otsake, hakemisto ja koodatut virrat tiedostoon
+fn write_index(
+    entries: Vec<ArchiveEntry>,
+    payload: Vec<u8>,
+    out_path: &Path,
+    bank: &PatternBank,
+    coder: Coder,
+    transform: Transform,
+) -> io::Result<ArchiveSummary> {
+    let files = entries.len();
+    let original_bytes = entries.iter().map(|e| e.original_len).sum();
+    let index = ArchiveIndex {
+        coder: coder.name().to_string(),
+        brain_fingerprint: bank.fingerprint(),
+        transform,
+        entries,
+    };
+    let index_json = serde_json::to_vec(&index).map_err(io::Error::other)?;
+
+    let mut out = Vec::with_capacity(MAGIC.len() + index_json.len() + payload.len() + 10);
+    out.extend_from_slice(MAGIC);
+    write_varint(&mut out, index_json.len() as u64);
+    out.extend(index_json);
+    out.extend(payload);
+    fs::write(out_path, &out)?;
+
+    Ok(ArchiveSummary {
+        files,
+        original_bytes,
+        archive_bytes: out.len() as u64,
+    })
+}
+
+/// Avattu arkisto: hakemisto + koodattu data
+pub struct Archive {
+    pub index: ArchiveIndex,
+    payload: Vec<u8>,
+}
+
+impl Archive {
+    /// Lue arkisto tiedostosta
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let data = fs::read(path)?;
+        if data.len() < MAGIC.len() || &data[..MAGIC.len()] != MAGIC {
+            return Err(invalid(&tr!("ei petri-arkisto", "not a petri archive")));
+        }
+        let mut pos = MAGIC.len();
+        let index_len = read_varint(&data, &mut pos)? as usize;
+        let index_end = pos
+            .checked_add(index_len)
+            .filter(|&end| end <= data.len())
+            .ok_or_else(|| invalid(&tr!("katkennut hakemisto", "truncated index")))?;
+        let index: ArchiveIndex =
+            serde_json::from_slice(&data[pos..index_end]).map_err(io::Error::other)?;
+
+        Ok(Archive {
+            index,
+            payload: data[index_end..].to_vec(),
+        })
+    }
+
+    /// Pura yksi tiedosto muistiin, käännä esikäsittely ja tarkista CRC
+    pub fn read_entry(&self, entry: &ArchiveEntry, bank: &PatternBank) -> io::Result<Vec<u8>> {
+        if bank.fingerprint() != self.index.brain_fingerprint {
+            return Err(invalid(&tr!(
+                "aivot eivät vastaa arkiston luonnissa käytettyjä aivoja",
+                "brain does not match the one used to create the archive"
+            )));
+        }
+        let coder = Coder::parse(&self.index.coder)
+            .ok_or_else(|| invalid(&tr!("tuntematon kooderi", "unknown coder")))?;
+
+        let start = entry.offset as usize;
+        let end = start
+            .checked_add(entry.length as usize)
+            .filter(|&end| end <= self.payload.len())
+            .ok_or_else(|| invalid(&tr!("katkennut arkisto", "truncated archive")))?;
+
+        let tokens = coder.decode(&self.payload[start..end])?;
+        let mut data = Vec::with_capacity(entry.original_len.min(MAX_PREALLOC) as usize);
+        for id in tokens {
+            data.extend(bank.decode(id));
+        }
+        let data = self.index.transform.invert(&data)?;
+
+        if data.len() as u64 != entry.original_len || crc32(&data) != entry.checksum {
+            return Err(invalid(&tr!(
+                "tarkistussumma ei täsmää: {}",
+                "checksum mismatch: {}",
+                entry.path
+            )));
+        }
+        Ok(data)
+    }
+
+    /// Pura tiedostot hakemistoon. Jos `only` ei ole tyhjä, puretaan vain
+    /// nimetyt polut. Palauttaa purettujen tiedostojen määrän.
+    pub fn extract(&self, dest: &Path, bank: &PatternBank, only: &[String]) -> io::Result<usize> {
+        let mut extracted = 0;
+        for entry in &self.index.entries {
+            if !only.is_empty() && !only.iter().any(|p| p == &entry.path) {
+                continue;
+            }
+            // Estä polut arkiston ulkopuolelle
+            if entry
+                .path
+                .split('/')
+                .any(|part| part == ".." || part.is_empty())
+            {
+                return Err(invalid(&tr!(
+                    "virheellinen polku: {}",
+                    "invalid path: {}",
+                    entry.path
+                )));
+            }
+
+            let data = self.read_entry(entry, bank)?;
+            let target = dest.join(&entry.path);
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(&target, data)?;
+            extracted += 1;
+        }
+        Ok(extracted)
+    }
+}
+
+/// Rekursiivinen tiedostojen keräys
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_files(&path, files)?;
+        } else if path.is_file() {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::Builder;
+
+    #[test]
+    fn test_archive_round_trip() {
+        let root = std::env::temp_dir().join(format!("petri_archive_{}", std::process::id()));
+        let src = root.join("src");
+        fs::create_dir_all(src.join("sub")).unwrap();
+        fs::write(src.join("a.txt"), b"funktio on joka funktio on joka").unwrap();
+        fs::write(src.join("sub/b.txt"), b"tama on esimerkki").unwrap();
+
+        // Opeta pieni pankki, jotta koodaus käyttää Combine-malleja
+        let mut builder = Builder::new(100);
+        builder.tokenize(b"funktio on joka funktio on joka funktio on joka");
+        for _ in 0..5 {
+            builder.live();
+        }
+
+        let out = root.join("out.petri");
+        let summary = create(&src, &out, &builder.bank, Coder::Huffman).unwrap();
+        assert_eq!(summary.files, 2);
+
+        let archive = Archive::open(&out).unwrap();
+        let dest = root.join("dest");
+        assert_eq!(archive.extract(&dest, &builder.bank, &[]).unwrap(), 2);
+        assert_eq!(
+            fs::read(dest.join("sub/b.txt")).unwrap(),
+            b"tama on esimerkki"
+        );
+
+        // Eri aivoilla purku epäonnistuu
+        assert!(archive.extract(&dest, &PatternBank::new(10), &[]).is_err());
+
+        // Väärennetty pituus hakemistossa: virhe eikä valtavaa varausta
+        let mut forged = archive.index.entries[0].clone();
+        forged.original_len = u64::MAX;
+        let err = archive.read_entry(&forged, &builder.bank).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        // Yksi tiedosto: yhden tiedoston arkisto, varmistettu purkamalla
+        let single = root.join("a.petri");
+        let summary =
+            encode_file(&src.join("a.txt"), &single, &builder.bank, Coder::Huffman).unwrap();
+        assert_eq!((summary.files, summary.original_bytes), (1, 31));
+        let archive = Archive::open(&single).unwrap();
+        assert_eq!(archive.index.entries[0].path, "a.txt");
+
+        // Oppimisajon oma virta sellaisenaan: purku palauttaa syötteen
+        let stream = root.join("stream.petri");
+        builder.push_boundary();
+        builder.tokenize(b" tama on");
+        let summary = save_stream(
+            &builder.token_stream,
+            "stream",
+            &stream,
+            &builder.bank,
+            Coder::Huffman,
+            Transform::None,
+        )
+        .unwrap();
+        assert_eq!(summary.original_bytes, 55);
+        let archive = Archive::open(&stream).unwrap();
+        assert_eq!(
+            archive
+                .read_entry(&archive.index.entries[0], &builder.bank)
+                .unwrap(),
+            builder.decode_stream()
+        );
+
+        fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn test_transformed_stream_decodes_to_original() {
+        use crate::feeder::Feeder;
+
+        let root = std::env::temp_dir().join(format!("petri_transform_{}", std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+        let text = b"funktio on joka funktio on joka\n\nfunktio on joka funktio on".repeat(3);
+        fs::write(root.join("a.txt"), &text).unwrap();
+
+        for transform in [Transform::Delta { stride: 0 }, Transform::BwtMtf] {
+            // Syöttö, oppiminen ja tallennus kuten oppimisajossa
+            let mut feeder =
+                Feeder::with_extensions(16, root.to_str().unwrap(), &["txt".into()]).unwrap();
+            feeder.set_transform(transform);
+            let mut builder = Builder::new(100);
+            while !feeder.is_depleted() {
+                feeder.feed_to_engine(&mut builder).unwrap();
+                builder.live();
+            }
+            assert_ne!(builder.decode_stream(), text);
+
+            let out = root.join("stream.petri");
+            let summary = save_stream(
+                &builder.token_stream,
+                "stream",
+                &out,
+                &builder.bank,
+                Coder::Huffman,
+                transform,
+            )
+            .unwrap();
+            assert_eq!(summary.original_bytes, text.len() as u64);
+
+            let archive = Archive::open(&out).unwrap();
+            assert_eq!(archive.index.transform, transform);
+            let dest = root.join("dest");
+            archive.extract(&dest, &builder.bank, &[]).unwrap();
+            assert_eq!(fs::read(dest.join("stream")).unwrap(), text);
+        }
+
+        fs::remove_dir_all(&root).ok();
+    }
+}
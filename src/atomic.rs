@@ -0,0 +1,54 @@
+// src/atomic.rs
+//
+// Atomic: Tiedostojen atominen kirjoitus.
+//
+// Aivot, kirjanmerkki ja istunto kirjoitetaan ensin väliaikaistiedostoon
+// (`<nimi>.tmp`), joka synkronoidaan levylle ja nimetään vasta sitten
+// lopulliseksi. Rename korvaa vanhan tiedoston yhdellä operaatiolla:
+// kaatuminen tai keskeytys kesken kirjoituksen jättää vanhan tiedoston
+// ehjäksi.
+
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// Kirjoita tiedosto atomisesti: väliaikaistiedosto, fsync ja rename
+pub fn write_atomic(
+    path: &Path,
+    write: impl FnOnce(&mut BufWriter<File>) -> io::Result<()>,
+) -> io::Result<()> {
+    let mut tmp_name = path.as_os_str().to_owned();
+    tmp_name.push(".tmp");
+    let tmp_path = PathBuf::from(tmp_name);
+    {
+        let mut writer = BufWriter::new(File::create(&tmp_path)?);
+        write(&mut writer)?;
+        writer.flush()?;
+        writer.get_ref().sync_all()?;
+    }
+    fs::rename(&tmp_path, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_failed_write_keeps_old_file() {
+        let path = std::env::temp_dir().join(format!("petri_atomic_{}.json", std::process::id()));
+        fs::write(&path, b"vanha").unwrap();
+        let failed = write_atomic(&path, |w| {
+            w.write_all(b"puoli")?;
+            Err(io::Error::other("keskeytys"))
+        });
+        assert!(failed.is_err());
+        assert_eq!(fs::read(&path).unwrap(), b"vanha");
+
+        write_atomic(&path, |w| w.write_all(b"uusi")).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"uusi");
+        fs::remove_file(&path).ok();
+        let mut tmp = path.into_os_string();
+        tmp.push(".tmp");
+        fs::remove_file(tmp).ok();
+    }
+}
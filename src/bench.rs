@@ -0,0 +1,300 @@
+// src/bench.rs
+//
+// Bench: Toistettava suorituskykymittaus vakiokorpuksella.
+//
+// Oppijan muutoksia on vaikea arvioida tuntumalta: nopeutuiko ajo, paraniko
+// tiivistys vai kasvoiko muistin käyttö? Bench kouluttaa tyhjät aivot
+// annetulla korpuksella (esim. enwik8:n palalla) kiinteillä asetuksilla ja
+// mittaa:
+//   - tiivistyssuhteen: entropiakoodatun tokenivirran koko / alkuperäinen,
+//   - nopeuden: syötetyt tokenit sekunnissa (vain oppiminen),
+//   - muistin huipun: prosessin VmHWM (Linux; muualla ei mitattu).
+//
+// Tulos lisätään historiatiedostoon (JSONL) ja sitä verrataan edelliseen
+// vertailukelpoiseen ajoon: sama data (CRC-32), syklit ja asetukset. Oppija
+// on deterministinen, joten ero johtuu koodista eikä sattumasta. Siemen
+// (`--seed`) valitsee korpuksesta palan alkukohdan; ilman siementä pala
+// alkaa korpuksen alusta.
+
+use crate::checksum::crc32;
+use crate::entropy::Coder;
+use crate::i18n::tr;
+use crate::lexer::Tokenization;
+use crate::sweep::{self, Candidate};
+use crate::term::{self, Level, say};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+/// Korpuspalan oletuskoko tavuina
+pub const DEFAULT_BYTES: usize = 1024 * 1024;
+
+/// Oletushistoria
+pub const DEFAULT_HISTORY: &str = "bench.jsonl";
+
+/// Yhden mittausajon tulos
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BenchResult {
+    pub corpus: String,
+    pub seed: Option<u64>,
+    pub offset: u64,
+    pub bytes: usize,
+    /// Palan tarkiste: vertailu vain samalla datalla
+    pub crc: u32,
+    pub settings: String,
+    pub coder: String,
+    pub cycles: usize,
+    pub tokens: usize,
+    pub patterns: usize,
+    pub ratio: f64,
+    pub tokens_per_sec: f64,
+    pub seconds: f64,
+    pub peak_memory_kib: Option<u64>,
+}
+
+impl BenchResult {
+    /// Sama data, syklit ja asetukset
+    pub fn comparable(&self, other: &BenchResult) -> bool {
+        self.crc == other.crc
+            && self.bytes == other.bytes
+            && self.cycles == other.cycles
+            && self.settings == other.settings
+            && self.coder == other.coder
+    }
+}
+
+/// Korpuksen pala tiedostoittain
+pub struct Chunk {
+    pub parts: Vec<Vec<u8>>,
+    pub offset: u64,
+}
+
+impl Chunk {
+    /// Lue `bytes` tavua tiedostojen yhteisestä jonosta. Siemen arpoo
+    /// alkukohdan, muuten pala alkaa alusta.
+    pub fn load(files: &[PathBuf], bytes: usize, seed: Option<u64>) -> io::Result<Self> {
+        let sizes: Vec<u64> = files
+            .iter()
+            .map(|path| fs::metadata(path).map(|m| m.len()))
+            .collect::<io::Result<_>>()?;
+        let total: u64 = sizes.iter().sum();
+        let slack = total.saturating_sub(bytes as u64);
+        let offset = match seed {
+            Some(seed) if slack > 0 => StdRng::seed_from_u64(seed).gen_range(0..=slack),
+            _ => 0,
+        };
+
+        let (mut start, end) = (offset, offset + bytes as u64);
+        let mut position = 0;
+        let mut parts = Vec::new();
+        for (path, size) in files.iter().zip(sizes) {
+            let (from, to) = (position, position + size);
+            position = to;
+            if to <= start || from >= end {
+                continue;
+            }
+            let data = fs::read(path)?;
+            let lo = (start - from) as usize;
+            let hi = ((end.min(to) - from) as usize).min(data.len());
+            if lo < hi {
+                parts.push(data[lo..hi].to_vec());
+            }
+            start = to;
+        }
+        Ok(Chunk { parts, offset })
+    }
+
+    pub fn len(&self) -> usize {
+        self.parts.iter().map(Vec::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Prosessin muistin huippu kibitavuina (Linux: /proc/self/status VmHWM)
+pub fn peak_memory_kib() -> Option<u64> {
+    let status = fs::read_to_string("/proc/self/status").ok()?;
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmHWM:"))
+        .and_then(|rest| rest.trim().trim_end_matches("kB").trim().parse().ok())
+}
+
+/// Kouluta tyhjät aivot palalla ja mittaa
+pub fn run(
+    corpus: &str,
+    chunk: &Chunk,
+    seed: Option<u64>,
+    candidate: &Candidate,
+    cycles: usize,
+    tokenization: Option<Tokenization>,
+    coder: Coder,
+) -> BenchResult {
+    let mut builder = candidate.builder(tokenization);
+    let started = Instant::now();
+    let tokens = sweep::train(&mut builder, &chunk.parts, cycles);
+    let seconds = started.elapsed().as_secs_f64();
+
+    let data = chunk.parts.concat();
+    let coded = coder.encode(&builder.bank.encode(&data)).len();
+    BenchResult {
+        corpus: corpus.to_string(),
+        seed,
+        offset: chunk.offset,
+        bytes: data.len(),
+        crc: crc32(&data),
+        settings: candidate.label(),
+        coder: coder.name().to_string(),
+        cycles,
+        tokens,
+        patterns: builder.bank.combine_count(),
+        ratio: coded as f64 / data.len().max(1) as f64,
+        tokens_per_sec: tokens as f64 / seconds.max(1e-9),
+        seconds,
+        peak_memory_kib: peak_memory_kib(),
+    }
+}
+
+/// Lue historia; rikkinäiset rivit ohitetaan
+pub fn load_history(path: &Path) -> io::Result<Vec<BenchResult>> {
+    let file = match fs::File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    let mut results = Vec::new();
+    for line in BufReader::new(file).lines() {
+        if let Ok(result) = serde_json::from_str(&line?) {
+            results.push(result);
+        }
+    }
+    Ok(results)
+}
+
+/// Lisää tulos historian loppuun
+pub fn append_history(path: &Path, result: &BenchResult) -> io::Result<()> {
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", serde_json::to_string(result)?)
+}
+
+/// Viimeisin vertailukelpoinen aiempi ajo
+pub fn previous<'a>(history: &'a [BenchResult], result: &BenchResult) -> Option<&'a BenchResult> {
+    history.iter().rev().find(|old| old.comparable(result))
+}
+
+/// Muutos prosentteina
+fn change(new: f64, old: f64) -> String {
+    if old == 0.0 {
+        return "-".to_string();
+    }
+    format!("{:+.1}%", (new / old - 1.0) * 100.0)
+}
+
+/// Tulosta tulos ja vertailu edelliseen
+pub fn print_report(result: &BenchResult, previous: Option<&BenchResult>) {
+    let memory = |r: &BenchResult| {
+        r.peak_memory_kib.map_or("-".to_string(), |kib| {
+            format!("{:.1} MiB", kib as f64 / 1024.0)
+        })
+    };
+    say!(
+        "\n  ⏱️  Tulos: {} tavua, {} tokenia, {} mallia, {:.1} s",
+        "\n  ⏱️  Result: {} bytes, {} tokens, {} patterns, {:.1} s",
+        result.bytes,
+        result.tokens,
+        result.patterns,
+        result.seconds
+    );
+    let Some(old) = previous else {
+        say!(
+            "     Tiivistyssuhde {:.4}, {:.0} tokenia/s, muistin huippu {}\n     Ei aiempaa vertailukelpoista ajoa.",
+            "     Compression ratio {:.4}, {:.0} tokens/s, peak memory {}\n     No comparable previous run.",
+            result.ratio,
+            result.tokens_per_sec,
+            memory(result)
+        );
+        return;
+    };
+    say!(
+        "                           edellinen          nyt    muutos",
+        "                            previous          now    change"
+    );
+    let rows = [
+        (
+            tr!("Tiivistyssuhde", "Compression ratio"),
+            format!("{:.4}", old.ratio),
+            format!("{:.4}", result.ratio),
+            change(result.ratio, old.ratio),
+        ),
+        (
+            tr!("Tokenia/s", "Tokens/s"),
+            format!("{:.0}", old.tokens_per_sec),
+            format!("{:.0}", result.tokens_per_sec),
+            change(result.tokens_per_sec, old.tokens_per_sec),
+        ),
+        (
+            tr!("Muistin huippu", "Peak memory"),
+            memory(old),
+            memory(result),
+            match (result.peak_memory_kib, old.peak_memory_kib) {
+                (Some(new), Some(old)) => change(new as f64, old as f64),
+                _ => "-".to_string(),
+            },
+        ),
+    ];
+    for (name, old, new, delta) in rows {
+        term::emit(
+            Level::Info,
+            &format!("     {:<18} {:>12} {:>12} {:>9}", name, old, new, delta),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chunk_run_and_history() {
+        let root = std::env::temp_dir().join(format!("petri_bench_{}", std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+        let files = [root.join("a.txt"), root.join("b.txt")];
+        fs::write(&files[0], b"kissa istuu, ".repeat(40)).unwrap();
+        fs::write(&files[1], b"koira juoksee, ".repeat(40)).unwrap();
+
+        // Pala ylittää tiedostorajan: osat pysyvät erillään
+        let chunk = Chunk::load(&files, 600, None).unwrap();
+        assert_eq!((chunk.offset, chunk.len()), (0, 600));
+        assert_eq!(chunk.parts[0].len(), 520);
+        let seeded = Chunk::load(&files, 600, Some(3)).unwrap();
+        assert_eq!(
+            seeded.offset,
+            Chunk::load(&files, 600, Some(3)).unwrap().offset
+        );
+        assert_eq!(seeded.len(), 600);
+
+        let candidate = Candidate::default();
+        let result = run("t", &chunk, None, &candidate, 5, None, Coder::Huffman);
+        assert!(result.tokens > 0 && result.patterns > 0);
+        assert!(result.ratio > 0.0 && result.ratio < 1.0);
+
+        let history_path = root.join("bench.jsonl");
+        assert!(load_history(&history_path).unwrap().is_empty());
+        append_history(&history_path, &result).unwrap();
+        let other = run("t", &seeded, Some(3), &candidate, 5, None, Coder::Huffman);
+        append_history(&history_path, &other).unwrap();
+        let history = load_history(&history_path).unwrap();
+        assert_eq!(history.len(), 2);
+        // Sama data ja asetukset: deterministinen tiivistys
+        let again = run("t", &chunk, None, &candidate, 5, None, Coder::Huffman);
+        let old = previous(&history, &again).unwrap();
+        assert_eq!((old.crc, old.ratio), (again.crc, again.ratio));
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
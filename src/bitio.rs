@@ -0,0 +1,154 @@
+// src/bitio.rs
+//
+// Bittitason apuvälineet entropiakoodereille ja tiedostomuodoille:
+// - BitWriter / BitReader: bitit MSB-ensin tavuihin pakattuna
+// - varint: LEB128-tyyliset vaihtuvamittaiset kokonaisluvut
+
+use crate::i18n::tr;
+use std::io;
+
+/// Kirjoittaa bittejä tavupuskuriin (eniten merkitsevä bitti ensin)
+#[derive(Default)]
+pub struct BitWriter {
+    bytes: Vec<u8>,
+    current: u8,
+    filled: u8,
+}
+
+impl BitWriter {
+    pub fn new() -> Self {
+        BitWriter::default()
+    }
+
+    /// Kirjoita `len` alinta bittiä arvosta `bits`
+    pub fn write_bits(&mut self, bits: u64, len: u8) {
+        for shift in (0..len).rev() {
+            let bit = ((bits >> shift) & 1) as u8;
+            self.current = (self.current << 1) | bit;
+            self.filled += 1;
+            if self.filled == 8 {
+                self.bytes.push(self.current);
+                self.current = 0;
+                self.filled = 0;
+            }
+        }
+    }
+
+    /// Viimeistele: täytä viimeinen tavu nollilla
+    pub fn finish(mut self) -> Vec<u8> {
+        if self.filled > 0 {
+            self.bytes.push(self.current << (8 - self.filled));
+        }
+        self.bytes
+    }
+}
+
+/// Lukee bittejä tavupuskurista (eniten merkitsevä bitti ensin)
+pub struct BitReader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> BitReader<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        BitReader { bytes, pos: 0 }
+    }
+
+    /// Lue yksi bitti, None jos data loppui
+    pub fn read_bit(&mut self) -> Option<u8> {
+        let byte = *self.bytes.get(self.pos / 8)?;
+        let bit = (byte >> (7 - (self.pos % 8))) & 1;
+        self.pos += 1;
+        Some(bit)
+    }
+}
+
+/// Kirjoita varint (7 bittiä per tavu, jatkobitti ylimpänä)
+pub fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push((value as u8 & 0x7F) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+/// Lue varint kohdasta `pos` ja siirrä `pos` sen yli
+pub fn read_varint(data: &[u8], pos: &mut usize) -> io::Result<u64> {
+    let mut value = 0u64;
+    let mut shift = 0;
+    loop {
+        let byte = *data.get(*pos).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                tr!("katkennut varint", "truncated varint"),
+            )
+        })?;
+        *pos += 1;
+        if shift >= 64 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                tr!("liian pitkä varint", "varint too long"),
+            ));
+        }
+        value |= ((byte & 0x7F) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+        shift += 7;
+    }
+}
+
+/// Base64-aakkosto (RFC 4648)
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Tavut Base64-merkkijonoksi (binääridata JSON-tiedostoihin)
+pub fn base64_encode(data: &[u8]) -> String {
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let bits = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |acc, (i, &b)| acc | (b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(BASE64[(bits >> (18 - 6 * i) & 0x3F) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+/// Base64-merkin arvo (0-63)
+fn base64_value(c: u8) -> Option<u32> {
+    let value = match c {
+        b'A'..=b'Z' => c - b'A',
+        b'a'..=b'z' => c - b'a' + 26,
+        b'0'..=b'9' => c - b'0' + 52,
+        b'+' => 62,
+        b'/' => 63,
+        _ => return None,
+    };
+    Some(value as u32)
+}
+
+/// Base64-merkkijono takaisin tavuiksi
+pub fn base64_decode(text: &str) -> io::Result<Vec<u8>> {
+    let text = text.trim_end_matches('=').as_bytes();
+    let mut out = Vec::with_capacity(text.len() * 3 / 4);
+    for chunk in text.chunks(4) {
+        let mut bits = 0u32;
+        for (i, &c) in chunk.iter().enumerate() {
+            let value = base64_value(c).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    tr!("virheellinen Base64-merkki", "invalid Base64 character"),
+                )
+            })?;
+            bits |= value << (18 - 6 * i);
+        }
+        out.extend(&bits.to_be_bytes()[1..chunk.len()]);
+    }
+    Ok(out)
+}
@@ -9,13 +9,22 @@
 // 4. Korvaa parit uusilla tokeneilla
 // 5. Virta tiivistyy hierarkkisesti
 
+use crate::atomic;
+use crate::dedup::{self, Relation};
+use crate::lexer::{self, Tokenization};
 use crate::operator::Operator;
-use crate::pattern::Pattern;
+use crate::pattern::{Pattern, TruthChange, TruthState};
+use crate::render;
+use crate::segment::{self, CollapsePolicy};
+use crate::term::{self, Level, say, trace};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use serde_json::json;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 use std::fs::File;
-use std::io::{BufReader, BufWriter};
+use std::io::BufReader;
 use std::path::Path;
+use std::sync::mpsc::{Receiver, TryRecvError};
 
 // ============================================================================
 // CONFIGURABLE CONSTANTS
@@ -34,7 +43,57 @@ const FORGET_CAPACITY_THRESHOLD: usize = 80;
 const FORGET_REMOVAL_PERCENTAGE: usize = 10;
 
 /// Default decay rate for pattern strength per cycle
-const DEFAULT_DECAY_RATE: f64 = 0.01;
+pub const DEFAULT_DECAY_RATE: f64 = 0.01;
+
+/// Oletusviipymä totuuskynnyksen ylityksissä (0 = ei vaimennusta)
+pub const DEFAULT_MIN_DWELL: u64 = 0;
+
+/// Lähes samojen mallien yhdistäminen tämän syklimäärän välein
+const MERGE_INTERVAL_CYCLES: u64 = 25;
+
+/// Aukollinen pari "A _ B" opitaan, kun keskellä on nähty näin monta eri tokenia
+const GAP_MIN_DISTINCT_MIDDLES: usize = 3;
+
+/// Reunojen yhteispituus tavuina: "a _ b" yksittäisillä tavuilla on kohinaa
+const GAP_MIN_OUTER_LEN: usize = 4;
+
+/// Uusia aukollisia malleja enintään näin monta sykliä kohden
+const MAX_NEW_GAPS: usize = 4;
+
+/// Kanavasta luetaan enintään näin monta tavua sykliä kohden
+/// (viesti luetaan aina kokonaan, joten raja voi ylittyä yhdellä viestillä)
+const MAX_PUMP_BYTES: usize = 64 * 1024;
+
+/// Virran pituus (tokeneina), jonka jälkeen syötettä hidastetaan: jokainen
+/// sykli käy koko virran läpi, joten pidempi virta tekee sykleistä hitaampia
+const STREAM_SOFT_LIMIT: usize = 1 << 20;
+
+/// Vastapaine ei koskaan pysäytä syöttöä kokonaan
+const MIN_CAPACITY_HINT: f64 = 0.1;
+
+/// "Totuuskynnys": mallia käytetään tiivistykseen vain jos strength ylittää tämän
+pub(crate) const TRUTH_THRESHOLD: f64 = 0.5;
+
+/// Elinaikaisten säästöjen paino unohtamisessa: eniten säästänyt malli saa
+/// tämän verran lisää "vahvuutta", muut logaritmisesti suhteessa siihen.
+/// Pitkäaikainen hyödyllinen malli ei unohdu hiljaisen jakson aikana.
+const SAVINGS_WEIGHT: f64 = 0.5;
+
+/// Parihaun rivi tallennetussa tiedostossa ("12,34": 56) tavuina
+const PAIR_LOOKUP_ENTRY_BYTES: usize = 24;
+
+/// Mallin sisennys aivotiedostossa (`patterns`-kartan sisällä)
+const PATTERN_INDENT: usize = 4;
+
+/// Tuttuuden mittaushorisontit tokeneina (lyhyt, keskipitkä, pitkä)
+pub const FAMILIARITY_HORIZONS: [usize; 3] = [1_000, 10_000, 100_000];
+
+/// Tilakyselyn tuttuustrendin pituus sykleinä
+const STATUS_TREND_LEN: usize = 32;
+
+/// Koko virran otoksen ositteiden määrä ja yhden otosikkunan pituus
+const FAMILIARITY_STRATA: usize = 16;
+const FAMILIARITY_SAMPLE_LEN: usize = 256;
 
 /// Luokkien kiinteät ID:t
 const CLASS_ID_DIGIT: u32 = 256;
@@ -42,6 +101,16 @@ const CLASS_ID_WHITESPACE: u32 = 257;
 const CLASS_ID_ALPHA_LOWER: u32 = 258;
 const PRESEEDED_CLASS_COUNT: usize = 3;
 
+/// Tietueraja virrassa: ei ole pankissa, joten sen yli ei muodostu pareja.
+/// Erottaa rinnakkain syötettyjen tiedostojen lomitetut palat.
+pub const BOUNDARY_TOKEN: u32 = u32::MAX;
+
+/// Dynaamisten literaalien enimmäismäärä pankissa
+const MAX_LEXEMES: usize = 8192;
+
+/// Pidempi leksemi syötetään tavuina (esim. pitkät merkkijonot)
+const MAX_LEXEME_LEN: usize = 32;
+
 // ============================================================================
 // PATTERN BANK
 // ============================================================================
@@ -51,9 +120,11 @@ const PRESEEDED_CLASS_COUNT: usize = 3;
 /// Tukee nopeaa hakua:
 /// - id -> Pattern
 /// - (left_id, right_id) -> id (tiedämme onko pari jo olemassa)
-#[derive(Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct PatternBank {
-    /// Kaikki mallit: id -> Pattern
+    /// Kaikki mallit: id -> Pattern.
+    /// Serialisoidaan ID-järjestyksessä, jotta samat aivot tuottavat saman tiedoston.
+    #[serde(serialize_with = "serialize_sorted")]
     patterns: HashMap<u32, Pattern>,
 
     /// Käänteinen haku: (left_id, right_id) -> pattern_id
@@ -65,14 +136,44 @@ pub struct PatternBank {
     )]
     pair_lookup: HashMap<(u32, u32), u32>,
 
+    /// Aukollisten mallien haku: (left_id, right_id) -> Gap-mallin id
+    #[serde(
+        default,
+        serialize_with = "serialize_pair_lookup",
+        deserialize_with = "deserialize_pair_lookup"
+    )]
+    gap_lookup: HashMap<(u32, u32), u32>,
+
     /// Seuraava vapaa ID
     next_id: u32,
 
     /// Maksimi mallien määrä (evoluutiopaine)
     capacity: usize,
+
+    /// Miten syöte jaetaan siemenyksiköiksi (tallennetaan aivojen mukana)
+    #[serde(default)]
+    tokenization: Tokenization,
+
+    /// Dynaamisten literaalien haku: tavut -> id (rakennetaan latauksessa)
+    #[serde(skip)]
+    lexeme_lookup: HashMap<Vec<u8>, u32>,
+
+    /// Sisällöstä johdetut vakaat ID:t: id -> rakenteen tiiviste.
+    /// Ylläpidetään luonnin ja poiston yhteydessä, rakennetaan latauksessa.
+    #[serde(skip)]
+    stable_ids: HashMap<u32, u64>,
+}
+
+/// Serialisoi HashMap avainten järjestyksessä (toistettava tiedosto)
+fn serialize_sorted<S, V>(map: &HashMap<u32, V>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+    V: Serialize,
+{
+    map.iter().collect::<BTreeMap<_, _>>().serialize(serializer)
 }
 
-/// Serialisoi pair_lookup HashMap String-avaimina
+/// Serialisoi pair_lookup HashMap String-avaimina (parijärjestyksessä)
 fn serialize_pair_lookup<S>(
     map: &HashMap<(u32, u32), u32>,
     serializer: S,
@@ -82,7 +183,7 @@ where
 {
     use serde::ser::SerializeMap;
     let mut ser_map = serializer.serialize_map(Some(map.len()))?;
-    for ((left, right), value) in map {
+    for ((left, right), value) in map.iter().collect::<BTreeMap<_, _>>() {
         let key = format!("{}_{}", left, right);
         ser_map.serialize_entry(&key, value)?;
     }
@@ -115,8 +216,12 @@ impl PatternBank {
         let mut bank = PatternBank {
             patterns: HashMap::with_capacity(256 + PRESEEDED_CLASS_COUNT + capacity),
             pair_lookup: HashMap::new(),
+            gap_lookup: HashMap::new(),
             next_id: 0,
             capacity: capacity + 300, // 256 literaalia + esiluokkia + hieman tilaa luokille
+            tokenization: Tokenization::Bytes,
+            lexeme_lookup: HashMap::new(),
+            stable_ids: HashMap::new(),
         };
 
         // Alusta 256 Literal-patternia (tavut 0-255)
@@ -128,6 +233,7 @@ impl PatternBank {
         }
 
         bank.initialize_classes();
+        bank.rebuild_lookups();
 
         bank
     }
@@ -147,6 +253,11 @@ impl PatternBank {
             complexity: 0,
             usage_count: 0,
             ref_count: eternal_refcount,
+            birth_cycle: 0,
+            strength_history: VecDeque::new(),
+            tokens_saved: 0,
+            pinned: false,
+            truth: TruthState::default(),
         };
         self.patterns.entry(CLASS_ID_DIGIT).or_insert(digit_pattern);
 
@@ -158,6 +269,11 @@ impl PatternBank {
             complexity: 0,
             usage_count: 0,
             ref_count: eternal_refcount,
+            birth_cycle: 0,
+            strength_history: VecDeque::new(),
+            tokens_saved: 0,
+            pinned: false,
+            truth: TruthState::default(),
         };
         self.patterns
             .entry(CLASS_ID_WHITESPACE)
@@ -171,6 +287,11 @@ impl PatternBank {
             complexity: 0,
             usage_count: 0,
             ref_count: eternal_refcount,
+            birth_cycle: 0,
+            strength_history: VecDeque::new(),
+            tokens_saved: 0,
+            pinned: false,
+            truth: TruthState::default(),
         };
         self.patterns
             .entry(CLASS_ID_ALPHA_LOWER)
@@ -194,7 +315,7 @@ impl PatternBank {
                 Operator::Class(class_id) => {
                     return Some(class_id);
                 }
-                Operator::Combine(_, _) => {}
+                Operator::Lexeme(_) | Operator::Combine(_, _) | Operator::Gap(_, _) => {}
             }
         }
         None
@@ -215,6 +336,142 @@ impl PatternBank {
         byte as u32
     }
 
+    /// Nykyinen tokenisointitila
+    pub fn tokenization(&self) -> Tokenization {
+        self.tokenization
+    }
+
+    /// Vaihda tokenisointitila (vanhat leksemit säilyvät pankissa)
+    pub fn set_tokenization(&mut self, tokenization: Tokenization) {
+        self.tokenization = tokenization;
+    }
+
+    /// Dynaamisten literaalien määrä
+    pub fn lexeme_count(&self) -> usize {
+        self.lexeme_lookup.len()
+    }
+
+    /// Jaa raakadata siemen-ID:iksi tokenisointitilan mukaan.
+    /// Uudet leksemit lisätään pankkiin dynaamisina literaaleina.
+    pub fn seed_ids(&mut self, data: &[u8]) -> Vec<u32> {
+        if self.tokenization == Tokenization::Bytes {
+            return data.iter().map(|&b| self.literal_id(b)).collect();
+        }
+
+        let mut ids = Vec::with_capacity(data.len() / 2);
+        for unit in lexer::split_source(data) {
+            match self.lexeme_id(unit, true) {
+                Some(id) => ids.push(id),
+                None => ids.extend(unit.iter().map(|&b| self.literal_id(b))),
+            }
+        }
+        ids
+    }
+
+    /// Hae (tai luo) leksemin ID. Yksittäiset tavut ovat aina Literaaleja.
+    fn lexeme_id(&mut self, unit: &[u8], create: bool) -> Option<u32> {
+        if unit.len() < 2 {
+            return None;
+        }
+        if let Some(&id) = self.lexeme_lookup.get(unit) {
+            return Some(id);
+        }
+        if !create || unit.len() > MAX_LEXEME_LEN || self.lexeme_lookup.len() >= MAX_LEXEMES {
+            return None;
+        }
+
+        let id = self.next_id;
+        self.next_id += 1;
+        let pattern = Pattern::new_lexeme(id, unit.to_vec());
+        self.record_stable_id(&pattern);
+        self.patterns.insert(id, pattern);
+        self.lexeme_lookup.insert(unit.to_vec(), id);
+        // Leksemit ovat literaaleja: ne eivät vie tilaa opituilta malleilta
+        self.capacity += 1;
+        Some(id)
+    }
+
+    /// Hae tai luo leksemi (vain lähdekooditilassa)
+    pub fn intern_lexeme(&mut self, unit: &[u8]) -> Option<u32> {
+        if self.tokenization != Tokenization::Source {
+            return None;
+        }
+        self.lexeme_id(unit, true)
+    }
+
+    /// Rakenna leksemihaku ja vakaat ID:t uudelleen (ei tallenneta
+    /// levylle). Kutsuttava aina kun pankki on deserialisoitu.
+    pub fn rebuild_lookups(&mut self) {
+        self.lexeme_lookup = self
+            .patterns
+            .iter()
+            .filter_map(|(id, p)| match &p.op {
+                Operator::Lexeme(bytes) => Some((bytes.clone(), *id)),
+                _ => None,
+            })
+            .collect();
+
+        // Osat ovat aina matalammalla tasolla kuin kokonaisuus
+        let mut ids: Vec<(u8, u32)> = self
+            .patterns
+            .iter()
+            .map(|(&id, p)| (p.complexity, id))
+            .collect();
+        ids.sort_unstable();
+        self.stable_ids = HashMap::with_capacity(ids.len());
+        for (_, id) in ids {
+            let hash = self.structure_hash(&self.patterns[&id].op);
+            self.stable_ids.insert(id, hash);
+        }
+    }
+
+    /// Rakenteen tiiviste (FNV-1a): sama rakenne saa saman arvon
+    /// riippumatta siitä, missä järjestyksessä mallit syntyivät
+    fn structure_hash(&self, op: &Operator) -> u64 {
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        let mut mix = |bytes: &[u8]| {
+            for &byte in bytes {
+                hash ^= byte as u64;
+                hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+            }
+        };
+        let part = |id: u32| self.stable_ids.get(&id).copied().unwrap_or(0);
+        match op {
+            Operator::Literal(b) => mix(&[0, *b]),
+            Operator::Lexeme(bytes) => {
+                mix(&[1]);
+                mix(bytes);
+            }
+            Operator::Combine(l, r) => {
+                mix(&[2]);
+                mix(&part(*l).to_le_bytes());
+                mix(&part(*r).to_le_bytes());
+            }
+            Operator::Class(c) => {
+                mix(&[3]);
+                mix(&c.to_le_bytes());
+            }
+            Operator::Gap(l, r) => {
+                mix(&[4]);
+                mix(&part(*l).to_le_bytes());
+                mix(&part(*r).to_le_bytes());
+            }
+        }
+        hash
+    }
+
+    /// Kirjaa uuden mallin vakaa ID (osien ID:t ovat jo taulussa)
+    fn record_stable_id(&mut self, pattern: &Pattern) {
+        let hash = self.structure_hash(&pattern.op);
+        self.stable_ids.insert(pattern.id, hash);
+    }
+
+    /// Sisällöstä johdettu vakaa ID: sama rakenne saa saman arvon
+    /// aivoista ja luontijärjestyksestä riippumatta (vientimuotoja varten)
+    pub fn stable_id(&self, id: u32) -> Option<u64> {
+        self.stable_ids.get(&id).copied()
+    }
+
     /// Tarkista onko pari (left, right) jo olemassa
     pub fn has_pair(&self, left: u32, right: u32) -> bool {
         self.pair_lookup.contains_key(&(left, right))
@@ -225,6 +482,17 @@ impl PatternBank {
         self.pair_lookup.get(&(left, right)).copied()
     }
 
+    /// Ennusta seuraava token: vahvimman Combine-mallin oikea puoli,
+    /// kun vasen puoli on `left` (tasatilanteessa pienin ID)
+    pub fn predict_next(&self, left: u32) -> Option<u32> {
+        self.pair_lookup
+            .iter()
+            .filter(|((l, _), _)| *l == left)
+            .filter_map(|(&(_, right), id)| self.get(*id).map(|p| (p.strength, right)))
+            .max_by(|a, b| a.0.total_cmp(&b.0).then(b.1.cmp(&a.1)))
+            .map(|(_, right)| right)
+    }
+
     /// Luo uusi Combine-malli parille (left, right)
     /// Palauttaa uuden mallin ID:n
     ///
@@ -251,18 +519,61 @@ impl PatternBank {
 
         let pattern =
             Pattern::new_combine(id, left, right, left_complexity, right_complexity, cycle);
+        self.record_stable_id(&pattern);
         self.patterns.insert(id, pattern);
         self.pair_lookup.insert((left, right), id);
 
         Some(id)
     }
 
+    /// Hae aukollisen mallin "left _ right" ID jos se on olemassa
+    pub fn get_gap_id(&self, left: u32, right: u32) -> Option<u32> {
+        self.gap_lookup.get(&(left, right)).copied()
+    }
+
+    /// Luo aukollinen malli "left _ right" (tai palauta olemassa oleva).
+    /// Kapasiteetti kuten create_combinella.
+    pub fn create_gap(&mut self, left: u32, right: u32, cycle: u64) -> Option<u32> {
+        if let Some(id) = self.get_gap_id(left, right) {
+            return Some(id);
+        }
+        if self.patterns.len() >= self.capacity * 95 / 100 {
+            return None;
+        }
+
+        let left_complexity = self.patterns.get(&left).map(|p| p.complexity).unwrap_or(0);
+        let right_complexity = self.patterns.get(&right).map(|p| p.complexity).unwrap_or(0);
+
+        let id = self.next_id;
+        self.next_id += 1;
+        let pattern = Pattern::new_gap(id, left, right, left_complexity, right_complexity, cycle);
+        self.record_stable_id(&pattern);
+        self.patterns.insert(id, pattern);
+        self.gap_lookup.insert((left, right), id);
+
+        Some(id)
+    }
+
+    /// Varaa tila `additional` uudelle mallille (kapasiteetti kasvaa
+    /// tarvittaessa; create_combine jättää aina 5 % varan)
+    pub fn reserve(&mut self, additional: usize) {
+        let needed = (self.patterns.len() + additional) * 100 / 95 + 1;
+        self.capacity = self.capacity.max(needed);
+    }
+
     /// Poista malli (unohtaminen)
     pub fn remove(&mut self, id: u32) -> Option<Pattern> {
         if let Some(pattern) = self.patterns.remove(&id) {
+            self.stable_ids.remove(&id);
             // Poista myös pair_lookup:ista jos kyseessä on Combine
-            if let Operator::Combine(left, right) = pattern.op {
-                self.pair_lookup.remove(&(left, right));
+            match pattern.op {
+                Operator::Combine(left, right) => {
+                    self.pair_lookup.remove(&(left, right));
+                }
+                Operator::Gap(left, right) => {
+                    self.gap_lookup.remove(&(left, right));
+                }
+                _ => {}
             }
             Some(pattern)
         } else {
@@ -270,25 +581,139 @@ impl PatternBank {
         }
     }
 
-    /// Hae heikoimmat mallit (paitsi Literaalit)
+    /// Hae heikoimmat mallit (paitsi Literaalit ja kiinnitetyt).
+    /// Vahvuuteen lisätään elinaikaisten säästöjen osuus (SAVINGS_WEIGHT).
+    ///
+    /// Mallin pisteet ovat vähintään sen varaan rakentuneiden mallien
+    /// pisteet: vahvan "the":n osa "th" ei ole heikko, vaikka sitä ei enää
+    /// käytetä suoraan. Kiinnitetyn mallin osat ovat itsekin kiinnitettyjä.
     pub fn get_weakest(&self, count: usize) -> Vec<u32> {
-        let mut combines: Vec<(u32, f64)> = self
-            .patterns
-            .iter()
-            .filter(|(_, p)| !p.is_literal() && !p.op.is_class())
-            .map(|(id, p)| (*id, p.strength))
+        let candidates = || self.learned();
+        let max_saved = candidates().map(|(_, p)| p.tokens_saved).max().unwrap_or(0);
+        let scale = (max_saved as f64).ln_1p().max(1.0);
+        let base: HashMap<u32, f64> = candidates()
+            .map(|(&id, p)| {
+                let savings = (p.tokens_saved as f64).ln_1p() / scale;
+                let score = if p.pinned {
+                    f64::INFINITY
+                } else {
+                    p.strength + SAVINGS_WEIGHT * savings
+                };
+                (id, score)
+            })
             .collect();
 
-        combines.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
+        let parents = self.parents();
+        let mut memo = HashMap::with_capacity(base.len());
+        let mut combines: Vec<(u32, f64)> = base
+            .keys()
+            .map(|&id| (id, effective_score(id, &base, &parents, &mut memo)))
+            .filter(|(_, score)| score.is_finite())
+            .collect();
+
+        combines.sort_by(|a, b| {
+            a.1.partial_cmp(&b.1)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then(a.0.cmp(&b.0))
+        });
 
         combines.into_iter().take(count).map(|(id, _)| id).collect()
     }
 
+    /// Aivojen koko levyllä tavuina (sama muoto kuin `save`)
+    pub fn estimated_size(&self) -> usize {
+        let mut counter = ByteCounter::default();
+        // Laskuriin kirjoittaminen ei epäonnistu
+        let _ = serde_json::to_writer_pretty(&mut counter, self);
+        counter.bytes
+    }
+
+    /// Yhden mallin arvioitu osuus aivotiedostosta: mallin oma JSON
+    /// sisennettynä, avain ja parihaun rivi
+    pub fn pattern_size(&self, id: u32) -> usize {
+        let Some(pattern) = self.patterns.get(&id) else {
+            return 0;
+        };
+        let mut counter = ByteCounter::default();
+        let _ = serde_json::to_writer_pretty(&mut counter, pattern);
+        let key = id.to_string().len() + 4;
+        let lookup = match pattern.op {
+            Operator::Combine(..) | Operator::Gap(..) => PAIR_LOOKUP_ENTRY_BYTES,
+            _ => 0,
+        };
+        counter.bytes + (counter.lines + 1) * PATTERN_INDENT + key + lookup
+    }
+
+    /// Opitut mallit MDL-arvon mukaan nousevasti. Arvo on mallin säästämät
+    /// tokenit (kukin ID:n bittimäärän verran) miinus mallin oma koko
+    /// tiedostossa. Kuten get_weakest: osa on vähintään yhtä arvokas kuin
+    /// sen varaan rakentuneet, joten kokonaisuudet poistuvat ensin.
+    pub fn least_valuable(&self) -> Vec<u32> {
+        let id_bytes = (self.patterns.len().max(2) as f64).log2() / 8.0;
+        let base: HashMap<u32, f64> = self
+            .learned()
+            .map(|(&id, p)| {
+                let score = if p.pinned {
+                    f64::INFINITY
+                } else {
+                    p.tokens_saved as f64 * id_bytes - self.pattern_size(id) as f64
+                };
+                (id, score)
+            })
+            .collect();
+
+        let parents = self.parents();
+        let mut memo = HashMap::with_capacity(base.len());
+        let mut ranked: Vec<(u32, f64)> = base
+            .keys()
+            .map(|&id| (id, effective_score(id, &base, &parents, &mut memo)))
+            .filter(|(_, score)| score.is_finite())
+            .collect();
+        ranked.sort_by(|a, b| {
+            a.1.partial_cmp(&b.1)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then(a.0.cmp(&b.0))
+        });
+        ranked.into_iter().map(|(id, _)| id).collect()
+    }
+
+    /// Lapsi -> mallit, jotka käyttävät sitä osanaan (Combine ja Gap)
+    pub fn parents(&self) -> HashMap<u32, Vec<u32>> {
+        let mut parents: HashMap<u32, Vec<u32>> = HashMap::new();
+        for (&id, pattern) in &self.patterns {
+            if let Operator::Combine(left, right) | Operator::Gap(left, right) = pattern.op {
+                parents.entry(left).or_default().push(id);
+                if right != left {
+                    parents.entry(right).or_default().push(id);
+                }
+            }
+        }
+        parents
+    }
+
+    /// Malli ja kaikki sen varaan (transitiivisesti) rakentuneet mallit
+    pub fn with_dependents(&self, id: u32, parents: &HashMap<u32, Vec<u32>>) -> HashSet<u32> {
+        let mut closure = HashSet::from([id]);
+        let mut stack = vec![id];
+        while let Some(node) = stack.pop() {
+            for &parent in parents.get(&node).into_iter().flatten() {
+                if closure.insert(parent) {
+                    stack.push(parent);
+                }
+            }
+        }
+        closure
+    }
+
     /// Mallien määrä
     pub fn len(&self) -> usize {
         self.patterns.len()
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
     /// Kapasiteetti
     pub fn capacity(&self) -> usize {
         self.capacity
@@ -296,10 +721,7 @@ impl PatternBank {
 
     /// Combine-mallien määrä (ei Literaalit)
     pub fn combine_count(&self) -> usize {
-        self.patterns
-            .values()
-            .filter(|p| !p.is_literal() && !p.op.is_class())
-            .count()
+        self.learned().count()
     }
 
     /// Iteroi kaikkien mallien yli
@@ -307,6 +729,72 @@ impl PatternBank {
         self.patterns.iter()
     }
 
+    /// Opitut mallit (Combine ja Gap): ei literaaleja, leksemejä eikä luokkia
+    pub fn learned(&self) -> impl Iterator<Item = (&u32, &Pattern)> {
+        self.patterns
+            .iter()
+            .filter(|(_, p)| !p.is_literal() && !p.op.is_class())
+    }
+
+    /// Opitut mallit hierarkiatasolla `level` (tasot alkavat 1:stä)
+    pub fn patterns_at_level(&self, level: u8) -> impl Iterator<Item = (&u32, &Pattern)> {
+        self.learned().filter(move |(_, p)| p.complexity == level)
+    }
+
+    /// Eniten käytetyt vahvat mallit (vahvuus vähintään totuuskynnys),
+    /// käyttömäärän mukaan laskevasti
+    pub fn most_used(&self, top_n: usize) -> Vec<(u32, &Pattern)> {
+        let mut patterns: Vec<_> = self
+            .learned()
+            .filter(|(_, p)| p.strength >= TRUTH_THRESHOLD)
+            .map(|(&id, p)| (id, p))
+            .collect();
+        patterns.sort_by_key(|(id, p)| (Reverse(p.usage_count), *id));
+        patterns.truncate(top_n);
+        patterns
+    }
+
+    /// Korkein opittu taso (0 = ei opittuja malleja)
+    pub fn max_level(&self) -> u8 {
+        self.learned().map(|(_, p)| p.complexity).max().unwrap_or(0)
+    }
+
+    /// Tasokohtaiset tilastot opituista malleista, tasojärjestyksessä
+    pub fn level_stats(&self) -> BTreeMap<u8, LevelStats> {
+        let mut levels: BTreeMap<u8, LevelStats> = BTreeMap::new();
+        let mut total_saved = 0;
+        for (_, p) in self.learned() {
+            let stats = levels.entry(p.complexity).or_default();
+            stats.count += 1;
+            stats.mean_strength += p.strength;
+            stats.tokens_saved += p.tokens_saved;
+            total_saved += p.tokens_saved;
+        }
+        for stats in levels.values_mut() {
+            stats.mean_strength /= stats.count as f64;
+            if total_saved > 0 {
+                stats.coverage = stats.tokens_saved as f64 / total_saved as f64;
+            }
+        }
+        levels
+    }
+
+    /// Pura token osiinsa, kunnes yksikään osa ei kuulu joukkoon `doomed`
+    pub fn expand_into(&self, token: u32, doomed: &HashSet<u32>, out: &mut Vec<u32>) {
+        match self.patterns.get(&token).and_then(|p| p.op.as_combine()) {
+            Some((left, right)) if doomed.contains(&token) => {
+                self.expand_into(left, doomed, out);
+                self.expand_into(right, doomed, out);
+            }
+            _ => out.push(token),
+        }
+    }
+
+    /// Dekoodaa token-jono yhdeksi tavujonoksi
+    pub fn decode_parts(&self, ids: &[u32]) -> Vec<u8> {
+        ids.iter().flat_map(|&id| self.decode(id)).collect()
+    }
+
     /// Dekoodaa token-ID takaisin tavuiksi
     ///
     /// Tämä on rekursiivinen: Combine hajotetaan osiinsa kunnes
@@ -323,6 +811,9 @@ impl PatternBank {
                 Operator::Literal(byte) => {
                     result.push(*byte);
                 }
+                Operator::Lexeme(bytes) => {
+                    result.extend_from_slice(bytes);
+                }
                 Operator::Combine(left, right) => {
                     self.decode_into(*left, result);
                     self.decode_into(*right, result);
@@ -331,40 +822,177 @@ impl PatternBank {
                     let label = format!("[CLASS_{}]", class_id);
                     result.extend_from_slice(label.as_bytes());
                 }
+                Operator::Gap(left, right) => {
+                    self.decode_into(*left, result);
+                    result.extend_from_slice(b"[_]");
+                    self.decode_into(*right, result);
+                }
             }
         }
     }
 
+    /// Mallin hierarkiataso (literaalit ja tuntemattomat 0)
+    pub fn complexity(&self, id: u32) -> u8 {
+        self.patterns.get(&id).map(|p| p.complexity).unwrap_or(0)
+    }
+
     /// Laske mallin "pituus" tavuina (dekoodattu muoto)
     pub fn pattern_length(&self, id: u32) -> usize {
         if let Some(pattern) = self.patterns.get(&id) {
             match &pattern.op {
                 Operator::Literal(_) => 1,
+                Operator::Lexeme(bytes) => bytes.len(),
                 Operator::Combine(left, right) => {
                     self.pattern_length(*left) + self.pattern_length(*right)
                 }
-                Operator::Class(_) => 0,
+                Operator::Class(_) | Operator::Gap(_, _) => 0,
             }
         } else {
             0
         }
     }
 
+    /// Koodaa tavut token-virraksi jäädytetyllä pankilla.
+    ///
+    /// Toimii kuten Builderin collapse, mutta ei luo eikä vahvista malleja:
+    /// parit korvataan vain olemassa olevilla, "tosilla" Combine-malleilla.
+    pub fn encode(&self, data: &[u8]) -> Vec<u32> {
+        self.encode_with(data, CollapsePolicy::Pairwise)
+    }
+
+    /// Koodaa valitulla jäsennyspolitiikalla; parittainen vaihe ajetaan
+    /// aina lopuksi, joten politiikka voi vain lisätä tiivistystä.
+    pub fn encode_with(&self, data: &[u8], policy: CollapsePolicy) -> Vec<u32> {
+        let (mut stream, _) = segment::segment(self, &self.encode_seeds(data), policy);
+
+        loop {
+            let mut next = Vec::with_capacity(stream.len());
+            let mut changed = false;
+            let mut i = 0;
+            while i < stream.len() {
+                if i + 1 < stream.len()
+                    && let Some(id) = self.get_pair_id(stream[i], stream[i + 1])
+                    && self
+                        .patterns
+                        .get(&id)
+                        .is_some_and(|p| p.is_true(TRUTH_THRESHOLD))
+                {
+                    next.push(id);
+                    changed = true;
+                    i += 2;
+                    continue;
+                }
+                next.push(stream[i]);
+                i += 1;
+            }
+            stream = next;
+            if !changed {
+                return stream;
+            }
+        }
+    }
+
+    /// Siemen-ID:t kuten `seed_ids`, mutta uusia leksemejä ei luoda
+    pub fn encode_seeds(&self, data: &[u8]) -> Vec<u32> {
+        match self.tokenization {
+            Tokenization::Bytes => data.iter().map(|&b| self.literal_id(b)).collect(),
+            Tokenization::Source => lexer::split_source(data)
+                .into_iter()
+                .flat_map(|unit| match self.lexeme_lookup.get(unit) {
+                    Some(&id) => vec![id],
+                    None => unit.iter().map(|&b| self.literal_id(b)).collect(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Rakenteen sormenjälki (FNV-1a): sama arvo tarkoittaa että
+    /// jokainen ID dekoodautuu samoiksi tavuiksi.
+    pub fn fingerprint(&self) -> u64 {
+        let mut ids: Vec<&u32> = self.patterns.keys().collect();
+        ids.sort();
+
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        let mut mix = |value: u32| {
+            for byte in value.to_le_bytes() {
+                hash ^= byte as u64;
+                hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+            }
+        };
+        for id in ids {
+            mix(*id);
+            match &self.patterns[id].op {
+                Operator::Literal(b) => mix(*b as u32),
+                Operator::Lexeme(bytes) => bytes.iter().for_each(|&b| mix(b as u32)),
+                Operator::Combine(l, r) => {
+                    mix(*l);
+                    mix(*r);
+                }
+                Operator::Class(c) => mix(*c),
+                Operator::Gap(l, r) => {
+                    // Jokeri erottaa aukon samoilla osilla olevasta parista
+                    mix(*l);
+                    mix(u32::MAX);
+                    mix(*r);
+                }
+            }
+        }
+        hash
+    }
+
     /// Tallenna PatternBank JSON-tiedostoon
     pub fn save(&self, path: &Path) -> std::io::Result<()> {
-        let file = File::create(path)?;
-        let writer = BufWriter::new(file);
-        serde_json::to_writer_pretty(writer, self)
-            .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))
+        atomic::write_atomic(path, |writer| {
+            serde_json::to_writer_pretty(writer, self).map_err(std::io::Error::other)
+        })
     }
 
     /// Lataa PatternBank JSON-tiedostosta
     pub fn load(path: &Path) -> std::io::Result<Self> {
         let file = File::open(path)?;
         let reader = BufReader::new(file);
-        serde_json::from_reader(reader)
-            .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))
+        let mut bank: Self = serde_json::from_reader(reader).map_err(std::io::Error::other)?;
+        bank.rebuild_lookups();
+        Ok(bank)
+    }
+}
+
+/// Kirjoittaja, joka vain laskee tavut ja rivit (koon arviointi)
+#[derive(Default)]
+struct ByteCounter {
+    bytes: usize,
+    lines: usize,
+}
+
+impl std::io::Write for ByteCounter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.bytes += buf.len();
+        self.lines += buf.iter().filter(|&&b| b == b'\n').count();
+        Ok(buf.len())
     }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Mallin pisteet vanhempineen: suurin omista ja vanhempien pisteistä.
+/// Hierarkia on syklitön (osat ovat olemassa ennen kokonaisuutta).
+fn effective_score(
+    id: u32,
+    base: &HashMap<u32, f64>,
+    parents: &HashMap<u32, Vec<u32>>,
+    memo: &mut HashMap<u32, f64>,
+) -> f64 {
+    if let Some(&score) = memo.get(&id) {
+        return score;
+    }
+    let mut score = base.get(&id).copied().unwrap_or(f64::NEG_INFINITY);
+    for &parent in parents.get(&id).into_iter().flatten() {
+        score = score.max(effective_score(parent, base, parents, memo));
+    }
+    memo.insert(id, score);
+    score
 }
 
 /// PairStats: Tilasto vierekkäisistä pareista
@@ -393,16 +1021,36 @@ impl PairStats {
 
     /// Hae parhaat parit (ylittävät kynnyksen)
     pub fn get_top_pairs(&self, threshold: u32, max_count: usize) -> Vec<((u32, u32), u32)> {
+        self.get_top_pairs_by(|_, _| threshold, max_count)
+    }
+
+    /// Kuten get_top_pairs, mutta jokaisella parilla on oma kynnyksensä.
+    /// Järjestys on esiintymät suhteessa kynnykseen: löysemmän kynnyksen
+    /// pari pääsee budjettiin harvemmilla esiintymillä.
+    pub fn get_top_pairs_by(
+        &self,
+        threshold: impl Fn(u32, u32) -> u32,
+        max_count: usize,
+    ) -> Vec<((u32, u32), u32)> {
         let mut pairs: Vec<_> = self
             .counts
             .iter()
-            .filter(|&(_, count)| *count >= threshold)
-            .map(|((l, r), count)| ((*l, *r), *count))
+            .map(|(&(l, r), &count)| ((l, r), count, threshold(l, r).max(1)))
+            .filter(|&(_, count, threshold)| count >= threshold)
             .collect();
 
-        pairs.sort_by(|a, b| b.1.cmp(&a.1));
-        pairs.truncate(max_count);
+        // Tasapelit ratkaistaan määrän ja ID:n mukaan, jotta ajo on toistettava
+        pairs.sort_by(|a, b| {
+            (b.1 as u64 * a.2 as u64)
+                .cmp(&(a.1 as u64 * b.2 as u64))
+                .then(b.1.cmp(&a.1))
+                .then(a.0.cmp(&b.0))
+        });
         pairs
+            .into_iter()
+            .take(max_count)
+            .map(|(pair, count, _)| (pair, count))
+            .collect()
     }
 }
 
@@ -430,16 +1078,41 @@ pub struct Builder {
     /// Kynnys parin luomiselle (kuinka monta kertaa pitää esiintyä)
     pub pair_threshold: u32,
 
-    /// Kynnys mallin "kuolemalle" (liian heikko strength)
-    #[allow(dead_code)]
-    pub death_threshold: f64,
+    /// Tasokohtaiset parikynnykset: ensimmäinen koskee literaalipareja
+    /// (taso 1), viimeinen kaikkia ylempiä tasoja. Tyhjä = pair_threshold.
+    pub level_thresholds: Vec<u32>,
 
     /// Vahvistuksen määrä onnistuneesta ennustuksesta
     pub strengthen_amount: f64,
 
-    /// Heikennyksen määrä epäonnistuneesta ennustuksesta
-    #[allow(dead_code)]
-    pub weaken_amount: f64,
+    /// Miten tunnetut mallit korvaavat virran tokeneita
+    pub collapse_policy: CollapsePolicy,
+
+    /// Tiivistyksen vahvuuskynnykset tasoittain kuten level_thresholds:
+    /// viimeinen koskee kaikkia ylempiä tasoja. Tyhjä = TRUTH_THRESHOLD.
+    pub collapse_thresholds: Vec<f64>,
+
+    /// Viimeisimmän collapse-kierroksen kynnykseen hylkäämät parit
+    collapse_rejected: usize,
+
+    /// Aivotiedoston kokobudjetti tavuina (None = vain mallien määrä)
+    pub size_budget: Option<usize>,
+
+    /// Vahvuuden heikkeneminen syklissä
+    pub decay_rate: f64,
+
+    /// Vähimmäisviipymä (sykleinä) ennen kuin malli saa vaihtaa puolta
+    /// totuuskynnykseen nähden uudelleen (0 = ei vaimennusta)
+    pub min_dwell: u64,
+
+    /// Viimeisimmän syklin tilastot (ks. status)
+    last_stats: Option<BuilderStats>,
+
+    /// Lyhyen horisontin tuttuus viimeisiltä sykleiltä (vanhin ensin)
+    familiarity_trend: VecDeque<f64>,
+
+    /// Syötekanava muille säikeille (ks. attach_channel)
+    inbox: Option<Receiver<Vec<u8>>>,
 }
 
 impl Builder {
@@ -450,10 +1123,18 @@ impl Builder {
             token_stream: Vec::new(),
             pair_stats: PairStats::new(),
             cycle: 0,
-            pair_threshold: 2,    // Pari pitää esiintyä vähintään 2 kertaa
-            death_threshold: 0.1, // Alle 0.1 strength -> kuolema
+            pair_threshold: 2, // Pari pitää esiintyä vähintään 2 kertaa
+            level_thresholds: Vec::new(),
             strengthen_amount: 0.1,
-            weaken_amount: 0.05,
+            collapse_policy: CollapsePolicy::Pairwise,
+            collapse_thresholds: Vec::new(),
+            collapse_rejected: 0,
+            size_budget: None,
+            decay_rate: DEFAULT_DECAY_RATE,
+            min_dwell: DEFAULT_MIN_DWELL,
+            last_stats: None,
+            familiarity_trend: VecDeque::new(),
+            inbox: None,
         }
     }
 
@@ -465,17 +1146,117 @@ impl Builder {
             pair_stats: PairStats::new(),
             cycle: 0,
             pair_threshold: 2,
-            death_threshold: 0.1,
+            level_thresholds: Vec::new(),
             strengthen_amount: 0.1,
-            weaken_amount: 0.05,
+            collapse_policy: CollapsePolicy::Pairwise,
+            collapse_thresholds: Vec::new(),
+            collapse_rejected: 0,
+            size_budget: None,
+            decay_rate: DEFAULT_DECAY_RATE,
+            min_dwell: DEFAULT_MIN_DWELL,
+            last_stats: None,
+            familiarity_trend: VecDeque::new(),
+            inbox: None,
+        }
+    }
+
+    /// Korvaa opittu tila (pankki, virta, sykli) istunnosta. Asetukset
+    /// säilyvät; paritilastot lasketaan uudelleen virrasta.
+    pub(crate) fn restore(&mut self, mut bank: PatternBank, token_stream: Vec<u32>, cycle: u64) {
+        bank.rebuild_lookups();
+        self.bank = bank;
+        self.token_stream = token_stream;
+        self.cycle = cycle;
+        self.pair_stats = PairStats::new();
+        self.last_stats = None;
+        self.familiarity_trend.clear();
+    }
+
+    /// Kytke syötekanava: upottava sovellus voi syöttää tavuja mistä
+    /// tahansa säikeestä ilman tiedostopohjaista Feederiä. Kanava
+    /// tyhjennetään jokaisen syklin alussa (ks. pump). Viestit ovat saman
+    /// jatkuvan virran paloja; kun lähettäjät sulkeutuvat, virtaan lisätään
+    /// tietueraja ja kanava irrotetaan.
+    pub fn attach_channel(&mut self, rx: Receiver<Vec<u8>>) {
+        self.inbox = Some(rx);
+    }
+
+    /// Onko syötekanava kytkettynä (lähettäjiä vielä jäljellä)
+    pub fn has_channel(&self) -> bool {
+        self.inbox.is_some()
+    }
+
+    /// Siirrä kanavaan jonoutuneet tavut virtaan odottamatta.
+    /// Palauttaa luettujen tavujen määrän.
+    ///
+    /// Sykliä kohden luetaan enintään MAX_PUMP_BYTES kerrottuna
+    /// kapasiteettivihjeellä; loput jäävät kanavaan. Rajatulla kanavalla
+    /// (`mpsc::sync_channel`) lähettäjät pysähtyvät, kun jono täyttyy.
+    pub fn pump(&mut self) -> usize {
+        let Some(rx) = self.inbox.take() else {
+            return 0;
+        };
+        let budget = (MAX_PUMP_BYTES as f64 * self.capacity_hint()) as usize;
+        let mut pumped = 0;
+        while pumped < budget {
+            match rx.try_recv() {
+                Ok(data) => {
+                    pumped += data.len();
+                    self.tokenize(&data);
+                }
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    self.push_boundary();
+                    return pumped;
+                }
+            }
+        }
+        self.inbox = Some(rx);
+        pumped
+    }
+
+    /// Parin kynnys tasolle (1 = literaalipari)
+    pub fn threshold_for_level(&self, level: u8) -> u32 {
+        match self.level_thresholds.len() {
+            0 => self.pair_threshold,
+            n => self.level_thresholds[(level.max(1) as usize - 1).min(n - 1)],
+        }
+    }
+
+    /// Tiivistyksen vahvuuskynnys mallin tasolle
+    pub fn collapse_threshold(&self, level: u8) -> f64 {
+        match self.collapse_thresholds.len() {
+            0 => TRUTH_THRESHOLD,
+            n => self.collapse_thresholds[(level.max(1) as usize - 1).min(n - 1)],
+        }
+    }
+
+    /// Vastapaine (0.0 - 1.0): täysi nopeus kunnes virta ylittää
+    /// STREAM_SOFT_LIMIT:n, sitten lineaarisesti alas kaksinkertaiseen
+    /// pituuteen asti (vähintään MIN_CAPACITY_HINT)
+    pub fn capacity_hint(&self) -> f64 {
+        let len = self.token_stream.len();
+        if len <= STREAM_SOFT_LIMIT {
+            return 1.0;
         }
+        let over = (len - STREAM_SOFT_LIMIT) as f64 / STREAM_SOFT_LIMIT as f64;
+        (1.0 - over).max(MIN_CAPACITY_HINT)
     }
 
     /// Tokenisoi raaka data Literal-ID:iksi ja lisää virtaan
     pub fn tokenize(&mut self, data: &[u8]) {
-        for &byte in data {
-            let id = self.bank.literal_id(byte);
-            self.token_stream.push(id);
+        let ids = self.bank.seed_ids(data);
+        self.token_stream.extend(ids);
+    }
+
+    /// Lisää tietueraja virtaan (ei peräkkäisiä eikä virran alkuun)
+    pub fn push_boundary(&mut self) {
+        if self
+            .token_stream
+            .last()
+            .is_some_and(|&t| t != BOUNDARY_TOKEN)
+        {
+            self.token_stream.push(BOUNDARY_TOKEN);
         }
     }
 
@@ -488,6 +1269,9 @@ impl Builder {
         }
 
         for window in self.token_stream.windows(2) {
+            if window.contains(&BOUNDARY_TOKEN) {
+                continue;
+            }
             self.pair_stats.record(window[0], window[1]);
         }
     }
@@ -498,10 +1282,15 @@ impl Builder {
     pub fn explore(&mut self) -> usize {
         self.compute_pair_stats();
 
-        // Hae parhaat parit
-        let top_pairs = self
-            .pair_stats
-            .get_top_pairs(self.pair_threshold, MAX_TOP_PAIRS);
+        // Hae parhaat parit (kynnys parin tason mukaan)
+        let bank = &self.bank;
+        let top_pairs = self.pair_stats.get_top_pairs_by(
+            |left, right| {
+                let level = bank.complexity(left).max(bank.complexity(right));
+                self.threshold_for_level(level.saturating_add(1))
+            },
+            MAX_TOP_PAIRS,
+        );
 
         let mut created = 0;
 
@@ -509,13 +1298,13 @@ impl Builder {
             // Tarkista ettei pari ole jo olemassa
             if self.bank.has_pair(left, right) {
                 // Vahvista olemassa olevaa mallia
-                if let Some(id) = self.bank.get_pair_id(left, right) {
-                    if let Some(pattern) = self.bank.get_mut(id) {
-                        pattern.strengthen(
-                            self.strengthen_amount * (count as f64 / STRENGTHEN_SCALE_FACTOR),
-                            self.cycle,
-                        );
-                    }
+                if let Some(id) = self.bank.get_pair_id(left, right)
+                    && let Some(pattern) = self.bank.get_mut(id)
+                {
+                    pattern.strengthen(
+                        self.strengthen_amount * (count as f64 / STRENGTHEN_SCALE_FACTOR),
+                        self.cycle,
+                    );
                 }
                 continue;
             }
@@ -528,20 +1317,35 @@ impl Builder {
                 let left_bytes = self.bank.decode(left);
                 let right_bytes = self.bank.decode(right);
                 let combined = self.bank.decode(new_id);
-
-                let left_str = String::from_utf8_lossy(&left_bytes);
-                let right_str = String::from_utf8_lossy(&right_bytes);
-                let combined_str = String::from_utf8_lossy(&combined);
-
-                println!(
-                    "  🧬 Syntyi: P_{} = \"{}\" + \"{}\" = \"{}\" ({} krt, taso {})",
-                    new_id,
-                    left_str,
-                    right_str,
-                    combined_str,
-                    count,
-                    self.bank.get(new_id).map(|p| p.complexity).unwrap_or(0)
-                );
+                let level = self.bank.complexity(new_id);
+
+                if term::is_json() {
+                    term::event(
+                        Level::Trace,
+                        "pattern_born",
+                        json!({
+                            "cycle": self.cycle,
+                            "id": new_id,
+                            "kind": "combine",
+                            "left": left,
+                            "right": right,
+                            "level": level,
+                            "count": count,
+                            "text": String::from_utf8_lossy(&combined),
+                        }),
+                    );
+                } else {
+                    trace!(
+                        "  🧬 Syntyi: P_{} = {} + {} = {} ({} krt, taso {})",
+                        "  🧬 Born: P_{} = {} + {} = {} ({}x, level {})",
+                        new_id,
+                        render::label(&left_bytes, render::PREVIEW_LEN),
+                        render::label(&right_bytes, render::PREVIEW_LEN),
+                        render::label(&combined, render::PREVIEW_LEN),
+                        count,
+                        level
+                    );
+                }
             }
         }
 
@@ -565,32 +1369,212 @@ impl Builder {
 
         let class_threshold = self.pair_threshold.saturating_mul(2);
 
+        let mut class_pairs: Vec<_> = class_pairs.into_iter().collect();
+        class_pairs.sort_unstable();
+
         for ((cls_l, cls_r), count) in class_pairs {
-            if count >= class_threshold {
-                if !self.bank.has_pair(cls_l, cls_r) {
-                    if let Some(new_id) = self.bank.create_combine(cls_l, cls_r, self.cycle) {
-                        created += 1;
-                        println!(
-                            "  🧠 OIVALLUS: P_{} = CLASS_{} + CLASS_{} (Tunnistettu {} kertaa)",
-                            new_id, cls_l, cls_r, count
-                        );
-                    }
+            if count >= class_threshold
+                && !self.bank.has_pair(cls_l, cls_r)
+                && let Some(new_id) = self.bank.create_combine(cls_l, cls_r, self.cycle)
+            {
+                created += 1;
+                if term::is_json() {
+                    term::event(
+                        Level::Trace,
+                        "pattern_born",
+                        json!({
+                            "cycle": self.cycle,
+                            "id": new_id,
+                            "kind": "class",
+                            "left": cls_l,
+                            "right": cls_r,
+                            "level": self.bank.complexity(new_id),
+                            "count": count,
+                        }),
+                    );
+                } else {
+                    trace!(
+                        "  🧠 OIVALLUS: P_{} = CLASS_{} + CLASS_{} (Tunnistettu {} kertaa)",
+                        "  🧠 INSIGHT: P_{} = CLASS_{} + CLASS_{} (Recognized {} times)",
+                        new_id,
+                        cls_l,
+                        cls_r,
+                        count
+                    );
                 }
             }
         }
 
-        created
+        created + self.explore_gaps(class_threshold)
     }
 
-    /// Parser: Korvaa kaikki tunnetut parit uusilla tokeneilla.
-    /// NYT MYÖS: Hyödyntää luokkia (Classes) uusien konkreettisten parien luomiseen.
-    pub fn collapse(&mut self) -> usize {
-        if self.token_stream.len() < 2 {
+    /// Etsi pohjia "A _ B": sama reunapari, jonka keskellä on vaihdellut
+    /// yksittäinen token. Pelkkä vierekkäisyys ei koskaan opi näitä, koska
+    /// jokainen keskiosa on eri pari.
+    ///
+    /// Palauttaa luotujen aukollisten mallien määrän
+    fn explore_gaps(&mut self, threshold: u32) -> usize {
+        let mut lengths: HashMap<u32, usize> = HashMap::new();
+        let mut gaps: HashMap<(u32, u32), (u32, HashSet<u32>)> = HashMap::new();
+
+        for window in self.token_stream.windows(3) {
+            if window.contains(&BOUNDARY_TOKEN) {
+                continue;
+            }
+            let (left, middle, right) = (window[0], window[1], window[2]);
+            let outer_len: usize = [left, right]
+                .iter()
+                .map(|&id| {
+                    *lengths
+                        .entry(id)
+                        .or_insert_with(|| self.bank.pattern_length(id))
+                })
+                .sum();
+            if outer_len < GAP_MIN_OUTER_LEN {
+                continue;
+            }
+            let (count, middles) = gaps.entry((left, right)).or_default();
+            *count += 1;
+            middles.insert(middle);
+        }
+
+        let mut candidates: Vec<((u32, u32), u32, usize)> = gaps
+            .into_iter()
+            .filter(|(_, (count, middles))| {
+                *count >= threshold && middles.len() >= GAP_MIN_DISTINCT_MIDDLES
+            })
+            .map(|(pair, (count, middles))| (pair, count, middles.len()))
+            .collect();
+        candidates.sort_unstable_by_key(|&(pair, count, distinct)| {
+            (Reverse(distinct), Reverse(count), pair)
+        });
+
+        let mut created = 0;
+        for ((left, right), count, distinct) in candidates {
+            if let Some(id) = self.bank.get_gap_id(left, right) {
+                if let Some(pattern) = self.bank.get_mut(id) {
+                    pattern.strengthen(
+                        self.strengthen_amount * (count as f64 / STRENGTHEN_SCALE_FACTOR),
+                        self.cycle,
+                    );
+                }
+                continue;
+            }
+            if created >= MAX_NEW_GAPS {
+                continue;
+            }
+            if let Some(new_id) = self.bank.create_gap(left, right, self.cycle) {
+                created += 1;
+                if term::is_json() {
+                    term::event(
+                        Level::Trace,
+                        "pattern_born",
+                        json!({
+                            "cycle": self.cycle,
+                            "id": new_id,
+                            "kind": "gap",
+                            "left": left,
+                            "right": right,
+                            "level": self.bank.complexity(new_id),
+                            "count": count,
+                            "distinct": distinct,
+                        }),
+                    );
+                } else {
+                    trace!(
+                        "  🕳️ Pohja: P_{} = {} _ {} ({} eri keskiosaa, {} krt)",
+                        "  🕳️ Template: P_{} = {} _ {} ({} distinct middles, {}x)",
+                        new_id,
+                        render::label(&self.bank.decode(left), render::PREVIEW_LEN),
+                        render::label(&self.bank.decode(right), render::PREVIEW_LEN),
+                        distinct,
+                        count
+                    );
+                }
+            }
+        }
+        created
+    }
+
+    /// Kirjaa säännöstä konkretisoidun mallin synty tapahtumalokiin
+    /// (vain JSON: tekstijälki hukkuisi näihin)
+    fn report_instance(&self, id: u32, kind: &str) {
+        if term::is_json() {
+            term::event(
+                Level::Trace,
+                "pattern_born",
+                json!({
+                    "cycle": self.cycle,
+                    "id": id,
+                    "kind": kind,
+                    "level": self.bank.complexity(id),
+                }),
+            );
+        }
+    }
+
+    /// Sido aukko: jos "left _ right" on tosi, luo kohtaan konkreettinen
+    /// malli (left + middle) + right. Palauttaa sen ID:n.
+    fn bind_gap(&mut self, left: u32, middle: u32, right: u32) -> Option<u32> {
+        if middle == BOUNDARY_TOKEN || right == BOUNDARY_TOKEN {
+            return None;
+        }
+        let gap_id = self.bank.get_gap_id(left, right)?;
+        if !self.bank.get(gap_id)?.is_true(TRUTH_THRESHOLD) {
+            return None;
+        }
+
+        let mut concrete = left;
+        for part in [middle, right] {
+            let existed = self.bank.has_pair(concrete, part);
+            concrete = self.bank.create_combine(concrete, part, self.cycle)?;
+            if !existed && let Some(p) = self.bank.get_mut(concrete) {
+                // Kuten luokkasäännöillä: pohja antaa lentävän lähdön
+                p.strength = 0.8;
+                self.report_instance(concrete, "bound");
+            }
+        }
+
+        if let Some(p) = self.bank.get_mut(concrete) {
+            p.tokens_saved += 2;
+        }
+        if let Some(gap) = self.bank.get_mut(gap_id) {
+            gap.strengthen(self.strengthen_amount, self.cycle);
+        }
+        Some(concrete)
+    }
+
+    /// Parser: Korvaa kaikki tunnetut parit uusilla tokeneilla.
+    /// NYT MYÖS: Hyödyntää luokkia (Classes) uusien konkreettisten parien luomiseen.
+    /// Aukolliset pohjat (Gap) sitovat keskimmäisen tokenin samoin.
+    ///
+    /// Muu kuin parittainen politiikka jäsentää virran ensin koko pankkia
+    /// vasten (ks. segment); parittainen vaihe hoitaa sen jälkeen luokat,
+    /// aukot ja jäljelle jääneet parit.
+    pub fn collapse(&mut self) -> usize {
+        if self.token_stream.len() < 2 {
             return 0;
         }
 
         let mut collapsed = 0;
+        if self.collapse_policy != CollapsePolicy::Pairwise {
+            let (stream, used) = segment::segment_with(
+                &self.bank,
+                &self.token_stream,
+                self.collapse_policy,
+                &|level| self.collapse_threshold(level),
+            );
+            for (id, len) in used {
+                if let Some(p) = self.bank.get_mut(id) {
+                    p.strengthen(self.strengthen_amount, self.cycle);
+                    p.tokens_saved += len as u64 - 1;
+                }
+                collapsed += len - 1;
+            }
+            self.token_stream = stream;
+        }
         let mut new_stream = Vec::with_capacity(self.token_stream.len());
+        let mut rejected = 0;
         let mut i = 0;
 
         while i < self.token_stream.len() {
@@ -599,21 +1583,23 @@ impl Builder {
                 let right = self.token_stream[i + 1];
 
                 // 1. TARKISTA TÄSMÄLLINEN PARI (Kuten ennenkin)
-                if let Some(combined_id) = self.bank.get_pair_id(left, right) {
-                    if let Some(pattern) = self.bank.get(combined_id) {
-                        // Käytä vain jos strength ylittää "totuuskynnyksen"
-                        if pattern.strength >= 0.5 {
-                            new_stream.push(combined_id);
-                            collapsed += 1;
-                            i += 2;
-
-                            // Vahvista käytettyä mallia
-                            if let Some(p) = self.bank.get_mut(combined_id) {
-                                p.strengthen(self.strengthen_amount, self.cycle);
-                            }
-                            continue;
+                if let Some(combined_id) = self.bank.get_pair_id(left, right)
+                    && let Some(pattern) = self.bank.get(combined_id)
+                {
+                    // Käytä vain jos strength ylittää tason tiivistyskynnyksen
+                    if pattern.is_true(self.collapse_threshold(pattern.complexity)) {
+                        new_stream.push(combined_id);
+                        collapsed += 1;
+                        i += 2;
+
+                        // Vahvista käytettyä mallia
+                        if let Some(p) = self.bank.get_mut(combined_id) {
+                            p.strengthen(self.strengthen_amount, self.cycle);
+                            p.tokens_saved += 1;
                         }
+                        continue;
                     }
+                    rejected += 1;
                 }
 
                 // 2. TARKISTA LUOKKA-PARI (Uusi logiikka matematiikalle)
@@ -625,28 +1611,32 @@ impl Builder {
                     // Onko olemassa abstrakti sääntö (esim. CLASS_DIGIT + CLASS_DIGIT)?
                     if let Some(abstract_id) = self.bank.get_pair_id(cl, cr) {
                         // Tarkista onko abstrakti sääntö tarpeeksi vahva ("totta")
-                        let abstract_strength =
-                            self.bank.get(abstract_id).map(|p| p.strength).unwrap_or(0.0);
+                        let abstract_true = self
+                            .bank
+                            .get(abstract_id)
+                            .is_some_and(|p| p.is_true(self.collapse_threshold(p.complexity)));
 
-                        if abstract_strength >= 0.5 {
+                        if abstract_true {
                             // HEUREKA! Löysimme kohdan, joka vastaa yleistä sääntöä.
                             // Luodaan HETI konkreettinen pari (esim. 1 + 2) tästä kohdasta.
 
                             // Huom: create_combine tarkistaa onko pari jo olemassa, joten tämä on turvallista.
+                            let existed = self.bank.has_pair(left, right);
                             if let Some(new_concrete_id) =
                                 self.bank.create_combine(left, right, self.cycle)
                             {
                                 // 1. Anna uudelle konkreettiselle mallille "lentävä lähtö", koska se perustuu sääntöön
                                 if let Some(p) = self.bank.get_mut(new_concrete_id) {
                                     p.strength = 0.8; // Korkea luottamus luokan ansiosta!
+                                    p.tokens_saved += 1;
+                                }
+                                if !existed {
+                                    self.report_instance(new_concrete_id, "instance");
                                 }
 
                                 // 2. Vahvista alkuperäistä ABSTRAKTIA sääntöä (koska se oli hyödyllinen!)
                                 if let Some(abstract_p) = self.bank.get_mut(abstract_id) {
-                                    abstract_p.strengthen(
-                                        self.strengthen_amount * 2.0,
-                                        self.cycle,
-                                    );
+                                    abstract_p.strengthen(self.strengthen_amount * 2.0, self.cycle);
                                 }
 
                                 // 3. Käytä uutta mallia heti tiivistykseen
@@ -658,6 +1648,16 @@ impl Builder {
                         }
                     }
                 }
+
+                // 3. TARKISTA AUKOLLINEN POHJA ("error: " _ " at line")
+                if i + 2 < self.token_stream.len()
+                    && let Some(bound_id) = self.bind_gap(left, right, self.token_stream[i + 2])
+                {
+                    new_stream.push(bound_id);
+                    collapsed += 2;
+                    i += 3;
+                    continue;
+                }
             }
 
             new_stream.push(self.token_stream[i]);
@@ -665,6 +1665,7 @@ impl Builder {
         }
 
         self.token_stream = new_stream;
+        self.collapse_rejected = rejected;
         collapsed
     }
 
@@ -700,47 +1701,245 @@ impl Builder {
             return 0;
         }
 
-        let weak_ids = self.bank.get_weakest(to_remove);
-        let mut removed = 0;
-
-        for id in weak_ids {
-            // Ennen poistoa: hajota malli takaisin osiinsa virrassa
-            if let Some(pattern) = self.bank.get(id) {
-                if let Operator::Combine(left, right) = pattern.op {
-                    // Korvaa kaikki id:t virrassa parilla (left, right)
-                    let mut new_stream = Vec::with_capacity(self.token_stream.len() * 2);
-                    for &token in &self.token_stream {
-                        if token == id {
-                            new_stream.push(left);
-                            new_stream.push(right);
-                        } else {
-                            new_stream.push(token);
-                        }
-                    }
-                    self.token_stream = new_stream;
+        // Malli unohdetaan yhdessä sen varaan rakentuneiden kanssa, jotta
+        // pankkiin ei jää Combine-malleja, joiden osa puuttuu. Heikoimpien
+        // järjestys takaa, että vanhemmat ovat enintään yhtä vahvoja.
+        let parents = self.bank.parents();
+        let mut doomed = HashSet::new();
+        let mut chosen = HashSet::new();
+        for id in self.bank.get_weakest(to_remove) {
+            if doomed.len() >= to_remove {
+                break;
+            }
+            if !doomed.contains(&id) {
+                chosen.insert(id);
+                doomed.extend(self.bank.with_dependents(id, &parents));
+            }
+        }
+        self.remove_patterns(doomed, &chosen)
+    }
+
+    /// Kokobudjetti: jos aivotiedosto kasvaisi budjettia suuremmaksi,
+    /// unohda vähiten arvokkaat mallit (MDL, ks. least_valuable), kunnes
+    /// arvioitu ylitys on poistettu. Palauttaa poistettujen määrän.
+    pub fn enforce_size_budget(&mut self) -> usize {
+        let Some(budget) = self.size_budget else {
+            return 0;
+        };
+        let mut over = self.bank.estimated_size().saturating_sub(budget);
+        if over == 0 {
+            return 0;
+        }
+
+        let parents = self.bank.parents();
+        let mut doomed = HashSet::new();
+        let mut chosen = HashSet::new();
+        for id in self.bank.least_valuable() {
+            if over == 0 {
+                break;
+            }
+            if doomed.contains(&id) {
+                continue;
+            }
+            chosen.insert(id);
+            for dependent in self.bank.with_dependents(id, &parents) {
+                if doomed.insert(dependent) {
+                    over = over.saturating_sub(self.bank.pattern_size(dependent));
+                }
+            }
+        }
+        self.remove_patterns(doomed, &chosen)
+    }
 
-                    // Tulosta poisto
-                    println!(
-                        "  🗑️ Unohdettiin: P_{} (strength: {:.2})",
-                        id, pattern.strength
+    /// Poista mallit virrasta ja pankista. `chosen` = itse valitut, muut
+    /// poistuvat osiensa mukana.
+    fn remove_patterns(&mut self, doomed: HashSet<u32>, chosen: &HashSet<u32>) -> usize {
+        if doomed.is_empty() {
+            return 0;
+        }
+
+        // Ennen poistoa: hajota mallit virrassa osiin, joita ei unohdeta
+        let mut new_stream = Vec::with_capacity(self.token_stream.len() * 2);
+        for &token in &self.token_stream {
+            self.bank.expand_into(token, &doomed, &mut new_stream);
+        }
+        self.token_stream = new_stream;
+
+        let mut ids: Vec<u32> = doomed.into_iter().collect();
+        ids.sort_unstable();
+        for &id in &ids {
+            if let Some(pattern) = self.bank.remove(id) {
+                if term::is_json() {
+                    // Syy: heikentynyt alle kynnyksen, vahvanakin tilan
+                    // puutteeseen poistettu vai poistetun osan mukana
+                    let cause = if !chosen.contains(&id) {
+                        "dependent"
+                    } else if pattern.strength < TRUTH_THRESHOLD {
+                        "decay"
+                    } else {
+                        "capacity"
+                    };
+                    term::event(
+                        Level::Trace,
+                        "pattern_forgotten",
+                        json!({
+                            "cycle": self.cycle,
+                            "id": id,
+                            "level": pattern.complexity,
+                            "born": pattern.birth_cycle,
+                            "cause": cause,
+                            "strength": pattern.strength,
+                            "saved": pattern.tokens_saved,
+                        }),
                     );
+                } else {
+                    trace!(
+                        "  🗑️ Unohdettiin: P_{} (strength: {:.2}, säästi {})",
+                        "  🗑️ Forgotten: P_{} (strength: {:.2}, saved {})",
+                        id,
+                        pattern.strength,
+                        pattern.tokens_saved
+                    );
+                }
+            }
+        }
+        ids.len()
+    }
+
+    /// Merge: Yhdistä lähes samat mallit ("funktio " ja "funktio").
+    ///
+    /// Heikompi (strength, sitten käyttö) yhdistetään vahvempaan: tilastot
+    /// summataan ja virran viittaukset kirjoitetaan uudelleen häviöttömästi.
+    /// Jos heikompi on vahvemman yhden tavun laajennus, se korvataan
+    /// [vahvempi, tavu] -parilla; muuten se hajotetaan osiinsa kuten
+    /// unohdettaessa. Toisten mallien osana olevia malleja ei yhdistetä.
+    ///
+    /// Palauttaa yhdistettyjen mallien määrän
+    pub fn merge_near_duplicates(&mut self) -> usize {
+        let mut referenced = HashSet::new();
+        let mut entries = Vec::new();
+        for (&id, pattern) in self.bank.iter() {
+            if let Operator::Gap(left, right) = pattern.op {
+                referenced.insert(left);
+                referenced.insert(right);
+            }
+            if let Operator::Combine(left, right) = pattern.op {
+                referenced.insert(left);
+                referenced.insert(right);
+                let bytes = self.bank.decode(id);
+                // Luokkia sisältävät mallit eivät ole konkreettisia tavuja
+                if self.bank.pattern_length(id) == bytes.len() {
+                    entries.push((id, bytes));
                 }
             }
+        }
+        entries.sort_unstable_by_key(|(id, _)| *id);
+
+        let mut replacements: HashMap<u32, Vec<u32>> = HashMap::new();
+        // Kerran yhdistetty (voittaja tai häviäjä) ei osallistu toiseen
+        // pariin: muuten voittajan korvaus voisi viitata myöhemmin
+        // poistettuun malliin
+        let mut touched = HashSet::new();
+        for (a, b, relation) in dedup::find_near_duplicates(&entries) {
+            if touched.contains(&a) || touched.contains(&b) {
+                continue;
+            }
+            let (Some(pa), Some(pb)) = (self.bank.get(a), self.bank.get(b)) else {
+                continue;
+            };
+            let a_wins = (pa.strength, pa.usage_count, Reverse(a))
+                > (pb.strength, pb.usage_count, Reverse(b));
+            let (survivor, victim) = if a_wins { (a, b) } else { (b, a) };
+            if referenced.contains(&victim) || self.bank.get(victim).is_some_and(|p| p.pinned) {
+                continue;
+            }
 
-            self.bank.remove(id);
-            removed += 1;
+            // `a` on pidempi: laajennus ilmaistaan voittajan avulla
+            let replacement = match relation {
+                Relation::Append(byte) if victim == a => vec![survivor, self.bank.literal_id(byte)],
+                Relation::Prepend(byte) if victim == a => {
+                    vec![self.bank.literal_id(byte), survivor]
+                }
+                _ => match self.bank.get(victim).and_then(|p| p.op.as_combine()) {
+                    Some((left, right)) => vec![left, right],
+                    None => continue,
+                },
+            };
+
+            let Some(removed) = self.bank.remove(victim) else {
+                continue;
+            };
+            if let Some(p) = self.bank.get_mut(survivor) {
+                p.usage_count += removed.usage_count;
+                p.tokens_saved += removed.tokens_saved;
+                p.strength = p.strength.max(removed.strength);
+                p.last_used = p.last_used.max(removed.last_used);
+            }
+            if term::is_json() {
+                term::event(
+                    Level::Trace,
+                    "pattern_merged",
+                    json!({
+                        "cycle": self.cycle,
+                        "id": victim,
+                        "level": removed.complexity,
+                        "born": removed.birth_cycle,
+                        "into": survivor,
+                    }),
+                );
+            } else {
+                trace!(
+                    "  🔗 Yhdistettiin: P_{} {} → P_{} {}",
+                    "  🔗 Merged: P_{} {} → P_{} {}",
+                    victim,
+                    render::label(&self.bank.decode_parts(&replacement), render::PREVIEW_LEN),
+                    survivor,
+                    render::label(&self.bank.decode(survivor), render::PREVIEW_LEN)
+                );
+            }
+            replacements.insert(victim, replacement);
+            touched.insert(survivor);
+            touched.insert(victim);
         }
 
-        removed
+        if !replacements.is_empty() {
+            let mut new_stream = Vec::with_capacity(self.token_stream.len());
+            for &token in &self.token_stream {
+                match replacements.get(&token) {
+                    Some(parts) => new_stream.extend_from_slice(parts),
+                    None => new_stream.push(token),
+                }
+            }
+            self.token_stream = new_stream;
+        }
+        replacements.len()
     }
 
     /// Decay: Heikennä kaikkien Combine-mallien strength-arvoja ajan myötä
     pub fn decay(&mut self, amount: f64) {
         for (_, pattern) in self.bank.patterns.iter_mut() {
-            if !pattern.is_literal() && !pattern.op.is_class() {
+            if !pattern.is_literal() && !pattern.op.is_class() && !pattern.pinned {
                 pattern.weaken(amount);
+                pattern.record_strength();
+            }
+        }
+    }
+
+    /// Syklin lopun totuustarkistus kaikille opituille malleille.
+    /// Palauttaa (heilahdelleet, viipymän pidättämät).
+    pub fn settle_truth(&mut self) -> (usize, usize) {
+        let (mut oscillated, mut held) = (0, 0);
+        for pattern in self.bank.patterns.values_mut() {
+            if pattern.is_literal() || pattern.op.is_class() {
+                continue;
+            }
+            match pattern.settle_truth(TRUTH_THRESHOLD, self.cycle, self.min_dwell) {
+                TruthChange::Oscillated => oscillated += 1,
+                TruthChange::Held => held += 1,
+                TruthChange::Unchanged | TruthChange::Crossed => {}
             }
         }
+        (oscillated, held)
     }
 
     /// Arvioi kuinka "tuttua" viimeksi lisätty data oli.
@@ -749,30 +1948,65 @@ impl Builder {
     /// Katso vain virran loppupäätä (viimeisintä dataa) ja laske
     /// kuinka hyvin se tiivistyi olemassa olevilla malleilla.
     pub fn assess_familiarity(&self, lookback_amount: usize) -> f64 {
-        if self.token_stream.is_empty() {
-            return 0.0;
-        }
-
         // Katso vain virran loppupäätä (viimeisintä dataa)
         let check_len = lookback_amount.min(self.token_stream.len());
         let start_idx = self.token_stream.len() - check_len;
-        let recent_slice = &self.token_stream[start_idx..];
+        self.familiarity_of(&self.token_stream[start_idx..])
+    }
 
-        // Laske "alkuperäinen" pituus tälle pätkälle
-        let original_bytes: usize = recent_slice
-            .iter()
-            .map(|&id| self.bank.pattern_length(id))
-            .sum();
+    /// Tuttuus virran lopun tokeneille, jotka kattavat vähintään
+    /// `original_bytes` alkuperäistä tavua (viimeksi syötetty pala).
+    pub fn tail_familiarity(&self, original_bytes: usize) -> f64 {
+        let mut covered = 0;
+        let mut start_idx = self.token_stream.len();
+        while start_idx > 0 && covered < original_bytes {
+            start_idx -= 1;
+            covered += self.bank.pattern_length(self.token_stream[start_idx]);
+        }
+        self.familiarity_of(&self.token_stream[start_idx..])
+    }
 
-        let current_tokens = recent_slice.len();
+    /// Tuttuus usealla horisontilla sekä ositettuna otoksena koko virrasta.
+    /// Yksi 1000 tokenin ikkuna ei näe keskipitkän aikavälin ajelehtimista.
+    pub fn familiarity_profile(&self) -> FamiliarityProfile {
+        let horizons = FAMILIARITY_HORIZONS.map(|h| self.assess_familiarity(h));
+
+        // Jokaisesta ositteesta yksi ikkuna sen alusta
+        let len = self.token_stream.len();
+        let stratum = len.div_ceil(FAMILIARITY_STRATA).max(1);
+        let mut original_bytes = 0;
+        let mut tokens = 0;
+        for start in (0..len).step_by(stratum) {
+            let end = (start + FAMILIARITY_SAMPLE_LEN.min(stratum)).min(len);
+            let window = &self.token_stream[start..end];
+            original_bytes += self.slice_original_len(window);
+            tokens += window.iter().filter(|&&t| t != BOUNDARY_TOKEN).count();
+        }
+        let sampled = if original_bytes > 0 {
+            1.0 - tokens as f64 / original_bytes as f64
+        } else {
+            0.0
+        };
+
+        FamiliarityProfile { horizons, sampled }
+    }
 
+    /// Tiivistyssuhde annetulle virran pätkälle
+    fn familiarity_of(&self, slice: &[u32]) -> f64 {
+        // Laske "alkuperäinen" pituus tälle pätkälle
+        let original_bytes = self.slice_original_len(slice);
         if original_bytes == 0 {
             return 0.0;
         }
 
         // Tiivistyssuhde kertoo tuttuuden
         // 1.0 - (tokeneja / tavuja) = kuinka paljon tiivistyi
-        1.0 - (current_tokens as f64 / original_bytes as f64)
+        let tokens = slice.iter().filter(|&&t| t != BOUNDARY_TOKEN).count();
+        1.0 - (tokens as f64 / original_bytes as f64)
+    }
+
+    fn slice_original_len(&self, slice: &[u32]) -> usize {
+        slice.iter().map(|&id| self.bank.pattern_length(id)).sum()
     }
 
     /// Pääsilmukka: Yksi sykli oppimista
@@ -781,10 +2015,23 @@ impl Builder {
     /// 1. Forget ENSIN: Tee tilaa uusille malleille
     /// 2. Explore: Etsi uusia pareja (nyt on tilaa!)
     /// 3. Collapse: Tiivistä virta
-    /// 4. Decay: Vanhenna malleja
+    /// 4. Merge: Yhdistä lähes samat mallit (harvoin)
+    /// 5. Budget: Pidä aivotiedosto kokobudjetissa (valinnainen)
+    /// 6. Decay: Vanhenna malleja
     pub fn live(&mut self) -> BuilderStats {
+        self.live_cycle(true)
+    }
+
+    /// Yksi oppimissykli, jossa explore voidaan ohittaa.
+    ///
+    /// Adaptiivinen pääsilmukka ohittaa explore-vaiheen kun data on
+    /// "tylsää" (tuttua): silloin vain tiivistetään olemassa olevilla malleilla.
+    pub fn live_cycle(&mut self, do_explore: bool) -> BuilderStats {
         self.cycle += 1;
 
+        // 0. Kanavasta saapunut data mukaan ennen mittauksia
+        self.pump();
+
         let stream_before = self.token_stream.len();
         let patterns_before = self.bank.combine_count();
 
@@ -793,7 +2040,7 @@ impl Builder {
         let forgotten = self.forget(0);
 
         // 2. Explore (nyt on tilaa uusille malleille)
-        let created = self.explore();
+        let created = if do_explore { self.explore() } else { 0 };
 
         // 3. Collapse (useita kierroksia kunnes ei enää tiivisty)
         let mut total_collapsed = 0;
@@ -805,19 +2052,34 @@ impl Builder {
             total_collapsed += collapsed;
         }
 
-        // 4. Decay
-        self.decay(DEFAULT_DECAY_RATE);
+        // 4. Lähes samat mallit yhteen (harvoin: vaatii koko pankin läpikäynnin)
+        let merged = if self.cycle.is_multiple_of(MERGE_INTERVAL_CYCLES) {
+            self.merge_near_duplicates()
+        } else {
+            0
+        };
+
+        // 5. Kokobudjetti: tallennettavat aivot pysyvät rajan alla
+        let forgotten = forgotten + self.enforce_size_budget();
+
+        // 6. Decay ja kynnyksen ylitykset (heilahtelun vaimennus)
+        self.decay(self.decay_rate);
+        let (oscillated, held) = self.settle_truth();
 
         let stream_after = self.token_stream.len();
         let patterns_after = self.bank.combine_count();
 
-        BuilderStats {
+        let stats = BuilderStats {
             cycle: self.cycle,
             stream_before,
             stream_after,
             patterns_created: created,
             patterns_collapsed: total_collapsed,
             patterns_forgotten: forgotten,
+            patterns_merged: merged,
+            patterns_oscillated: oscillated,
+            patterns_held: held,
+            collapse_rejected: self.collapse_rejected,
             patterns_total: patterns_after,
             compression_ratio: if stream_before > 0 {
                 1.0 - (stream_after as f64 / stream_before as f64)
@@ -825,11 +2087,35 @@ impl Builder {
                 0.0
             },
             patterns_before,
+            familiarity: self.familiarity_profile(),
+        };
+        if self.familiarity_trend.len() == STATUS_TREND_LEN {
+            self.familiarity_trend.pop_front();
+        }
+        self.familiarity_trend
+            .push_back(stats.familiarity.horizons[0]);
+        self.last_stats = Some(stats.clone());
+        stats
+    }
+
+    /// Kevyt tilannekuva kyselyä varten (upottava sovellus, palvelintila):
+    /// ei käy virtaa läpi, joten sitä voi kutsua joka syklin välissä.
+    pub fn status(&self) -> BuilderStatus {
+        let patterns = self.bank.combine_count();
+        let trend: Vec<f64> = self.familiarity_trend.iter().copied().collect();
+        BuilderStatus {
+            cycle: self.cycle,
+            stream_len: self.token_stream.len(),
+            patterns,
+            capacity: self.bank.capacity(),
+            occupancy: self.bank.len() as f64 / self.bank.capacity().max(1) as f64,
+            last_cycle: self.last_stats.clone(),
+            familiarity_slope: slope(&trend),
+            familiarity_trend: trend,
         }
     }
 
     /// Dekoodaa koko token-virta takaisin tavuiksi
-    #[allow(dead_code)]
     pub fn decode_stream(&self) -> Vec<u8> {
         let mut result = Vec::new();
         for &id in &self.token_stream {
@@ -851,43 +2137,181 @@ impl Builder {
             .sum()
     }
 
+    /// Etsi tavujono virrasta.
+    ///
+    /// Virta jäsentää saman tavujonon eri kohdissa eri tokeneiksi, joten
+    /// kyselyn omia tokeneita ei voi verrata virran tokeneihin: osuma voi
+    /// olla kokonaan yhden mallin sisällä ("b" tokenissa "ab") tai ylittää
+    /// tokenien rajan ("ba" tokeneissa "ab" + "ab"). Siksi jokainen token
+    /// puretaan tavuiksi ja kyselyä verrataan niihin sekä edellisten
+    /// tokenien viimeisiin tavuihin. Osumat eivät ylitä tietuerajoja; ne
+    /// palautetaan alkutavun mukaan järjestettynä.
+    pub fn find(&self, bytes: &[u8]) -> Vec<StreamPos> {
+        if bytes.is_empty() {
+            return Vec::new();
+        }
+        let mut found = Vec::new();
+        // Tokenien alkutavut (tietuerajat ovat nollan mittaisia)
+        let mut offsets = Vec::with_capacity(self.token_stream.len());
+        let mut offset = 0;
+        // Ikkuna: edellisten tokenien viimeiset (kyselyn pituus - 1) tavua
+        // ja nykyinen token; `window_start` on ikkunan alkutavu virrassa
+        let mut window: Vec<u8> = Vec::new();
+        let mut window_start = 0;
+        for &id in &self.token_stream {
+            offsets.push(offset);
+            if id == BOUNDARY_TOKEN {
+                window.clear();
+                window_start = offset;
+                continue;
+            }
+            let expanded = self.bank.decode(id);
+            let fresh = window.len();
+            offset += expanded.len();
+            window.extend(expanded);
+            // Vain osumat, jotka päättyvät tähän tokeniin (aiemmat on jo löydetty)
+            if window.len() >= bytes.len() {
+                let first = (fresh + 1).saturating_sub(bytes.len());
+                for s in first..=window.len() - bytes.len() {
+                    if window[s..].starts_with(bytes) {
+                        let byte = window_start + s;
+                        found.push(StreamPos {
+                            // Token, jonka tavuista osuma alkaa
+                            token: offsets.partition_point(|&o| o <= byte) - 1,
+                            byte,
+                        });
+                    }
+                }
+            }
+            let excess = window.len().saturating_sub(bytes.len() - 1);
+            window.drain(..excess);
+            window_start += excess;
+        }
+        found
+    }
+
     /// Tulosta hierarkia tietylle mallille
     pub fn print_hierarchy(&self, id: u32, indent: usize) {
         let prefix = "  ".repeat(indent);
 
         if let Some(pattern) = self.bank.get(id) {
             match &pattern.op {
-                Operator::Literal(byte) => {
-                    let ch = if byte.is_ascii_graphic() || *byte == b' ' {
-                        char::from(*byte).to_string()
-                    } else {
-                        format!("0x{:02X}", byte)
-                    };
-                    println!("{}P_{}: Literal('{}') [L0]", prefix, id, ch);
+                Operator::Literal(_) | Operator::Lexeme(_) => {
+                    term::emit(
+                        Level::Info,
+                        &format!("{}P_{}: {} [L0]", prefix, id, pattern.op),
+                    );
                 }
                 Operator::Combine(left, right) => {
                     let decoded = self.bank.decode(id);
-                    let decoded_str = String::from_utf8_lossy(&decoded);
-                    println!(
-                        "{}P_{}: Combine(P_{}, P_{}) = \"{}\" [L{}, str={:.2}]",
-                        prefix, id, left, right, decoded_str, pattern.complexity, pattern.strength
+                    term::emit(
+                        Level::Info,
+                        &format!(
+                            "{}P_{}: Combine(P_{}, P_{}) = {} [L{}, str={:.2}]",
+                            prefix,
+                            id,
+                            left,
+                            right,
+                            render::label(&decoded, render::PREVIEW_LEN),
+                            pattern.complexity,
+                            pattern.strength
+                        ),
                     );
                     self.print_hierarchy(*left, indent + 1);
                     self.print_hierarchy(*right, indent + 1);
                 }
                 Operator::Class(class_id) => {
-                    println!(
-                        "{}P_{}: Class(CLASS_{}) [L{}, str={:.2}]",
-                        prefix, id, class_id, pattern.complexity, pattern.strength
+                    term::emit(
+                        Level::Info,
+                        &format!(
+                            "{}P_{}: Class(CLASS_{}) [L{}, str={:.2}]",
+                            prefix, id, class_id, pattern.complexity, pattern.strength
+                        ),
                     );
                 }
+                Operator::Gap(left, right) => {
+                    term::emit(
+                        Level::Info,
+                        &format!(
+                            "{}P_{}: Gap(P_{}, _, P_{}) = {} [L{}, str={:.2}]",
+                            prefix,
+                            id,
+                            left,
+                            right,
+                            render::label(&self.bank.decode(id), render::PREVIEW_LEN),
+                            pattern.complexity,
+                            pattern.strength
+                        ),
+                    );
+                    self.print_hierarchy(*left, indent + 1);
+                    self.print_hierarchy(*right, indent + 1);
+                }
             }
         }
     }
+
+    /// Tulosta opitut hierarkkiset mallit (korkein taso ensin) ja
+    /// hierarkiaesimerkki korkeimman tason mallista
+    pub fn print_top_patterns(&self, top_n: usize) {
+        say!(
+            "\n  🧬 Opitut hierarkkiset mallit (TOP {}):",
+            "\n  🧬 Learned hierarchical patterns (TOP {}):",
+            top_n
+        );
+        let mut patterns: Vec<_> = self.bank.learned().collect();
+        patterns.sort_by(|a, b| {
+            // Lajittele: ensin tason mukaan (korkein ensin), sitten käytön mukaan
+            let level_cmp = b.1.complexity.cmp(&a.1.complexity);
+            if level_cmp == std::cmp::Ordering::Equal {
+                b.1.usage_count.cmp(&a.1.usage_count)
+            } else {
+                level_cmp
+            }
+        });
+
+        for (id, pattern) in patterns.iter().take(top_n) {
+            let decoded = self.bank.decode(**id);
+            say!(
+                "     P_{}: {} [L{}, käyttö {}, str {:.2}, refs {}, syntyi {}, säästi {}]",
+                "     P_{}: {} [L{}, usage {}, str {:.2}, refs {}, born {}, saved {}]",
+                id,
+                render::label(&decoded, render::PREVIEW_LEN),
+                pattern.complexity,
+                pattern.usage_count,
+                pattern.strength,
+                pattern.ref_count,
+                pattern.birth_cycle,
+                pattern.tokens_saved
+            );
+        }
+
+        // Tulosta hierarkiaesimerkki korkeimman tason mallista
+        if let Some((id, _)) = patterns.first() {
+            say!(
+                "\n  🌳 Hierarkiaesimerkki (P_{}):",
+                "\n  🌳 Hierarchy example (P_{}):",
+                id
+            );
+            self.print_hierarchy(**id, 2);
+        }
+    }
+}
+
+/// Yhden hierarkiatason yhteenveto (`PatternBank::level_stats`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct LevelStats {
+    /// Opittujen mallien määrä tasolla
+    pub count: usize,
+    /// Keskimääräinen vahvuus
+    pub mean_strength: f64,
+    /// Tason mallien elinaikaiset säästöt (tokeneina)
+    pub tokens_saved: u64,
+    /// Tason osuus kaikista säästöistä (0.0 - 1.0)
+    pub coverage: f64,
 }
 
 /// Tilastot yhdestä build-syklistä
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BuilderStats {
     pub cycle: u64,
     pub stream_before: usize,
@@ -895,16 +2319,86 @@ pub struct BuilderStats {
     pub patterns_created: usize,
     pub patterns_collapsed: usize,
     pub patterns_forgotten: usize,
+    /// Lähes samoina yhdistetyt mallit
+    pub patterns_merged: usize,
+    /// Mallit, joiden suunta totuuskynnykseen nähden kääntyi tällä syklillä
+    #[serde(default)]
+    pub patterns_oscillated: usize,
+    /// Mallit, joiden puolen vaihdon vähimmäisviipymä esti
+    #[serde(default)]
+    pub patterns_held: usize,
+    /// Virran parit, joiden malli jäi tiivistyskynnyksen alle
+    #[serde(default)]
+    pub collapse_rejected: usize,
     pub patterns_total: usize,
     pub compression_ratio: f64,
-    #[allow(dead_code)]
     pub patterns_before: usize,
+    /// Tuttuus syklin jälkeen eri horisonteilla
+    pub familiarity: FamiliarityProfile,
+}
+
+/// Tuttuus (0.0 - 1.0) usealla mittakaavalla
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct FamiliarityProfile {
+    /// Viimeiset 1k / 10k / 100k tokenia (FAMILIARITY_HORIZONS)
+    pub horizons: [f64; 3],
+    /// Ositettu otos koko virrasta
+    pub sampled: f64,
+}
+
+/// Hakuosuma token-virrassa (ks. Builder::find)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StreamPos {
+    /// Virran token, jonka tavuista osuma alkaa
+    pub token: usize,
+    /// Osuman alku dekoodatussa virrassa (syötetyt tavut) tavuina
+    pub byte: usize,
+}
+
+/// Builderin tila kyselyhetkellä (ks. Builder::status)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BuilderStatus {
+    pub cycle: u64,
+    pub stream_len: usize,
+    /// Opitut mallit (ei literaalit eikä luokat)
+    pub patterns: usize,
+    pub capacity: usize,
+    /// Pankin täyttöaste (kaikki mallit / kapasiteetti)
+    pub occupancy: f64,
+    /// Viimeisimmän syklin tilastot (None ennen ensimmäistä sykliä)
+    pub last_cycle: Option<BuilderStats>,
+    /// Lyhyen horisontin tuttuus viimeisiltä sykleiltä (vanhin ensin)
+    pub familiarity_trend: Vec<f64>,
+    /// Trendin kulmakerroin (muutos sykliä kohden, pienimmän neliösumman sovitus)
+    pub familiarity_slope: f64,
+}
+
+/// Pienimmän neliösumman suoran kulmakerroin tasavälisille arvoille
+fn slope(values: &[f64]) -> f64 {
+    let n = values.len() as f64;
+    if values.len() < 2 {
+        return 0.0;
+    }
+    let mean_x = (n - 1.0) / 2.0;
+    let mean_y = values.iter().sum::<f64>() / n;
+    let (mut num, mut den) = (0.0, 0.0);
+    for (i, &y) in values.iter().enumerate() {
+        let dx = i as f64 - mean_x;
+        num += dx * (y - mean_y);
+        den += dx * dx;
+    }
+    num / den
 }
 
 impl BuilderStats {
     pub fn print(&self) {
-        println!(
+        if term::is_json() {
+            term::event(Level::Info, "cycle_stats", json!(self));
+            return;
+        }
+        say!(
             "  📊 Sykli {}: virta {} → {} ({:.1}% tiivistys), malleja {} (+{} -{}) ",
+            "  📊 Cycle {}: stream {} → {} ({:.1}% compression), patterns {} (+{} -{}) ",
             self.cycle,
             self.stream_before,
             self.stream_after,
@@ -913,12 +2407,22 @@ impl BuilderStats {
             self.patterns_created,
             self.patterns_forgotten
         );
+        let [short, medium, long] = self.familiarity.horizons;
+        say!(
+            "     Tuttuus: 1k {:.1}%, 10k {:.1}%, 100k {:.1}%, otos {:.1}%",
+            "     Familiarity: 1k {:.1}%, 10k {:.1}%, 100k {:.1}%, sampled {:.1}%",
+            short * 100.0,
+            medium * 100.0,
+            long * 100.0,
+            self.familiarity.sampled * 100.0
+        );
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::pattern::STRENGTH_HISTORY_LEN;
 
     #[test]
     fn test_pattern_bank_literals() {
@@ -957,6 +2461,39 @@ mod tests {
         assert_eq!(ab_id2, Some(ab_id)); // Palauttaa olemassa olevan
     }
 
+    #[test]
+    fn test_level_queries_and_stats() {
+        let mut bank = PatternBank::new(100);
+        let ab = bank.create_combine(97, 98, 0).unwrap();
+        let cd = bank.create_combine(99, 100, 0).unwrap();
+        let abcd = bank.create_combine(ab, cd, 0).unwrap();
+        for (id, strength, saved) in [(ab, 0.8, 30), (cd, 0.4, 10), (abcd, 0.5, 60)] {
+            let p = bank.get_mut(id).unwrap();
+            p.strength = strength;
+            p.tokens_saved = saved;
+        }
+
+        let mut level1: Vec<u32> = bank.patterns_at_level(1).map(|(&id, _)| id).collect();
+        level1.sort_unstable();
+        assert_eq!(level1, [ab, cd]);
+        assert_eq!(bank.patterns_at_level(0).count(), 0);
+        assert_eq!(bank.max_level(), 2);
+
+        let stats = bank.level_stats();
+        assert_eq!(stats.keys().copied().collect::<Vec<_>>(), [1, 2]);
+        assert_eq!(stats[&1].count, 2);
+        assert!((stats[&1].mean_strength - 0.6).abs() < 1e-9);
+        assert_eq!((stats[&1].coverage, stats[&2].coverage), (0.4, 0.6));
+
+        // Heikot mallit eivät kuulu eniten käytettyihin
+        bank.get_mut(abcd).unwrap().usage_count = 5;
+        bank.get_mut(ab).unwrap().usage_count = 2;
+        bank.get_mut(cd).unwrap().usage_count = 9;
+        let used: Vec<u32> = bank.most_used(10).iter().map(|&(id, _)| id).collect();
+        assert_eq!(used, [abcd, ab]);
+        assert_eq!(bank.most_used(1).len(), 1);
+    }
+
     #[test]
     fn test_builder_tokenize() {
         let mut builder = Builder::new(100);
@@ -972,6 +2509,125 @@ mod tests {
         assert_eq!(builder.decode_stream(), b"abc");
     }
 
+    #[test]
+    fn test_pattern_lifecycle_metadata() {
+        let mut builder = Builder::new(100);
+        builder.tokenize(&b"abab".repeat(20));
+        for _ in 0..(STRENGTH_HISTORY_LEN + 2) {
+            builder.live();
+        }
+
+        let ab = builder.bank.get_pair_id(97, 98).expect("pari 'ab' opittu");
+        let pattern = builder.bank.get(ab).unwrap();
+        assert_eq!(pattern.birth_cycle, 1);
+        assert!(pattern.tokens_saved >= 40);
+        assert_eq!(pattern.strength_history.len(), STRENGTH_HISTORY_LEN);
+        assert_eq!(pattern.strength_history.back(), Some(&pattern.strength));
+
+        // Hiljainen jakso: säästänyt malli ei ole heikoin, vaikka vahvuus on sama
+        let fresh = builder.bank.create_combine(120, 121, 0).unwrap();
+        let strength = builder.bank.get(ab).unwrap().strength;
+        builder.bank.get_mut(fresh).unwrap().strength = strength;
+        let order = builder.bank.get_weakest(builder.bank.len());
+        let rank = |id| order.iter().position(|&x| x == id).unwrap();
+        assert!(rank(fresh) < rank(ab));
+    }
+
+    #[test]
+    fn test_forget_takes_dependents_along() {
+        let mut builder = Builder::new(100);
+        let bank = &mut builder.bank;
+        let ab = bank.create_combine(97, 98, 0).unwrap();
+        let abc = bank.create_combine(ab, 99, 0).unwrap();
+        let abcd = bank.create_combine(abc, 100, 0).unwrap();
+        let xy = bank.create_combine(120, 121, 0).unwrap();
+        for (id, strength) in [(ab, 0.1), (abc, 0.2), (abcd, 0.3), (xy, 0.25)] {
+            bank.get_mut(id).unwrap().strength = strength;
+        }
+        builder.token_stream = vec![abcd, abc, xy, ab, 101];
+        let original = builder.bank.decode_parts(&builder.token_stream);
+
+        // Kiinnitetty "abcd" suojaa kaikki osansa
+        builder.bank.get_mut(abcd).unwrap().pinned = true;
+        assert_eq!(builder.bank.get_weakest(10), [xy]);
+        builder.bank.get_mut(abcd).unwrap().pinned = false;
+
+        // "ab" on yksinään heikoin, mutta sen varaan rakentunut "abcd" ei
+        assert_eq!(builder.forget(1), 1);
+        assert!(builder.bank.get(xy).is_none());
+        assert!(builder.bank.get(ab).is_some());
+
+        // Koko ketju lähtee kerralla; virta purkautuu jäljelle jääviin osiin
+        assert_eq!(builder.forget(1), 3);
+        for id in [ab, abc, abcd] {
+            assert!(builder.bank.get(id).is_none());
+        }
+        assert!(
+            builder
+                .token_stream
+                .iter()
+                .all(|&t| builder.bank.get(t).is_some())
+        );
+        assert_eq!(builder.bank.decode_parts(&builder.token_stream), original);
+    }
+
+    #[test]
+    fn test_status_snapshot() {
+        let mut builder = Builder::new(100);
+        assert!(builder.status().last_cycle.is_none());
+        builder.tokenize(&b"abcabc".repeat(20));
+        for _ in 0..3 {
+            builder.live();
+        }
+
+        let status = builder.status();
+        assert_eq!(status.cycle, 3);
+        assert_eq!(status.stream_len, builder.token_stream.len());
+        assert_eq!(status.last_cycle.as_ref().unwrap().cycle, 3);
+        assert_eq!(status.familiarity_trend.len(), 3);
+        assert!(status.patterns > 0 && status.occupancy > 0.0);
+
+        let json = serde_json::to_string(&status).unwrap();
+        let back: BuilderStatus = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.familiarity_trend, status.familiarity_trend);
+        assert!((slope(&[0.0, 0.1, 0.2]) - 0.1).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_familiarity_profile_horizons() {
+        let mut builder = Builder::new(100);
+        builder.tokenize(&b"funktio on joka ".repeat(50));
+        for _ in 0..5 {
+            builder.live();
+        }
+
+        let profile = builder.familiarity_profile();
+        // Lyhyt virta: kaikki horisontit kattavat koko virran
+        assert_eq!(profile.horizons[0], profile.horizons[2]);
+        assert!(profile.horizons[0] > 0.5);
+        assert!(profile.sampled > 0.5);
+    }
+
+    #[test]
+    fn test_source_tokenization_lexemes() {
+        let mut builder = Builder::new(100);
+        builder.bank.set_tokenization(Tokenization::Source);
+
+        let src = b"self.len() + self.len()";
+        builder.tokenize(src);
+
+        // self . len () ' ' + ' ' self . len ()
+        assert_eq!(builder.token_stream.len(), 11);
+        assert_eq!(builder.token_stream[0], builder.token_stream[7]);
+        assert_eq!(builder.bank.lexeme_count(), 3); // self, len, ()
+        assert_eq!(builder.decode_stream(), src);
+        assert_eq!(builder.original_len(), src.len());
+
+        // Jäädytetty koodaus käyttää samoja leksemejä
+        let encoded = builder.bank.encode(b"self.len()");
+        assert_eq!(encoded[0], builder.token_stream[0]);
+    }
+
     #[test]
     fn test_builder_explore_and_collapse() {
         let mut builder = Builder::new(100);
@@ -1000,6 +2656,298 @@ mod tests {
         assert_eq!(builder.decode_stream(), b"abab");
     }
 
+    #[test]
+    fn test_find_through_collapsed_stream() {
+        let mut builder = Builder::new(100);
+        builder.tokenize(b"abcabc");
+        builder.push_boundary();
+        builder.tokenize(b"xabc");
+        for (left, right) in [(b'a', b'b'), (b'b', b'c')] {
+            let id = builder
+                .bank
+                .create_combine(left as u32, right as u32, 0)
+                .unwrap();
+            builder.bank.get_mut(id).unwrap().strength = 0.9;
+        }
+        builder.collapse();
+        assert!(builder.stream_len() < 11);
+
+        // "bcab" koodautuu tokeneiksi "bc" + "ab", mutta virrassa "bc" on
+        // osa tokeneita "ab" + "c": vertailu tehdään tavuina
+        let stream = builder.decode_stream();
+        let hits = builder.find(b"bcab");
+        assert_eq!(hits.iter().map(|p| p.byte).collect::<Vec<_>>(), vec![1]);
+        assert_eq!(&stream[1..5], b"bcab");
+        assert_eq!(hits[0].token, 0);
+        // Sama tavujono tietuerajan yli ei kelpaa ("c" | "xa")
+        assert!(builder.find(b"cxa").is_empty());
+        let abc: Vec<usize> = builder.find(b"abc").iter().map(|p| p.byte).collect();
+        assert_eq!(abc, vec![0, 3, 7]);
+        assert!(builder.find(b"").is_empty());
+    }
+
+    #[test]
+    fn test_merge_never_leaves_removed_ids() {
+        // "hello " < "hello" < "hallo" ID:iden mukaan: ensimmäisen parin
+        // voittaja "hello" olisi toisen parin häviäjä
+        let build = |gap: bool| {
+            let mut builder = Builder::new(100);
+            let lit = |builder: &Builder, byte: u8| builder.bank.literal_id(byte);
+            let chain = |builder: &mut Builder, text: &[u8]| {
+                let mut id = lit(builder, text[0]);
+                for &byte in &text[1..] {
+                    let right = lit(builder, byte);
+                    id = builder.bank.create_combine(id, right, 0).unwrap();
+                }
+                id
+            };
+            let hell = chain(&mut builder, b"hell");
+            let o_space = chain(&mut builder, b"o ");
+            let spaced = builder.bank.create_combine(hell, o_space, 0).unwrap();
+            let o = lit(&builder, b'o');
+            let hello = builder.bank.create_combine(hell, o, 0).unwrap();
+            let hall = chain(&mut builder, b"hall");
+            let hallo = builder.bank.create_combine(hall, o, 0).unwrap();
+            for (id, strength) in [(spaced, 0.3), (hello, 0.6), (hallo, 0.9)] {
+                builder.bank.get_mut(id).unwrap().strength = strength;
+            }
+            // Aukon reuna on viitattu malli, vaikkei se ole minkään osana
+            if gap {
+                let x = lit(&builder, b'x');
+                builder.bank.create_gap(spaced, x, 0).unwrap();
+            }
+            let space = lit(&builder, b' ');
+            builder.token_stream = vec![spaced, space, hello, space, hallo];
+            (builder, [spaced, hello, hallo])
+        };
+
+        let (mut builder, [spaced, hello, hallo]) = build(false);
+        let text = builder.decode_stream();
+        assert_eq!(builder.merge_near_duplicates(), 1);
+        assert!(builder.bank.get(spaced).is_none());
+        assert!(builder.bank.get(hello).is_some() && builder.bank.get(hallo).is_some());
+        assert!(
+            builder
+                .token_stream
+                .iter()
+                .all(|&id| builder.bank.get(id).is_some())
+        );
+        assert_eq!(builder.decode_stream(), text);
+
+        let (mut builder, [spaced, hello, _]) = build(true);
+        assert_eq!(builder.merge_near_duplicates(), 1);
+        assert!(builder.bank.get(spaced).is_some());
+        assert!(builder.bank.get(hello).is_none());
+        assert!(
+            builder
+                .token_stream
+                .iter()
+                .all(|&id| builder.bank.get(id).is_some())
+        );
+        assert_eq!(builder.decode_stream(), text);
+    }
+
+    #[test]
+    fn test_find_inside_and_across_tokens() {
+        let mut builder = Builder::new(100);
+        builder.tokenize(b"abab xab");
+        let ab = builder
+            .bank
+            .create_combine(b'a' as u32, b'b' as u32, 0)
+            .unwrap();
+        builder.bank.get_mut(ab).unwrap().strength = 0.9;
+        builder.collapse();
+        assert_eq!(
+            builder.token_stream,
+            vec![ab, ab, b' ' as u32, b'x' as u32, ab]
+        );
+
+        // Kokonaan tokenin sisällä: "b" ei ole virrassa yhtenäkään tokenina
+        let hits = builder.find(b"b");
+        assert_eq!(
+            hits.iter().map(|p| p.byte).collect::<Vec<_>>(),
+            vec![1, 3, 7]
+        );
+        assert_eq!(
+            hits.iter().map(|p| p.token).collect::<Vec<_>>(),
+            vec![0, 1, 4]
+        );
+        // Tokenien rajan yli: "ba" = "ab" + "ab" keskeltä
+        assert_eq!(builder.find(b"ba"), vec![StreamPos { token: 0, byte: 1 }]);
+        assert_eq!(builder.find(b"b xa"), vec![StreamPos { token: 1, byte: 3 }]);
+        assert_eq!(builder.find(b"abab xab").len(), 1);
+        assert!(builder.find(b"abab xabx").is_empty());
+    }
+
+    #[test]
+    fn test_level_thresholds_in_explore() {
+        let mut builder = Builder::new(100);
+        builder.level_thresholds = vec![3, 1];
+        assert_eq!(builder.threshold_for_level(1), 3);
+        assert_eq!(builder.threshold_for_level(7), 1);
+
+        // Kaksi esiintymää riittää tason 2 parille, ei literaaliparille
+        let ab = builder.bank.create_combine(97, 98, 0).unwrap();
+        let [c, x, y] = [b'c', b'x', b'y'].map(|b| b as u32);
+        builder.token_stream = vec![ab, c, ab, c, x, y, x, y];
+        builder.explore();
+        assert!(builder.bank.has_pair(ab, c));
+        assert!(!builder.bank.has_pair(x, y));
+
+        // Sama parien tilasto: järjestys esiintymien ja kynnyksen suhteen mukaan
+        let mut stats = PairStats::new();
+        for (pair, count) in [((1, 2), 6), ((3, 4), 3)] {
+            for _ in 0..count {
+                stats.record(pair.0, pair.1);
+            }
+        }
+        let top = stats.get_top_pairs_by(|l, _| if l == 1 { 3 } else { 1 }, 2);
+        assert_eq!(top, [((3, 4), 3), ((1, 2), 6)]);
+    }
+
+    #[test]
+    fn test_collapse_thresholds_by_level() {
+        let mut builder = Builder::new(100);
+        let ab = builder.bank.create_combine(97, 98, 0).unwrap();
+        let abc = builder.bank.create_combine(ab, 99, 0).unwrap();
+        for id in [ab, abc] {
+            builder.bank.get_mut(id).unwrap().strength = 0.6;
+        }
+        let [a, b, c] = [97, 98, 99];
+        let stream = vec![a, b, c, a, b, c];
+
+        // Taso 2 vaatii enemmän: "ab" tiivistyy, "abc" hylätään
+        builder.collapse_thresholds = vec![0.5, 0.7];
+        assert_eq!(builder.collapse_threshold(9), 0.7);
+        builder.token_stream = stream.clone();
+        while builder.collapse() > 0 {}
+        assert_eq!(builder.token_stream, [ab, c, ab, c]);
+        assert_eq!(builder.collapse_rejected, 2);
+
+        builder.collapse_thresholds.clear();
+        builder.token_stream = stream;
+        while builder.collapse() > 0 {}
+        assert_eq!(builder.token_stream, [abc, abc]);
+        assert_eq!(builder.collapse_rejected, 0);
+    }
+
+    #[test]
+    fn test_size_budget_evicts_least_valuable() {
+        let mut builder = Builder::new(1000);
+        builder.tokenize(&b"kissa istuu puussa, koira juoksee pihalla. ".repeat(30));
+        for _ in 0..10 {
+            builder.live();
+        }
+        let text = builder.decode_stream();
+        let size = builder.bank.estimated_size();
+        let mut file = Vec::new();
+        serde_json::to_writer_pretty(&mut file, &builder.bank).unwrap();
+        assert_eq!(size, file.len());
+
+        // Käytetyin malli on arvokkaampi kuin käyttämätön
+        let ranked = builder.bank.least_valuable();
+        let most_saved = builder
+            .bank
+            .learned()
+            .max_by_key(|(id, p)| (p.tokens_saved, Reverse(**id)))
+            .map(|(&id, _)| id)
+            .unwrap();
+        let rank = |id| ranked.iter().position(|&r| r == id).unwrap();
+        let unused = ranked[0];
+        assert!(rank(most_saved) > rank(unused));
+
+        // Budjetti ylittyy: poistetaan, kunnes arvio mahtuu, virta säilyy.
+        // Literaalit vievät suuren osan: budjetti puolittaa opitut mallit.
+        let base = Builder::new(1000).bank.estimated_size();
+        let budget = base + (size - base) / 2;
+        let patterns = builder.bank.combine_count();
+        builder.size_budget = Some(budget);
+        let removed = builder.enforce_size_budget();
+        assert!(removed > 0 && removed < patterns);
+        assert!(builder.bank.estimated_size() <= budget);
+        assert_eq!(builder.decode_stream(), text);
+        builder.size_budget = Some(size);
+        assert_eq!(builder.enforce_size_budget(), 0);
+    }
+
+    #[test]
+    fn test_gap_template_learned_and_bound() {
+        let mut builder = Builder::new(100);
+        let mut chain = |text: &[u8]| {
+            let mut acc = text[0] as u32;
+            for &b in &text[1..] {
+                acc = builder.bank.create_combine(acc, b as u32, 0).unwrap();
+                builder.bank.get_mut(acc).unwrap().strength = 1.0;
+            }
+            acc
+        };
+        let error = chain(b"error: ");
+        let at_line = chain(b" at line");
+
+        // Keskiosa vaihtelee joka kerta: yksikään vierekkäinen pari ei toistu
+        let mut text = Vec::new();
+        for middle in [b'1', b'x', b'2', b'y'] {
+            builder
+                .token_stream
+                .extend([error, middle as u32, at_line, BOUNDARY_TOKEN]);
+            text.extend_from_slice(b"error: ");
+            text.push(middle);
+            text.extend_from_slice(b" at line");
+        }
+
+        assert!(builder.explore() > 0);
+        let gap = builder.bank.get_gap_id(error, at_line).unwrap();
+        assert_eq!(builder.bank.decode(gap), b"error: [_] at line");
+
+        // Collapse sitoo aukon: jokainen tietue on yksi token
+        assert_eq!(builder.collapse(), 8);
+        let tokens = builder
+            .token_stream
+            .iter()
+            .filter(|&&t| t != BOUNDARY_TOKEN);
+        assert_eq!(tokens.count(), 4);
+        assert_eq!(builder.decode_stream(), text);
+
+        // Pohja säilyy kieliopin kautta
+        let exported = crate::grammar::to_grammar(&builder.bank, 0.5, crate::grammar::Naming::Ids);
+        let mut fresh = PatternBank::new(100);
+        crate::grammar::import(&mut fresh, &exported, 0.8).unwrap();
+        let error2 = fresh.encode(b"error: ");
+        let at_line2 = fresh.encode(b" at line");
+        assert!(fresh.get_gap_id(error2[0], at_line2[0]).is_some());
+    }
+
+    #[test]
+    fn test_channel_pumped_at_cycle_start() {
+        let (tx, rx) = std::sync::mpsc::channel();
+        let mut builder = Builder::new(100);
+        builder.attach_channel(rx);
+
+        let sender = std::thread::spawn(move || {
+            for _ in 0..4 {
+                tx.send(b"abab ".to_vec()).unwrap();
+            }
+        });
+        sender.join().unwrap();
+
+        // Kaikki jonossa oleva tulee mukaan, sulkeutunut kanava irrotetaan
+        let stats = builder.live();
+        assert_eq!(stats.stream_before, 20 + 1); // + tietueraja
+        assert!(!builder.has_channel());
+        assert_eq!(builder.token_stream.last(), Some(&BOUNDARY_TOKEN));
+        assert_eq!(builder.decode_stream(), b"abab ".repeat(4));
+        assert_eq!(builder.pump(), 0);
+
+        // Vastapaine: pitkä virta hidastaa syöttöä, mutta ei pysäytä sitä
+        assert_eq!(builder.capacity_hint(), 1.0);
+        builder.token_stream = vec![b'a' as u32; STREAM_SOFT_LIMIT * 3 / 2];
+        assert!((builder.capacity_hint() - 0.5).abs() < 1e-9);
+        builder
+            .token_stream
+            .resize(STREAM_SOFT_LIMIT * 3, b'a' as u32);
+        assert_eq!(builder.capacity_hint(), MIN_CAPACITY_HINT);
+    }
+
     #[test]
     fn test_builder_hierarchical() {
         let mut builder = Builder::new(100);
@@ -1023,4 +2971,21 @@ mod tests {
         // Decode pitäisi silti palauttaa alkuperäinen
         assert_eq!(builder.decode_stream(), b"aabbaabbaabb");
     }
+
+    #[test]
+    fn test_identical_runs_serialize_identically() {
+        // Sama data kahdesti: sama oppiminen ja tavulleen sama aivotiedosto,
+        // vaikka HashMapien iterointijärjestys vaihtelee
+        let run = || {
+            let mut builder = Builder::new(50);
+            builder.tokenize(&b"kissa istuu matolla, koira istuu kissan vieressa. ".repeat(10));
+            for _ in 0..6 {
+                builder.live();
+            }
+            serde_json::to_string(&builder.bank).unwrap()
+        };
+        let first = run();
+        assert!(first.contains("\"next_id\""));
+        assert_eq!(first, run());
+    }
 }
@@ -0,0 +1,306 @@
+// src/checkpoint.rs
+//
+// Checkpoint: Säännölliset välitallennukset pitkille ajoille.
+//
+// Ilman välitallennuksia pitkä ajo on kaikki tai ei mitään: kaatuminen
+// syklillä 9 999 hukkaa koko oppimisen. Checkpointer tallentaa aivot ja
+// istunnon N syklin tai M minuutin välein aikaleimattuihin tiedostoihin
+// ja karsii vanhat: viimeiset K säilytetään, ja lisäksi joka kymmenes
+// pysyvästi, jotta pitkän ajon kehitystä voi tutkia jälkikäteen.
+//
+// Tiedostonimi: ckpt-<järjestysnumero>-c<sykli>-<unix-aika>.{session,brain.json}
+// Kansio on oletuksena profiilin kansiossa aivojen, kirjanmerkin ja CSV:n
+// vieressä (`profiles/<nimi>/checkpoints`, ilman profiilia `checkpoints`).
+//
+// Automaattitallennus (Autosave) on kevyempi: se kirjoittaa ajon omat
+// aivot ja kirjanmerkin (brain.json, feeder_state.json) N syklin tai N
+// syötetyn tavun välein. Aivot, kirjanmerkki ja istunto kirjoitetaan
+// atomisesti (ks. atomic.rs), joten kesken jäänyt tallennus ei riko vanhaa.
+
+use crate::engine::Engine;
+use crate::session::Session;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+const PREFIX: &str = "ckpt-";
+const SESSION_EXT: &str = ".session";
+const BRAIN_EXT: &str = ".brain.json";
+/// Joka n:s välitallennus säilytetään aina
+const PERMANENT_EVERY: u64 = 10;
+/// Välitallennusten kansio profiilin kansiossa
+pub const DEFAULT_DIR: &str = "checkpoints";
+
+/// Milloin ja minne välitallennetaan
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CheckpointPolicy {
+    /// Tallenna N syklin välein
+    pub every_cycles: Option<usize>,
+    /// Tallenna M minuutin välein
+    pub every_minutes: Option<f64>,
+    /// Montako viimeisintä säilytetään
+    pub keep_last: usize,
+    pub dir: String,
+}
+
+impl Default for CheckpointPolicy {
+    fn default() -> Self {
+        CheckpointPolicy {
+            every_cycles: None,
+            every_minutes: None,
+            keep_last: 5,
+            dir: DEFAULT_DIR.to_string(),
+        }
+    }
+}
+
+impl CheckpointPolicy {
+    pub fn is_enabled(&self) -> bool {
+        self.every_cycles.is_some() || self.every_minutes.is_some()
+    }
+}
+
+/// Ajon aivojen ja kirjanmerkin automaattitallennuksen väli
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AutosavePolicy {
+    /// Tallenna N syklin välein
+    pub every_cycles: Option<usize>,
+    /// Tallenna N syötetyn tavun välein
+    pub every_bytes: Option<usize>,
+}
+
+impl AutosavePolicy {
+    pub fn is_enabled(&self) -> bool {
+        self.every_cycles.is_some() || self.every_bytes.is_some()
+    }
+}
+
+/// Automaattitallennuksen ajastus
+pub struct Autosave {
+    policy: AutosavePolicy,
+    /// Syötetyt tavut edellisellä tallennuksella
+    saved_bytes: usize,
+}
+
+impl Autosave {
+    /// `fed` = jo syötetyt tavut (jatketussa ajossa ei tallenneta heti)
+    pub fn new(policy: AutosavePolicy, fed: usize) -> Self {
+        Autosave {
+            policy,
+            saved_bytes: fed,
+        }
+    }
+
+    /// Onko tallennus ajankohtainen; tavurajalla aloitetaan uusi väli
+    pub fn is_due(&mut self, cycle: usize, fed: usize) -> bool {
+        let by_cycles = self
+            .policy
+            .every_cycles
+            .is_some_and(|n| n > 0 && cycle.is_multiple_of(n));
+        let by_bytes = self
+            .policy
+            .every_bytes
+            .is_some_and(|n| n > 0 && fed.saturating_sub(self.saved_bytes) >= n);
+        if by_cycles || by_bytes {
+            self.saved_bytes = fed;
+        }
+        by_cycles || by_bytes
+    }
+}
+
+/// Välitallennusten ajastus ja kierrätys
+pub struct Checkpointer {
+    policy: CheckpointPolicy,
+    next_seq: u64,
+    last_save: Instant,
+}
+
+impl Checkpointer {
+    /// Luo checkpointer; järjestysnumerointi jatkuu kansion olemassa olevista
+    pub fn new(policy: CheckpointPolicy) -> io::Result<Self> {
+        fs::create_dir_all(&policy.dir)?;
+        let next_seq = list_checkpoints(Path::new(&policy.dir))?
+            .last()
+            .map_or(1, |(seq, _)| seq + 1);
+        Ok(Checkpointer {
+            policy,
+            next_seq,
+            last_save: Instant::now(),
+        })
+    }
+
+    /// Onko välitallennus ajankohtainen tällä syklillä
+    pub fn is_due(&self, cycle: usize) -> bool {
+        let by_cycles = self
+            .policy
+            .every_cycles
+            .is_some_and(|n| n > 0 && cycle.is_multiple_of(n));
+        let by_time = self
+            .policy
+            .every_minutes
+            .is_some_and(|m| self.last_save.elapsed().as_secs_f64() >= m * 60.0);
+        by_cycles || by_time
+    }
+
+    /// Tallenna istunto ja aivot, karsi vanhat. Palauttaa istuntotiedoston polun.
+    pub fn save(
+        &mut self,
+        cycle: usize,
+        session: &Session,
+        engine: &dyn Engine,
+    ) -> io::Result<PathBuf> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        let stem = format!("{}{:06}-c{}-{}", PREFIX, self.next_seq, cycle, timestamp);
+        let dir = Path::new(&self.policy.dir);
+
+        let session_path = dir.join(format!("{}{}", stem, SESSION_EXT));
+        engine.save(&dir.join(format!("{}{}", stem, BRAIN_EXT)))?;
+        session.save(&session_path)?;
+
+        self.next_seq += 1;
+        self.last_save = Instant::now();
+        self.rotate()?;
+        Ok(session_path)
+    }
+
+    /// Poista välitallennukset, joita säilytyssääntö ei suojaa
+    fn rotate(&self) -> io::Result<()> {
+        let dir = Path::new(&self.policy.dir);
+        let checkpoints = list_checkpoints(dir)?;
+        let seqs: Vec<u64> = checkpoints.iter().map(|(seq, _)| *seq).collect();
+        let keep = retained(&seqs, self.policy.keep_last);
+
+        for ((_, stem), keep) in checkpoints.iter().zip(keep) {
+            if !keep {
+                for ext in [SESSION_EXT, BRAIN_EXT] {
+                    let path = dir.join(format!("{}{}", stem, ext));
+                    if path.exists() {
+                        fs::remove_file(path)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Kansion välitallennukset (järjestysnumero, tiedostonimen runko) nousevasti
+fn list_checkpoints(dir: &Path) -> io::Result<Vec<(u64, String)>> {
+    let mut checkpoints = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let name = entry?.file_name().to_string_lossy().into_owned();
+        let Some(stem) = name.strip_suffix(SESSION_EXT) else {
+            continue;
+        };
+        let seq = stem
+            .strip_prefix(PREFIX)
+            .and_then(|rest| rest.split('-').next())
+            .and_then(|s| s.parse().ok());
+        if let Some(seq) = seq {
+            checkpoints.push((seq, stem.to_string()));
+        }
+    }
+    checkpoints.sort();
+    Ok(checkpoints)
+}
+
+/// Säilytyssääntö: viimeiset `keep_last` sekä joka kymmenes.
+/// `seqs` on nousevassa järjestyksessä.
+fn retained(seqs: &[u64], keep_last: usize) -> Vec<bool> {
+    let recent_from = seqs.len().saturating_sub(keep_last);
+    seqs.iter()
+        .enumerate()
+        .map(|(i, &seq)| i >= recent_from || seq.is_multiple_of(PERMANENT_EVERY))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retention_keeps_recent_and_every_tenth() {
+        let seqs: Vec<u64> = (1..=25).collect();
+        let keep = retained(&seqs, 3);
+        let kept: Vec<u64> = seqs
+            .iter()
+            .zip(keep)
+            .filter(|(_, k)| *k)
+            .map(|(s, _)| *s)
+            .collect();
+        assert_eq!(kept, [10, 20, 23, 24, 25]);
+    }
+
+    #[test]
+    fn test_rotation_on_disk() {
+        let dir = std::env::temp_dir().join(format!("petri_ckpt_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let keep_last = 3;
+        let policy = CheckpointPolicy {
+            every_cycles: Some(1),
+            keep_last,
+            dir: dir.display().to_string(),
+            ..CheckpointPolicy::default()
+        };
+
+        // Kuten save: kirjoita tiedostoparit ja karsi jokaisen jälkeen
+        let mut checkpointer = Checkpointer::new(policy.clone()).unwrap();
+        let total = keep_last as u64 + 12;
+        for cycle in 1..=total {
+            let stem = format!("{}{:06}-c{}-0", PREFIX, checkpointer.next_seq, cycle);
+            for ext in [SESSION_EXT, BRAIN_EXT] {
+                fs::write(dir.join(format!("{}{}", stem, ext)), b"{}").unwrap();
+            }
+            checkpointer.next_seq += 1;
+            checkpointer.rotate().unwrap();
+        }
+
+        let mut files: Vec<String> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        files.sort();
+        let expected: Vec<String> = [10, 13, 14, 15]
+            .iter()
+            .flat_map(|&seq| {
+                let stem = format!("{}{:06}-c{}-0", PREFIX, seq, seq);
+                [
+                    format!("{}{}", stem, BRAIN_EXT),
+                    format!("{}{}", stem, SESSION_EXT),
+                ]
+            })
+            .collect();
+        assert_eq!(files, expected);
+
+        // Uusi ajo jatkaa numerointia kansion viimeisestä
+        assert_eq!(Checkpointer::new(policy).unwrap().next_seq, total + 1);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_autosave_interval() {
+        let policy = AutosavePolicy {
+            every_cycles: Some(10),
+            every_bytes: Some(1000),
+        };
+        let mut autosave = Autosave::new(policy, 0);
+        let due: Vec<usize> = [
+            (1, 300),
+            (2, 900),
+            (3, 1200),
+            (4, 1900),
+            (10, 2000),
+            (11, 2300),
+        ]
+        .into_iter()
+        .filter(|&(cycle, fed)| autosave.is_due(cycle, fed))
+        .map(|(cycle, _)| cycle)
+        .collect();
+        // Tavuväli alkaa alusta jokaisesta tallennuksesta
+        assert_eq!(due, [3, 10]);
+    }
+}
@@ -0,0 +1,28 @@
+// src/checksum.rs
+//
+// CRC-32 (IEEE 802.3) tarkistussumma tiedostomuotoja varten.
+// Käytetään varmistamaan, että purettu data vastaa alkuperäistä.
+
+/// Laske CRC-32 tavuille
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crc32_known_value() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        assert_eq!(crc32(b""), 0);
+    }
+}
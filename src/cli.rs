@@ -0,0 +1,356 @@
+// src/cli.rs
+//
+// Komentorivi: valitsimien poiminta, tarkistus ja alikomentojen ohjeet.
+//
+// Valitsimet luetaan käsin, jotta virheilmoitukset ja ohjeet tulostuvat
+// valitulla kielellä. Yleiset valitsimet ja asetukset poistetaan
+// argumenteista ennen alikomennon valintaa; oppimisajo lukee omat
+// valitsimensa paikallaan, joten sen jäljelle jääneet argumentit
+// tarkistetaan erikseen: tuntematon valitsin tai puuttuva arvo on virhe
+// eikä hiljaa ohitettava kirjoitusvirhe.
+//
+// Miksi ei clap: sen ohjeet ja virheilmoitukset ovat englanniksi, ja
+// kaksikielisyys vaatisi jokaisen tekstin korvaamisen käsin. Asetukset
+// luetaan lisäksi joko valitsimesta tai ympäristömuuttujasta yhden
+// taulukon (main.rs `SETTINGS`) kautta, ja jatkettu istunto tarvitsee
+// tiedon siitä, mitkä asetukset annettiin komentorivillä. Nämä olisivat
+// clapin päällä yhtä paljon koodia kuin tämä pieni jäsennin, jonka
+// käytös on lukittu alla olevilla testeillä.
+
+use crate::i18n::{self, Lang, tr};
+use crate::term::Level;
+use std::fmt;
+use std::time::Duration;
+
+/// Alikomentojen käyttöohjeet (`petri_dish <alikomento> --help`)
+const USAGES: &[(&str, &str, &str)] = &[
+    (
+        "archive",
+        "Käyttö: petri_dish archive <hakemisto> <arkisto.petri>",
+        "Usage: petri_dish archive <directory> <archive.petri>",
+    ),
+    (
+        "extract",
+        "Käyttö: petri_dish extract <arkisto.petri> [kohdehakemisto] [polku...]",
+        "Usage: petri_dish extract <archive.petri> [target directory] [path...]",
+    ),
+    (
+        "decode",
+        "Käyttö: petri_dish decode <P_id...> | <arkisto.petri> [kohde] [--brain aivot.json]\n       (oppimisajon virta: petri_dish learn --save-stream virta.petri)",
+        "Usage: petri_dish decode <P_id...> | <archive.petri> [target] [--brain brain.json]\n       (a learning run's stream: petri_dish learn --save-stream stream.petri)",
+    ),
+    (
+        "encode-file",
+        "Käyttö: petri_dish encode-file <tiedosto> <ulos.petri> [--brain aivot.json]",
+        "Usage: petri_dish encode-file <file> <out.petri> [--brain brain.json]",
+    ),
+    (
+        "repair",
+        "Käyttö: petri_dish repair <hakemisto> [aivot.json]",
+        "Usage: petri_dish repair <directory> [brain.json]",
+    ),
+    (
+        "diff",
+        "Käyttö: petri_dish diff <vanha> <uusi>",
+        "Usage: petri_dish diff <old> <new>",
+    ),
+    (
+        "cluster",
+        "Käyttö: petri_dish cluster <hakemisto> [tulos.json] [--clusters k]",
+        "Usage: petri_dish cluster <directory> [result.json] [--clusters k]",
+    ),
+    (
+        "detect",
+        "Käyttö: petri_dish detect <näyte> <aivot.json> [aivot.json...]",
+        "Usage: petri_dish detect <sample> <brain.json> [brain.json...]",
+    ),
+    (
+        "tournament",
+        "Käyttö: petri_dish tournament <aivot.json> [aivot.json...] --corpus <hakemisto>",
+        "Usage: petri_dish tournament <brain.json> [brain.json...] --corpus <directory>",
+    ),
+    (
+        "history",
+        "Käyttö: petri_dish history <ajo.jsonl> [P_id...]",
+        "Usage: petri_dish history <run.jsonl> [P_id...]",
+    ),
+    (
+        "inspect",
+        "Käyttö: petri_dish inspect [aivot.json] [P_id...] [--level N] [--min-level N] [--max-level N] [--min-strength S] [--max-strength S] [--min-usage N] [--sort strength|usage|saved|level|id] [--limit N]",
+        "Usage: petri_dish inspect [brain.json] [P_id...] [--level N] [--min-level N] [--max-level N] [--min-strength S] [--max-strength S] [--min-usage N] [--sort strength|usage|saved|level|id] [--limit N]",
+    ),
+    (
+        "repl",
+        "Käyttö: petri_dish repl [aivot.json]",
+        "Usage: petri_dish repl [brain.json]",
+    ),
+    (
+        "sweep",
+        "Käyttö: petri_dish sweep <nimi=a,b,c | nimi=lo..hi>... [--corpus hakemisto] [--cycles N] [--sample-bytes N] [--samples N] [--seed S] [--jobs N]\n  Parametrit: pair_threshold, capacity, decay, level_thresholds (taulukot kaksoispistein, esim. 3:2:1)",
+        "Usage: petri_dish sweep <name=a,b,c | name=lo..hi>... [--corpus directory] [--cycles N] [--sample-bytes N] [--samples N] [--seed S] [--jobs N]\n  Parameters: pair_threshold, capacity, decay, level_thresholds (tables with colons, e.g. 3:2:1)",
+    ),
+    (
+        "bench",
+        "Käyttö: petri_dish bench <tiedosto | hakemisto> [--bytes N] [--cycles N] [--history bench.jsonl] [--no-save] [--seed S]",
+        "Usage: petri_dish bench <file | directory> [--bytes N] [--cycles N] [--history bench.jsonl] [--no-save] [--seed S]",
+    ),
+    (
+        "plot",
+        "Käyttö: petri_dish plot [results.csv] [kuva.svg]",
+        "Usage: petri_dish plot [results.csv] [chart.svg]",
+    ),
+    (
+        "survival",
+        "Käyttö: petri_dish survival <ajo.log>\n  Loki tallennetaan ajamalla: petri_dish --log-format json -vv > ajo.log",
+        "Usage: petri_dish survival <run.log>\n  Record the log by running: petri_dish --log-format json -vv > run.log",
+    ),
+    (
+        "stats",
+        "Käyttö: petri_dish stats [results.csv]",
+        "Usage: petri_dish stats [results.csv]",
+    ),
+];
+
+/// Alikomennon käyttöohje valitulla kielellä; `None` tuntemattomalle
+pub fn usage(command: &str) -> Option<&'static str> {
+    let &(_, fi, en) = USAGES.iter().find(|&&(name, _, _)| name == command)?;
+    Some(match i18n::lang() {
+        Lang::Fi => fi,
+        Lang::En => en,
+    })
+}
+
+/// Tulosta alikomennon käyttöohje
+pub fn print_usage(command: &str) {
+    if let Some(text) = usage(command) {
+        crate::term::emit(Level::Info, text);
+    }
+}
+
+/// Pyydettiinkö ohjetta (`--help`, `-h`)
+pub fn wants_help(args: &[String]) -> bool {
+    args.iter().any(|a| a == "--help" || a == "-h")
+}
+
+/// Virheellinen komentorivi
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ArgError {
+    /// Valitsin on viimeisenä tai sitä seuraa toinen valitsin
+    MissingValue(String),
+    /// Valitsinta ei tunneta (tai ylimääräinen argumentti)
+    Unknown(String),
+    /// Arvo ei kelpaa valitsimelle: (valitsin, arvo)
+    Invalid(String, String),
+}
+
+impl fmt::Display for ArgError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ArgError::MissingValue(flag) => f.write_str(&tr!(
+                "valitsimelta {} puuttuu arvo",
+                "option {} is missing a value",
+                flag
+            )),
+            ArgError::Unknown(arg) => f.write_str(&tr!(
+                "tuntematon valitsin tai argumentti '{}'",
+                "unknown option or argument '{}'",
+                arg
+            )),
+            ArgError::Invalid(flag, value) => f.write_str(&tr!(
+                "virheellinen arvo: {} {}",
+                "invalid value: {} {}",
+                flag,
+                value
+            )),
+        }
+    }
+}
+
+/// Kelpaako argumentti valitsimen arvoksi: negatiivinen luku kelpaa,
+/// muu viivalla alkava on seuraava valitsin
+fn is_value(arg: &str) -> bool {
+    !arg.starts_with('-') || arg.parse::<f64>().is_ok()
+}
+
+/// Sekunteina annettu aikaväli: äärellinen ja positiivinen
+pub fn parse_seconds(value: &str) -> Option<Duration> {
+    let seconds: f64 = value.parse().ok()?;
+    if seconds <= 0.0 {
+        return None;
+    }
+    Duration::try_from_secs_f64(seconds).ok()
+}
+
+/// Poista lippu arvoineen argumenteista (`--lippu arvo`)
+pub fn take_value(args: &mut Vec<String>, flag: &str) -> Option<String> {
+    let i = args.iter().position(|a| a == flag)?;
+    let end = (i + 2).min(args.len());
+    args.drain(i..end).nth(1)
+}
+
+/// Poista lippu argumenteista; palauttaa, oliko se mukana
+pub fn take_switch(args: &mut Vec<String>, names: &[&str]) -> bool {
+    let before = args.len();
+    args.retain(|a| !names.contains(&a.as_str()));
+    args.len() != before
+}
+
+/// Hae lipun arvo komentoriviltä (`--lippu arvo`)
+pub fn flag_value(args: &[String], flag: &str) -> Option<String> {
+    args.iter()
+        .position(|a| a == flag)
+        .and_then(|i| args.get(i + 1).cloned())
+}
+
+/// Tarkista, että jokaisella annetulla arvollisella valitsimella on arvo
+pub fn check_values(args: &[String], with_value: &[&str]) -> Result<(), ArgError> {
+    for (i, arg) in args.iter().enumerate() {
+        if with_value.contains(&arg.as_str()) && !args.get(i + 1).is_some_and(|v| is_value(v)) {
+            return Err(ArgError::MissingValue(arg.clone()));
+        }
+    }
+    Ok(())
+}
+
+/// Tarkista kaikki argumentit: sallitut ovat arvolliset valitsimet arvoineen,
+/// kytkimet ja kytkimet, joiden perässä voi olla arvo (esim. `--recommend 5`)
+pub fn check_args(
+    args: &[String],
+    with_value: &[&str],
+    switches: &[&str],
+    optional_value: &[&str],
+) -> Result<(), ArgError> {
+    let mut i = 0;
+    while i < args.len() {
+        let arg = args[i].as_str();
+        let next_is_value = args.get(i + 1).is_some_and(|v| is_value(v));
+        if with_value.contains(&arg) {
+            if !next_is_value {
+                return Err(ArgError::MissingValue(arg.to_string()));
+            }
+            i += 2;
+        } else if optional_value.contains(&arg) {
+            i += if next_is_value { 2 } else { 1 };
+        } else if switches.contains(&arg) {
+            i += 1;
+        } else {
+            return Err(ArgError::Unknown(arg.to_string()));
+        }
+    }
+    Ok(())
+}
+
+/// Tulosteiden taso valitsimista (`-q`, `-v`, `-vv`); `None`, jos ei annettu.
+/// Hiljaisuus voittaa, ja `-vv` poistetaan ennen `-v`:tä, jotta se ei jää
+/// argumentiksi.
+pub fn take_verbosity(args: &mut Vec<String>) -> Option<Level> {
+    let quiet = take_switch(args, &["--quiet", "-q"]);
+    let trace = take_switch(args, &["-vv", "--trace"]);
+    let verbose = take_switch(args, &["--verbose", "-v"]);
+    match () {
+        _ if quiet => Some(Level::Warn),
+        _ if trace => Some(Level::Trace),
+        _ if verbose => Some(Level::Detail),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_take_value_and_switch() {
+        let mut a = args(&["petri_dish", "--brain", "b.json", "--plain", "x"]);
+        assert_eq!(flag_value(&a, "--brain").as_deref(), Some("b.json"));
+        assert_eq!(take_value(&mut a, "--brain").as_deref(), Some("b.json"));
+        assert!(take_switch(&mut a, &["--plain"]));
+        assert!(!take_switch(&mut a, &["--plain"]));
+        assert_eq!(a, args(&["petri_dish", "x"]));
+        assert_eq!(take_value(&mut a, "--brain"), None);
+    }
+
+    #[test]
+    fn test_missing_value_is_reported() {
+        let last = args(&["petri_dish", "--data"]);
+        assert_eq!(
+            check_values(&last, &["--data"]),
+            Err(ArgError::MissingValue("--data".into()))
+        );
+        // Seuraava valitsin ei ole arvo, negatiivinen luku on
+        let followed = args(&["--seed", "--plain"]);
+        assert!(check_values(&followed, &["--seed"]).is_err());
+        let negative = args(&["--decay", "-0.5"]);
+        assert_eq!(check_values(&negative, &["--decay"]), Ok(()));
+        let learn = args(&["--watch", "--session"]);
+        assert_eq!(
+            check_args(&learn, &["--session"], &["--watch"], &[]),
+            Err(ArgError::MissingValue("--session".into()))
+        );
+    }
+
+    #[test]
+    fn test_unknown_flags_are_rejected() {
+        let known = (["--session", "--holdout"], ["--watch"], ["--recommend"]);
+        let ok = args(&[
+            "--session",
+            "s",
+            "--watch",
+            "--recommend",
+            "5",
+            "--holdout",
+            "h",
+        ]);
+        assert_eq!(check_args(&ok, &known.0, &known.1, &known.2), Ok(()));
+        let bare = args(&["--recommend", "--watch"]);
+        assert_eq!(check_args(&bare, &known.0, &known.1, &known.2), Ok(()));
+        let typo = args(&["--wacth"]);
+        assert_eq!(
+            check_args(&typo, &known.0, &known.1, &known.2),
+            Err(ArgError::Unknown("--wacth".into()))
+        );
+        let stray = args(&["--watch", "data"]);
+        assert_eq!(
+            check_args(&stray, &known.0, &known.1, &known.2),
+            Err(ArgError::Unknown("data".into()))
+        );
+    }
+
+    #[test]
+    fn test_parse_seconds() {
+        assert_eq!(parse_seconds("2"), Some(Duration::from_secs(2)));
+        assert_eq!(parse_seconds("0.5"), Some(Duration::from_millis(500)));
+        for bad in ["0", "-1", "NaN", "inf", "1e300", "x", ""] {
+            assert_eq!(parse_seconds(bad), None, "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_verbosity_flags() {
+        let mut a = args(&["petri_dish", "-vv", "learn"]);
+        assert_eq!(take_verbosity(&mut a), Some(Level::Trace));
+        assert_eq!(a, args(&["petri_dish", "learn"]));
+        let mut a = args(&["-v"]);
+        assert_eq!(take_verbosity(&mut a), Some(Level::Detail));
+        let mut a = args(&["-vv", "-q"]);
+        assert_eq!(take_verbosity(&mut a), Some(Level::Warn));
+        assert!(a.is_empty());
+        assert_eq!(take_verbosity(&mut args(&["learn"])), None);
+    }
+
+    #[test]
+    fn test_every_usage_names_its_command() {
+        for &(name, fi, en) in USAGES {
+            assert!(fi.contains(&format!("petri_dish {}", name)), "{}", name);
+            assert!(en.contains(&format!("petri_dish {}", name)), "{}", name);
+        }
+        assert!(usage("archive").is_some());
+        assert!(usage("learn").is_none());
+        assert!(wants_help(&args(&["x", "-h"])));
+        assert!(!wants_help(&args(&["x"])));
+    }
+}
@@ -0,0 +1,261 @@
+// src/cluster.rs
+//
+// Cluster: Tiedostojen ryhmittely pakkausetäisyydellä.
+//
+// Normalisoitu pakkausetäisyys (NCD, Cilibrasi & Vitányi 2005):
+//
+//     NCD(x, y) = (C(xy) - min(C(x), C(y))) / max(C(x), C(y))
+//
+// missä C on koodattu koko. Kun C on opetettujen aivojen koodaus ja
+// entropiakooderi, etäisyys kertoo, kuinka paljon yhteistä rakennetta
+// aivot löytävät tiedostoista: lähellä 0 = samankaltaiset, lähellä 1 =
+// toisistaan riippumattomat.
+//
+// Etäisyysmatriisista rakennetaan dendrogrammi keskiarvokytkennällä
+// (UPGMA), ja puu leikataan haluttuun ryhmämäärään purkamalla aina
+// kaukaisin yhdistys.
+
+use crate::term::{self, Level, say};
+use serde::Serialize;
+use std::fs::File;
+use std::io::{self, BufWriter};
+use std::path::Path;
+
+/// Normalisoitu pakkausetäisyys koodatuista kooista
+pub fn ncd(c_x: usize, c_y: usize, c_xy: usize) -> f64 {
+    let (min, max) = (c_x.min(c_y), c_x.max(c_y));
+    if max == 0 {
+        return 0.0;
+    }
+    (c_xy.saturating_sub(min) as f64 / max as f64).clamp(0.0, 1.0)
+}
+
+/// Parittaiset etäisyydet. `size` palauttaa datan koodatun koon.
+/// Yhdistelmä koodataan molemmissa järjestyksissä ja pienempi käytetään,
+/// jotta matriisi on symmetrinen.
+pub fn distance_matrix(files: &[Vec<u8>], size: impl Fn(&[u8]) -> usize) -> Vec<Vec<f64>> {
+    let n = files.len();
+    let sizes: Vec<usize> = files.iter().map(|f| size(f)).collect();
+    let mut distances = vec![vec![0.0; n]; n];
+    for i in 0..n {
+        for j in i + 1..n {
+            let joined = |a: &[u8], b: &[u8]| size(&[a, b].concat());
+            let c_xy = joined(&files[i], &files[j]).min(joined(&files[j], &files[i]));
+            let d = ncd(sizes[i], sizes[j], c_xy);
+            distances[i][j] = d;
+            distances[j][i] = d;
+        }
+    }
+    distances
+}
+
+/// Dendrogrammin solmu: lehti on tiedoston indeksi
+#[derive(Debug, Clone, Serialize)]
+#[serde(untagged)]
+pub enum Dendrogram {
+    Leaf {
+        file: usize,
+    },
+    Merge {
+        distance: f64,
+        size: usize,
+        children: Vec<Dendrogram>,
+    },
+}
+
+impl Dendrogram {
+    fn size(&self) -> usize {
+        match self {
+            Dendrogram::Leaf { .. } => 1,
+            Dendrogram::Merge { size, .. } => *size,
+        }
+    }
+
+    fn distance(&self) -> f64 {
+        match self {
+            Dendrogram::Leaf { .. } => 0.0,
+            Dendrogram::Merge { distance, .. } => *distance,
+        }
+    }
+
+    fn leaves(&self, out: &mut Vec<usize>) {
+        match self {
+            Dendrogram::Leaf { file } => out.push(*file),
+            Dendrogram::Merge { children, .. } => children.iter().for_each(|c| c.leaves(out)),
+        }
+    }
+
+    /// Leikkaa puu `k` ryhmään. Palauttaa ryhmän numeron jokaiselle
+    /// tiedostolle (numerot pienimmän tiedostoindeksin mukaan).
+    pub fn cut(&self, k: usize) -> Vec<usize> {
+        let mut roots = vec![self];
+        while roots.len() < k {
+            let Some((i, _)) = roots
+                .iter()
+                .enumerate()
+                .filter(|(_, r)| matches!(r, Dendrogram::Merge { .. }))
+                .max_by(|a, b| a.1.distance().total_cmp(&b.1.distance()))
+            else {
+                break;
+            };
+            if let Dendrogram::Merge { children, .. } = roots.swap_remove(i) {
+                roots.extend(children.iter());
+            }
+        }
+
+        let mut groups: Vec<Vec<usize>> = roots
+            .iter()
+            .map(|r| {
+                let mut leaves = Vec::new();
+                r.leaves(&mut leaves);
+                leaves
+            })
+            .collect();
+        groups.sort_by_key(|g| g.iter().min().copied());
+
+        let mut assignment = vec![0; self.size()];
+        for (cluster, group) in groups.iter().enumerate() {
+            for &file in group {
+                assignment[file] = cluster;
+            }
+        }
+        assignment
+    }
+}
+
+/// Rakenna dendrogrammi keskiarvokytkennällä. `None` tyhjälle syötteelle.
+pub fn agglomerate(distances: &[Vec<f64>]) -> Option<Dendrogram> {
+    let mut nodes: Vec<Option<Dendrogram>> = (0..distances.len())
+        .map(|file| Some(Dendrogram::Leaf { file }))
+        .collect();
+    // Ryhmien väliset etäisyydet (päivitetään yhdistettäessä)
+    let mut d: Vec<Vec<f64>> = distances.to_vec();
+
+    loop {
+        let alive: Vec<usize> = (0..nodes.len()).filter(|&i| nodes[i].is_some()).collect();
+        if alive.len() <= 1 {
+            return alive.first().and_then(|&i| nodes[i].take());
+        }
+
+        let mut best = (f64::INFINITY, 0, 0);
+        for (x, &i) in alive.iter().enumerate() {
+            for &j in &alive[x + 1..] {
+                if d[i][j] < best.0 {
+                    best = (d[i][j], i, j);
+                }
+            }
+        }
+        let (distance, i, j) = best;
+        let (a, b) = (nodes[i].take().unwrap(), nodes[j].take().unwrap());
+        let (size_a, size_b) = (a.size() as f64, b.size() as f64);
+
+        // Uusi ryhmä korvaa i:n; etäisyys on jäsenparien keskiarvo
+        for &other in &alive {
+            if other != i && other != j {
+                let merged = (d[i][other] * size_a + d[j][other] * size_b) / (size_a + size_b);
+                d[i][other] = merged;
+                d[other][i] = merged;
+            }
+        }
+        nodes[i] = Some(Dendrogram::Merge {
+            distance,
+            size: a.size() + b.size(),
+            children: vec![a, b],
+        });
+    }
+}
+
+/// Ryhmittelyn tulos JSON-tiedostoon
+#[derive(Debug, Serialize)]
+pub struct ClusterReport {
+    pub files: Vec<String>,
+    pub distances: Vec<Vec<f64>>,
+    pub clusters: Vec<usize>,
+    pub dendrogram: Option<Dendrogram>,
+}
+
+impl ClusterReport {
+    /// Laske etäisyydet, dendrogrammi ja `k` ryhmää
+    pub fn build(
+        files: Vec<String>,
+        data: &[Vec<u8>],
+        k: usize,
+        size: impl Fn(&[u8]) -> usize,
+    ) -> Self {
+        let distances = distance_matrix(data, size);
+        let dendrogram = agglomerate(&distances);
+        let clusters = dendrogram.as_ref().map(|t| t.cut(k)).unwrap_or_default();
+        ClusterReport {
+            files,
+            distances,
+            clusters,
+            dendrogram,
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer_pretty(writer, self).map_err(io::Error::other)
+    }
+
+    /// Tulosta ryhmät ja kunkin tiedoston lähin naapuri
+    pub fn print(&self) {
+        let count = self.clusters.iter().max().map_or(0, |&c| c + 1);
+        for cluster in 0..count {
+            say!("  🗂️ Ryhmä {}:", "  🗂️ Cluster {}:", cluster + 1);
+            for (i, file) in self.files.iter().enumerate() {
+                if self.clusters[i] != cluster {
+                    continue;
+                }
+                let nearest = (0..self.files.len())
+                    .filter(|&j| j != i)
+                    .min_by(|&a, &b| self.distances[i][a].total_cmp(&self.distances[i][b]));
+                match nearest {
+                    Some(j) => say!(
+                        "     {} (lähin: {}, NCD {:.3})",
+                        "     {} (nearest: {}, NCD {:.3})",
+                        file,
+                        self.files[j],
+                        self.distances[i][j]
+                    ),
+                    None => term::emit(Level::Info, &format!("     {}", file)),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_similar_files_cluster_together() {
+        let lorem = b"lorem ipsum dolor sit amet consectetur ".repeat(8);
+        let digits = b"0123456789 9876543210 0246813579 ".repeat(8);
+        let data = vec![
+            lorem.clone(),
+            digits.clone(),
+            [lorem.as_slice(), b"adipiscing"].concat(),
+            [digits.as_slice(), b"1111"].concat(),
+        ];
+        // Karkea kompressori: erilaisten 4-grammien määrä
+        let size = |bytes: &[u8]| {
+            let mut grams: Vec<&[u8]> = bytes.windows(4).collect();
+            grams.sort();
+            grams.dedup();
+            grams.len()
+        };
+
+        let names = (0..4).map(|i| format!("f{}", i)).collect();
+        let report = ClusterReport::build(names, &data, 2, size);
+        assert!(report.distances[0][2] < report.distances[0][1]);
+        assert_eq!(report.distances[1][3], report.distances[3][1]);
+        assert_eq!(report.clusters, [0, 1, 0, 1]);
+        assert_eq!(report.dendrogram.as_ref().unwrap().cut(1), [0; 4]);
+        assert_eq!(report.dendrogram.as_ref().unwrap().cut(9), [0, 1, 2, 3]);
+
+        let json = serde_json::to_string(&report).unwrap();
+        assert!(json.contains("\"children\""));
+    }
+}
@@ -0,0 +1,57 @@
+// src/commands/archive.rs
+//
+// Alikomento `archive`: hakemiston pakkaus arkistoksi jaetuilla aivoilla.
+
+use crate::{Config, cli, load_or_create_brain};
+use petri_dish::i18n::tr;
+use petri_dish::term::{error, say, warning};
+use petri_dish::{archive, progress};
+use std::path::Path;
+
+/// `archive <hakemisto> <arkisto.petri>`: pakkaa hakemisto jaetuilla aivoilla
+pub fn run(config: &Config, args: &[String]) {
+    let (Some(dir), Some(out)) = (args.first(), args.get(1)) else {
+        cli::print_usage("archive");
+        return;
+    };
+
+    let bank = load_or_create_brain(config);
+    if bank.combine_count() == 0 {
+        warning!(
+            "  ⚠️  Aivoissa ei ole opittuja malleja: arkisto ei tiivisty.",
+            "  ⚠️  The brain has no learned patterns: the archive will not compress."
+        );
+    }
+
+    say!(
+        "  📦 Arkistoidaan '{}' -> '{}'...",
+        "  📦 Archiving '{}' -> '{}'...",
+        dir,
+        out
+    );
+    let created = progress::with_spinner(tr!("Arkistoidaan", "Archiving"), || {
+        archive::create(Path::new(dir), Path::new(out), &bank, config.coder)
+    });
+    match created {
+        Ok(summary) => {
+            let ratio = if summary.original_bytes > 0 {
+                summary.archive_bytes as f64 / summary.original_bytes as f64
+            } else {
+                0.0
+            };
+            say!(
+                "  ✅ {} tiedostoa, {} -> {} tavua ({:.1}% alkuperäisestä, {})",
+                "  ✅ {} files, {} -> {} bytes ({:.1}% of original, {})",
+                summary.files,
+                summary.original_bytes,
+                summary.archive_bytes,
+                ratio * 100.0,
+                config.coder.name()
+            );
+        }
+        Err(e) => error!(
+            "  ❌ Arkistointi epäonnistui: {}",
+            "  ❌ Archiving failed: {}", e
+        ),
+    }
+}
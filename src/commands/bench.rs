@@ -0,0 +1,109 @@
+// src/commands/bench.rs
+//
+// Alikomento `bench`: toistettava mittaus ja vertailu edelliseen ajoon.
+
+use crate::cli::take_value;
+use crate::{Config, cli};
+use petri_dish::feeder::Feeder;
+use petri_dish::term::{error, say, warning};
+use petri_dish::{bench, builder, sweep};
+use std::path::Path;
+
+/// `bench <korpus> [valinnat]`: toistettava mittaus ja vertailu edelliseen ajoon
+pub fn run(config: &Config, args: &[String]) {
+    let mut args = args.to_vec();
+    let usage = || cli::print_usage("bench");
+    let mut invalid = None;
+    let mut number = |args: &mut Vec<String>, flag: &str| -> Option<usize> {
+        let value = take_value(args, flag)?;
+        let parsed = value.parse().ok().filter(|&n| n > 0);
+        if parsed.is_none() {
+            invalid = Some(format!("{} {}", flag, value));
+        }
+        parsed
+    };
+    let bytes = number(&mut args, "--bytes").unwrap_or(bench::DEFAULT_BYTES);
+    let cycles = number(&mut args, "--cycles").unwrap_or(sweep::DEFAULT_CYCLES);
+    let history_path =
+        take_value(&mut args, "--history").unwrap_or_else(|| bench::DEFAULT_HISTORY.to_string());
+    let save = !args.iter().any(|a| a == "--no-save");
+    if let Some(arg) = invalid {
+        error!("  ❌ Virheellinen arvo: {}", "  ❌ Invalid value: {}", arg);
+        return usage();
+    }
+    let Some(corpus) = args.iter().find(|a| !a.starts_with("--")) else {
+        return usage();
+    };
+
+    let files = if Path::new(corpus).is_file() {
+        Ok(vec![Path::new(corpus).to_path_buf()])
+    } else {
+        Feeder::list_files(corpus, &config.extensions)
+    };
+    let chunk = match files.and_then(|files| bench::Chunk::load(&files, bytes, config.seed)) {
+        Ok(chunk) if !chunk.is_empty() => chunk,
+        Ok(_) => {
+            warning!(
+                "  ⚠️  Korpuksesta '{}' ei saatu dataa ({}).",
+                "  ⚠️  No data from corpus '{}' ({}).",
+                corpus,
+                config.extensions.join(", ")
+            );
+            return;
+        }
+        Err(e) => {
+            error!(
+                "  ❌ Korpuksen luku epäonnistui: {}",
+                "  ❌ Failed to read corpus: {}", e
+            );
+            return;
+        }
+    };
+    let candidate = sweep::Candidate {
+        pair_threshold: config.pair_threshold,
+        capacity: config.pattern_capacity,
+        decay: config.decay.unwrap_or(builder::DEFAULT_DECAY_RATE),
+        level_thresholds: config.level_thresholds.clone(),
+    };
+    say!(
+        "  ⏱️  Bench: {} tavua kohdasta {} ({}), {} sykliä, {}",
+        "  ⏱️  Bench: {} bytes from offset {} ({}), {} cycles, {}",
+        chunk.len(),
+        chunk.offset,
+        corpus,
+        cycles,
+        candidate.label()
+    );
+
+    let result = bench::run(
+        corpus,
+        &chunk,
+        config.seed,
+        &candidate,
+        cycles,
+        config.tokenization,
+        config.coder,
+    );
+    let history = match bench::load_history(Path::new(&history_path)) {
+        Ok(history) => history,
+        Err(e) => {
+            warning!(
+                "  ⚠️  Historian '{}' luku epäonnistui: {}",
+                "  ⚠️  Failed to read history '{}': {}",
+                history_path,
+                e
+            );
+            Vec::new()
+        }
+    };
+    bench::print_report(&result, bench::previous(&history, &result));
+    if save {
+        match bench::append_history(Path::new(&history_path), &result) {
+            Ok(()) => say!("  💾 Tallennettu: {}", "  💾 Saved: {}", history_path),
+            Err(e) => error!(
+                "  ❌ Tallennus epäonnistui: {}",
+                "  ❌ Saving failed: {}", e
+            ),
+        }
+    }
+}
@@ -0,0 +1,98 @@
+// src/commands/cluster.rs
+//
+// Alikomento `cluster`: tiedostojen ryhmittely pakkausetäisyydellä.
+
+use crate::cli::flag_value;
+use crate::{Config, cli, load_brain_file};
+use petri_dish::feeder::Feeder;
+use petri_dish::i18n::tr;
+use petri_dish::term::{error, say, warning};
+use petri_dish::{cluster, progress};
+use std::fs;
+use std::path::Path;
+
+/// `cluster <hakemisto> [tulos.json] [--clusters k]`: ryhmittele tiedostot
+/// pakkausetäisyydellä (NCD) opetettujen aivojen koodauksella
+pub fn run(config: &Config, args: &[String]) {
+    let Some(dir) = args.first() else {
+        cli::print_usage("cluster");
+        return;
+    };
+    let out = args
+        .get(1)
+        .filter(|a| !a.starts_with("--"))
+        .map(String::as_str)
+        .unwrap_or("clusters.json");
+    let files = match Feeder::list_files(dir, &config.extensions) {
+        Ok(files) => files,
+        Err(e) => {
+            error!(
+                "  ❌ Hakemiston luku epäonnistui: {}",
+                "  ❌ Failed to read directory: {}", e
+            );
+            return;
+        }
+    };
+
+    let mut names = Vec::new();
+    let mut data = Vec::new();
+    for path in &files {
+        match fs::read(path) {
+            Ok(bytes) => {
+                names.push(path.display().to_string());
+                data.push(bytes);
+            }
+            Err(e) => warning!(
+                "  ⚠️  Ohitetaan '{}': {}",
+                "  ⚠️  Skipping '{}': {}",
+                path.display(),
+                e
+            ),
+        }
+    }
+    if data.len() < 2 {
+        warning!(
+            "  ⚠️  Ryhmittelyyn tarvitaan vähintään kaksi tiedostoa.",
+            "  ⚠️  Clustering needs at least two files."
+        );
+        return;
+    }
+    // Oletus: nyrkkisääntö k ≈ √(n/2)
+    let k = flag_value(args, "--clusters")
+        .and_then(|v| v.parse().ok())
+        .unwrap_or_else(|| ((data.len() as f64 / 2.0).sqrt().round() as usize).max(1));
+
+    let bank = load_brain_file(config);
+    if bank.combine_count() == 0 {
+        warning!(
+            "  ⚠️  Aivoissa ei ole opittuja malleja: etäisyydet mittaavat vain tavuja.",
+            "  ⚠️  The brain has no learned patterns: distances only measure bytes."
+        );
+    }
+    say!(
+        "  📐 Lasketaan {} parin pakkausetäisyydet ({})...",
+        "  📐 Computing compression distances for {} pairs ({})...",
+        data.len() * (data.len() - 1) / 2,
+        config.coder.name()
+    );
+    // Jokainen tiedosto kerran ja jokainen pari molemmissa järjestyksissä
+    let n = data.len() as u64;
+    let encodings = progress::bar(n * n, tr!("Koodataan", "Encoding"));
+    let report = cluster::ClusterReport::build(names, &data, k, |bytes| {
+        encodings.inc(1);
+        config.coder.encode(&bank.encode(bytes)).len()
+    });
+    encodings.finish_and_clear();
+    report.print();
+    match report.save(Path::new(out)) {
+        Ok(()) => say!(
+            "  💾 Ryhmittely ja dendrogrammi tallennettu: {}",
+            "  💾 Clusters and dendrogram saved: {}",
+            out
+        ),
+        Err(e) => error!(
+            "  ❌ Tallennus epäonnistui: {}",
+            "  ❌ Saving failed: {}", e
+        ),
+    }
+}
@@ -0,0 +1,101 @@
+// src/commands/decode.rs
+//
+// Alikomento `decode`: mallin tavut ja hierarkia tai arkiston purku.
+
+use crate::{Config, cli, load_or_create_brain};
+use petri_dish::builder::{Builder, PatternBank};
+use petri_dish::i18n::tr;
+use petri_dish::term::{Level, error, say};
+use petri_dish::{archive, progress, render, term};
+use std::fs;
+use std::path::Path;
+
+/// `decode <P_id...|arkisto.petri> [kohde]`: mallin tavut ja hierarkia,
+/// tai arkiston purku tarkistussummien kanssa. Yhden tiedoston arkisto
+/// puretaan suoraan annettuun tiedostoon, muuten kohdehakemistoon.
+pub fn run(config: &Config, args: &[String]) {
+    let Some(target) = args.first() else {
+        cli::print_usage("decode");
+        return;
+    };
+    let bank = load_or_create_brain(config);
+    if Path::new(target).is_file() {
+        return decode_archive(&bank, target, args.get(1).map(String::as_str));
+    }
+
+    let builder = Builder::with_bank(bank);
+    for arg in args {
+        let Some(id) = arg
+            .trim_start_matches("P_")
+            .parse::<u32>()
+            .ok()
+            .filter(|&id| builder.bank.get(id).is_some())
+        else {
+            error!(
+                "  ❌ '{}' ei ole tiedosto eikä aivojen malli.",
+                "  ❌ '{}' is neither a file nor a pattern in the brain.", arg
+            );
+            continue;
+        };
+        let bytes = builder.bank.decode(id);
+        say!(
+            "
+  P_{}: {} tavua",
+            "
+  P_{}: {} bytes",
+            id,
+            bytes.len()
+        );
+        term::emit(Level::Info, &format!("  \"{}\"", render::escape(&bytes)));
+        builder.print_hierarchy(id, 1);
+    }
+}
+
+/// Pura arkisto ja tarkista jokaisen tiedoston CRC
+fn decode_archive(bank: &PatternBank, path: &str, target: Option<&str>) {
+    let archive = match archive::Archive::open(Path::new(path)) {
+        Ok(a) => a,
+        Err(e) => {
+            error!(
+                "  ❌ Arkiston avaus epäonnistui: {}",
+                "  ❌ Failed to open archive: {}", e
+            );
+            return;
+        }
+    };
+
+    // Yksi tiedosto ja kohde, joka ei ole hakemisto: kirjoita suoraan
+    if let ([entry], Some(out)) = (archive.index.entries.as_slice(), target)
+        && !Path::new(out).is_dir()
+    {
+        let written = archive
+            .read_entry(entry, bank)
+            .and_then(|data| fs::write(out, &data).map(|_| data.len()));
+        match written {
+            Ok(len) => say!(
+                "  ✅ '{}' -> '{}': {} tavua, CRC-32 {:08x} täsmää.",
+                "  ✅ '{}' -> '{}': {} bytes, CRC-32 {:08x} verified.",
+                entry.path,
+                out,
+                len,
+                entry.checksum
+            ),
+            Err(e) => error!("  ❌ Purku epäonnistui: {}", "  ❌ Decoding failed: {}", e),
+        }
+        return;
+    }
+
+    let dest = target.unwrap_or(".");
+    let extracted = progress::with_spinner(tr!("Puretaan", "Decoding"), || {
+        archive.extract(Path::new(dest), bank, &[])
+    });
+    match extracted {
+        Ok(n) => say!(
+            "  ✅ Purettiin {} tiedostoa hakemistoon '{}', tarkistussummat täsmäävät.",
+            "  ✅ Decoded {} files into '{}', checksums verified.",
+            n,
+            dest
+        ),
+        Err(e) => error!("  ❌ Purku epäonnistui: {}", "  ❌ Decoding failed: {}", e),
+    }
+}
@@ -0,0 +1,86 @@
+// src/commands/detect.rs
+//
+// Alikomento `detect`: mitkä aivot tuntevat näytteen parhaiten.
+
+use crate::cli;
+use petri_dish::builder::PatternBank;
+use petri_dish::i18n::tr;
+use petri_dish::term::{error, say, warning};
+use petri_dish::{detect, progress};
+use std::fs;
+use std::path::Path;
+
+/// `detect <näyte> <aivot.json>...`: mitkä aivot tuntevat näytteen parhaiten
+pub fn run(args: &[String]) {
+    let Some((sample_path, brain_paths)) = args.split_first().filter(|(_, b)| !b.is_empty()) else {
+        cli::print_usage("detect");
+        return;
+    };
+    let sample = match fs::read(sample_path) {
+        Ok(data) => data,
+        Err(e) => {
+            error!(
+                "  ❌ Näytteen luku epäonnistui: {}",
+                "  ❌ Failed to read sample: {}", e
+            );
+            return;
+        }
+    };
+
+    let mut brains = Vec::new();
+    let loading = progress::bar(
+        brain_paths.len() as u64,
+        tr!("Ladataan aivoja", "Loading brains"),
+    );
+    for path in brain_paths {
+        loading.inc(1);
+        let path = Path::new(path);
+        match PatternBank::load(path) {
+            Ok(bank) => {
+                let name = path.file_stem().unwrap_or_default().to_string_lossy();
+                brains.push((name.into_owned(), bank));
+            }
+            Err(e) => warning!(
+                "  ⚠️  Ohitetaan '{}': {}",
+                "  ⚠️  Skipping '{}': {}",
+                path.display(),
+                e
+            ),
+        }
+    }
+
+    loading.finish_and_clear();
+
+    let scored = progress::with_spinner(tr!("Pisteytetään", "Scoring"), || {
+        detect::detect(&brains, &sample)
+    });
+    let Some(result) = scored else {
+        warning!(
+            "  ⚠️  Ei pisteytettävää (tyhjä näyte tai ei aivoja).",
+            "  ⚠️  Nothing to score (empty sample or no brains)."
+        );
+        return;
+    };
+    say!(
+        "  🔎 Näyte '{}' ({} tavua):",
+        "  🔎 Sample '{}' ({} bytes):",
+        sample_path,
+        sample.len()
+    );
+    for score in &result.scores {
+        say!(
+            "     {:<20} {:.3} bittiä/tavu  ({:.1}%)",
+            "     {:<20} {:.3} bits/byte  ({:.1}%)",
+            score.name,
+            score.bits_per_byte,
+            score.posterior * 100.0
+        );
+    }
+    let best = result.best();
+    say!(
+        "  ✅ Paras vastaavuus: {} (varmuus {:.1}%)",
+        "  ✅ Best match: {} (confidence {:.1}%)",
+        best.name,
+        best.posterior * 100.0
+    );
+}
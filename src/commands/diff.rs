@@ -0,0 +1,35 @@
+// src/commands/diff.rs
+//
+// Alikomento `diff`: rakennetietoinen vertailu jäädytetyillä aivoilla.
+
+use crate::{Config, cli, load_brain_file};
+use petri_dish::diff;
+use petri_dish::term::{error, warning};
+use std::fs;
+
+/// `diff <vanha> <uusi>`: rakennetietoinen vertailu jäädytetyillä aivoilla
+pub fn run(config: &Config, args: &[String]) {
+    let (Some(old_path), Some(new_path)) = (args.first(), args.get(1)) else {
+        cli::print_usage("diff");
+        return;
+    };
+    let (old, new) = match (fs::read(old_path), fs::read(new_path)) {
+        (Ok(old), Ok(new)) => (old, new),
+        (Err(e), _) | (_, Err(e)) => {
+            error!(
+                "  ❌ Tiedoston luku epäonnistui: {}",
+                "  ❌ Failed to read file: {}", e
+            );
+            return;
+        }
+    };
+
+    let bank = load_brain_file(config);
+    if bank.combine_count() == 0 {
+        warning!(
+            "  ⚠️  Aivoissa ei ole opittuja malleja: vertailu tehdään tavuina.",
+            "  ⚠️  The brain has no learned patterns: comparing bytes."
+        );
+    }
+    diff::diff(&bank, &old, &new).print(&bank, 50);
+}
@@ -0,0 +1,60 @@
+// src/commands/encode_file.rs
+//
+// Alikomento `encode-file`: yhden tiedoston pakkaus jäädytetyillä aivoilla.
+
+use crate::{Config, cli, load_or_create_brain};
+use petri_dish::i18n::tr;
+use petri_dish::term::{error, say, warning};
+use petri_dish::{archive, progress};
+use std::fs;
+use std::path::Path;
+
+/// `encode-file <tiedosto> <ulos.petri>`: yhden tiedoston pakkaus
+/// jäädytetyillä aivoilla ja varmistus purkamalla
+pub fn run(config: &Config, args: &[String]) {
+    let (Some(input), Some(out)) = (args.first(), args.get(1)) else {
+        cli::print_usage("encode-file");
+        return;
+    };
+
+    let bank = load_or_create_brain(config);
+    if bank.combine_count() == 0 {
+        warning!(
+            "  ⚠️  Aivoissa ei ole opittuja malleja: tiedosto ei tiivisty.",
+            "  ⚠️  The brain has no learned patterns: the file will not compress."
+        );
+    }
+
+    let encoded = progress::with_spinner(tr!("Koodataan", "Encoding"), || {
+        archive::encode_file(Path::new(input), Path::new(out), &bank, config.coder)
+    });
+    let summary = match encoded {
+        Ok(summary) => summary,
+        Err(e) => {
+            error!(
+                "  ❌ Koodaus epäonnistui: {}",
+                "  ❌ Encoding failed: {}", e
+            );
+            return;
+        }
+    };
+    // Todellinen koko levyllä (hakemisto ja otsake mukana)
+    let on_disk = fs::metadata(out)
+        .map(|m| m.len())
+        .unwrap_or(summary.archive_bytes);
+    let ratio = if summary.original_bytes > 0 {
+        on_disk as f64 / summary.original_bytes as f64
+    } else {
+        0.0
+    };
+    say!(
+        "  ✅ '{}' -> '{}': {} -> {} tavua ({:.1}% alkuperäisestä, {}), purku tarkistettu.",
+        "  ✅ '{}' -> '{}': {} -> {} bytes ({:.1}% of original, {}), decoding verified.",
+        input,
+        out,
+        summary.original_bytes,
+        on_disk,
+        ratio * 100.0,
+        config.coder.name()
+    );
+}
@@ -0,0 +1,53 @@
+// src/commands/extract.rs
+//
+// Alikomento `extract`: arkiston (tai sen osan) purku.
+
+use crate::{Config, cli, load_or_create_brain};
+use petri_dish::i18n::tr;
+use petri_dish::term::{error, say};
+use petri_dish::{archive, progress};
+use std::path::Path;
+
+/// `extract <arkisto.petri> [kohde] [polku...]`: pura arkisto (tai osa siitä)
+pub fn run(config: &Config, args: &[String]) {
+    let Some(archive_path) = args.first() else {
+        cli::print_usage("extract");
+        return;
+    };
+    let dest = args.get(1).map(String::as_str).unwrap_or(".");
+    let only = args.get(2..).unwrap_or_default();
+
+    let archive = match archive::Archive::open(Path::new(archive_path)) {
+        Ok(a) => a,
+        Err(e) => {
+            error!(
+                "  ❌ Arkiston avaus epäonnistui: {}",
+                "  ❌ Failed to open archive: {}", e
+            );
+            return;
+        }
+    };
+    say!(
+        "  📦 Arkistossa {} tiedostoa (kooderi {}).",
+        "  📦 Archive holds {} files (coder {}).",
+        archive.index.entries.len(),
+        archive.index.coder
+    );
+
+    let bank = load_or_create_brain(config);
+    let extracted = progress::with_spinner(tr!("Puretaan", "Extracting"), || {
+        archive.extract(Path::new(dest), &bank, only)
+    });
+    match extracted {
+        Ok(n) => say!(
+            "  ✅ Purettiin {} tiedostoa hakemistoon '{}'.",
+            "  ✅ Extracted {} files into '{}'.",
+            n,
+            dest
+        ),
+        Err(e) => error!(
+            "  ❌ Purku epäonnistui: {}",
+            "  ❌ Extraction failed: {}", e
+        ),
+    }
+}
@@ -0,0 +1,46 @@
+// src/commands/history.rs
+//
+// Alikomento `history`: mallien kehitys tilannekuvista.
+
+use crate::cli;
+use petri_dish::snapshot;
+use petri_dish::term::{error, warning};
+use std::path::Path;
+
+/// `history <ajo.jsonl> [P_id...]`: mallien kehitys tilannekuvista
+pub fn run(args: &[String]) {
+    let Some(path) = args.first() else {
+        cli::print_usage("history");
+        return;
+    };
+    let snapshots = match snapshot::load(Path::new(path)) {
+        Ok(s) if !s.is_empty() => s,
+        Ok(_) => {
+            warning!(
+                "  ⚠️  Tiedostossa '{}' ei ole tilannekuvia.",
+                "  ⚠️  No snapshots in '{}'.",
+                path
+            );
+            return;
+        }
+        Err(e) => {
+            error!(
+                "  ❌ Tilannekuvien luku epäonnistui: {}",
+                "  ❌ Failed to read snapshots: {}", e
+            );
+            return;
+        }
+    };
+    if args.len() == 1 {
+        return snapshot::print_overview(&snapshots);
+    }
+    for arg in &args[1..] {
+        match arg.trim_start_matches("P_").parse() {
+            Ok(id) => snapshot::print_history(&snapshots, id),
+            Err(_) => error!(
+                "  ❌ Virheellinen mallin ID: {}",
+                "  ❌ Invalid pattern ID: {}", arg
+            ),
+        }
+    }
+}
@@ -0,0 +1,81 @@
+// src/commands/inspect.rs
+//
+// Alikomento `inspect`: aivojen tarkastelu suodattimin.
+
+use crate::cli::take_value;
+use crate::{Config, cli};
+use petri_dish::builder::{Builder, PatternBank};
+use petri_dish::inspect;
+use petri_dish::term::error;
+use std::path::Path;
+
+/// `inspect [aivot.json] [P_id...] [suodattimet]`: aivojen tarkastelu
+pub fn run(config: &Config, args: &[String]) {
+    let mut args = args.to_vec();
+    let mut invalid = None;
+    let mut number = |args: &mut Vec<String>, flag: &str| -> Option<f64> {
+        let value = take_value(args, flag)?;
+        let parsed = value.parse().ok();
+        if parsed.is_none() {
+            invalid = Some(format!("{} {}", flag, value));
+        }
+        parsed
+    };
+    let level = number(&mut args, "--level").map(|l| l as u8);
+    let mut filter = inspect::Filter {
+        min_level: number(&mut args, "--min-level").map(|l| l as u8).or(level),
+        max_level: number(&mut args, "--max-level").map(|l| l as u8).or(level),
+        min_strength: number(&mut args, "--min-strength"),
+        max_strength: number(&mut args, "--max-strength"),
+        min_usage: number(&mut args, "--min-usage").map(|u| u as u32),
+        sort: inspect::SortKey::default(),
+    };
+    let limit = number(&mut args, "--limit").map_or(inspect::DEFAULT_LIMIT, |l| l as usize);
+    if let Some(name) = take_value(&mut args, "--sort") {
+        match inspect::SortKey::parse(&name) {
+            Some(key) => filter.sort = key,
+            None => invalid = Some(format!("--sort {}", name)),
+        }
+    }
+    if let Some(arg) = invalid {
+        error!("  ❌ Virheellinen arvo: {}", "  ❌ Invalid value: {}", arg);
+        cli::print_usage("inspect");
+        return;
+    }
+
+    // Ensimmäinen olemassa oleva tiedosto on aivot (muuten --brain / oletus)
+    let brain_path = match args.first() {
+        Some(first) if Path::new(first).is_file() => args.remove(0),
+        _ => config.brain_path.clone(),
+    };
+    let bank = match PatternBank::load(Path::new(&brain_path)) {
+        Ok(bank) => bank,
+        Err(e) => {
+            error!(
+                "  ❌ Aivojen '{}' lataus epäonnistui: {}",
+                "  ❌ Failed to load brain '{}': {}", brain_path, e
+            );
+            return;
+        }
+    };
+
+    if args.is_empty() {
+        inspect::print_summary(&bank);
+        return inspect::print_list(&bank, &inspect::select(&bank, &filter), limit);
+    }
+    let builder = Builder::with_bank(bank);
+    for arg in &args {
+        match arg
+            .trim_start_matches("P_")
+            .parse::<u32>()
+            .ok()
+            .filter(|&id| builder.bank.get(id).is_some())
+        {
+            Some(id) => inspect::print_pattern(&builder, id),
+            None => error!(
+                "  ❌ '{}' ei ole aivojen malli.",
+                "  ❌ '{}' is not a pattern in the brain.", arg
+            ),
+        }
+    }
+}
@@ -0,0 +1,1360 @@
+// src/commands/learn.rs
+//
+// Oppimisajo (oletusalikomento `learn`): pääsilmukka, joka syöttää dataa
+// moottorille, säätää syöttönopeutta ja tallentaa aivot, kirjanmerkin ja
+// istunnon.
+
+use crate::checkpoint::{Autosave, Checkpointer};
+use crate::cli::flag_value;
+use crate::session::{RegulatorState, Session};
+use crate::{
+    Config, FEEDER_STATE_PATH, PROFILES_DIR, QUARANTINE_PATH, RESULTS_PATH, cli, exit_usage,
+    load_or_create_brain,
+};
+use petri_dish::engine::{Engine, create_engine, restore_engine};
+use petri_dish::evaluator::Evaluator;
+use petri_dish::feeder::Feeder;
+use petri_dish::i18n::tr;
+use petri_dish::markov::MarkovBaseline;
+use petri_dish::stop::{StopMonitor, StopReason};
+use petri_dish::term::{Level, detail, error, say, warning};
+use petri_dish::{
+    analysis, archive, drift, feeder, grammar, heatmap, prior, progress, quarantine, recommend,
+    render, snapshot, stop, term, wordlist,
+};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Oppimisajo: `args` on tarkistettu, `explicit` luettelee komentorivillä
+/// annetut asetukset, joita jatkettu istunto ei ohita
+pub fn run(mut config: Config, explicit: &[&str], args: &[String]) {
+    if let Some(value) = flag_value(args, "--watch-interval")
+        && cli::parse_seconds(&value).is_none()
+    {
+        exit_usage(cli::ArgError::Invalid("--watch-interval".into(), value));
+    }
+
+    // Istunto: `--resume polku` jatkaa tarkasti, `--session polku` tallentaa
+    let mut resumed = None;
+    let mut resumed_reports = None;
+    let session_path = flag_value(args, "--resume").or_else(|| flag_value(args, "--session"));
+    if let Some(path) = flag_value(args, "--resume") {
+        match Session::load(Path::new(&path)) {
+            Ok(session) => {
+                say!(
+                    "  ♻️  Jatketaan istuntoa '{}'.",
+                    "  ♻️  Resuming session '{}'.",
+                    path
+                );
+                // Istunnon konfiguraatio pohjana, annetut asetukset voittavat
+                config = config.resume_from(session.config, explicit);
+                resumed = Some((session.engine, session.feeder, session.regulator));
+                resumed_reports = Some((session.markov, session.heatmap));
+            }
+            Err(e) => {
+                error!(
+                    "  ❌ Istunnon lataus epäonnistui: {}",
+                    "  ❌ Failed to load session: {}", e
+                );
+                return;
+            }
+        }
+    }
+
+    // Profiilin tiedostot (jatketussa istunnossa istunnon profiili)
+    let feeder_state_path = config.profile_path(FEEDER_STATE_PATH);
+    let quarantine_path = config.profile_path(QUARANTINE_PATH);
+    let results_path = config.profile_path(RESULTS_PATH);
+    if let Some(name) = &config.profile {
+        let dir = Path::new(PROFILES_DIR).join(name);
+        if let Err(e) = fs::create_dir_all(&dir) {
+            error!(
+                "  ❌ Profiilikansion '{}' luonti epäonnistui: {}",
+                "  ❌ Failed to create profile directory '{}': {}",
+                dir.display(),
+                e
+            );
+            return;
+        }
+    }
+
+    say!(
+        "=== Petrimalja Älykkyyelle: HIERARKKINEN TIEDONRAKENNUSKONE ===\n",
+        "=== Petri Dish for Intelligence: HIERARCHICAL KNOWLEDGE BUILDER ===\n"
+    );
+    say!(
+        "Ydinfilosofia: \"Totuus on pysyvä yhteys kahden asian välillä.\"\n",
+        "Core philosophy: \"Truth is a lasting connection between two things.\"\n"
+    );
+    say!(
+        "Petri Dish 2.0: \"Ikuinen Oppija\" - Pysyvä muisti + Adaptiivinen oppiminen.\n",
+        "Petri Dish 2.0: \"Eternal Learner\" - Persistent memory + Adaptive learning.\n"
+    );
+
+    let (mut engine, feeder_state, regulator, bank_capacity, brain_tokenization) = match resumed {
+        Some((state, feeder_state, regulator)) => {
+            let bank = state.bank();
+            let capacity = bank.capacity();
+            let tokenization = (bank.tokenization(), bank.lexeme_count());
+            let engine = match restore_engine(state, &config.builder_settings()) {
+                Ok(engine) => engine,
+                Err(e) => {
+                    error!(
+                        "  ❌ Istunnon lataus epäonnistui: {}",
+                        "  ❌ Failed to load session: {}", e
+                    );
+                    return;
+                }
+            };
+            (
+                engine,
+                Some(feeder_state),
+                regulator,
+                capacity,
+                tokenization,
+            )
+        }
+        None => {
+            // Lataa olemassa olevat aivot tai luo uudet
+            let mut brain = load_or_create_brain(&config);
+            // Tunnettu rakenne valmiiksi (`--seed-grammar polku`)
+            if let Some(path) = flag_value(args, "--seed-grammar") {
+                let strength = flag_value(args, "--seed-strength")
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(0.8);
+                match fs::read_to_string(&path)
+                    .map_err(|e| e.to_string())
+                    .and_then(|text| grammar::import(&mut brain, &text, strength))
+                {
+                    Ok(summary) => say!(
+                        "  🌱 Kielioppi '{}': {} sääntöä, {} uutta mallia.",
+                        "  🌱 Grammar '{}': {} rules, {} new patterns.",
+                        path,
+                        summary.rules,
+                        summary.created
+                    ),
+                    Err(e) => {
+                        error!(
+                            "  ❌ Kieliopin tuonti epäonnistui: {}",
+                            "  ❌ Grammar import failed: {}", e
+                        );
+                        return;
+                    }
+                }
+            }
+            // Toimialan sanasto (`--seed-words polku`, kiinnitys `--pin-words`)
+            if let Some(path) = flag_value(args, "--seed-words") {
+                let strength = flag_value(args, "--seed-strength")
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(0.8);
+                let pin = args.iter().any(|a| a == "--pin-words");
+                match fs::read_to_string(&path) {
+                    Ok(text) => {
+                        let summary = wordlist::teach(&mut brain, &text, strength, pin);
+                        say!(
+                            "  🌱 Sanasto '{}': {} riviä, {} uutta mallia.",
+                            "  🌱 Wordlist '{}': {} entries, {} new patterns.",
+                            path,
+                            summary.entries,
+                            summary.created
+                        );
+                        if summary.skipped > 0 {
+                            warning!(
+                                "  ⚠️  Pankki täyttyi: {} riviä jäi pois.",
+                                "  ⚠️  Bank full: {} entries left out.",
+                                summary.skipped
+                            );
+                        }
+                    }
+                    Err(e) => {
+                        error!(
+                            "  ❌ Sanaston luku epäonnistui: {}",
+                            "  ❌ Failed to read wordlist: {}", e
+                        );
+                        return;
+                    }
+                }
+            }
+            // Yleisimmät parit valmiiksi korpuksen esiskannauksesta
+            // (`--bigram-prior K`, budjetti `--prior-bytes N`)
+            if let Some(top_k) = flag_value(args, "--bigram-prior").and_then(|v| v.parse().ok()) {
+                let max_bytes = flag_value(args, "--prior-bytes")
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(prior::DEFAULT_SCAN_BYTES);
+                let files =
+                    Feeder::list_files(config.data_dir(), &config.extensions).unwrap_or_default();
+                let summary = prior::apply(&mut brain, &files, top_k, max_bytes);
+                say!(
+                    "  🌱 Esiskannaus: {} tavua, {} uutta parimallia.",
+                    "  🌱 Pre-scan: {} bytes, {} new pair patterns.",
+                    summary.scanned,
+                    summary.created
+                );
+            }
+            let capacity = brain.capacity();
+            let tokenization = (brain.tokenization(), brain.lexeme_count());
+            // Luo moottori ladatulla PatternBankilla
+            let engine = create_engine(config.engine, brain, &config.builder_settings());
+            (
+                engine,
+                None,
+                RegulatorState::default(),
+                capacity,
+                tokenization,
+            )
+        }
+    };
+
+    // Luo Feeder ja lataa edellinen tila (kirjanmerkki)
+    let feeder_result =
+        Feeder::with_extensions(config.feed_rate, config.data_dir(), &config.extensions);
+
+    let mut feeder = match feeder_result {
+        Ok(mut f) => {
+            // Yritä ladata vanha tila (istunnosta tai kirjanmerkistä)
+            match &feeder_state {
+                Some(state) => f.restore_state(state),
+                None => f.load_state(&feeder_state_path),
+            }
+            f.set_transform(config.transform);
+            f.set_row_hint(config.row_width);
+            f.set_markup(config.markup);
+            f.set_boundaries(config.boundaries);
+            f.set_entropy_gate(config.entropy_gate);
+            f.set_known_threshold(config.skip_familiar);
+            if let Some(threshold) = config.quarantine {
+                match quarantine::Quarantine::open(
+                    Path::new(&quarantine_path),
+                    threshold,
+                    quarantine::DEFAULT_MAX_BYTES,
+                ) {
+                    Ok(q) => {
+                        if !q.is_empty() {
+                            say!(
+                                "  🧫 Karanteenissa {} palaa ({} tavua) edellisistä ajoista.",
+                                "  🧫 Quarantine holds {} chunks ({} bytes) from earlier runs.",
+                                q.len(),
+                                q.bytes()
+                            );
+                        }
+                        f.set_quarantine(Some(q));
+                    }
+                    Err(e) => warning!(
+                        "  ⚠️  Karanteeni pois käytöstä: {}",
+                        "  ⚠️  Quarantine disabled: {}",
+                        e
+                    ),
+                }
+            }
+            f.start_readers(config.readers);
+            f
+        }
+        Err(e) => {
+            warning!(
+                "⚠️  Datakansio '{}' ei löydy tai on tyhjä: {}",
+                "⚠️  Data folder '{}' is missing or empty: {}",
+                config.data_dir(),
+                e
+            );
+            say!(
+                "    Luodaan esimerkkidata demonstraatiota varten...\n",
+                "    Creating sample data for a demonstration...\n"
+            );
+
+            // Syötä esimerkkidata suoraan moottoriin
+            let sample_text = b"funktio on joka funktio on joka funktio on joka \
+                               tama on esimerkki tama on esimerkki tama on esimerkki \
+                               alku alku alku loppu loppu loppu \
+                               aabbaabbaabb ccddccddccdd";
+
+            engine.feed(sample_text);
+
+            say!(
+                "Aloitustilanne (esimerkkidata):",
+                "Initial state (sample data):"
+            );
+            say!("  Syötetty: {} tavua", "  Fed: {} bytes", sample_text.len());
+            say!(
+                "  Token-virta: {} tokenia",
+                "  Token stream: {} tokens",
+                engine.stream_len()
+            );
+            say!(
+                "  PatternBank: {} mallia (256 literaalia + 3 luokkaa)",
+                "  PatternBank: {} patterns (256 literals + 3 classes)",
+                engine.symbol_count()
+            );
+            say!(
+                "\n--- Aloitetaan hierarkkinen oppiminen ---\n",
+                "\n--- Starting hierarchical learning ---\n"
+            );
+
+            // Aja oppimissyklit
+            let evaluator = Evaluator::with_coder(config.coder);
+
+            for _ in 0..config.max_cycles {
+                let stats = engine.step(true);
+
+                if stats.patterns_created > 0 || stats.patterns_collapsed > 0 {
+                    stats.print();
+                }
+
+                // Lopeta jos virta ei enää tiivisty
+                if stats.stream_before == stats.stream_after && stats.patterns_created == 0 {
+                    break;
+                }
+            }
+
+            say!("\n=== LOPPUTILANNE ===", "\n=== FINAL STATE ===");
+            evaluator.print_analysis(engine.as_ref());
+
+            // Tulosta muutama esimerkki opituista malleista: eniten
+            // käytetyt vahvat mallit
+            if let Some(bank) = engine.bank() {
+                for (id, pattern) in bank.most_used(10) {
+                    say!(
+                        "     P_{}: {} [taso {}, käyttö {}, vahvuus {:.2}, viittauksia {}]",
+                        "     P_{}: {} [level {}, usage {}, strength {:.2}, refs {}]",
+                        id,
+                        render::label(&bank.decode(id), render::PREVIEW_LEN),
+                        pattern.complexity,
+                        pattern.usage_count,
+                        pattern.strength,
+                        pattern.ref_count
+                    );
+                }
+            }
+
+            // Tallenna aivot
+            save_brain(engine.as_ref(), &config.brain_path);
+
+            say!("\n✅ Demonstraatio valmis!", "\n✅ Demonstration complete!");
+            return;
+        }
+    };
+
+    // Luo Evaluator
+    let evaluator = Evaluator::with_coder(config.coder);
+
+    say!("\nAloitustilanne:", "\nInitial state:");
+    say!("  Moottori: {}", "  Engine: {}", engine.name());
+    say!(
+        "  PatternBank kapasiteetti: {} mallia",
+        "  PatternBank capacity: {} patterns",
+        bank_capacity
+    );
+    say!(
+        "  Olemassa olevia malleja: {} (256 literaalia + 3 luokkaa + {} combine)",
+        "  Existing patterns: {} (256 literals + 3 classes + {} combine)",
+        engine.symbol_count(),
+        engine.model_size()
+    );
+    say!(
+        "  Feeder nopeus: {} tavua/sykli (perus)",
+        "  Feeder rate: {} bytes/cycle (base)",
+        config.feed_rate
+    );
+    say!(
+        "  Parin kynnys: {} esiintymää",
+        "  Pair threshold: {} occurrences",
+        config.pair_threshold
+    );
+    if !config.level_thresholds.is_empty() {
+        let table: Vec<String> = config
+            .level_thresholds
+            .iter()
+            .map(|t| t.to_string())
+            .collect();
+        say!(
+            "  Tasokynnykset (taso 1, 2, ...): {}",
+            "  Level thresholds (level 1, 2, ...): {}",
+            table.join(", ")
+        );
+    }
+    if !config.collapse_thresholds.is_empty() {
+        let table: Vec<String> = config
+            .collapse_thresholds
+            .iter()
+            .map(|t| t.to_string())
+            .collect();
+        say!(
+            "  Tiivistyskynnykset (taso 1, 2, ...): {}",
+            "  Collapse thresholds (level 1, 2, ...): {}",
+            table.join(", ")
+        );
+    }
+    if let Some(decay) = config.decay {
+        say!("  Heikkeneminen: {} / sykli", "  Decay: {} / cycle", decay);
+    }
+    if let Some(dwell) = config.min_dwell {
+        say!(
+            "  Vähimmäisviipymä: {} sykliä",
+            "  Minimum dwell: {} cycles",
+            dwell
+        );
+    }
+    if let Some(budget) = config.brain_budget {
+        say!(
+            "  Aivojen kokobudjetti: {} tavua (ylitys unohtaa vähiten säästäviä malleja)",
+            "  Brain size budget: {} bytes (overruns forget the least saving patterns)",
+            budget
+        );
+    }
+    // Seurantatila (`--watch`, väli `--watch-interval S`): data loppuu vain
+    // hetkeksi, joten syklien enimmäismäärä ei rajaa ajoa
+    let watch = args.iter().any(|a| a == "--watch").then(|| {
+        flag_value(args, "--watch-interval")
+            .and_then(|v| cli::parse_seconds(&v))
+            .unwrap_or(feeder::DEFAULT_WATCH_INTERVAL)
+    });
+    let max_cycles = if watch.is_some() {
+        usize::MAX
+    } else {
+        config.max_cycles
+    };
+    match watch {
+        Some(interval) => say!(
+            "  Seurantatila: '{}' (varaskannaus {:.1} s välein), ei syklirajaa",
+            "  Watch mode: '{}' (fallback scan every {:.1} s), no cycle limit",
+            config.data_dir(),
+            interval.as_secs_f64()
+        ),
+        None => say!(
+            "  Maksimi syklit: {}",
+            "  Maximum cycles: {}",
+            config.max_cycles
+        ),
+    }
+    say!(
+        "  Esikäsittely: {}",
+        "  Preprocessing: {}",
+        config.transform.name()
+    );
+    say!("  Merkintäkieli: {}", "  Markup: {}", config.markup.name());
+    say!(
+        "  Tietuerajat: {}",
+        "  Record boundaries: {}",
+        config.boundaries.name()
+    );
+    say!(
+        "  Tokenisointi: {} ({} leksemiä)",
+        "  Tokenization: {} ({} lexemes)",
+        brain_tokenization.0.name(),
+        brain_tokenization.1
+    );
+    say!(
+        "  Entropiakooderi: {}",
+        "  Entropy coder: {}",
+        config.coder.name()
+    );
+    if let Some(seed) = config.seed {
+        say!(
+            "  Siemen: {} (toistettava ajo)",
+            "  Seed: {} (reproducible run)",
+            seed
+        );
+    }
+    if let Some(threshold) = config.quarantine {
+        say!(
+            "  Karanteeni: palat alle {:.0}% tuttuuden -> {}",
+            "  Quarantine: chunks below {:.0}% familiarity -> {}",
+            threshold * 100.0,
+            quarantine_path
+        );
+    }
+    say!(
+        "  Jäsennyspolitiikka: {}",
+        "  Collapse policy: {}",
+        config.collapse.name()
+    );
+    if let Some(name) = &config.profile {
+        say!("  Profiili: {}", "  Profile: {}", name);
+    }
+    say!(
+        "  Aivojen tallennuspolku: {}",
+        "  Brain save path: {}",
+        config.brain_path
+    );
+    say!(
+        "  Tylsistymiskynnys: {:.0}%",
+        "  Boredom threshold: {:.0}%",
+        config.boredom_threshold * 100.0
+    );
+    say!(
+        "  Uteliaisuuskynnys: {:.0}%",
+        "  Curiosity threshold: {:.0}%",
+        config.curiosity_threshold * 100.0
+    );
+
+    // Avaa CSV-tiedosto (jatketussa istunnossa lisätään perään)
+    let is_resumed = feeder_state.is_some();
+    let mut csv_file = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(is_resumed)
+        .truncate(!is_resumed)
+        .open(&results_path)
+        .unwrap_or_else(|e| {
+            panic!(
+                "{}",
+                tr!(
+                    "CSV-tiedoston luonti epäonnistui: {}",
+                    "Failed to create CSV file: {}",
+                    e
+                )
+            )
+        });
+    if !is_resumed {
+        writeln!(
+            csv_file,
+            "cycle,stream_len,original_len,patterns_count,compression_ratio,patterns_created,patterns_collapsed,familiarity,mode,fam_1k,fam_10k,fam_100k,fam_sampled,bpb,markov1_bpb,markov2_bpb,markov3_bpb,drift,oscillated,rejected"
+        )
+        .unwrap_or_else(|e| {
+            panic!(
+                "{}",
+                tr!(
+                    "CSV-otsikkojen kirjoitus epäonnistui: {}",
+                    "Failed to write CSV header: {}",
+                    e
+                )
+            )
+        });
+    }
+
+    say!(
+        "\n--- Aloitetaan hierarkkinen oppiminen (Adaptiivinen moodi) ---\n",
+        "\n--- Starting hierarchical learning (Adaptive mode) ---\n"
+    );
+
+    // Pääsilmukka - ADAPTIIVINEN VERSIO
+    let mut cycle = regulator.cycle;
+    let mut last_stream_len = regulator.last_stream_len;
+    let mut stagnant_cycles = regulator.stagnant_cycles;
+    let mut drift = regulator.drift;
+    let base_rate = config.feed_rate;
+    // Vertailumalli näkee samat tavut kuin moottori; jatketussa ajossa
+    // se ja tuttuuskartta jatkavat istunnon tilasta
+    let (mut markov, mut heatmap): (MarkovBaseline, heatmap::FamiliarityMap) =
+        resumed_reports.unwrap_or_default();
+    let mut autosave = config
+        .autosave
+        .is_enabled()
+        .then(|| Autosave::new(config.autosave.clone(), feeder.total_fed));
+    let mut checkpointer = if config.checkpoint.is_enabled() {
+        match Checkpointer::new(config.checkpoint.clone()) {
+            Ok(c) => Some(c),
+            Err(e) => {
+                warning!(
+                    "  ⚠️  Välitallennukset pois käytöstä: {}",
+                    "  ⚠️  Checkpoints disabled: {}",
+                    e
+                );
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    // Pankin tilannekuvat (`--snapshots polku`, väli `--snapshot-every N`)
+    let mut snapshots = flag_value(args, "--snapshots").and_then(|path| {
+        let every = flag_value(args, "--snapshot-every")
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(snapshot::DEFAULT_EVERY);
+        match snapshot::SnapshotWriter::create(Path::new(&path), every, is_resumed) {
+            Ok(writer) => Some(writer),
+            Err(e) => {
+                warning!(
+                    "  ⚠️  Tilannekuvat pois käytöstä: {}",
+                    "  ⚠️  Snapshots disabled: {}",
+                    e
+                );
+                None
+            }
+        }
+    });
+
+    // Holdout-joukko: pysäytysehto ajon aikana ja suositukset lopussa
+    let holdout = flag_value(args, "--holdout");
+    let holdout_files = match &holdout {
+        Some(dir) => Feeder::list_files(dir, &config.extensions).unwrap_or_else(|e| {
+            warning!(
+                "  ⚠️  Holdout-kansion luku epäonnistui: {}",
+                "  ⚠️  Failed to read holdout folder: {}",
+                e
+            );
+            Vec::new()
+        }),
+        None => Vec::new(),
+    };
+    let holdout_sample = if config.stop.target_holdout_bpb.is_some() {
+        stop::load_holdout(&holdout_files)
+    } else {
+        Vec::new()
+    };
+    if config.stop.target_holdout_bpb.is_some() && holdout_sample.is_empty() {
+        warning!(
+            "  ⚠️  Holdout-tavoite ohitetaan: anna data --holdout-kansiolla",
+            "  ⚠️  Ignoring holdout target: provide data with a --holdout folder"
+        );
+    }
+    let mut stop_monitor = StopMonitor::new(config.stop.clone(), holdout_sample, config.coder);
+    stop_monitor.restore(regulator.stop);
+    let mut stop_reason = StopReason::MaxCycles;
+    // Edistyminen ja arvio jäljellä olevasta ajasta korpuksen koosta
+    let eta = progress::Eta::new(feeder.corpus_progress().0);
+
+    // Ctrl-C / SIGTERM: lopetetaan syklin päätyttyä, jolloin aivot,
+    // kirjanmerkki ja istunto tallennetaan kuten normaalissa lopussa.
+    // Toinen painallus lopettaa heti tallentamatta.
+    let interrupted = Arc::new(AtomicBool::new(false));
+    let flag = Arc::clone(&interrupted);
+    let handler = ctrlc::set_handler(move || {
+        if flag.swap(true, Ordering::SeqCst) {
+            std::process::exit(130);
+        }
+        warning!(
+            "\n  ✋ Keskeytys: tallennetaan tila syklin päätyttyä (uudelleen = lopeta heti).",
+            "\n  ✋ Interrupt: saving state after this cycle (again = quit now)."
+        );
+    });
+    if let Err(e) = handler {
+        warning!(
+            "  ⚠️  Keskeytyksen käsittelijää ei voitu asentaa: {}",
+            "  ⚠️  Could not install the interrupt handler: {}",
+            e
+        );
+    }
+
+    while cycle < max_cycles {
+        if interrupted.load(Ordering::SeqCst) {
+            stop_reason = StopReason::Interrupted;
+            break;
+        }
+        // Seurantatila: tallenna ja odota uutta dataa lopettamisen sijaan
+        if let Some(interval) = watch
+            && feeder.is_depleted()
+        {
+            save_brain(engine.as_ref(), &config.brain_path);
+            if let Err(e) = feeder.save_state(&feeder_state_path) {
+                warning!(
+                    "  ⚠️  Feederin tilan tallennus epäonnistui: {}",
+                    "  ⚠️  Failed to save feeder state: {}",
+                    e
+                );
+            }
+            say!(
+                "  👀 Odotetaan uutta dataa kansioon '{}'...",
+                "  👀 Waiting for new data in '{}'...",
+                config.data_dir()
+            );
+            let added = feeder.wait_for_data(interval, &interrupted);
+            if added == 0 {
+                // Keskeytetty odotus: tallennus kuten normaalissa lopussa
+                stop_reason = StopReason::Interrupted;
+                break;
+            }
+            say!(
+                "  👀 {} uutta tai jatkettua tiedostoa, jatketaan oppimista.",
+                "  👀 {} new or appended files, resuming learning.",
+                added
+            );
+            stagnant_cycles = 0;
+        }
+        cycle += 1;
+        // Syklin numero jokaiselle sen aikana tulostetulle JSON-riville
+        let _cycle_span = tracing::info_span!("cycle", cycle).entered();
+
+        // 1. MITTAA: Kuinka hyvin ymmärsimme edellisen kierroksen?
+        // Katsotaan viimeistä 1000 tokenia
+        let familiarity = engine.familiarity(1000);
+
+        // 2. SÄÄDÄ: Päätä nopeus ja strategia tuttuuden perusteella
+        let (new_rate, do_explore, mode_str) = if familiarity > config.boredom_threshold {
+            // TYLSÄÄ: Juokse läpi!
+            // 5x nopeus, ei uusien etsimistä (säästää aikaa), vain vanhan käyttöä
+            (base_rate * 5, false, "SPEED ⏩")
+        } else if familiarity < config.curiosity_threshold {
+            // VAIKEAA: Hidasta ja tutki!
+            // 0.5x nopeus, etsi aggressiivisesti uusia malleja
+            (((base_rate as f64) * 0.5) as usize, true, "FOCUS 🔍")
+        } else {
+            // NORMAALI
+            (base_rate, true, "NORMAL 📖")
+        };
+
+        // Aseta uusi nopeus
+        feeder.set_feed_rate(new_rate);
+
+        // 3. SYÖTÄ: Hae uutta dataa
+        let fed = match feeder.feed_to_engine(engine.as_mut()) {
+            Ok(fed) => {
+                // Karanteeni: palauta palat, joille on nyt syntynyt malleja
+                let replayed = if cycle.is_multiple_of(quarantine::REPLAY_INTERVAL)
+                    || (fed == 0 && feeder.is_depleted())
+                {
+                    feeder.replay_quarantine(engine.as_mut())
+                } else {
+                    0
+                };
+                if replayed > 0 {
+                    say!(
+                        "     🧫 Karanteenista palautettiin {} tavua.",
+                        "     🧫 Replayed {} bytes from quarantine.",
+                        replayed
+                    );
+                }
+                let fed = fed + replayed;
+                if fed == 0 && feeder.is_depleted() && watch.is_none() {
+                    say!("  ✓ Kaikki data käsitelty.", "  ✓ All data processed.");
+                    stop_reason = StopReason::Depleted;
+                    break;
+                }
+                // Tulosta aina tilannekatsaus (JSON: syklin tapahtuma alla)
+                if fed > 0 && !term::is_json() {
+                    say!(
+                        "  {} Sykli {}: Fam {:.1}%, Rate {}, +{} tavua",
+                        "  {} Cycle {}: Fam {:.1}%, Rate {}, +{} bytes",
+                        mode_str,
+                        cycle,
+                        familiarity * 100.0,
+                        new_rate,
+                        fed
+                    );
+                    let (done, total) = feeder.corpus_progress();
+                    if total > 0 && watch.is_none() {
+                        let left = eta
+                            .remaining(done, total)
+                            .map_or("-".to_string(), progress::format_duration);
+                        say!(
+                            "     ⏳ {:.1}% korpuksesta ({} / {}), jäljellä noin {}",
+                            "     ⏳ {:.1}% of corpus ({} / {}), about {} left",
+                            done as f64 / total as f64 * 100.0,
+                            progress::format_bytes(done),
+                            progress::format_bytes(total),
+                            left
+                        );
+                    }
+                    if feeder.throttle() < 1.0 {
+                        say!(
+                            "     🚦 Vastapaine: syöttö {:.0}% (virta {} tok)",
+                            "     🚦 Backpressure: feeding {:.0}% (stream {} tok)",
+                            feeder.throttle() * 100.0,
+                            engine.stream_len()
+                        );
+                    }
+                }
+                fed
+            }
+            Err(e) => {
+                error!("❌ Virhe: {}", "❌ Error: {}", e);
+                stop_reason = StopReason::Error(e.to_string());
+                break;
+            }
+        };
+
+        // 4. OPPIMISSYKLI: Unohda, tutki (vain jos ollaan "uteliaita" tai
+        // "normaaleja"), tiivistä ja vanhenna
+        let stats = engine.step(do_explore);
+        markov.update(feeder.last_fed());
+        let created = stats.patterns_created;
+        let collapsed = stats.patterns_collapsed;
+        let forgotten = stats.patterns_forgotten;
+        let merged = stats.patterns_merged;
+        let oscillated = stats.patterns_oscillated;
+
+        // Kirjaa palan tuttuus korpuksen sijaintiin
+        if let Some(chunk) = feeder.last_chunk() {
+            let chunk_familiarity = engine.tail_familiarity(chunk.engine_bytes);
+            heatmap.record(chunk, cycle, chunk_familiarity, created);
+
+            // Datan muutos: saapuva data vieraampaa kuin aivot ovat tottuneet
+            if let Some(threshold) = config.drift_threshold
+                && chunk.engine_bytes > 0
+            {
+                match drift.observe(chunk_familiarity, threshold) {
+                    Some(drift::DriftEvent::Shifted { baseline, recent }) => warning!(
+                        "     🌊 Datan muutos: tuttuus {:.1}% (perustaso {:.1}%). Harkitse uudelleenkoulutusta tai plastisuuden lisäämistä (PETRI_DECAY, PETRI_PAIR_THRESHOLD).",
+                        "     🌊 Data drift: familiarity {:.1}% (baseline {:.1}%). Consider retraining or more plasticity (PETRI_DECAY, PETRI_PAIR_THRESHOLD).",
+                        recent * 100.0,
+                        baseline * 100.0
+                    ),
+                    Some(drift::DriftEvent::Recovered { baseline, recent }) => say!(
+                        "     🌊 Aivot sopeutuivat: tuttuus {:.1}% (perustaso {:.1}%).",
+                        "     🌊 Brain adapted: familiarity {:.1}% (baseline {:.1}%).",
+                        recent * 100.0,
+                        baseline * 100.0
+                    ),
+                    None => {}
+                }
+            }
+            feeder.note_familiarity(chunk_familiarity);
+        }
+
+        // Tulosta tilastot
+        if (created > 0 || collapsed > 0 || forgotten > 0 || merged > 0) && !term::is_json() {
+            say!(
+                "     📊 Virta: {} tok, Malleja: {} (+{} -{} ~{}) Tiiv: {}",
+                "     📊 Stream: {} tok, Patterns: {} (+{} -{} ~{}) Collapsed: {}",
+                engine.stream_len(),
+                engine.model_size(),
+                created,
+                forgotten,
+                merged,
+                collapsed
+            );
+        }
+        if (oscillated > 0 || stats.patterns_held > 0) && !term::is_json() {
+            say!(
+                "     〰️  Heilahtelua: {} mallia vaihtoi suuntaa, {} pidätettiin (PETRI_MIN_DWELL)",
+                "     〰️  Oscillation: {} patterns reversed, {} held back (PETRI_MIN_DWELL)",
+                oscillated,
+                stats.patterns_held
+            );
+        }
+
+        if stats.collapse_rejected > 0 && !term::is_json() {
+            detail!(
+                "     🚧 Tiivistyskynnyksen alle jäi {} paria (PETRI_COLLAPSE_THRESHOLDS)",
+                "     🚧 {} pairs stayed below the collapse threshold (PETRI_COLLAPSE_THRESHOLDS)",
+                stats.collapse_rejected
+            );
+        }
+
+        // Bittiä/tavu: hierarkia vs. pelkkä tavufrekvenssien mallinnus
+        let bpb = evaluator.bits_per_byte(engine.as_ref());
+        let markov_bpb = markov.bits_per_byte();
+        if fed > 0 && !term::is_json() {
+            say!(
+                "     📏 Bittiä/tavu: hierarkia {:.3} | markov-1 {:.3}, -2 {:.3}, -3 {:.3}",
+                "     📏 Bits/byte: hierarchy {:.3} | markov-1 {:.3}, -2 {:.3}, -3 {:.3}",
+                bpb,
+                markov_bpb[0],
+                markov_bpb[1],
+                markov_bpb[2]
+            );
+        }
+
+        // Kirjoita CSV
+        writeln!(
+            csv_file,
+            "{},{},{},{},{:.4},{},{},{:.4},{},{:.4},{:.4},{:.4},{:.4},{:.4},{:.4},{:.4},{:.4},{:.4},{},{}",
+            cycle,
+            engine.stream_len(),
+            engine.original_len(),
+            engine.model_size(),
+            evaluator.compression_ratio(engine.as_ref()),
+            created,
+            collapsed,
+            familiarity,
+            if do_explore { "explore" } else { "speed" },
+            stats.familiarity.horizons[0],
+            stats.familiarity.horizons[1],
+            stats.familiarity.horizons[2],
+            stats.familiarity.sampled,
+            bpb,
+            markov_bpb[0],
+            markov_bpb[1],
+            markov_bpb[2],
+            drift.score(),
+            oscillated,
+            stats.collapse_rejected
+        )
+        .unwrap_or_else(|e| {
+            panic!(
+                "{}",
+                tr!(
+                    "CSV-rivin kirjoitus epäonnistui: {}",
+                    "Failed to write CSV row: {}",
+                    e
+                )
+            )
+        });
+
+        // Rakenteinen lokirivi samoista luvuista
+        if term::is_json() {
+            let (done, total) = feeder.corpus_progress();
+            term::event(
+                Level::Info,
+                "cycle",
+                serde_json::json!({
+                    "cycle": cycle,
+                    "mode": if do_explore { "explore" } else { "speed" },
+                    "familiarity": familiarity,
+                    "rate": new_rate,
+                    "fed": fed,
+                    "throttle": feeder.throttle(),
+                    "stream_len": engine.stream_len(),
+                    "original_len": engine.original_len(),
+                    "patterns": engine.model_size(),
+                    "created": created,
+                    "forgotten": forgotten,
+                    "merged": merged,
+                    "oscillated": oscillated,
+                    "held": stats.patterns_held,
+                    "rejected": stats.collapse_rejected,
+                    "collapsed": collapsed,
+                    "compression_ratio": evaluator.compression_ratio(engine.as_ref()),
+                    "bpb": bpb,
+                    "markov_bpb": markov_bpb,
+                    "drift": drift.score(),
+                    "total_fed": feeder.total_fed,
+                    "corpus_done": done,
+                    "corpus_total": total,
+                    "eta_secs": eta.remaining(done, total).map(|d| d.as_secs()),
+                }),
+            );
+        }
+
+        // Tarkista stagnaatio
+        if engine.stream_len() == last_stream_len && fed == 0 {
+            stagnant_cycles += 1;
+        } else {
+            stagnant_cycles = 0;
+        }
+        last_stream_len = engine.stream_len();
+
+        // Välitallennus: pitkä ajo ei ole enää kaikki tai ei mitään
+        if let Some(checkpointer) = checkpointer.as_mut()
+            && checkpointer.is_due(cycle)
+        {
+            let regulator = RegulatorState {
+                cycle,
+                last_stream_len,
+                stagnant_cycles,
+                drift,
+                stop: stop_monitor.state(),
+            };
+            let session = Session::new(
+                config.clone(),
+                engine.state(),
+                feeder.state(),
+                regulator,
+                markov.clone(),
+                heatmap.clone(),
+            );
+            match checkpointer.save(cycle, &session, engine.as_ref()) {
+                Ok(path) => say!(
+                    "     💾 Välitallennus: {}",
+                    "     💾 Checkpoint: {}",
+                    path.display()
+                ),
+                Err(e) => warning!(
+                    "     ⚠️  Välitallennus epäonnistui: {}",
+                    "     ⚠️  Checkpoint failed: {}",
+                    e
+                ),
+            }
+        }
+
+        // Automaattitallennus: ajon omat aivot ja kirjanmerkki (atomisesti)
+        if let Some(autosave) = autosave.as_mut()
+            && autosave.is_due(cycle, feeder.total_fed)
+        {
+            let saved = engine
+                .save(Path::new(&config.brain_path))
+                .and_then(|()| feeder.save_state(&feeder_state_path));
+            match saved {
+                Ok(()) => detail!(
+                    "     💾 Automaattitallennus: {} ja {}",
+                    "     💾 Autosave: {} and {}",
+                    config.brain_path,
+                    feeder_state_path
+                ),
+                Err(e) => warning!(
+                    "     ⚠️  Automaattitallennus epäonnistui: {}",
+                    "     ⚠️  Autosave failed: {}",
+                    e
+                ),
+            }
+        }
+
+        if let Some(writer) = snapshots.as_mut()
+            && let Some(bank) = engine.bank()
+            && let Err(e) = writer.record(bank, cycle, engine.stream_len())
+        {
+            warning!(
+                "     ⚠️  Tilannekuva epäonnistui: {}",
+                "     ⚠️  Snapshot failed: {}",
+                e
+            );
+        }
+
+        // Lopeta jos feeder on tyhjä ja stagnaatio jatkuu
+        if feeder.is_depleted() && stagnant_cycles >= 5 && watch.is_none() {
+            say!(
+                "\n  ✓ Oppiminen saturoitunut ({} sykliä ilman muutosta)",
+                "\n  ✓ Learning saturated ({} cycles without change)",
+                stagnant_cycles
+            );
+            stop_reason = StopReason::Depleted;
+            break;
+        }
+
+        // Tavoitteet: suhde, holdout, vakaa pankki tai tavubudjetti
+        if stop_monitor.is_enabled()
+            && let Some(reason) = stop_monitor.check(
+                engine.as_ref(),
+                cycle,
+                evaluator.compression_ratio(engine.as_ref()),
+                feeder.total_fed,
+            )
+        {
+            say!(
+                "\n  🎯 Tavoite saavutettu: {}",
+                "\n  🎯 Goal reached: {}",
+                reason
+            );
+            stop_reason = reason;
+            break;
+        }
+    }
+
+    // Loppuraportti
+    say!("\n=== LOPPUTILANNE ===", "\n=== FINAL STATE ===");
+
+    match &stop_reason {
+        StopReason::Depleted => say!("✅ Kaikki data käsitelty!", "✅ All data processed!"),
+        StopReason::MaxCycles => warning!(
+            "⚠️  Keskeytettiin syklien maksimirajalla ({}).",
+            "⚠️  Stopped at the cycle limit ({}).",
+            config.max_cycles
+        ),
+        StopReason::Error(_) => error!("❌ Pysäytetty: {}", "❌ Stopped: {}", stop_reason),
+        StopReason::Interrupted => warning!(
+            "✋ Keskeytetty syklillä {}: tila tallennetaan.",
+            "✋ Interrupted at cycle {}: saving state.",
+            cycle
+        ),
+        _ => say!(
+            "🎯 Pysäytetty tavoitteeseen: {}",
+            "🎯 Stopped at goal: {}",
+            stop_reason
+        ),
+    }
+
+    if drift.is_drifting() {
+        warning!(
+            "🌊 Saapuva data poikkeaa yhä perustasosta ({:.1} %-yksikköä): aivot kaipaavat uudelleenkoulutusta.",
+            "🌊 Incoming data still differs from the baseline ({:.1} points): the brain needs retraining.",
+            drift.score() * 100.0
+        );
+    }
+
+    evaluator.print_analysis(engine.as_ref());
+    evaluator.print_policy_comparison(engine.as_ref());
+    if markov.bytes() > 0 {
+        let markov_bpb = markov.bits_per_byte();
+        say!(
+            "     Markov-vertailu ({} tavua): order-1 {:.3}, order-2 {:.3}, order-3 {:.3} bittiä/tavu",
+            "     Markov baseline ({} bytes): order-1 {:.3}, order-2 {:.3}, order-3 {:.3} bits/byte",
+            markov.bytes(),
+            markov_bpb[0],
+            markov_bpb[1],
+            markov_bpb[2]
+        );
+    }
+
+    // Token-frekvenssit ja Zipf-sovitus
+    let histogram = analysis::token_histogram(engine.as_ref());
+    let zipf = analysis::zipf_fit(&histogram);
+    analysis::print_summary(&zipf);
+    if let Some(prefix) = flag_value(args, "--token-stats") {
+        match analysis::export(&prefix, &histogram, &zipf) {
+            Ok(()) => say!(
+                "  📈 Token-tilastot: {}.csv, {}.json",
+                "  📈 Token statistics: {}.csv, {}.json",
+                prefix,
+                prefix
+            ),
+            Err(e) => warning!(
+                "  ⚠️  Token-tilastojen vienti epäonnistui: {}",
+                "  ⚠️  Token statistics export failed: {}",
+                e
+            ),
+        }
+    }
+
+    // Mitkä tiedostot opettivat jotain
+    let file_summaries = heatmap.file_summaries();
+    heatmap::print_file_report(&file_summaries, 10);
+    if let Some(path) = flag_value(args, "--file-report") {
+        match heatmap::export_file_report(&path, &file_summaries) {
+            Ok(()) => say!("  📚 Tiedostoraportti: {}", "  📚 File report: {}", path),
+            Err(e) => warning!(
+                "  ⚠️  Tiedostoraportin vienti epäonnistui: {}",
+                "  ⚠️  File report export failed: {}",
+                e
+            ),
+        }
+    }
+    // Aktiivinen oppiminen: mitä kannattaisi syöttää seuraavaksi
+    if args.iter().any(|a| a == "--recommend") || holdout.is_some() {
+        let top_n = flag_value(args, "--recommend")
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(10);
+        let candidates = match &holdout {
+            Some(_) => holdout_files.clone(),
+            None => feeder.pending_files(),
+        };
+        let ranked = recommend::rank(engine.as_ref(), &candidates);
+        recommend::print_recommendations(&ranked, top_n);
+    }
+
+    // Hierarkia kielioppina (`--grammar polku`, valinnainen vahvuusraja;
+    // `--grammar-stable-ids` nimeää säännöt rakenteen mukaan)
+    if let Some(path) = flag_value(args, "--grammar")
+        && let Some(bank) = engine.bank()
+    {
+        let min_strength = flag_value(args, "--grammar-min-strength")
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(0.0);
+        let naming = if args.iter().any(|a| a == "--grammar-stable-ids") {
+            grammar::Naming::Stable
+        } else {
+            grammar::Naming::Ids
+        };
+        match grammar::export(bank, Path::new(&path), min_strength, naming) {
+            Ok(rules) => say!(
+                "  📜 Kielioppi ({} sääntöä): {}",
+                "  📜 Grammar ({} rules): {}",
+                rules,
+                path
+            ),
+            Err(e) => warning!(
+                "  ⚠️  Kieliopin vienti epäonnistui: {}",
+                "  ⚠️  Grammar export failed: {}",
+                e
+            ),
+        }
+    }
+
+    if let Some(prefix) = flag_value(args, "--heatmap") {
+        match heatmap.export(&prefix) {
+            Ok(()) => say!(
+                "  🗺️  Tuttuuskartta ({} palaa): {}.csv, {}.json",
+                "  🗺️  Familiarity map ({} chunks): {}.csv, {}.json",
+                heatmap.len(),
+                prefix,
+                prefix
+            ),
+            Err(e) => warning!(
+                "  ⚠️  Tuttuuskartan vienti epäonnistui: {}",
+                "  ⚠️  Familiarity map export failed: {}",
+                e
+            ),
+        }
+    }
+
+    say!("\n  📊 Tilastot:", "\n  📊 Statistics:");
+    say!("     Syklit: {}", "     Cycles: {}", cycle);
+    say!(
+        "     Syötetty: {} tavua",
+        "     Fed: {} bytes",
+        feeder.total_fed
+    );
+    if feeder.skipped_bytes > 0 {
+        say!(
+            "     Ohitettu (korkea entropia): {} tavua",
+            "     Skipped (high entropy): {} bytes",
+            feeder.skipped_bytes
+        );
+    }
+    if feeder.known_bytes > 0 {
+        say!(
+            "     Ohitettu (jo opittu): {} tavua",
+            "     Skipped (already learned): {} bytes",
+            feeder.known_bytes
+        );
+    }
+    if let Some(q) = feeder.quarantine()
+        && (q.diverted > 0 || !q.is_empty())
+    {
+        say!(
+            "     Karanteeni: {} tavua eristetty, {} palautettu, {} hylätty; jäljellä {} palaa ({} tavua)",
+            "     Quarantine: {} bytes diverted, {} replayed, {} dropped; {} chunks left ({} bytes)",
+            q.diverted,
+            q.replayed,
+            q.dropped,
+            q.len(),
+            q.bytes()
+        );
+    }
+    say!(
+        "     Token-virta: {} tokenia",
+        "     Token stream: {} tokens",
+        engine.stream_len()
+    );
+    say!(
+        "     Combine-malleja: {}",
+        "     Combine patterns: {}",
+        engine.model_size()
+    );
+    if let Some(bank) = engine.bank() {
+        let size = bank.estimated_size();
+        match config.brain_budget {
+            Some(budget) => say!(
+                "     Aivotiedosto: {:.1} KiB ({:.0}% budjetista)",
+                "     Brain file: {:.1} KiB ({:.0}% of budget)",
+                size as f64 / 1024.0,
+                size as f64 / budget as f64 * 100.0
+            ),
+            None => say!(
+                "     Aivotiedosto: {:.1} KiB",
+                "     Brain file: {:.1} KiB",
+                size as f64 / 1024.0
+            ),
+        }
+    }
+
+    // Tulosta hierarkkiset mallit
+    engine.print_model(20);
+
+    // === TALLENNA TILA ===
+    say!("\n=== TALLENNETAAN TILA ===", "\n=== SAVING STATE ===");
+
+    // 1. Tallenna aivot
+    save_brain(engine.as_ref(), &config.brain_path);
+
+    // 2. Tallenna feederin tila (kirjanmerkki)
+    if let Err(e) = feeder.save_state(&feeder_state_path) {
+        warning!(
+            "  ⚠️  Feederin tilan tallennus epäonnistui: {}",
+            "  ⚠️  Failed to save feeder state: {}",
+            e
+        );
+    } else {
+        say!(
+            "  🔖 Kirjanmerkki tallennettu: {}",
+            "  🔖 Bookmark saved: {}",
+            feeder_state_path
+        );
+    }
+
+    // 3. Tallenna koko istunto (tarkkaa jatkamista varten)
+    if let Some(path) = &session_path {
+        let regulator = RegulatorState {
+            cycle,
+            last_stream_len,
+            stagnant_cycles,
+            drift,
+            stop: stop_monitor.state(),
+        };
+        let mut session = Session::new(
+            config.clone(),
+            engine.state(),
+            feeder.state(),
+            regulator,
+            markov,
+            heatmap,
+        );
+        session.stop_reason = Some(stop_reason.clone());
+        match session.save(Path::new(path)) {
+            Ok(()) => say!(
+                "  ♻️  Istunto tallennettu: {}",
+                "  ♻️  Session saved: {}",
+                path
+            ),
+            Err(e) => warning!(
+                "  ⚠️  Istunnon tallennus epäonnistui: {}",
+                "  ⚠️  Failed to save session: {}",
+                e
+            ),
+        }
+    }
+
+    // 4. Token-virta sellaisenaan (`--save-stream polku`), purku: `decode`
+    if let Some(path) = flag_value(args, "--save-stream") {
+        save_stream(engine.as_ref(), &config, &path);
+    }
+
+    say!(
+        "\n=== HIERARKKINEN TIEDONRAKENNUSKONE VALMIS ===",
+        "\n=== HIERARCHICAL KNOWLEDGE BUILDER DONE ==="
+    );
+    say!("\n📊 Analyysi:", "\n📊 Analysis:");
+    say!("  • CSV tallennettu: {}", "  • CSV saved: {}", results_path);
+    say!(
+        "  • Aivot tallennettu: {}",
+        "  • Brain saved: {}",
+        config.brain_path
+    );
+    say!(
+        "  • Kirjanmerkki tallennettu: {}",
+        "  • Bookmark saved: {}",
+        feeder_state_path
+    );
+    say!(
+        "  • Järjestelmä oppi kielen rakenteita hierarkkisesti",
+        "  • The system learned language structure hierarchically"
+    );
+    say!(
+        "  • Kirjaimista → tavuihin → sanoihin → lauseisiin",
+        "  • Letters → syllables → words → sentences"
+    );
+    say!(
+        "\n✅ \"Totuus on pysyvä yhteys kahden asian välillä.\"",
+        "\n✅ \"Truth is a lasting connection between two things.\""
+    );
+}
+
+/// Tallenna oppimisajon token-virta arkistoksi ja varmista purku
+/// (virran muunnos kumotaan purettaessa, ks. archive::save_stream)
+fn save_stream(engine: &dyn Engine, config: &Config, path: &str) {
+    let Some(bank) = engine.bank() else {
+        warning!(
+            "  ⚠️  Moottorilla {} ei ole mallipankkia: virtaa ei tallenneta.",
+            "  ⚠️  Engine {} has no pattern bank: stream not saved.",
+            engine.name()
+        );
+        return;
+    };
+    let saved = progress::with_spinner(tr!("Tallennetaan virtaa", "Saving stream"), || {
+        archive::save_stream(
+            engine.token_stream(),
+            "stream",
+            Path::new(path),
+            bank,
+            config.coder,
+            config.transform,
+        )
+    });
+    match saved {
+        Ok(summary) => say!(
+            "  🧵 Token-virta tallennettu: {} ({} tokenia, {} -> {} tavua), purku tarkistettu.",
+            "  🧵 Token stream saved: {} ({} tokens, {} -> {} bytes), decoding verified.",
+            path,
+            engine.stream_len(),
+            summary.original_bytes,
+            summary.archive_bytes
+        ),
+        Err(e) => warning!(
+            "  ⚠️  Virran tallennus epäonnistui: {}",
+            "  ⚠️  Failed to save stream: {}",
+            e
+        ),
+    }
+}
+
+/// Tallenna moottorin aivot tiedostoon
+fn save_brain(engine: &dyn Engine, path: &str) {
+    let path = Path::new(path);
+    let saved = progress::with_spinner(tr!("Tallennetaan aivoja", "Saving brain"), || {
+        engine.save(path)
+    });
+    match saved {
+        Ok(()) => say!(
+            "  💾 Aivot tallennettu tiedostoon '{}'.",
+            "  💾 Brain saved to '{}'.",
+            path.display()
+        ),
+        Err(e) => warning!(
+            "  ⚠️  Aivojen tallennus epäonnistui: {}",
+            "  ⚠️  Failed to save brain: {}",
+            e
+        ),
+    }
+}
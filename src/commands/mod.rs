@@ -0,0 +1,24 @@
+// src/commands/mod.rs
+//
+// Alikomennot: yksi moduuli kutakin `petri_dish <alikomento>`-komentoa
+// kohden. Yhteiset osat (konfiguraatio, aivojen lataus, asetukset) ovat
+// main.rs:ssä.
+
+pub mod archive;
+pub mod bench;
+pub mod cluster;
+pub mod decode;
+pub mod detect;
+pub mod diff;
+pub mod encode_file;
+pub mod extract;
+pub mod history;
+pub mod inspect;
+pub mod learn;
+pub mod plot;
+pub mod repair;
+pub mod repl;
+pub mod stats;
+pub mod survival;
+pub mod sweep;
+pub mod tournament;
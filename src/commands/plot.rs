@@ -0,0 +1,51 @@
+// src/commands/plot.rs
+//
+// Alikomento `plot`: ajon mittarit SVG-kuvaajiksi.
+
+use crate::{Config, RESULTS_PATH};
+use petri_dish::plot;
+use petri_dish::term::{error, say, warning};
+use std::fs;
+use std::path::Path;
+
+/// `plot [results.csv] [kuva.svg]`: ajon mittarit SVG-kuvaajiksi
+pub fn run(config: &Config, args: &[String]) {
+    let default_csv = config.profile_path(RESULTS_PATH);
+    let csv = args.first().unwrap_or(&default_csv).as_str();
+    let out = args
+        .get(1)
+        .cloned()
+        .unwrap_or_else(|| Path::new(csv).with_extension("svg").display().to_string());
+
+    let metrics = match plot::Metrics::load(Path::new(csv)) {
+        Ok(m) if !m.is_empty() => m,
+        Ok(_) => {
+            warning!(
+                "  ⚠️  '{}' ei sisällä mittausrivejä.",
+                "  ⚠️  '{}' has no metric rows.",
+                csv
+            );
+            return;
+        }
+        Err(e) => {
+            error!(
+                "  ❌ Tiedoston luku epäonnistui: {}",
+                "  ❌ Failed to read file: {}", e
+            );
+            return;
+        }
+    };
+    let svg = plot::render(&plot::default_panels(&metrics));
+    match fs::write(&out, svg) {
+        Ok(()) => say!(
+            "  📈 Kuvaajat ({} sykliä): {}",
+            "  📈 Charts ({} cycles): {}",
+            metrics.len(),
+            out
+        ),
+        Err(e) => error!(
+            "  ❌ Tallennus epäonnistui: {}",
+            "  ❌ Saving failed: {}", e
+        ),
+    }
+}
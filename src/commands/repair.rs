@@ -0,0 +1,153 @@
+// src/commands/repair.rs
+//
+// Alikomento `repair`: Re-Pair-laatukatto nykyisille aivoille.
+
+use crate::{Config, cli, load_brain_file};
+use petri_dish::builder::{BOUNDARY_TOKEN, Builder, PatternBank};
+use petri_dish::evaluator::Evaluator;
+use petri_dish::feeder::Feeder;
+use petri_dish::i18n::tr;
+use petri_dish::term::{error, say, warning};
+use petri_dish::{progress, repair};
+use std::fs;
+use std::path::Path;
+use std::time::Instant;
+
+/// `repair <hakemisto> [aivot.json]`: Re-Pair koko korpukselle ja vertailu
+/// nykyisiin aivoihin (online-oppijan laatukatto)
+pub fn run(config: &Config, args: &[String]) {
+    let Some(dir) = args.first() else {
+        cli::print_usage("repair");
+        return;
+    };
+    let files = match Feeder::list_files(dir, &config.extensions) {
+        Ok(files) if !files.is_empty() => files,
+        Ok(_) => {
+            warning!(
+                "  ⚠️  Hakemistossa '{}' ei ole syötettäviä tiedostoja.",
+                "  ⚠️  Directory '{}' has no files to feed.",
+                dir
+            );
+            return;
+        }
+        Err(e) => {
+            error!(
+                "  ❌ Hakemiston luku epäonnistui: {}",
+                "  ❌ Failed to read directory: {}", e
+            );
+            return;
+        }
+    };
+
+    // Sama tokenisointi kuin online-oppijalla, tiedostojen välissä raja
+    let mut bank = PatternBank::new(config.pattern_capacity);
+    if let Some(tokenization) = config.tokenization {
+        bank.set_tokenization(tokenization);
+    }
+    let mut corpus = Vec::new();
+    let mut stream = Vec::new();
+    let scan = progress::bar(
+        files.len() as u64,
+        tr!("Luetaan korpusta", "Reading corpus"),
+    );
+    for path in &files {
+        scan.inc(1);
+        match fs::read(path) {
+            Ok(data) => {
+                if !stream.is_empty() {
+                    stream.push(BOUNDARY_TOKEN);
+                }
+                stream.extend(bank.seed_ids(&data));
+                corpus.push(data);
+            }
+            Err(e) => warning!(
+                "  ⚠️  Ohitetaan '{}': {}",
+                "  ⚠️  Skipping '{}': {}",
+                path.display(),
+                e
+            ),
+        }
+    }
+    scan.finish_and_clear();
+    let original: usize = corpus.iter().map(Vec::len).sum();
+    say!(
+        "  🔁 Re-Pair: {} tiedostoa, {} tavua, {} siemen-tokenia...",
+        "  🔁 Re-Pair: {} files, {} bytes, {} seed tokens...",
+        corpus.len(),
+        original,
+        stream.len()
+    );
+
+    let start = Instant::now();
+    let stats = progress::with_spinner(tr!("Re-Pair käynnissä", "Running Re-Pair"), || {
+        repair::compress(&mut bank, &mut stream)
+    });
+    say!(
+        "  ✅ {} sääntöä, {} korvausta ({:.1} s)",
+        "  ✅ {} rules, {} replacements ({:.1} s)",
+        stats.rules,
+        stats.replacements,
+        start.elapsed().as_secs_f64()
+    );
+
+    let mut offline = Builder::with_bank(bank);
+    offline.token_stream = stream;
+    let evaluator = Evaluator::with_coder(config.coder);
+    evaluator.print_analysis(&offline);
+
+    // Online-oppijan aivot samalla korpuksella
+    let brain = load_brain_file(config);
+    if brain.combine_count() > 0 {
+        let mut online: Vec<u32> = Vec::new();
+        let encoding = progress::bar(
+            corpus.len() as u64,
+            tr!("Koodataan aivoilla", "Encoding with brain"),
+        );
+        for data in &corpus {
+            if !online.is_empty() {
+                online.push(BOUNDARY_TOKEN);
+            }
+            online.extend(brain.encode(data));
+            encoding.inc(1);
+        }
+        encoding.finish_and_clear();
+        let online_coded = config.coder.encode(&online).len();
+        let offline_coded = evaluator.coded_bytes(&offline);
+        let bits = |coded: usize| coded as f64 * 8.0 / original.max(1) as f64;
+        say!(
+            "\n  ⚖️  Vertailu ({}):",
+            "\n  ⚖️  Comparison ({}):",
+            config.coder.name()
+        );
+        say!(
+            "     Re-Pair: {} tokenia, {} mallia, {} tavua ({:.3} bittiä/tavu)",
+            "     Re-Pair: {} tokens, {} patterns, {} bytes ({:.3} bits/byte)",
+            offline.stream_len(),
+            offline.bank.combine_count(),
+            offline_coded,
+            bits(offline_coded)
+        );
+        say!(
+            "     Aivot:   {} tokenia, {} mallia, {} tavua ({:.3} bittiä/tavu)",
+            "     Brain:   {} tokens, {} patterns, {} bytes ({:.3} bits/byte)",
+            online.len(),
+            brain.combine_count(),
+            online_coded,
+            bits(online_coded)
+        );
+    }
+
+    if let Some(out) = args.get(1) {
+        match offline.bank.save(Path::new(out)) {
+            Ok(()) => say!(
+                "  💾 Re-Pair-aivot tallennettu: {}",
+                "  💾 Re-Pair brain saved: {}",
+                out
+            ),
+            Err(e) => error!(
+                "  ❌ Tallennus epäonnistui: {}",
+                "  ❌ Saving failed: {}", e
+            ),
+        }
+    }
+}
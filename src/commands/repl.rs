@@ -0,0 +1,34 @@
+// src/commands/repl.rs
+//
+// Alikomento `repl`: aivojen tutkiminen komento kerrallaan.
+
+use crate::Config;
+use petri_dish::builder::{Builder, PatternBank};
+use petri_dish::repl;
+use petri_dish::term::{error, say};
+use std::path::Path;
+
+/// `repl [aivot.json]`: aivojen tutkiminen komento kerrallaan
+pub fn run(config: &Config, args: &[String]) {
+    let brain_path = args
+        .first()
+        .cloned()
+        .unwrap_or_else(|| config.brain_path.clone());
+    let bank = match PatternBank::load(Path::new(&brain_path)) {
+        Ok(bank) => bank,
+        Err(e) => {
+            error!(
+                "  ❌ Aivojen '{}' lataus epäonnistui: {}",
+                "  ❌ Failed to load brain '{}': {}", brain_path, e
+            );
+            return;
+        }
+    };
+    say!(
+        "  🧫 {}: {} opittua mallia. Komennot: help, lopetus: quit",
+        "  🧫 {}: {} learned patterns. Commands: help, exit: quit",
+        brain_path,
+        bank.combine_count()
+    );
+    repl::run(&Builder::with_bank(bank), std::io::stdin().lock());
+}
@@ -0,0 +1,72 @@
+// src/commands/stats.rs
+//
+// Alikomento `stats`: ajon mittareiden yhteenveto.
+
+use crate::{Config, RESULTS_PATH};
+use petri_dish::i18n::tr;
+use petri_dish::term::{Level, error, say, warning};
+use petri_dish::{plot, term};
+use std::path::Path;
+
+/// `stats [results.csv]`: ajon mittareiden yhteenveto
+pub fn run(config: &Config, args: &[String]) {
+    let default_csv = config.profile_path(RESULTS_PATH);
+    let csv = args.first().unwrap_or(&default_csv).as_str();
+    let metrics = match plot::Metrics::load(Path::new(csv)) {
+        Ok(m) if !m.is_empty() => m,
+        Ok(_) => {
+            warning!(
+                "  ⚠️  '{}' ei sisällä mittausrivejä.",
+                "  ⚠️  '{}' has no metric rows.",
+                csv
+            );
+            return;
+        }
+        Err(e) => {
+            error!(
+                "  ❌ Tiedoston luku epäonnistui: {}",
+                "  ❌ Failed to read file: {}", e
+            );
+            return;
+        }
+    };
+    say!(
+        "\n  📊 Ajon mittarit ({}): {} sykliä",
+        "\n  📊 Run metrics ({}): {} cycles",
+        csv,
+        metrics.len()
+    );
+    say!(
+        "     Mittari                    alku      loppu        min        max",
+        "     Metric                    first       last        min        max"
+    );
+    let rows = [
+        ("patterns_count", tr!("Malleja", "Patterns")),
+        (
+            "compression_ratio",
+            tr!("Tiivistyssuhde", "Compression ratio"),
+        ),
+        ("bpb", tr!("Bittiä/tavu", "Bits/byte")),
+        ("familiarity", tr!("Tuttuus", "Familiarity")),
+    ];
+    for (column, name) in rows {
+        // Vanhemmista CSV:istä puuttuvat sarakkeet ohitetaan
+        let Some(points) = metrics.series(column, 1.0).filter(|p| !p.is_empty()) else {
+            continue;
+        };
+        let values = points.iter().map(|&(_, y)| y);
+        let min = values.clone().fold(f64::INFINITY, f64::min);
+        let max = values.fold(f64::NEG_INFINITY, f64::max);
+        term::emit(
+            Level::Info,
+            &format!(
+                "     {:<20} {:>10.4} {:>10.4} {:>10.4} {:>10.4}",
+                name,
+                points[0].1,
+                points[points.len() - 1].1,
+                min,
+                max
+            ),
+        );
+    }
+}
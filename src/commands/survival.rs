@@ -0,0 +1,36 @@
+// src/commands/survival.rs
+//
+// Alikomento `survival`: mallien elinajat tapahtumalokista.
+
+use crate::cli;
+use petri_dish::survival;
+use petri_dish::term::{error, warning};
+use std::fs;
+
+/// `survival <ajo.log>`: mallien elinajat JSON-tapahtumalokista
+pub fn run(args: &[String]) {
+    let Some(path) = args.first() else {
+        cli::print_usage("survival");
+        return;
+    };
+    let log =
+        match fs::File::open(path).and_then(|f| survival::read_log(std::io::BufReader::new(f))) {
+            Ok(log) => log,
+            Err(e) => {
+                error!(
+                    "  ❌ Tiedoston luku epäonnistui: {}",
+                    "  ❌ Failed to read file: {}", e
+                );
+                return;
+            }
+        };
+    if log.lifetimes.is_empty() {
+        warning!(
+            "  ⚠️  '{}' ei sisällä mallitapahtumia (tarvitaan --log-format json -vv).",
+            "  ⚠️  '{}' has no pattern events (needs --log-format json -vv).",
+            path
+        );
+        return;
+    }
+    survival::print_report(&log);
+}
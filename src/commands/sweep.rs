@@ -0,0 +1,94 @@
+// src/commands/sweep.rs
+//
+// Alikomento `sweep`: hyperparametrien vertailu lyhyillä ajoilla.
+
+use crate::cli::take_value;
+use crate::{Config, cli};
+use petri_dish::feeder::Feeder;
+use petri_dish::i18n::tr;
+use petri_dish::term::{error, say, warning};
+use petri_dish::{builder, progress, sweep};
+
+/// `sweep [nimi=arvot...] [valinnat]`: hyperparametrien vertailu lyhyillä ajoilla
+pub fn run(config: &Config, args: &[String]) {
+    let mut args = args.to_vec();
+    let usage = || cli::print_usage("sweep");
+    let mut invalid = None;
+    let mut number = |args: &mut Vec<String>, flag: &str| -> Option<u64> {
+        let value = take_value(args, flag)?;
+        let parsed = value.parse().ok();
+        if parsed.is_none() {
+            invalid = Some(format!("{} {}", flag, value));
+        }
+        parsed
+    };
+    let cycles = number(&mut args, "--cycles").map_or(sweep::DEFAULT_CYCLES, |c| c as usize);
+    let sample_bytes =
+        number(&mut args, "--sample-bytes").map_or(sweep::DEFAULT_SAMPLE_BYTES, |b| b as usize);
+    let samples = number(&mut args, "--samples").map(|s| s as usize);
+    let seed = config.seed.unwrap_or(0);
+    let jobs = number(&mut args, "--jobs").map_or(1, |j| j as usize);
+    let corpus = take_value(&mut args, "--corpus").unwrap_or_else(|| config.data_dir().to_string());
+    if let Some(arg) = invalid {
+        error!("  ❌ Virheellinen arvo: {}", "  ❌ Invalid value: {}", arg);
+        return usage();
+    }
+    let axes: Vec<sweep::Axis> = match args.iter().map(|a| sweep::Axis::parse(a)).collect() {
+        Ok(axes) => axes,
+        Err(e) => {
+            error!("  ❌ {}", "  ❌ {}", e);
+            return usage();
+        }
+    };
+
+    let current = sweep::Candidate {
+        pair_threshold: config.pair_threshold,
+        capacity: config.pattern_capacity,
+        decay: config.decay.unwrap_or(builder::DEFAULT_DECAY_RATE),
+        level_thresholds: config.level_thresholds.clone(),
+    };
+    let candidates = sweep::candidates(&current, &axes, samples, seed);
+    let files = match Feeder::list_files(&corpus, &config.extensions) {
+        Ok(files) => files,
+        Err(e) => {
+            error!(
+                "  ❌ Korpuksen luku epäonnistui: {}",
+                "  ❌ Failed to read corpus: {}", e
+            );
+            return;
+        }
+    };
+    let sample = sweep::Sample::load(&files, sample_bytes);
+    if sample.train.is_empty() || sample.holdout.is_empty() {
+        warning!(
+            "  ⚠️  Korpuksesta '{}' ei saatu näytettä ({}).",
+            "  ⚠️  No sample from corpus '{}' ({}).",
+            corpus,
+            config.extensions.join(", ")
+        );
+        return;
+    }
+    say!(
+        "  🧪 Sweep: {} ehdokasta, {} sykliä, {} opetus- ja {} holdout-tavua {} tiedostosta, {} säiettä.",
+        "  🧪 Sweep: {} candidates, {} cycles, {} training and {} holdout bytes from {} files, {} threads.",
+        candidates.len(),
+        cycles,
+        sample.train_bytes(),
+        sample.holdout.len(),
+        sample.train.len(),
+        jobs.max(1)
+    );
+
+    let running = progress::bar(candidates.len() as u64, tr!("Ehdokkaat", "Candidates"));
+    let trials = sweep::run_all(
+        &candidates,
+        &sample,
+        cycles,
+        config.tokenization,
+        config.coder,
+        jobs,
+        || running.inc(1),
+    );
+    running.finish_and_clear();
+    sweep::print_report(&trials, &current);
+}
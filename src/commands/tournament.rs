@@ -0,0 +1,117 @@
+// src/commands/tournament.rs
+//
+// Alikomento `tournament`: aivojen vertailu samalla korpuksella.
+
+use crate::cli::take_value;
+use crate::{Config, cli};
+use petri_dish::builder::PatternBank;
+use petri_dish::feeder::Feeder;
+use petri_dish::i18n::tr;
+use petri_dish::term::{error, say, warning};
+use petri_dish::{progress, tournament};
+use std::fs;
+use std::path::Path;
+
+/// `tournament <aivot.json>... --corpus <hakemisto>`: aivojen vertailu
+/// samalla korpuksella
+pub fn run(config: &Config, args: &[String]) {
+    let mut brain_paths = args.to_vec();
+    let corpus = take_value(&mut brain_paths, "--corpus");
+    let (Some(corpus), false) = (corpus, brain_paths.is_empty()) else {
+        cli::print_usage("tournament");
+        return;
+    };
+    let files = match Feeder::list_files(&corpus, &config.extensions) {
+        Ok(files) if !files.is_empty() => files,
+        Ok(_) => {
+            warning!(
+                "  ⚠️  Korpuksessa '{}' ei ole tiedostoja ({}).",
+                "  ⚠️  Corpus '{}' has no files ({}).",
+                corpus,
+                config.extensions.join(", ")
+            );
+            return;
+        }
+        Err(e) => {
+            error!(
+                "  ❌ Korpuksen luku epäonnistui: {}",
+                "  ❌ Failed to read corpus: {}", e
+            );
+            return;
+        }
+    };
+
+    let mut names = Vec::new();
+    let mut data = Vec::new();
+    for path in &files {
+        match fs::read(path) {
+            Ok(bytes) => {
+                names.push(path.display().to_string());
+                data.push(bytes);
+            }
+            Err(e) => warning!(
+                "  ⚠️  Ohitetaan '{}': {}",
+                "  ⚠️  Skipping '{}': {}",
+                path.display(),
+                e
+            ),
+        }
+    }
+    say!(
+        "  🏟️  Turnaus: {} aivoa, {} tiedostoa, {} tavua ({}).",
+        "  🏟️  Tournament: {} brains, {} files, {} bytes ({}).",
+        brain_paths.len(),
+        data.len(),
+        data.iter().map(Vec::len).sum::<usize>(),
+        config.coder.name()
+    );
+
+    let mut contestants = Vec::new();
+    let scoring = progress::bar(
+        (brain_paths.len() * data.len()) as u64,
+        tr!("Koodataan", "Encoding"),
+    );
+    for path in &brain_paths {
+        let path = Path::new(path);
+        let bank = match PatternBank::load(path) {
+            Ok(bank) => bank,
+            Err(e) => {
+                warning!(
+                    "  ⚠️  Ohitetaan '{}': {}",
+                    "  ⚠️  Skipping '{}': {}",
+                    path.display(),
+                    e
+                );
+                scoring.inc(data.len() as u64);
+                continue;
+            }
+        };
+        let scores = data
+            .iter()
+            .map(|bytes| {
+                scoring.inc(1);
+                tournament::score_file(&bank, bytes, config.coder)
+            })
+            .collect();
+        contestants.push(tournament::Contestant {
+            name: path
+                .file_stem()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .into_owned(),
+            patterns: bank.combine_count(),
+            files: scores,
+        });
+    }
+    scoring.finish_and_clear();
+
+    if contestants.is_empty() {
+        warning!(
+            "  ⚠️  Yhtään aivotiedostoa ei voitu ladata.",
+            "  ⚠️  No brain files could be loaded."
+        );
+        return;
+    }
+    tournament::rank(&mut contestants);
+    tournament::print_leaderboard(&contestants, &names);
+}
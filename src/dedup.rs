@@ -0,0 +1,134 @@
+// src/dedup.rs
+//
+// Dedup: Lähes samojen mallien tunnistus.
+//
+// Pankkiin kertyy ajan myötä malleja, jotka eroavat yhdellä merkillä:
+// "funktio " ja "funktio", "funktio" ja "funktia". Ne kilpailevat samoista
+// kohdista virrassa ja jakavat tilastonsa kahtia. Tämä moduuli löytää
+// parit, joiden editointietäisyys on 1; Builder päättää, kumpi säilyy.
+//
+// Haku käyttää poistonaapurustoja: jokaisesta merkkijonosta muodostetaan
+// kaikki yhden tavun poistot. Kaksi samanpituista jonoa, joilla on sama
+// poisto samasta kohdasta, eroavat yhdellä korvauksella; jono, jonka poisto
+// on toinen jono kokonaisuudessaan, on sen yhden tavun laajennus.
+
+use std::collections::HashMap;
+
+/// Lyhyemmät mallit ("th" vs "to") eroavat yhdellä merkillä sattumalta
+pub const MIN_NEAR_DUPLICATE_LEN: usize = 5;
+
+/// Poistonaapurusto kasvaa neliöllisesti: pitkiä malleja ei verrata
+pub const MAX_NEAR_DUPLICATE_LEN: usize = 64;
+
+/// Kuinka pidempi malli eroaa lyhyemmästä (tai samanpituiset toisistaan)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Relation {
+    /// Pidempi = lyhyempi + tavu
+    Append(u8),
+    /// Pidempi = tavu + lyhyempi
+    Prepend(u8),
+    /// Lisäys keskelle tai yhden tavun korvaus
+    Edit,
+}
+
+/// Lähes sama pari: (pidempi tai ensimmäinen, lyhyempi tai toinen, suhde)
+pub type NearDuplicate = (u32, u32, Relation);
+
+/// Etsi parit, joiden editointietäisyys on 1. Syöte: (id, tavut).
+/// Tulos on deterministinen (järjestetty ID:iden mukaan).
+pub fn find_near_duplicates(entries: &[(u32, Vec<u8>)]) -> Vec<NearDuplicate> {
+    let usable = |bytes: &Vec<u8>| {
+        (MIN_NEAR_DUPLICATE_LEN - 1..=MAX_NEAR_DUPLICATE_LEN).contains(&bytes.len())
+    };
+    let by_bytes: HashMap<&[u8], u32> = entries
+        .iter()
+        .filter(|(_, b)| usable(b))
+        .map(|(id, b)| (b.as_slice(), *id))
+        .collect();
+
+    let mut deletions: HashMap<(Vec<u8>, usize), Vec<u32>> = HashMap::new();
+    let mut found = Vec::new();
+    for (id, bytes) in entries.iter().filter(|(_, b)| usable(b)) {
+        if bytes.len() < MIN_NEAR_DUPLICATE_LEN {
+            continue;
+        }
+        for i in 0..bytes.len() {
+            let mut deleted = bytes.clone();
+            deleted.remove(i);
+
+            // Lisäys: poisto on toinen malli sellaisenaan
+            if let Some(&shorter) = by_bytes.get(deleted.as_slice()) {
+                let relation = if i == bytes.len() - 1 {
+                    Relation::Append(bytes[i])
+                } else if i == 0 {
+                    Relation::Prepend(bytes[0])
+                } else {
+                    Relation::Edit
+                };
+                found.push((*id, shorter, relation));
+            }
+
+            // Korvaus: sama poisto samasta kohdasta
+            deletions.entry((deleted, i)).or_default().push(*id);
+        }
+    }
+    for ids in deletions.values() {
+        for (k, &a) in ids.iter().enumerate() {
+            for &b in &ids[k + 1..] {
+                found.push((a.min(b), a.max(b), Relation::Edit));
+            }
+        }
+    }
+
+    // Toistuvat tavut ("aab" -> "ab") tuottavat saman parin useasti
+    found.sort_by_key(|&(a, b, r)| (a, b, r != Relation::Edit));
+    found.dedup_by_key(|&mut (a, b, _)| (a, b));
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::Builder;
+
+    #[test]
+    fn test_finds_and_merges_near_duplicates() {
+        let entries = vec![
+            (1, b"funktio".to_vec()),
+            (2, b"funktio ".to_vec()),
+            (3, b"funktia".to_vec()),
+            (4, b" funktio".to_vec()),
+            (5, b"abc".to_vec()),
+            (6, b"abd".to_vec()),
+        ];
+        let found = find_near_duplicates(&entries);
+        assert!(found.contains(&(2, 1, Relation::Append(b' '))));
+        assert!(found.contains(&(4, 1, Relation::Prepend(b' '))));
+        assert!(found.contains(&(1, 3, Relation::Edit)));
+        // Lyhyet mallit eivät ole lähes samoja
+        assert!(!found.iter().any(|&(a, b, _)| a == 5 || b == 5));
+
+        // Heikompi laajennus korvataan virrassa vahvemmalla + tavulla
+        let text = b"funktio funktio funktio".to_vec();
+        let mut builder = Builder::new(100);
+        builder.tokenize(&text);
+        let ids: Vec<u32> = "funktio"
+            .bytes()
+            .map(|b| builder.bank.literal_id(b))
+            .collect();
+        let mut word = ids[0];
+        for &id in &ids[1..] {
+            word = builder.bank.create_combine(word, id, 0).unwrap();
+        }
+        let space = builder.bank.literal_id(b' ');
+        let spaced = builder.bank.create_combine(word, space, 0).unwrap();
+        builder.bank.get_mut(word).unwrap().strength = 0.9;
+        builder.bank.get_mut(spaced).unwrap().strength = 0.6;
+        builder.token_stream = vec![spaced, spaced, word];
+
+        assert_eq!(builder.merge_near_duplicates(), 1);
+        assert!(builder.bank.get(spaced).is_none());
+        assert_eq!(builder.token_stream, [word, space, word, space, word]);
+        assert_eq!(builder.decode_stream(), text);
+    }
+}
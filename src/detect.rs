@@ -0,0 +1,151 @@
+// src/detect.rs
+//
+// Detect: Tunnista näytteen tyyppi kilpailevilla aivoilla.
+//
+// Jokainen aivotiedosto on oma kielimallinsa: pienet, yhdellä kielellä
+// tai lokiformaatilla opetetut aivot koodaavat tutun datan halvemmalla
+// kuin vieraan. Näyte koodataan jokaisilla aivoilla, ja tokenien hinta
+// lasketaan aivojen omista käyttömääristä (Laplace-tasoitus, joten
+// tuntematon literaali on kallis). Paras ehdokas on pienin bittiä/tavu.
+//
+// Varmuus on jälkitodennäköisyys tasaisella priorilla, P(i) ∝ 2^-bitit_i:
+// ero kasvaa näytteen pituuden mukana, joten lyhyetkin näytteet antavat
+// selvän tuloksen, kun aivot todella eroavat.
+
+use crate::builder::PatternBank;
+use std::collections::HashMap;
+
+/// Aivojen tokenimalli: hinta bitteinä jokaiselle koodattavalle mallille
+struct TokenModel {
+    costs: HashMap<u32, f64>,
+    /// Hinta mallille, jota ei ole pankissa (ei pitäisi tapahtua)
+    unknown: f64,
+}
+
+impl TokenModel {
+    fn new(bank: &PatternBank) -> Self {
+        // Vain virtaan päätyvät mallit: ei luokkia eikä aukollisia pohjia
+        let counts: Vec<(u32, u32)> = bank
+            .iter()
+            .filter(|(_, p)| p.is_literal() || p.op.as_combine().is_some())
+            .map(|(&id, p)| (id, p.usage_count))
+            .collect();
+        let total: f64 = counts.iter().map(|&(_, c)| c as f64 + 1.0).sum();
+        let costs = counts
+            .into_iter()
+            .map(|(id, c)| (id, (total / (c as f64 + 1.0)).log2()))
+            .collect();
+        TokenModel {
+            costs,
+            unknown: (total + 1.0).log2(),
+        }
+    }
+
+    fn bits(&self, tokens: &[u32]) -> f64 {
+        tokens
+            .iter()
+            .map(|t| self.costs.get(t).copied().unwrap_or(self.unknown))
+            .sum()
+    }
+}
+
+/// Näytteen hinta bitteinä yksillä aivoilla
+pub fn sample_bits(bank: &PatternBank, sample: &[u8]) -> f64 {
+    TokenModel::new(bank).bits(&bank.encode(sample))
+}
+
+/// Yhden ehdokkaan tulos
+#[derive(Debug, Clone)]
+pub struct Score {
+    pub name: String,
+    pub bits_per_byte: f64,
+    /// Jälkitodennäköisyys (0.0 - 1.0)
+    pub posterior: f64,
+}
+
+/// Tunnistuksen tulos: ehdokkaat parhaasta huonoimpaan
+#[derive(Debug, Clone)]
+pub struct Detection {
+    pub scores: Vec<Score>,
+}
+
+impl Detection {
+    /// Paras ehdokas
+    pub fn best(&self) -> &Score {
+        &self.scores[0]
+    }
+}
+
+/// Pisteytä näyte kaikilla aivoilla. `None`, jos aivoja tai dataa ei ole.
+pub fn detect(brains: &[(String, PatternBank)], sample: &[u8]) -> Option<Detection> {
+    if brains.is_empty() || sample.is_empty() {
+        return None;
+    }
+    let bits: Vec<f64> = brains
+        .iter()
+        .map(|(_, bank)| sample_bits(bank, sample))
+        .collect();
+
+    // Log-sum-exp kakkoskannassa: pitkillä näytteillä 2^-bitit alivuotaa
+    let min = bits.iter().copied().fold(f64::INFINITY, f64::min);
+    let weights: Vec<f64> = bits.iter().map(|b| (min - b).exp2()).collect();
+    let sum: f64 = weights.iter().sum();
+
+    let mut scores: Vec<Score> = brains
+        .iter()
+        .zip(bits.iter().zip(weights))
+        .map(|((name, _), (&b, w))| Score {
+            name: name.clone(),
+            bits_per_byte: b / sample.len() as f64,
+            posterior: w / sum,
+        })
+        .collect();
+    scores.sort_by(|a, b| a.bits_per_byte.total_cmp(&b.bits_per_byte));
+    Some(Detection { scores })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Opeta pieni pankki: sanat Combine-ketjuina, käyttö kuin virrasta
+    fn brain(words: &[&str]) -> PatternBank {
+        let mut bank = PatternBank::new(200);
+        for word in words {
+            let bytes = word.as_bytes();
+            let mut acc = bytes[0] as u32;
+            for &b in &bytes[1..] {
+                acc = bank.create_combine(acc, b as u32, 0).unwrap();
+                bank.get_mut(acc).unwrap().strength = 1.0;
+            }
+            bank.get_mut(acc).unwrap().usage_count = 50;
+        }
+        bank
+    }
+
+    #[test]
+    fn test_detects_language_of_sample() {
+        let brains = vec![
+            (
+                "fi".to_string(),
+                brain(&["ja ", "on ", "että ", "kissa ", "talo "]),
+            ),
+            (
+                "en".to_string(),
+                brain(&["and ", "the ", "is ", "cat ", "house "]),
+            ),
+        ];
+
+        let result = detect(&brains, b"the cat and the house ").unwrap();
+        assert_eq!(result.best().name, "en");
+        assert!(result.best().posterior > 0.99);
+        assert!(result.scores[1].bits_per_byte > result.best().bits_per_byte);
+
+        let result = detect(&brains, b"kissa on talo ja kissa ").unwrap();
+        assert_eq!(result.best().name, "fi");
+
+        let total: f64 = result.scores.iter().map(|s| s.posterior).sum();
+        assert!((total - 1.0).abs() < 1e-9);
+        assert!(detect(&brains, b"").is_none());
+    }
+}
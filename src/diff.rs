@@ -0,0 +1,352 @@
+// src/diff.rs
+//
+// Diff: Rakennetietoinen vertailu opituilla malleilla.
+//
+// Molemmat tavujonot koodataan samoilla jäädytetyillä aivoilla, ja
+// token-virrat rinnastetaan Myersin O((N+M)D) -algoritmilla. Koska tokenit
+// ovat opittuja malleja, muutos raportoidaan rakenteina ("tämä lause
+// vaihtui tuohon") eikä yksittäisinä tavuina, ja tuttu rakenne kutistaa
+// rinnastettavan jonon murto-osaan tavuista.
+//
+// Yhteinen alku ja loppu poistetaan ennen rinnastusta. Jos muokkauksia on
+// yli MAX_EDIT_DISTANCE, loppuosa raportoidaan yhtenä muutoksena: täysin
+// erilaisten tiedostojen rinnastus olisi neliöllinen.
+
+use crate::builder::PatternBank;
+use crate::render;
+use crate::term::{self, Level, say};
+use std::ops::Range;
+
+/// Myersin haun yläraja (lisäykset + poistot)
+const MAX_EDIT_DISTANCE: usize = 10_000;
+
+/// Tulosteessa näytettävät mallit segmenttiä kohden
+const MAX_LABELS_PER_SEGMENT: usize = 8;
+
+/// Segmentin laji
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SegmentKind {
+    Equal,
+    Delete,
+    Insert,
+    /// Poisto ja lisäys samassa kohdassa
+    Change,
+}
+
+/// Yhtenäinen pätkä rinnastusta: token-välit vanhassa ja uudessa virrassa
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Segment {
+    pub kind: SegmentKind,
+    pub old: Range<usize>,
+    pub new: Range<usize>,
+}
+
+/// Yksittäinen muokkausaskel ennen ryhmittelyä
+#[derive(Clone, Copy, PartialEq)]
+enum Step {
+    Equal,
+    Delete,
+    Insert,
+}
+
+/// Lyhin muokkausjono (Myers). `None`, jos etäisyys ylittää rajan.
+fn edit_script(a: &[u32], b: &[u32], max_distance: usize) -> Option<Vec<Step>> {
+    let (n, m) = (a.len() as isize, b.len() as isize);
+    let max = (a.len() + b.len()).min(max_distance) as isize;
+    let offset = max + 1;
+    let mut v = vec![0isize; 2 * max as usize + 3];
+    // Jokaisen kierroksen alussa tarvittava osa v:stä (k = -d-1..=d+1)
+    let mut trace: Vec<Vec<isize>> = Vec::new();
+
+    let mut found = None;
+    'search: for d in 0..=max {
+        trace.push(v[(offset - d - 1) as usize..=(offset + d + 1) as usize].to_vec());
+        for k in (-d..=d).step_by(2) {
+            let idx = (offset + k) as usize;
+            let mut x = if k == -d || (k != d && v[idx - 1] < v[idx + 1]) {
+                v[idx + 1]
+            } else {
+                v[idx - 1] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            v[idx] = x;
+            if x >= n && y >= m {
+                found = Some(d);
+                break 'search;
+            }
+        }
+    }
+    found?;
+
+    // Kulje polku takaperin lopusta alkuun
+    let mut steps = Vec::with_capacity(a.len() + b.len());
+    let (mut x, mut y) = (n, m);
+    for (d, snapshot) in trace.iter().enumerate().rev() {
+        let d = d as isize;
+        let at = |k: isize| snapshot[(k + d + 1) as usize];
+        let k = x - y;
+        let prev_k = if k == -d || (k != d && at(k - 1) < at(k + 1)) {
+            k + 1
+        } else {
+            k - 1
+        };
+        let prev_x = at(prev_k);
+        let prev_y = prev_x - prev_k;
+        while x > prev_x && y > prev_y {
+            steps.push(Step::Equal);
+            x -= 1;
+            y -= 1;
+        }
+        if d > 0 {
+            steps.push(if x == prev_x {
+                Step::Insert
+            } else {
+                Step::Delete
+            });
+        }
+        x = prev_x;
+        y = prev_y;
+    }
+    steps.reverse();
+    Some(steps)
+}
+
+/// Ryhmittele askeleet segmenteiksi; vierekkäiset poistot ja lisäykset
+/// yhdistetään muutokseksi
+fn group(steps: &[Step], old_start: usize, new_start: usize) -> Vec<Segment> {
+    let mut segments: Vec<Segment> = Vec::new();
+    let (mut i, mut j) = (old_start, new_start);
+    for &step in steps {
+        let (kind, di, dj) = match step {
+            Step::Equal => (SegmentKind::Equal, 1, 1),
+            Step::Delete => (SegmentKind::Delete, 1, 0),
+            Step::Insert => (SegmentKind::Insert, 0, 1),
+        };
+        match segments.last_mut() {
+            Some(last)
+                if last.kind == kind
+                    || (kind != SegmentKind::Equal && last.kind == SegmentKind::Change) =>
+            {
+                last.old.end += di;
+                last.new.end += dj;
+            }
+            Some(last) if last.kind != SegmentKind::Equal && kind != SegmentKind::Equal => {
+                last.kind = SegmentKind::Change;
+                last.old.end += di;
+                last.new.end += dj;
+            }
+            _ => segments.push(Segment {
+                kind,
+                old: i..i + di,
+                new: j..j + dj,
+            }),
+        }
+        i += di;
+        j += dj;
+    }
+    segments
+}
+
+/// Rinnasta kaksi token-virtaa segmenteiksi
+pub fn align(old: &[u32], new: &[u32]) -> Vec<Segment> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let (old_mid, new_mid) = (
+        &old[prefix..old.len() - suffix],
+        &new[prefix..new.len() - suffix],
+    );
+
+    let mut segments = Vec::new();
+    if prefix > 0 {
+        segments.push(Segment {
+            kind: SegmentKind::Equal,
+            old: 0..prefix,
+            new: 0..prefix,
+        });
+    }
+    match edit_script(old_mid, new_mid, MAX_EDIT_DISTANCE) {
+        Some(steps) => segments.extend(group(&steps, prefix, prefix)),
+        None => segments.push(Segment {
+            kind: SegmentKind::Change,
+            old: prefix..old.len() - suffix,
+            new: prefix..new.len() - suffix,
+        }),
+    }
+    if suffix > 0 {
+        segments.push(Segment {
+            kind: SegmentKind::Equal,
+            old: old.len() - suffix..old.len(),
+            new: new.len() - suffix..new.len(),
+        });
+    }
+    segments
+}
+
+/// Kahden tavujonon vertailu token-tasolla
+pub struct StreamDiff {
+    pub old: Vec<u32>,
+    pub new: Vec<u32>,
+    pub segments: Vec<Segment>,
+}
+
+/// Koodaa molemmat jäädytetyillä aivoilla ja rinnasta
+pub fn diff(bank: &PatternBank, old: &[u8], new: &[u8]) -> StreamDiff {
+    let old = bank.encode(old);
+    let new = bank.encode(new);
+    let segments = align(&old, &new);
+    StreamDiff { old, new, segments }
+}
+
+impl StreamDiff {
+    /// Muuttuneet segmentit (ei Equal)
+    pub fn changes(&self) -> impl Iterator<Item = &Segment> {
+        self.segments
+            .iter()
+            .filter(|s| s.kind != SegmentKind::Equal)
+    }
+
+    /// Samoina säilyneiden tavujen osuus pidemmästä syötteestä
+    pub fn similarity(&self, bank: &PatternBank) -> f64 {
+        let bytes =
+            |tokens: &[u32]| -> usize { tokens.iter().map(|&t| bank.pattern_length(t)).sum() };
+        let equal: usize = self
+            .segments
+            .iter()
+            .filter(|s| s.kind == SegmentKind::Equal)
+            .map(|s| bytes(&self.old[s.old.clone()]))
+            .sum();
+        let longer = bytes(&self.old).max(bytes(&self.new));
+        if longer == 0 {
+            1.0
+        } else {
+            equal as f64 / longer as f64
+        }
+    }
+
+    /// Tulosta muutokset malleina (enintään `max_segments` kpl)
+    pub fn print(&self, bank: &PatternBank, max_segments: usize) {
+        let offsets = |tokens: &[u32]| -> Vec<usize> {
+            let mut acc = 0;
+            let mut out = vec![0];
+            for &t in tokens {
+                acc += bank.pattern_length(t);
+                out.push(acc);
+            }
+            out
+        };
+        let (old_at, new_at) = (offsets(&self.old), offsets(&self.new));
+        let describe = |tokens: &[u32]| -> String {
+            let mut labels: Vec<String> = tokens
+                .iter()
+                .take(MAX_LABELS_PER_SEGMENT)
+                .map(|&t| {
+                    format!(
+                        "P_{} {}",
+                        t,
+                        render::label(&bank.decode(t), render::PREVIEW_LEN)
+                    )
+                })
+                .collect();
+            if tokens.len() > MAX_LABELS_PER_SEGMENT {
+                labels.push(format!("... (+{})", tokens.len() - MAX_LABELS_PER_SEGMENT));
+            }
+            labels.join(", ")
+        };
+
+        let changes: Vec<&Segment> = self.changes().collect();
+        say!(
+            "  🔍 Vertailu: {} -> {} tokenia, {} muutosta, samankaltaisuus {:.1}%",
+            "  🔍 Diff: {} -> {} tokens, {} changes, similarity {:.1}%",
+            self.old.len(),
+            self.new.len(),
+            changes.len(),
+            self.similarity(bank) * 100.0
+        );
+        for segment in changes.iter().take(max_segments) {
+            let old = &self.old[segment.old.clone()];
+            let new = &self.new[segment.new.clone()];
+            let position = format!(
+                "@ {}..{} -> {}..{}",
+                old_at[segment.old.start],
+                old_at[segment.old.end],
+                new_at[segment.new.start],
+                new_at[segment.new.end]
+            );
+            match segment.kind {
+                SegmentKind::Delete => term::emit(
+                    Level::Info,
+                    &format!("     ➖ {} {}", position, describe(old)),
+                ),
+                SegmentKind::Insert => term::emit(
+                    Level::Info,
+                    &format!("     ➕ {} {}", position, describe(new)),
+                ),
+                SegmentKind::Change => {
+                    term::emit(
+                        Level::Info,
+                        &format!("     ✏️ {} {}", position, describe(old)),
+                    );
+                    term::emit(Level::Info, &format!("        -> {}", describe(new)));
+                }
+                SegmentKind::Equal => {}
+            }
+        }
+        if changes.len() > max_segments {
+            say!(
+                "     ... ja {} muuta",
+                "     ... and {} more",
+                changes.len() - max_segments
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_alignment_reports_pattern_level_changes() {
+        let segments = align(&[1, 2, 3, 4, 5], &[1, 9, 3, 5, 6]);
+        let kinds: Vec<SegmentKind> = segments.iter().map(|s| s.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                SegmentKind::Equal,
+                SegmentKind::Change,
+                SegmentKind::Equal,
+                SegmentKind::Delete,
+                SegmentKind::Equal,
+                SegmentKind::Insert
+            ]
+        );
+        assert_eq!(segments[1].old, 1..2);
+        assert_eq!(segments[1].new, 1..2);
+        assert_eq!(segments[3].old, 3..4);
+        assert_eq!(segments[5].new, 4..5);
+        assert!(align(&[], &[]).is_empty());
+
+        // Tuttu sana on yksi token: muutos näkyy yhtenä mallina
+        let mut bank = PatternBank::new(50);
+        let mut word = b'h' as u32;
+        for &b in b"ello" {
+            word = bank.create_combine(word, b as u32, 0).unwrap();
+            bank.get_mut(word).unwrap().strength = 1.0;
+        }
+        let result = diff(&bank, b"say hello now", b"say hullo now");
+        let changes: Vec<&Segment> = result.changes().collect();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(result.old[changes[0].old.clone()], [word]);
+        assert_eq!(changes[0].new.len(), 5);
+        assert!(result.similarity(&bank) > 0.5);
+    }
+}
@@ -0,0 +1,120 @@
+// src/drift.rs
+//
+// Drift: Saapuvan datan muutoksen tunnistus.
+//
+// Pitkä (seuranta)ajo olettaa, että uusi data muistuttaa vanhaa. Kun lähde
+// vaihtuu (eri kieli, uusi projekti, toinen lokimuoto), saapuvien palojen
+// tuttuus putoaa, mutta yksittäinen vieras pala ei vielä kerro mitään.
+// Tunnistin pitää palojen tuttuudesta kaksi liukuvaa keskiarvoa:
+//   - lyhyt (noin 10 sykliä): mitä juuri nyt saapuu,
+//   - pitkä perustaso (noin 200 sykliä): mihin aivot ovat tottuneet.
+// Kun lyhyt keskiarvo putoaa perustasosta yli kynnyksen, ajo saa
+// hälytyksen: aivot vanhenevat, ja kannattaa harkita uudelleenkoulutusta
+// tai plastisuuden lisäämistä. Hälytyksen ajaksi perustaso jäädytetään,
+// jottei muutos "normalisoidu" itsestään; hälytys päättyy vasta, kun
+// tuttuus palaa perustason lähelle eli aivot ovat sopeutuneet.
+
+use serde::{Deserialize, Serialize};
+
+/// Oletuskynnys: tuttuuden pudotus perustasosta (0.0 - 1.0)
+pub const DEFAULT_THRESHOLD: f64 = 0.15;
+
+/// Liukuvien keskiarvojen painot (noin 10 ja 200 syklin ikkunat)
+const RECENT_ALPHA: f64 = 2.0 / 11.0;
+const BASELINE_ALPHA: f64 = 2.0 / 201.0;
+
+/// Havaintoja ennen kuin perustasoon luotetaan
+const WARMUP: usize = 30;
+
+/// Hälytys päättyy, kun pudotus on alle tämän osuuden kynnyksestä
+const RECOVERY_FRACTION: f64 = 0.5;
+
+/// Tunnistimen tapahtuma
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DriftEvent {
+    /// Saapuva data on muuttunut: tuttuus pudonnut perustasosta
+    Shifted { baseline: f64, recent: f64 },
+    /// Tuttuus palasi perustason lähelle
+    Recovered { baseline: f64, recent: f64 },
+}
+
+/// Palojen tuttuuden seuranta (tallentuu istuntoon)
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct DriftDetector {
+    baseline: f64,
+    recent: f64,
+    observed: usize,
+    drifting: bool,
+}
+
+impl DriftDetector {
+    /// Kirjaa syötetyn palan tuttuus. Palauttaa tapahtuman, kun tila
+    /// vaihtuu (ei joka syklillä hälytyksen aikana).
+    pub fn observe(&mut self, familiarity: f64, threshold: f64) -> Option<DriftEvent> {
+        if self.observed == 0 {
+            self.baseline = familiarity;
+            self.recent = familiarity;
+        }
+        self.observed += 1;
+        self.recent += RECENT_ALPHA * (familiarity - self.recent);
+        if !self.drifting {
+            self.baseline += BASELINE_ALPHA * (familiarity - self.baseline);
+        }
+        if self.observed < WARMUP {
+            return None;
+        }
+
+        let (baseline, recent) = (self.baseline, self.recent);
+        if !self.drifting && self.score() > threshold {
+            self.drifting = true;
+            return Some(DriftEvent::Shifted { baseline, recent });
+        }
+        if self.drifting && self.score() < threshold * RECOVERY_FRACTION {
+            self.drifting = false;
+            return Some(DriftEvent::Recovered { baseline, recent });
+        }
+        None
+    }
+
+    /// Mittari: tuttuuden pudotus perustasosta (0 ennen lämpenemistä)
+    pub fn score(&self) -> f64 {
+        if self.observed < WARMUP {
+            return 0.0;
+        }
+        (self.baseline - self.recent).max(0.0)
+    }
+
+    pub fn is_drifting(&self) -> bool {
+        self.drifting
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shift_detected_and_recovered() {
+        let mut detector = DriftDetector::default();
+        let events: Vec<_> = (0..100)
+            .filter_map(|_| detector.observe(0.8, DEFAULT_THRESHOLD))
+            .collect();
+        assert!(events.is_empty());
+        assert_eq!(detector.score(), 0.0);
+
+        // Vieras data: yksi hälytys, ei toistoa
+        let events: Vec<_> = (0..30)
+            .filter_map(|_| detector.observe(0.3, DEFAULT_THRESHOLD))
+            .collect();
+        assert!(matches!(events[..], [DriftEvent::Shifted { .. }]));
+        assert!(detector.is_drifting());
+        assert!(detector.score() > 0.4);
+
+        // Perustaso on jäädytetty: sopeutuminen palauttaa tilan
+        let events: Vec<_> = (0..30)
+            .filter_map(|_| detector.observe(0.78, DEFAULT_THRESHOLD))
+            .collect();
+        assert!(matches!(events[..], [DriftEvent::Recovered { .. }]));
+        assert!(!detector.is_drifting());
+    }
+}
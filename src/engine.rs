@@ -0,0 +1,650 @@
+// src/engine.rs
+//
+// Engine (Moottori): Yhteinen rajapinta oppimismoottoreille.
+//
+// Pääsilmukka ajaa moottoria tämän rajapinnan kautta:
+// 1. feed: syötä raakadataa
+// 2. step: aja yksi oppimissykli
+// 3. familiarity: mittaa kuinka tuttua viimeisin data oli
+// 4. save: tallenna opittu malli levylle
+// 5. state / load: koko tila istuntoon ja takaisin
+//
+// Näin moottori voidaan valita konfiguraatiosta ilman että
+// pääsilmukkaa tai Feederiä tarvitsee muuttaa.
+
+use crate::bitio::{base64_decode, base64_encode, read_varint, write_varint};
+use crate::builder::{Builder, BuilderStats, PatternBank};
+use crate::i18n::tr;
+use crate::lzw::Lzw;
+use crate::segment::CollapsePolicy;
+use crate::sequitur::{Grammar, GrammarState, Sequitur};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::io;
+use std::path::Path;
+
+/// Käytettävissä olevat moottorit
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EngineKind {
+    /// Hierarkkinen token-virran rakentaja (oletus)
+    Builder,
+    /// Sequitur: digrammien yksikäsitteisyys, yksi läpikäynti
+    Sequitur,
+    /// LZW: inkrementaalinen sanakirja (klassinen vertailukohta)
+    Lzw,
+}
+
+impl EngineKind {
+    /// Tulkitse moottorin nimi konfiguraatiosta
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "builder" => Some(EngineKind::Builder),
+            "sequitur" => Some(EngineKind::Sequitur),
+            "lzw" => Some(EngineKind::Lzw),
+            _ => None,
+        }
+    }
+}
+
+/// Engine: Oppimismoottorin yhteinen rajapinta.
+pub trait Engine {
+    /// Moottorin nimi raportteja varten
+    fn name(&self) -> &'static str;
+
+    /// Syötä raakadataa moottorin virtaan
+    fn feed(&mut self, data: &[u8]);
+
+    /// Merkitse tietueraja: seuraava data ei jatka edellistä
+    fn feed_boundary(&mut self);
+
+    /// Aja yksi oppimissykli. Jos `explore` on false, uusia malleja ei etsitä.
+    fn step(&mut self, explore: bool) -> BuilderStats;
+
+    /// Arvioi kuinka tuttua viimeisin data oli (0.0 - 1.0)
+    fn familiarity(&self, lookback: usize) -> f64;
+
+    /// Tuttuus virran lopussa, joka kattaa annetun määrän alkuperäisiä tavuja
+    fn tail_familiarity(&self, original_bytes: usize) -> f64;
+
+    /// Virran pituus tokeneina
+    fn stream_len(&self) -> usize;
+
+    /// Nykyinen token-virta (entropiakoodausta ja raportteja varten)
+    fn token_stream(&self) -> &[u32];
+
+    /// Virran alkuperäinen pituus tavuina
+    fn original_len(&self) -> usize;
+
+    /// Yksittäisen tokenin alkuperäiset tavut
+    fn decode_token(&self, id: u32) -> Vec<u8>;
+
+    /// Koodaa dataa opitulla mallilla muuttamatta moottorin tilaa
+    fn encode(&self, data: &[u8]) -> Vec<u32>;
+
+    /// Opittujen mallien määrä (ei perussymboleita)
+    fn model_size(&self) -> usize;
+
+    /// Kaikkien symbolien määrä, joihin virta voi viitata
+    fn symbol_count(&self) -> usize;
+
+    /// Tallenna opittu malli tiedostoon
+    fn save(&self, path: &Path) -> io::Result<()>;
+
+    /// Tulosta opitun mallin tärkeimmät osat
+    fn print_model(&self, top_n: usize);
+
+    /// Koko sisäinen tila istuntotiedostoa varten
+    fn state(&self) -> EngineState;
+
+    /// Korvaa sisäinen tila istunnon tilalla (`state`-metodin vastapari).
+    /// Asetukset säilyvät; toisen moottorin tila on virhe.
+    fn load(&mut self, state: EngineState) -> io::Result<()>;
+
+    /// Opittu mallipankki, jos moottori käyttää sellaista
+    fn bank(&self) -> Option<&PatternBank>;
+
+    /// Vastapaine: kuinka suuren osan normaalista syötteestä moottori
+    /// jaksaa ottaa vastaan (1.0 = täysi nopeus). Feeder skaalaa palan
+    /// kokoa tällä, jotta syklit eivät paisu rajatta.
+    fn capacity_hint(&self) -> f64 {
+        1.0
+    }
+}
+
+/// Moottorin täydellinen tila (tarkka jatkaminen istunnosta)
+#[derive(Serialize, Deserialize)]
+pub enum EngineState {
+    Builder {
+        bank: PatternBank,
+        #[serde(with = "packed_stream")]
+        token_stream: Vec<u32>,
+        cycle: u64,
+    },
+    Sequitur {
+        /// Siemen-ID:t (literaalit, luokat, leksemit)
+        base: PatternBank,
+        grammar: GrammarState,
+        cycle: u64,
+    },
+    Lzw {
+        bank: PatternBank,
+        /// Viimeinen token on keskeneräinen fraasi
+        #[serde(with = "packed_stream")]
+        token_stream: Vec<u32>,
+        cycle: u64,
+    },
+}
+
+impl EngineState {
+    /// Moottori, jonka tila tämä on
+    pub fn kind(&self) -> EngineKind {
+        match self {
+            EngineState::Builder { .. } => EngineKind::Builder,
+            EngineState::Sequitur { .. } => EngineKind::Sequitur,
+            EngineState::Lzw { .. } => EngineKind::Lzw,
+        }
+    }
+
+    /// Tilan pankki (kapasiteetti ja tokenisointi raportteja varten)
+    pub fn bank(&self) -> &PatternBank {
+        match self {
+            EngineState::Builder { bank, .. } | EngineState::Lzw { bank, .. } => bank,
+            EngineState::Sequitur { base, .. } => base,
+        }
+    }
+}
+
+impl Engine for Builder {
+    fn name(&self) -> &'static str {
+        "builder"
+    }
+
+    fn feed(&mut self, data: &[u8]) {
+        self.tokenize(data);
+    }
+
+    fn feed_boundary(&mut self) {
+        self.push_boundary();
+    }
+
+    fn step(&mut self, explore: bool) -> BuilderStats {
+        self.live_cycle(explore)
+    }
+
+    fn familiarity(&self, lookback: usize) -> f64 {
+        self.assess_familiarity(lookback)
+    }
+
+    fn tail_familiarity(&self, original_bytes: usize) -> f64 {
+        Builder::tail_familiarity(self, original_bytes)
+    }
+
+    fn stream_len(&self) -> usize {
+        Builder::stream_len(self)
+    }
+
+    fn token_stream(&self) -> &[u32] {
+        &self.token_stream
+    }
+
+    fn original_len(&self) -> usize {
+        Builder::original_len(self)
+    }
+
+    fn decode_token(&self, id: u32) -> Vec<u8> {
+        self.bank.decode(id)
+    }
+
+    fn encode(&self, data: &[u8]) -> Vec<u32> {
+        self.bank.encode(data)
+    }
+
+    fn model_size(&self) -> usize {
+        self.bank.combine_count()
+    }
+
+    fn symbol_count(&self) -> usize {
+        self.bank.len()
+    }
+
+    fn save(&self, path: &Path) -> io::Result<()> {
+        self.bank.save(path)
+    }
+
+    fn print_model(&self, top_n: usize) {
+        self.print_top_patterns(top_n);
+    }
+
+    fn bank(&self) -> Option<&PatternBank> {
+        Some(&self.bank)
+    }
+
+    fn capacity_hint(&self) -> f64 {
+        Builder::capacity_hint(self)
+    }
+
+    fn state(&self) -> EngineState {
+        EngineState::Builder {
+            bank: self.bank.clone(),
+            token_stream: self.token_stream.clone(),
+            cycle: self.cycle,
+        }
+    }
+
+    fn load(&mut self, state: EngineState) -> io::Result<()> {
+        let EngineState::Builder {
+            bank,
+            token_stream,
+            cycle,
+        } = state
+        else {
+            return Err(wrong_engine(Engine::name(self)));
+        };
+        self.restore(bank, token_stream, cycle);
+        Ok(())
+    }
+}
+
+impl Engine for Sequitur {
+    fn name(&self) -> &'static str {
+        "sequitur"
+    }
+
+    fn feed(&mut self, data: &[u8]) {
+        Sequitur::feed(self, data);
+    }
+
+    fn feed_boundary(&mut self) {
+        self.push_boundary();
+    }
+
+    fn step(&mut self, _explore: bool) -> BuilderStats {
+        Sequitur::step(self)
+    }
+
+    fn familiarity(&self, lookback: usize) -> f64 {
+        self.view.assess_familiarity(lookback)
+    }
+
+    fn tail_familiarity(&self, original_bytes: usize) -> f64 {
+        self.view.tail_familiarity(original_bytes)
+    }
+
+    fn stream_len(&self) -> usize {
+        self.view.stream_len()
+    }
+
+    fn token_stream(&self) -> &[u32] {
+        &self.view.token_stream
+    }
+
+    fn original_len(&self) -> usize {
+        self.view.original_len()
+    }
+
+    fn decode_token(&self, id: u32) -> Vec<u8> {
+        self.view.bank.decode(id)
+    }
+
+    fn encode(&self, data: &[u8]) -> Vec<u32> {
+        self.view.bank.encode(data)
+    }
+
+    fn model_size(&self) -> usize {
+        self.view.bank.combine_count()
+    }
+
+    fn symbol_count(&self) -> usize {
+        self.view.bank.len()
+    }
+
+    fn save(&self, path: &Path) -> io::Result<()> {
+        self.view.bank.save(path)
+    }
+
+    fn print_model(&self, top_n: usize) {
+        Sequitur::print_model(self, top_n);
+    }
+
+    fn bank(&self) -> Option<&PatternBank> {
+        Some(&self.view.bank)
+    }
+
+    fn state(&self) -> EngineState {
+        EngineState::Sequitur {
+            base: self.base().clone(),
+            grammar: self.grammar().state(),
+            cycle: self.view.cycle,
+        }
+    }
+
+    fn load(&mut self, state: EngineState) -> io::Result<()> {
+        let EngineState::Sequitur {
+            mut base,
+            grammar,
+            cycle,
+        } = state
+        else {
+            return Err(wrong_engine(Engine::name(self)));
+        };
+        base.rebuild_lookups();
+        *self = Sequitur::with_grammar(base, Grammar::from_state(grammar), cycle);
+        Ok(())
+    }
+}
+
+impl Engine for Lzw {
+    fn name(&self) -> &'static str {
+        "lzw"
+    }
+
+    fn feed(&mut self, data: &[u8]) {
+        Lzw::feed(self, data);
+    }
+
+    fn feed_boundary(&mut self) {
+        self.inner.push_boundary();
+    }
+
+    fn step(&mut self, _explore: bool) -> BuilderStats {
+        Lzw::step(self)
+    }
+
+    fn familiarity(&self, lookback: usize) -> f64 {
+        self.inner.assess_familiarity(lookback)
+    }
+
+    fn tail_familiarity(&self, original_bytes: usize) -> f64 {
+        self.inner.tail_familiarity(original_bytes)
+    }
+
+    fn stream_len(&self) -> usize {
+        self.inner.stream_len()
+    }
+
+    fn token_stream(&self) -> &[u32] {
+        &self.inner.token_stream
+    }
+
+    fn original_len(&self) -> usize {
+        self.inner.original_len()
+    }
+
+    fn decode_token(&self, id: u32) -> Vec<u8> {
+        self.inner.bank.decode(id)
+    }
+
+    fn encode(&self, data: &[u8]) -> Vec<u32> {
+        self.parse(data)
+    }
+
+    fn model_size(&self) -> usize {
+        self.inner.bank.combine_count()
+    }
+
+    fn symbol_count(&self) -> usize {
+        self.inner.bank.len()
+    }
+
+    fn save(&self, path: &Path) -> io::Result<()> {
+        self.inner.bank.save(path)
+    }
+
+    fn print_model(&self, top_n: usize) {
+        self.inner.print_top_patterns(top_n);
+    }
+
+    fn bank(&self) -> Option<&PatternBank> {
+        Some(&self.inner.bank)
+    }
+
+    fn state(&self) -> EngineState {
+        EngineState::Lzw {
+            bank: self.inner.bank.clone(),
+            token_stream: self.inner.token_stream.clone(),
+            cycle: self.inner.cycle,
+        }
+    }
+
+    fn load(&mut self, state: EngineState) -> io::Result<()> {
+        let EngineState::Lzw {
+            bank,
+            token_stream,
+            cycle,
+        } = state
+        else {
+            return Err(wrong_engine(Engine::name(self)));
+        };
+        // Fraasin laskurit alkavat alusta jatketun virran lopusta
+        let mut inner = std::mem::replace(&mut self.inner, Builder::new(0));
+        inner.restore(bank, token_stream, cycle);
+        *self = Lzw::new(inner);
+        Ok(())
+    }
+}
+
+fn wrong_engine(name: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        tr!(
+            "istunnon tila on eri moottorin kuin {}",
+            "the session state belongs to another engine than {}",
+            name
+        ),
+    )
+}
+
+/// Builder-moottorin oppimisasetukset konfiguraatiosta
+#[derive(Debug, Clone, Default)]
+pub struct BuilderSettings {
+    pub pair_threshold: u32,
+    /// Tasokohtaiset parikynnykset (tyhjä = pair_threshold kaikille)
+    pub level_thresholds: Vec<u32>,
+    pub collapse: CollapsePolicy,
+    /// Tasokohtaiset tiivistyskynnykset (tyhjä = totuuskynnys kaikille)
+    pub collapse_thresholds: Vec<f64>,
+    /// Vahvuuden heikkeneminen syklissä (None = oletus)
+    pub decay_rate: Option<f64>,
+    /// Totuuskynnyksen vähimmäisviipymä sykleinä (None = oletus)
+    pub min_dwell: Option<u64>,
+    /// Aivotiedoston kokobudjetti tavuina (None = ei rajaa)
+    pub size_budget: Option<usize>,
+}
+
+impl BuilderSettings {
+    fn apply(&self, builder: &mut Builder) {
+        builder.pair_threshold = self.pair_threshold;
+        builder.level_thresholds = self.level_thresholds.clone();
+        builder.collapse_policy = self.collapse;
+        builder.collapse_thresholds = self.collapse_thresholds.clone();
+        if let Some(rate) = self.decay_rate {
+            builder.decay_rate = rate;
+        }
+        if let Some(dwell) = self.min_dwell {
+            builder.min_dwell = dwell;
+        }
+        builder.size_budget = self.size_budget;
+    }
+}
+
+/// Luo moottori valitun tyypin mukaan.
+///
+/// `bank` on ladattu (tai uusi) PatternBank Builder-moottoria varten.
+/// Asetukset koskevat vain Builder-moottoria.
+pub fn create_engine(
+    kind: EngineKind,
+    bank: PatternBank,
+    settings: &BuilderSettings,
+) -> Box<dyn Engine> {
+    match kind {
+        EngineKind::Builder => {
+            let mut builder = Builder::with_bank(bank);
+            settings.apply(&mut builder);
+            Box::new(builder)
+        }
+        EngineKind::Sequitur => Box::new(Sequitur::new(bank)),
+        EngineKind::Lzw => Box::new(Lzw::new(Builder::with_bank(bank))),
+    }
+}
+
+/// Palauta moottori istunnon tilasta: tyhjä moottori samoilla asetuksilla,
+/// johon tila ladataan (`Engine::load`)
+pub fn restore_engine(
+    state: EngineState,
+    settings: &BuilderSettings,
+) -> io::Result<Box<dyn Engine>> {
+    let mut engine = create_engine(state.kind(), PatternBank::new(0), settings);
+    engine.load(state)?;
+    Ok(engine)
+}
+
+/// Pakkaa token-virta: ajot varintteina (ks. moduulin kuvaus)
+pub fn pack_tokens(tokens: &[u32]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(tokens.len() * 2);
+    let mut i = 0;
+    while i < tokens.len() {
+        let id = tokens[i];
+        let run = tokens[i..].iter().take_while(|&&t| t == id).count();
+        write_varint(&mut out, (id as u64) << 1 | (run > 1) as u64);
+        if run > 1 {
+            write_varint(&mut out, run as u64 - 1);
+        }
+        i += run;
+    }
+    out
+}
+
+/// Pura pakattu token-virta
+pub fn unpack_tokens(data: &[u8]) -> io::Result<Vec<u32>> {
+    let mut tokens = Vec::with_capacity(data.len());
+    let mut pos = 0;
+    while pos < data.len() {
+        let value = read_varint(data, &mut pos)?;
+        let run = if value & 1 == 1 {
+            read_varint(data, &mut pos)? as usize + 1
+        } else {
+            1
+        };
+        let id = u32::try_from(value >> 1).map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                tr!("virheellinen token-ID", "invalid token ID"),
+            )
+        })?;
+        tokens.extend(std::iter::repeat_n(id, run));
+    }
+    Ok(tokens)
+}
+
+/// Serde-sovitin moottorin token-virralle (`#[serde(with = ...)]`)
+pub mod packed_stream {
+    use super::*;
+
+    /// Tallennettu virta: pakattu tai vanha lukulista
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Stored {
+        Packed { tokens: usize, packed: String },
+        Raw(Vec<u32>),
+    }
+
+    #[derive(Serialize)]
+    struct Packed {
+        tokens: usize,
+        packed: String,
+    }
+
+    pub fn serialize<S: Serializer>(tokens: &[u32], serializer: S) -> Result<S::Ok, S::Error> {
+        Packed {
+            tokens: tokens.len(),
+            packed: base64_encode(&pack_tokens(tokens)),
+        }
+        .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u32>, D::Error> {
+        use serde::de::Error;
+        match Stored::deserialize(deserializer)? {
+            Stored::Raw(tokens) => Ok(tokens),
+            Stored::Packed { tokens, packed } => {
+                let stream = base64_decode(&packed)
+                    .and_then(|data| unpack_tokens(&data))
+                    .map_err(D::Error::custom)?;
+                if stream.len() != tokens {
+                    return Err(D::Error::custom(tr!(
+                        "virran pituus {} ei vastaa tallennettua {}",
+                        "stream length {} does not match stored {}",
+                        stream.len(),
+                        tokens
+                    )));
+                }
+                Ok(stream)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_packed_stream_round_trip() {
+        #[derive(Serialize, Deserialize)]
+        struct State {
+            #[serde(with = "packed_stream")]
+            token_stream: Vec<u32>,
+        }
+
+        let stream = vec![97, 97, 97, 300, 98, u32::MAX, 70_000, 70_000, 97];
+        assert_eq!(unpack_tokens(&pack_tokens(&stream)).unwrap(), stream);
+
+        // Toisto ja pienet ID:t: alle kaksi tavua tokenia kohden
+        let long: Vec<u32> = (0..1000).map(|i| [32, 32, 32, 400, 101][i % 5]).collect();
+        assert!(pack_tokens(&long).len() < 2 * long.len());
+
+        let json = serde_json::to_string(&State {
+            token_stream: stream.clone(),
+        })
+        .unwrap();
+        assert!(json.contains("\"packed\""));
+        let state: State = serde_json::from_str(&json).unwrap();
+        assert_eq!(state.token_stream, stream);
+
+        // Vanha muoto: lukulista
+        let old: State = serde_json::from_str(r#"{"token_stream":[1,2,3]}"#).unwrap();
+        assert_eq!(old.token_stream, [1, 2, 3]);
+    }
+
+    #[test]
+    fn test_load_restores_state_for_every_engine() {
+        let data = b"abcabcabd abcabcabd abcabcabd";
+        for kind in [EngineKind::Builder, EngineKind::Sequitur, EngineKind::Lzw] {
+            let settings = BuilderSettings {
+                pair_threshold: 2,
+                ..BuilderSettings::default()
+            };
+            let mut engine = create_engine(kind, PatternBank::new(100), &settings);
+            engine.feed(data);
+            for _ in 0..3 {
+                engine.step(true);
+            }
+            let state = engine.state();
+            let (stream, size) = (engine.token_stream().to_vec(), engine.model_size());
+
+            let restored = restore_engine(state, &settings).unwrap();
+            assert_eq!(restored.name(), engine.name());
+            assert_eq!(restored.token_stream(), stream);
+            assert_eq!(restored.model_size(), size);
+            assert_eq!(restored.encode(data), engine.encode(data));
+        }
+
+        // Toisen moottorin tila hylätään
+        let lzw = create_engine(
+            EngineKind::Lzw,
+            PatternBank::new(10),
+            &BuilderSettings::default(),
+        );
+        let mut builder = Builder::new(10);
+        assert!(builder.load(lzw.state()).is_err());
+    }
+}
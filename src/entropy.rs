@@ -0,0 +1,109 @@
+// src/entropy.rs
+//
+// Entropiakooderin valinta: yhteinen rajapinta Huffmanille ja
+// adaptiiviselle range-kooderille, jotta raportit ja tiedostomuodot
+// voivat käyttää konfiguraatiossa valittua kooderia.
+//
+// Lisäksi halpa tavuentropian arvio, jolla Feeder tunnistaa jo
+// pakatun tai salatun datan ennen kuin se päätyy token-virtaan.
+
+use crate::{huffman, range_coder};
+use serde::{Deserialize, Serialize};
+use std::io;
+
+/// Token-virran entropiakooderi
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Coder {
+    /// Kanoninen Huffman (kooditaulukko + bittivirta)
+    #[default]
+    Huffman,
+    /// Adaptiivinen range-kooderi, order-0
+    Range,
+    /// Adaptiivinen range-kooderi, order-1 (edellinen token kontekstina)
+    RangeContext,
+}
+
+impl Coder {
+    /// Tulkitse kooderin nimi konfiguraatiosta
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "huffman" => Some(Coder::Huffman),
+            "range" => Some(Coder::Range),
+            "range-ctx" | "range1" => Some(Coder::RangeContext),
+            _ => None,
+        }
+    }
+
+    /// Nimi raportteja ja tiedostomuotoja varten
+    pub fn name(&self) -> &'static str {
+        match self {
+            Coder::Huffman => "huffman",
+            Coder::Range => "range",
+            Coder::RangeContext => "range-ctx",
+        }
+    }
+
+    /// Koodaa token-virta
+    pub fn encode(&self, tokens: &[u32]) -> Vec<u8> {
+        match self {
+            Coder::Huffman => huffman::encode(tokens),
+            Coder::Range => range_coder::encode(tokens, false),
+            Coder::RangeContext => range_coder::encode(tokens, true),
+        }
+    }
+
+    /// Pura koodattu token-virta
+    pub fn decode(&self, data: &[u8]) -> io::Result<Vec<u32>> {
+        match self {
+            Coder::Huffman => huffman::decode(data),
+            Coder::Range | Coder::RangeContext => range_coder::decode(data),
+        }
+    }
+}
+
+/// Arvioi palan order-0 entropia bitteinä tavua kohden.
+///
+/// Pienille paloille lisätään Miller–Madow-korjaus, koska satunnainen
+/// 500 tavun pala ei ehdi käyttää kaikkia 256 arvoa ja näyttäisi
+/// muuten todellista järjestyneemmältä.
+pub fn byte_entropy(data: &[u8]) -> f64 {
+    if data.is_empty() {
+        return 0.0;
+    }
+    let mut counts = [0usize; 256];
+    for &b in data {
+        counts[b as usize] += 1;
+    }
+
+    let n = data.len() as f64;
+    let mut entropy = 0.0;
+    let mut distinct = 0usize;
+    for &c in counts.iter().filter(|&&c| c > 0) {
+        let p = c as f64 / n;
+        entropy -= p * p.log2();
+        distinct += 1;
+    }
+    let correction = (distinct - 1) as f64 / (2.0 * n * std::f64::consts::LN_2);
+    (entropy + correction).min(8.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_byte_entropy_separates_text_from_noise() {
+        let text = b"funktio on joka funktio on joka tama on esimerkki ".repeat(10);
+        let mut state = 0x1234_5678u32;
+        let noise: Vec<u8> = (0..500)
+            .map(|_| {
+                state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                (state >> 24) as u8
+            })
+            .collect();
+
+        assert!(byte_entropy(&text) < 5.0);
+        assert!(byte_entropy(&noise) > 7.5);
+        assert_eq!(byte_entropy(&[7; 100]), 0.0);
+    }
+}
@@ -1,4 +1,11 @@
-use crate::builder::Builder;
+use crate::engine::Engine;
+use crate::entropy::Coder;
+use crate::segment::CollapsePolicy;
+use crate::term::say;
+use std::time::Instant;
+
+/// Jäsennyspolitiikkojen vertailuotoksen koko (tavuina virran lopusta)
+const POLICY_SAMPLE_BYTES: usize = 64 * 1024;
 
 /// Evaluator (Arvioija): Mittaa hierarkkisen oppimisen tehokkuutta.
 ///
@@ -6,28 +13,33 @@ use crate::builder::Builder;
 /// - C(tokens): Token-virran pituus (kuinka monta symbolia)
 /// - C(patterns): Mallien muistikustannus
 /// - Tiivistyssuhde: alkuperäinen tavumäärä / token-määrä
-pub struct Evaluator {}
+pub struct Evaluator {
+    /// Entropiakooderi, jolla todellinen koodattu koko mitataan
+    coder: Coder,
+}
+
+impl Default for Evaluator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl Evaluator {
     pub fn new() -> Self {
-        Evaluator {}
+        Evaluator {
+            coder: Coder::default(),
+        }
     }
 
-    /// Laske kokonaiskustannus Builder-tilasta
-    ///
-    /// Kustannus = token-virran pituus + mallien määrä / 10
-    /// (mallien kustannus on pienempi koska ne ovat uudelleenkäytettäviä)
-    #[allow(dead_code)]
-    pub fn calculate_cost(&self, builder: &Builder) -> usize {
-        let token_cost = builder.stream_len();
-        let pattern_cost = builder.bank.combine_count() / 10;
-        token_cost + pattern_cost
+    /// Luo Evaluator valitulla entropiakooderilla
+    pub fn with_coder(coder: Coder) -> Self {
+        Evaluator { coder }
     }
 
     /// Laske tiivistyssuhde
-    pub fn compression_ratio(&self, builder: &Builder) -> f64 {
-        let original = builder.original_len();
-        let compressed = builder.stream_len();
+    pub fn compression_ratio(&self, engine: &dyn Engine) -> f64 {
+        let original = engine.original_len();
+        let compressed = engine.stream_len();
 
         if original == 0 {
             return 0.0;
@@ -39,33 +51,116 @@ impl Evaluator {
     /// Laske "bittikustannus" - teoreettinen minimikoodaus
     ///
     /// Jokaiselle tokenille: log2(mallien_määrä) bittiä
-    pub fn bit_cost(&self, builder: &Builder) -> f64 {
-        let pattern_count = builder.bank.len();
+    pub fn bit_cost(&self, engine: &dyn Engine) -> f64 {
+        let pattern_count = engine.symbol_count();
         if pattern_count <= 1 {
             return 0.0;
         }
 
         let bits_per_token = (pattern_count as f64).log2();
-        bits_per_token * builder.stream_len() as f64
+        bits_per_token * engine.stream_len() as f64
+    }
+
+    /// Todellinen koodattu koko tavuina valitulla entropiakooderilla
+    pub fn coded_bytes(&self, engine: &dyn Engine) -> usize {
+        self.coder.encode(engine.token_stream()).len()
+    }
+
+    /// Koodattu koko bitteinä alkuperäistä tavua kohden
+    pub fn bits_per_byte(&self, engine: &dyn Engine) -> f64 {
+        let original_bytes = engine.original_len();
+        if original_bytes == 0 {
+            return 0.0;
+        }
+        self.coded_bytes(engine) as f64 * 8.0 / original_bytes as f64
     }
 
     /// Tulosta kustannusanalyysi
-    pub fn print_analysis(&self, builder: &Builder) {
-        let original_bytes = builder.original_len();
-        let tokens = builder.stream_len();
-        let patterns = builder.bank.combine_count();
-        let ratio = self.compression_ratio(builder);
-        let bits = self.bit_cost(builder);
-
-        println!("  📊 Kustannusanalyysi:");
-        println!("     Alkuperäinen: {} tavua", original_bytes);
-        println!("     Token-virta: {} tokenia", tokens);
-        println!("     Combine-malleja: {}", patterns);
-        println!("     Tiivistyssuhde: {:.1}%", ratio * 100.0);
-        println!(
+    pub fn print_analysis(&self, engine: &dyn Engine) {
+        let original_bytes = engine.original_len();
+        let tokens = engine.stream_len();
+        let patterns = engine.model_size();
+        let ratio = self.compression_ratio(engine);
+        let bits = self.bit_cost(engine);
+        let coded = self.coded_bytes(engine);
+        let bits_per_byte = self.bits_per_byte(engine);
+
+        say!("  📊 Kustannusanalyysi:", "  📊 Cost analysis:");
+        say!(
+            "     Alkuperäinen: {} tavua",
+            "     Original: {} bytes",
+            original_bytes
+        );
+        say!(
+            "     Token-virta: {} tokenia",
+            "     Token stream: {} tokens",
+            tokens
+        );
+        say!(
+            "     Combine-malleja: {}",
+            "     Combine patterns: {}",
+            patterns
+        );
+        say!(
+            "     Tiivistyssuhde: {:.1}%",
+            "     Compression ratio: {:.1}%",
+            ratio * 100.0
+        );
+        say!(
             "     Bittikustannus: {:.1} bittiä ({:.1} tavua)",
+            "     Bit cost: {:.1} bits ({:.1} bytes)",
             bits,
             bits / 8.0
         );
+        say!(
+            "     Entropiakoodattu ({}): {} tavua ({:.3} bittiä/tavu)",
+            "     Entropy coded ({}): {} bytes ({:.3} bits/byte)",
+            self.coder.name(),
+            coded,
+            bits_per_byte
+        );
+    }
+
+    /// Vertaa jäsennyspolitiikkoja: virran loppu puretaan tavuiksi ja
+    /// koodataan jäädytetyllä pankilla kullakin politiikalla.
+    pub fn print_policy_comparison(&self, engine: &dyn Engine) {
+        let Some(bank) = engine.bank().filter(|b| b.combine_count() > 0) else {
+            return;
+        };
+        let mut chunks = Vec::new();
+        let mut len = 0;
+        for &token in engine.token_stream().iter().rev() {
+            if len >= POLICY_SAMPLE_BYTES {
+                break;
+            }
+            let bytes = engine.decode_token(token);
+            len += bytes.len();
+            chunks.push(bytes);
+        }
+        let sample: Vec<u8> = chunks.into_iter().rev().flatten().collect();
+        if sample.is_empty() {
+            return;
+        }
+
+        say!(
+            "\n  ✂️  Jäsennyspolitiikat ({} tavun otos):",
+            "\n  ✂️  Collapse policies ({} byte sample):",
+            sample.len()
+        );
+        for policy in CollapsePolicy::ALL {
+            let start = Instant::now();
+            let tokens = bank.encode_with(&sample, policy);
+            let elapsed = start.elapsed().as_secs_f64() * 1000.0;
+            let coded = self.coder.encode(&tokens).len();
+            say!(
+                "     {:<9} {:>7} tokenia, {:>7} tavua ({:.3} bittiä/tavu), {:.0} ms",
+                "     {:<9} {:>7} tokens, {:>7} bytes ({:.3} bits/byte), {:.0} ms",
+                policy.name(),
+                tokens.len(),
+                coded,
+                coded as f64 * 8.0 / sample.len() as f64,
+                elapsed
+            );
+        }
     }
 }
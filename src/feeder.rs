@@ -1,10 +1,155 @@
 // src/feeder.rs
-use crate::builder::Builder;
+use crate::atomic;
+use crate::engine::Engine;
+use crate::entropy::byte_entropy;
+use crate::markup::{MarkupFilter, MarkupMode};
+use crate::media::{self, MediaLayout};
+use crate::quarantine::{self, Quarantine};
+use crate::reader_pool::{ReadChunk, ReaderMessage, ReaderPool};
+use crate::term::{self, Level, detail, say, warning};
+use crate::transform::Transform;
+use notify::{RecursiveMode, Watcher};
 use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::fs::{self, File};
 use std::io::BufReader;
-use std::io::{self, Read, Seek, SeekFrom};
-use std::path::PathBuf;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::time::Duration;
+
+/// Tätä pienempiä paloja ei arvioida entropian perusteella
+const MIN_GATED_CHUNK: usize = 64;
+
+/// Jo opitun datan tunnistuslohko. Palan koko vaihtelee syöttönopeuden
+/// mukaan, joten tunnisteet lasketaan kiinteän kokoisista lohkoista, jotka
+/// on tasattu tiedoston alkuun (palat pyöristetään lohkon monikerroiksi).
+const KNOWN_BLOCK: usize = 256;
+
+/// Muistettujen lohkojen enimmäismäärä (kirjanmerkki pysyy kohtuullisena)
+const MAX_KNOWN_BLOCKS: usize = 100_000;
+
+/// Seurantatilan varaskannausten oletusväli: muutokset huomataan heti
+/// ilmoituksista, skannaus kattaa vain kadonneet ilmoitukset
+pub const DEFAULT_WATCH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Lohkon sisällön tunniste: FNV-1a (vakaa ajojen välillä, toisin kuin
+/// std:n satunnaistettu hajautus)
+pub(crate) fn block_hash(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &b| {
+        (hash ^ b as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Mihin Feeder lisää tietuerajan (varattu `BOUNDARY_TOKEN`), jonka yli
+/// moottori ei muodosta malleja
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BoundaryPolicy {
+    /// Jokaisen syöttökutsun palan eteen
+    pub feeds: bool,
+    /// Tiedostojen väliin (rinnakkaislukijoilla aina, koska palat lomittuvat)
+    pub files: bool,
+    /// Tyhjän rivin jälkeen: kappaleet, luvut ja sivut omiksi tietueikseen
+    pub blank_lines: bool,
+}
+
+impl Default for BoundaryPolicy {
+    fn default() -> Self {
+        BoundaryPolicy {
+            feeds: false,
+            files: true,
+            blank_lines: false,
+        }
+    }
+}
+
+impl BoundaryPolicy {
+    /// Tulkitse pilkuin eroteltu luettelo (`files,blank`) tai `none`
+    pub fn parse(spec: &str) -> Option<Self> {
+        let mut policy = BoundaryPolicy {
+            files: false,
+            ..BoundaryPolicy::default()
+        };
+        for name in spec.split(',').map(|s| s.trim().to_ascii_lowercase()) {
+            match name.as_str() {
+                "none" | "" => {}
+                "feeds" | "feed" => policy.feeds = true,
+                "files" | "file" => policy.files = true,
+                "blank" | "blank_lines" | "paragraphs" => policy.blank_lines = true,
+                _ => return None,
+            }
+        }
+        Some(policy)
+    }
+
+    /// Nimi raportteja varten
+    pub fn name(&self) -> String {
+        let names: Vec<&str> = [
+            (self.feeds, "feeds"),
+            (self.files, "files"),
+            (self.blank_lines, "blank"),
+        ]
+        .iter()
+        .filter(|(on, _)| *on)
+        .map(|&(_, name)| name)
+        .collect();
+        if names.is_empty() {
+            "none".to_string()
+        } else {
+            names.join(",")
+        }
+    }
+}
+
+/// Tyhjien rivien tunnistus paloista. Tila säilyy palojen yli, joten
+/// palan rajalle osuva tyhjä rivi löytyy.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct BlankLines {
+    /// Edellinen kokonainen rivi oli tyhjä (vain välilyöntejä)
+    prev_blank: bool,
+    /// Nykyisellä rivillä on jo tekstiä
+    line_text: bool,
+}
+
+impl BlankLines {
+    /// Kohdat, joista alkaa tekstirivi tyhjän rivin jälkeen
+    pub(crate) fn cuts(&mut self, data: &[u8]) -> Vec<usize> {
+        let mut cuts = Vec::new();
+        let mut line_start = 0;
+        for (i, &b) in data.iter().enumerate() {
+            match b {
+                b'\n' => {
+                    self.prev_blank = !self.line_text;
+                    self.line_text = false;
+                    line_start = i + 1;
+                }
+                b' ' | b'\t' | b'\r' => {}
+                _ => {
+                    if !self.line_text && self.prev_blank {
+                        cuts.push(line_start);
+                    }
+                    self.line_text = true;
+                }
+            }
+        }
+        cuts
+    }
+}
+
+/// Viimeksi syötetyn palan sijainti korpuksessa
+#[derive(Debug, Clone)]
+pub struct ChunkInfo {
+    pub file: PathBuf,
+    /// Palan alku tiedostossa
+    pub offset: u64,
+    /// Luettu tavumäärä
+    pub length: usize,
+    /// Moottorille syötetty tavumäärä (muunnosten jälkeen, 0 jos ohitettu)
+    pub engine_bytes: usize,
+}
 
 /// FeederState: Tämä tallennetaan levylle (kirjanmerkki)
 #[derive(Serialize, Deserialize)]
@@ -12,19 +157,39 @@ pub struct FeederState {
     pub current_file_index: usize,
     pub current_file_pos: u64,
     pub total_fed: usize,
+    #[serde(default)]
+    pub skipped_bytes: usize,
+    /// Katkesiko edellinen pala kesken HTML/XML-tagin
+    #[serde(default)]
+    pub markup_in_tag: bool,
+    /// Rinnakkaissyötössä kesken jääneet muut tiedostot (indeksi, kohta)
+    #[serde(default)]
+    pub open_files: Vec<(usize, u64)>,
+    /// Rinnakkaissyötössä jo loppuun luetut tiedostot nykyisen jälkeen
+    #[serde(default)]
+    pub done_files: Vec<usize>,
+    /// Kesken jääneistä muista tiedostoista ne, joiden pala katkesi tagiin
+    #[serde(default)]
+    pub open_tags: Vec<usize>,
+    /// Tuttuina syötettyjen lohkojen tunnisteet
+    #[serde(default)]
+    pub known_blocks: Vec<u64>,
+    #[serde(default)]
+    pub known_bytes: usize,
 }
 
 /// Feeder: "Striimaa" dataa kaikista .txt-tiedostoista annetussa kansiossa.
 ///
-/// Uudessa arkkitehtuurissa Feeder syöttää dataa suoraan Builderiin,
-/// joka tokenisoi sen.
+/// Tiedostopäätteet voi vaihtaa (esim. "wav"), jolloin mediatiedostoista
+/// syötetään vain näytedata näytteen rajoille tasattuina paloina.
+///
+/// Uudessa arkkitehtuurissa Feeder syöttää dataa suoraan moottoriin
+/// (Engine), esim. Builder tokenisoi sen.
 ///
 /// Tukee nyt tilallisuutta: muistaa missä kohtaa dataa ollaan ja voi
 /// jatkaa siitä mihin jäätiin (kirjanmerkki).
 pub struct Feeder {
     pub feed_rate: usize,
-    #[allow(dead_code)]
-    base_feed_rate: usize,
     file_paths: Vec<PathBuf>,
     current_file_index: usize,
     current_file_pos: u64, // Missä tavussa mennään nykyisessä tiedostossa
@@ -32,78 +197,319 @@ pub struct Feeder {
     is_depleted: bool,
     /// Yhteensä syötetty tavumäärä
     pub total_fed: usize,
+    /// Esikäsittely, joka ajetaan jokaiselle palalle ennen syöttöä
+    transform: Transform,
+    /// Nykyisen tiedoston rakenne (None = tavallinen tekstitiedosto)
+    layout: Option<MediaLayout>,
+    /// Otsikottomien RAW-kuvien rivin leveys tavuina
+    row_hint: Option<usize>,
+    /// Ohitettu (liian satunnainen) tavumäärä, sisältyy total_fed:iin
+    pub skipped_bytes: usize,
+    /// Entropiaraja bitteinä/tavu: tätä satunnaisempia paloja ei syötetä
+    entropy_gate: Option<f64>,
+    /// Viimeisimmän syöttökutsun pala (None jos mitään ei syötetty)
+    last_chunk: Option<ChunkInfo>,
+    /// Viimeisimmän syöttökutsun tavut sellaisina kuin moottori ne sai
+    last_fed: Vec<u8>,
+    /// HTML/XML-tagien käsittely
+    markup: MarkupMode,
+    markup_filter: MarkupFilter,
+    /// Rinnakkaiset lukijat (None = tiedostot luetaan yksi kerrallaan)
+    pool: Option<ReaderPool>,
+    /// Kesken olevien tiedostojen kulutettu kohta (yhdistetty kirjanmerkki)
+    progress: BTreeMap<usize, u64>,
+    /// Loppuun luetut tiedostot current_file_index:n jälkeen
+    done_files: BTreeSet<usize>,
+    /// Tiedostokohtaiset tagisuodattimet rinnakkaissyötössä
+    markup_filters: HashMap<usize, MarkupFilter>,
+    /// Edellisen palan tiedosto (tietuerajoja varten)
+    last_file: Option<usize>,
+    /// Vastapaine: moottorin kapasiteettivihje (0.0 - 1.0) skaalaa nopeutta
+    throttle: f64,
+    /// Tuttuusraja (0.0 - 1.0): tätä tutumpina syötettyjen palojen lohkot
+    /// muistetaan ja niiden kopiot ohitetaan. None = ei käytössä.
+    known_threshold: Option<f64>,
+    /// Jo opittujen lohkojen tunnisteet
+    known_blocks: HashSet<u64>,
+    /// Viimeisimmän syöttökutsun moottorille syötettyjen lohkojen tunnisteet
+    last_hashes: Vec<u64>,
+    /// Onko edellinen lohko ohitettu (tietueraja vain kerran per jakso)
+    skipping_known: bool,
+    /// Jo opittuina ohitettu tavumäärä, sisältyy total_fed:iin
+    pub known_bytes: usize,
+    /// Datakansio ja päätteet (seurantatilan uudelleenskannaus)
+    data_dir: String,
+    extensions: Vec<String>,
+    /// Loppuun luettujen tiedostojen pituus lukuhetkellä (kasvun tunnistus)
+    consumed: HashMap<usize, u64>,
+    /// Tiedostojen koot edistymisen arviointiin (ks. corpus_progress)
+    file_sizes: Vec<u64>,
+    /// Vieraiden palojen karanteeni (None = kaikki syötetään)
+    quarantine: Option<Quarantine>,
+    /// Tietuerajojen lisäys
+    boundaries: BoundaryPolicy,
+    /// Nykyisen tiedoston tyhjien rivien tunnistus
+    paragraphs: BlankLines,
+    /// Tiedostokohtaiset tunnistukset rinnakkaissyötössä
+    paragraph_states: HashMap<usize, BlankLines>,
+}
+
+/// Palan koko: mediatiedostoissa tasattu näytteisiin (kuvissa riveihin)
+/// ja rajattu datalohkon loppuun
+pub(crate) fn aligned_chunk_len(feed_rate: usize, layout: Option<MediaLayout>, pos: u64) -> usize {
+    match layout {
+        None => feed_rate,
+        Some(layout) => {
+            let align = layout.chunk_align();
+            let aligned = (feed_rate / align).max(1) * align;
+            let remaining = layout.data_end().saturating_sub(pos);
+            aligned.min(remaining as usize)
+        }
+    }
 }
 
 impl Feeder {
-    /// Luo uuden Feederin, joka etsii kaikki .txt-tiedostot data_dir_path-kansiosta
-    pub fn new(feed_rate: usize, data_dir_path: &str) -> io::Result<Self> {
-        println!(
+    /// Luo Feederin, joka etsii annetuilla päätteillä olevat tiedostot
+    pub fn with_extensions(
+        feed_rate: usize,
+        data_dir_path: &str,
+        extensions: &[String],
+    ) -> io::Result<Self> {
+        say!(
             "  📥 Feeder: Etsitään datatiedostoja kansiosta '{}'...",
+            "  📥 Feeder: Looking for data files in '{}'...",
             data_dir_path
         );
 
-        let mut file_paths = Vec::new();
+        let file_paths = Self::list_files(data_dir_path, extensions)?;
 
-        // Rekursiivinen haku: etsii myös alikansioista
-        Self::find_txt_files(data_dir_path, &mut file_paths)?;
-
-        file_paths.sort();
-
-        println!(
-            "  📥 Feeder: Löydettiin {} .txt-tiedostoa.",
-            file_paths.len()
+        say!(
+            "  📥 Feeder: Löydettiin {} tiedostoa ({}).",
+            "  📥 Feeder: Found {} files ({}).",
+            file_paths.len(),
+            extensions.join(", ")
         );
         for (i, path) in file_paths.iter().enumerate() {
-            println!("     {}: {}", i + 1, path.display());
+            term::emit(
+                Level::Detail,
+                &format!("     {}: {}", i + 1, path.display()),
+            );
         }
 
         Ok(Feeder {
             feed_rate,
-            base_feed_rate: feed_rate,
             file_paths,
             current_file_index: 0,
             current_file_pos: 0, // Alussa 0
             current_file: None,
             is_depleted: false,
             total_fed: 0,
+            transform: Transform::None,
+            layout: None,
+            row_hint: None,
+            skipped_bytes: 0,
+            entropy_gate: None,
+            last_chunk: None,
+            last_fed: Vec::new(),
+            markup: MarkupMode::Keep,
+            markup_filter: MarkupFilter::default(),
+            pool: None,
+            progress: BTreeMap::new(),
+            done_files: BTreeSet::new(),
+            markup_filters: HashMap::new(),
+            last_file: None,
+            throttle: 1.0,
+            known_threshold: None,
+            known_blocks: HashSet::new(),
+            last_hashes: Vec::new(),
+            skipping_known: false,
+            known_bytes: 0,
+            data_dir: data_dir_path.to_string(),
+            extensions: extensions.to_vec(),
+            consumed: HashMap::new(),
+            file_sizes: Vec::new(),
+            quarantine: None,
+            boundaries: BoundaryPolicy::default(),
+            paragraphs: BlankLines::default(),
+            paragraph_states: HashMap::new(),
         })
     }
 
     /// Tallenna Feederin tila (kirjanmerkki)
     pub fn save_state(&self, path: &str) -> io::Result<()> {
-        let state = FeederState {
-            current_file_index: self.current_file_index,
-            current_file_pos: self.current_file_pos,
-            total_fed: self.total_fed,
-        };
-        let json = serde_json::to_string_pretty(&state)
-            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
-        std::fs::write(path, json)?;
-        Ok(())
+        let json = serde_json::to_string_pretty(&self.state()).map_err(io::Error::other)?;
+        atomic::write_atomic(Path::new(path), |writer| writer.write_all(json.as_bytes()))
     }
 
     /// Lataa Feederin tila (kirjanmerkki)
     pub fn load_state(&mut self, path: &str) {
-        if let Ok(content) = std::fs::read_to_string(path) {
-            if let Ok(state) = serde_json::from_str::<FeederState>(&content) {
-                println!("  🔖 Feeder: Ladattiin kirjanmerkki.");
-                println!(
-                    "     Jatketaan tiedostosta indeksi {} kohdasta {}.",
-                    state.current_file_index, state.current_file_pos
-                );
+        if let Ok(content) = std::fs::read_to_string(path)
+            && let Ok(state) = serde_json::from_str::<FeederState>(&content)
+        {
+            say!(
+                "  🔖 Feeder: Ladattiin kirjanmerkki.",
+                "  🔖 Feeder: Bookmark loaded."
+            );
+            self.restore_state(&state);
+        }
+    }
 
-                self.current_file_index = state.current_file_index;
-                self.current_file_pos = state.current_file_pos;
-                self.total_fed = state.total_fed;
+    /// Korpuksen edistyminen (luettu, yhteensä) tavuina tiedostojen
+    /// koosta. Koot luetaan kerran; seurantatilassa uudet tiedostot
+    /// lisätään mukaan.
+    pub fn corpus_progress(&mut self) -> (u64, u64) {
+        for path in &self.file_paths[self.file_sizes.len()..] {
+            self.file_sizes
+                .push(fs::metadata(path).map_or(0, |m| m.len()));
+        }
+        let state = self.state();
+        let size = |i: usize| self.file_sizes.get(i).copied().unwrap_or(0);
+        let consumed = (0..state.current_file_index).map(size).sum::<u64>()
+            + state.current_file_pos
+            + state.open_files.iter().map(|&(_, pos)| pos).sum::<u64>()
+            + state.done_files.iter().map(|&i| size(i)).sum::<u64>();
+        let total = self.file_sizes.iter().sum();
+        (consumed.min(total), total)
+    }
 
-                // Nollaa nykyinen tiedostokahva jotta open_next_file avaa sen oikein
-                self.current_file = None;
-            }
+    /// Nykyinen tila (kirjanmerkki ja istuntotiedosto)
+    pub fn state(&self) -> FeederState {
+        let mut known_blocks: Vec<u64> = self.known_blocks.iter().copied().collect();
+        known_blocks.sort_unstable();
+        if self.pool.is_none() {
+            return FeederState {
+                current_file_index: self.current_file_index,
+                current_file_pos: self.current_file_pos,
+                total_fed: self.total_fed,
+                skipped_bytes: self.skipped_bytes,
+                markup_in_tag: self.markup_filter.in_tag,
+                open_files: self.progress.iter().map(|(&i, &p)| (i, p)).collect(),
+                done_files: self.done_files.iter().copied().collect(),
+                open_tags: self.open_tags(self.current_file_index),
+                known_blocks,
+                known_bytes: self.known_bytes,
+            };
+        }
+
+        // Yhdistetty kirjanmerkki: ensimmäinen keskeneräinen tiedosto ja
+        // sen jälkeiset kesken olevat / valmiit tiedostot
+        let mut low = self.current_file_index;
+        while self.done_files.contains(&low) {
+            low += 1;
+        }
+        FeederState {
+            current_file_index: low,
+            current_file_pos: self.progress.get(&low).copied().unwrap_or(0),
+            total_fed: self.total_fed,
+            skipped_bytes: self.skipped_bytes,
+            markup_in_tag: self.markup_filters.get(&low).is_some_and(|f| f.in_tag),
+            open_files: self
+                .progress
+                .range(low + 1..)
+                .map(|(&i, &p)| (i, p))
+                .collect(),
+            done_files: self.done_files.range(low + 1..).copied().collect(),
+            open_tags: self.open_tags(low),
+            known_blocks,
+            known_bytes: self.known_bytes,
         }
     }
 
-    /// Rekursiivinen .txt-tiedostojen etsintä
-    fn find_txt_files(dir_path: &str, file_paths: &mut Vec<PathBuf>) -> io::Result<()> {
+    /// Tiedostot `current`:n jälkeen, joiden tagisuodatin on kesken tagin
+    fn open_tags(&self, current: usize) -> Vec<usize> {
+        let mut open: Vec<usize> = self
+            .markup_filters
+            .iter()
+            .filter(|&(&i, f)| i > current && f.in_tag)
+            .map(|(&i, _)| i)
+            .collect();
+        open.sort_unstable();
+        open
+    }
+
+    /// Palauta tallennettu tila
+    pub fn restore_state(&mut self, state: &FeederState) {
+        say!(
+            "     Jatketaan tiedostosta indeksi {} kohdasta {}.",
+            "     Resuming from file index {} at position {}.",
+            state.current_file_index,
+            state.current_file_pos
+        );
+
+        self.current_file_index = state.current_file_index;
+        self.current_file_pos = state.current_file_pos;
+        self.total_fed = state.total_fed;
+        self.skipped_bytes = state.skipped_bytes;
+        self.markup_filter.in_tag = state.markup_in_tag;
+        self.markup_filters = state
+            .open_tags
+            .iter()
+            .map(|&i| (i, MarkupFilter { in_tag: true }))
+            .collect();
+        self.progress = state.open_files.iter().copied().collect();
+        self.done_files = state.done_files.iter().copied().collect();
+        self.known_blocks = state.known_blocks.iter().copied().collect();
+        self.known_bytes = state.known_bytes;
+
+        // Nollaa nykyinen tiedostokahva jotta open_next_file avaa sen oikein
+        self.current_file = None;
+    }
+
+    /// Käynnistä rinnakkaiset lukijat (alle 2 = tavallinen peräkkäinen luku).
+    /// Kutsutaan kirjanmerkin palautuksen jälkeen.
+    pub fn start_readers(&mut self, readers: usize) {
+        if readers < 2 || self.is_depleted {
+            return;
+        }
+
+        self.progress
+            .insert(self.current_file_index, self.current_file_pos);
+        let work: Vec<(usize, u64)> = (self.current_file_index..self.file_paths.len())
+            .filter(|i| !self.done_files.contains(i))
+            .map(|i| (i, self.progress.get(&i).copied().unwrap_or(0)))
+            .collect();
+
+        say!(
+            "  📥 Feeder: {} rinnakkaista lukijaa, {} tiedostoa jonossa.",
+            "  📥 Feeder: {} parallel readers, {} files queued.",
+            readers,
+            work.len()
+        );
+        // Kesken olevan tiedoston tagi- ja kappaletila siirtyy lukijoille
+        if self.current_file_pos > 0 {
+            self.markup_filters.insert(
+                self.current_file_index,
+                std::mem::take(&mut self.markup_filter),
+            );
+            self.paragraph_states.insert(
+                self.current_file_index,
+                std::mem::take(&mut self.paragraphs),
+            );
+        }
+        self.current_file = None;
+        self.pool = Some(ReaderPool::spawn(
+            self.file_paths.clone(),
+            work,
+            readers,
+            self.effective_rate(),
+            self.row_hint,
+        ));
+    }
+
+    /// Kansion tiedostot annetuilla päätteillä (rekursiivisesti, järjestettynä)
+    pub fn list_files(dir_path: &str, extensions: &[String]) -> io::Result<Vec<PathBuf>> {
+        let mut file_paths = Vec::new();
+        Self::find_files(dir_path, extensions, &mut file_paths)?;
+        file_paths.sort();
+        Ok(file_paths)
+    }
+
+    /// Rekursiivinen tiedostojen etsintä päätteen perusteella
+    fn find_files(
+        dir_path: &str,
+        extensions: &[String],
+        file_paths: &mut Vec<PathBuf>,
+    ) -> io::Result<()> {
         for entry in fs::read_dir(dir_path)? {
             let entry = entry?;
             let path = entry.path();
@@ -111,14 +517,15 @@ impl Feeder {
             if path.is_dir() {
                 // Rekursiivisesti alikansioihin
                 if let Some(path_str) = path.to_str() {
-                    Self::find_txt_files(path_str, file_paths)?;
-                }
-            } else if path.is_file() {
-                if let Some(ext) = path.extension() {
-                    if ext == "txt" {
-                        file_paths.push(path);
-                    }
+                    Self::find_files(path_str, extensions, file_paths)?;
                 }
+            } else if path.is_file()
+                && let Some(ext) = path.extension()
+                && extensions
+                    .iter()
+                    .any(|e| ext.eq_ignore_ascii_case(e.as_str()))
+            {
+                file_paths.push(path);
             }
         }
         Ok(())
@@ -126,32 +533,103 @@ impl Feeder {
 
     /// Apufunktio, joka avaa seuraavan tiedoston listalta JA kelaa oikeaan kohtaan
     fn open_next_file(&mut self) -> io::Result<()> {
-        if let Some(path) = self.file_paths.get(self.current_file_index) {
-            println!("  📥 Feeder: Avataan tiedosto '{}'...", path.display());
+        // Rinnakkaisajon kirjanmerkistä: ohita valmiit, jatka keskeneräisistä
+        while self.done_files.remove(&self.current_file_index) {
+            self.current_file_index += 1;
+            self.current_file_pos = 0;
+        }
+        if let Some(pos) = self.progress.remove(&self.current_file_index) {
+            self.current_file_pos = self.current_file_pos.max(pos);
+            if let Some(filter) = self.markup_filters.remove(&self.current_file_index) {
+                self.markup_filter = filter;
+            }
+            if let Some(paragraphs) = self.paragraph_states.remove(&self.current_file_index) {
+                self.paragraphs = paragraphs;
+            }
+        }
+
+        if let Some(path) = self.file_paths.get(self.current_file_index).cloned() {
+            if term::is_json() {
+                term::event(
+                    Level::Detail,
+                    "file_opened",
+                    json!({"path": path, "offset": self.current_file_pos}),
+                );
+            } else {
+                detail!(
+                    "  📥 Feeder: Avataan tiedosto '{}'...",
+                    "  📥 Feeder: Opening file '{}'...",
+                    path.display()
+                );
+            }
+
+            let mut file = File::open(&path)?;
+            if self.current_file_pos == 0 {
+                self.markup_filter = MarkupFilter::default();
+                self.paragraphs = BlankLines::default();
+            }
 
-            let mut file = File::open(path)?;
+            // Mediatiedostoista syötetään vain datalohko
+            self.layout = match media::probe(&path, &mut file, self.row_hint) {
+                Ok(layout) => layout,
+                Err(e) => {
+                    warning!(
+                        "  ⚠️  Ohitetaan '{}': {}",
+                        "  ⚠️  Skipping '{}': {}",
+                        path.display(),
+                        e
+                    );
+                    self.current_file_index += 1;
+                    self.current_file_pos = 0;
+                    return self.open_next_file();
+                }
+            };
+            if let Some(layout) = self.layout {
+                self.current_file_pos = self.current_file_pos.max(layout.data_start);
+                if let Some(width) = layout.row_width {
+                    detail!(
+                        "     🖼️  Rivin leveys: {} tavua",
+                        "     🖼️  Row width: {} bytes",
+                        width
+                    );
+                }
+            }
 
             // Jos meillä on offset (pos > 0), hypätään sinne!
             if self.current_file_pos > 0 {
-                println!("     ⏩ Kelataan kohtaan {}...", self.current_file_pos);
+                detail!(
+                    "     ⏩ Kelataan kohtaan {}...",
+                    "     ⏩ Seeking to {}...",
+                    self.current_file_pos
+                );
                 file.seek(SeekFrom::Start(self.current_file_pos))?;
             }
 
             self.current_file = Some(BufReader::new(file));
             // HUOM: Älä kasvata indexiä tässä, se tehdään vasta kun tiedosto on loppu!
         } else {
-            println!("  📥 Feeder: Kaikki datatiedostot käsitelty.");
+            say!(
+                "  📥 Feeder: Kaikki datatiedostot käsitelty.",
+                "  📥 Feeder: All data files processed."
+            );
             self.is_depleted = true;
             self.current_file = None;
         }
         Ok(())
     }
 
-    /// Syötä seuraava pala dataa suoraan Builderiin (tokenisoi samalla)
-    pub fn feed_to_builder(&mut self, builder: &mut Builder) -> Result<usize, String> {
+    /// Syötä seuraava pala dataa suoraan moottoriin (Builder tokenisoi samalla)
+    pub fn feed_to_engine(&mut self, engine: &mut dyn Engine) -> Result<usize, String> {
+        self.last_chunk = None;
+        self.last_fed.clear();
+        self.last_hashes.clear();
+        self.set_throttle(engine.capacity_hint());
         if self.is_depleted {
             return Ok(0);
         }
+        if self.pool.is_some() {
+            return self.feed_from_pool(engine);
+        }
 
         if self.current_file.is_none() {
             self.open_next_file().map_err(|e| e.to_string())?;
@@ -160,27 +638,36 @@ impl Feeder {
             }
         }
 
+        let chunk = self.chunk_len();
         if let Some(ref mut file) = self.current_file {
-            let mut buffer = vec![0u8; self.feed_rate];
+            let mut buffer = Vec::with_capacity(chunk);
 
-            match file.read(&mut buffer) {
+            match file.by_ref().take(chunk as u64).read_to_end(&mut buffer) {
                 Ok(0) => {
                     // Tiedosto loppui
-                    println!(
-                        "  📥 Feeder: Tiedosto '{}' luettu loppuun.",
-                        self.file_paths[self.current_file_index].display()
-                    );
+                    self.report_file_done(self.current_file_index);
                     self.current_file = None;
+                    self.consumed
+                        .insert(self.current_file_index, self.current_file_pos);
                     self.current_file_index += 1; // Siirry seuraavaan
                     self.current_file_pos = 0; // Nollaa positio seuraavaa varten
+                    if self.boundaries.files {
+                        engine.feed_boundary();
+                    }
 
                     // Rekursiivinen kutsu jotta ei tule tyhjä sykli
-                    self.feed_to_builder(builder)
+                    self.feed_to_engine(engine)
                 }
                 Ok(bytes_read) => {
-                    // Tokenisoi suoraan Builderiin
-                    builder.tokenize(&buffer[..bytes_read]);
-                    self.total_fed += bytes_read;
+                    let mut filter = std::mem::take(&mut self.markup_filter);
+                    let fed = self.feed_chunk(engine, &buffer, self.layout, &mut filter);
+                    self.markup_filter = filter;
+                    self.record_chunk(
+                        self.current_file_index,
+                        self.current_file_pos,
+                        bytes_read,
+                        fed,
+                    );
                     self.current_file_pos += bytes_read as u64; // Päivitä positio
                     Ok(bytes_read)
                 }
@@ -192,20 +679,783 @@ impl Feeder {
         }
     }
 
+    /// Kerro luetusta tiedostosta (JSON-tilassa edistymistapahtuma)
+    fn report_file_done(&self, file_index: usize) {
+        let path = &self.file_paths[file_index];
+        if term::is_json() {
+            term::event(
+                Level::Info,
+                "file_done",
+                json!({
+                    "path": path,
+                    "file_index": file_index,
+                    "files_total": self.file_paths.len(),
+                    "total_fed": self.total_fed,
+                }),
+            );
+        } else {
+            detail!(
+                "  📥 Feeder: Tiedosto '{}' luettu loppuun.",
+                "  📥 Feeder: Finished reading file '{}'.",
+                path.display()
+            );
+        }
+    }
+
+    /// Syötä pala rinnakkaisten lukijoiden kanavasta
+    fn feed_from_pool(&mut self, engine: &mut dyn Engine) -> Result<usize, String> {
+        loop {
+            let Some(message) = self.pool.as_ref().and_then(|pool| pool.recv()) else {
+                say!(
+                    "  📥 Feeder: Kaikki datatiedostot käsitelty.",
+                    "  📥 Feeder: All data files processed."
+                );
+                self.is_depleted = true;
+                return Ok(0);
+            };
+
+            match message {
+                ReaderMessage::Chunk(chunk) => return Ok(self.feed_pooled_chunk(engine, chunk)),
+                ReaderMessage::FileDone(file_index) => {
+                    self.report_file_done(file_index);
+                    self.finish_file(file_index);
+                }
+                ReaderMessage::Failed { file_index, error } => {
+                    warning!(
+                        "  ⚠️  Ohitetaan '{}': {}",
+                        "  ⚠️  Skipping '{}': {}",
+                        self.file_paths[file_index].display(),
+                        error
+                    );
+                    self.finish_file(file_index);
+                }
+            }
+        }
+    }
+
+    /// Syötä lukijalta saatu pala. Eri tiedoston palaa edeltää tietueraja,
+    /// jotta lomitetut tiedostot eivät muodosta pareja keskenään.
+    fn feed_pooled_chunk(&mut self, engine: &mut dyn Engine, chunk: ReadChunk) -> usize {
+        if self.last_file.is_some_and(|f| f != chunk.file_index) {
+            engine.feed_boundary();
+        }
+        self.last_file = Some(chunk.file_index);
+
+        let mut filter = self
+            .markup_filters
+            .remove(&chunk.file_index)
+            .unwrap_or_default();
+        self.paragraphs = self
+            .paragraph_states
+            .remove(&chunk.file_index)
+            .unwrap_or_default();
+        let fed = self.feed_chunk(engine, &chunk.data, chunk.layout, &mut filter);
+        self.markup_filters.insert(chunk.file_index, filter);
+        self.paragraph_states
+            .insert(chunk.file_index, self.paragraphs);
+
+        let len = chunk.data.len();
+        self.record_chunk(chunk.file_index, chunk.offset, len, fed);
+        self.progress
+            .insert(chunk.file_index, chunk.offset + len as u64);
+        len
+    }
+
+    fn finish_file(&mut self, file_index: usize) {
+        let consumed = self.progress.remove(&file_index).unwrap_or(0);
+        self.consumed.insert(file_index, consumed);
+        self.markup_filters.remove(&file_index);
+        self.paragraph_states.remove(&file_index);
+        self.done_files.insert(file_index);
+    }
+
+    /// Syötä luettu pala: entropiaportti, opittujen lohkojen ohitus,
+    /// tagien käsittely ja muunnos.
+    /// Palauttaa moottorille syötetyt tavut, None jos pala ohitettiin.
+    fn feed_chunk(
+        &mut self,
+        engine: &mut dyn Engine,
+        chunk: &[u8],
+        layout: Option<MediaLayout>,
+        filter: &mut MarkupFilter,
+    ) -> Option<usize> {
+        if self.boundaries.feeds {
+            engine.feed_boundary();
+        }
+        // Jo pakattu tai salattu data vain täyttäisi paritilastot
+        let too_random = self
+            .entropy_gate
+            .is_some_and(|gate| chunk.len() >= MIN_GATED_CHUNK && byte_entropy(chunk) > gate);
+        if too_random {
+            return None;
+        }
+
+        if self.known_threshold.is_none() {
+            return Some(self.feed_markup(engine, layout, chunk, filter));
+        }
+
+        // Jo opitut lohkot (esim. toistuva lisenssiotsake) ohitetaan ilman
+        // uudelleentokenisointia; niiden välissä oleva uusi data syötetään.
+        // Tagisuodatin käy ohitetut lohkot läpi, jotta sen tila säilyy.
+        let mut fed = 0;
+        let mut start = 0;
+        for (i, block) in chunk.chunks(KNOWN_BLOCK).enumerate() {
+            if block.len() < KNOWN_BLOCK {
+                break;
+            }
+            let hash = block_hash(block);
+            if !self.known_blocks.contains(&hash) {
+                self.last_hashes.push(hash);
+                continue;
+            }
+            let pos = i * KNOWN_BLOCK;
+            fed += self.feed_markup(engine, layout, &chunk[start..pos], filter);
+            if self.markup != MarkupMode::Keep {
+                filter.split(block);
+            }
+            if !self.skipping_known {
+                engine.feed_boundary();
+            }
+            self.skipping_known = true;
+            self.known_bytes += KNOWN_BLOCK;
+            start = pos + KNOWN_BLOCK;
+        }
+        fed += self.feed_markup(engine, layout, &chunk[start..], filter);
+        Some(fed)
+    }
+
+    /// Syötä data tagien käsittelyn mukaan
+    fn feed_markup(
+        &mut self,
+        engine: &mut dyn Engine,
+        layout: Option<MediaLayout>,
+        chunk: &[u8],
+        filter: &mut MarkupFilter,
+    ) -> usize {
+        if chunk.is_empty() {
+            return 0;
+        }
+        self.skipping_known = false;
+        match self.markup {
+            MarkupMode::Keep => self.feed_record(engine, layout, chunk),
+            MarkupMode::Strip => {
+                let (_, text) = filter.split(chunk);
+                self.feed_record(engine, layout, &text)
+            }
+            MarkupMode::Split => {
+                // Rakenne ja sisältö omina tietueinaan
+                let (tags, text) = filter.split(chunk);
+                self.feed_record(engine, layout, &tags) + self.feed_record(engine, layout, &text)
+            }
+        }
+    }
+
+    /// Kirjaa syötetty pala tilastoihin ja tuttuuskarttaa varten
+    fn record_chunk(&mut self, file_index: usize, offset: u64, length: usize, fed: Option<usize>) {
+        if fed.is_none() {
+            self.skipped_bytes += length;
+        }
+        self.last_chunk = Some(ChunkInfo {
+            file: self.file_paths[file_index].clone(),
+            offset,
+            length,
+            engine_bytes: fed.unwrap_or(0),
+        });
+        self.total_fed += length;
+    }
+
+    /// Syötä tietue; tekstissä tyhjät rivit voivat katkaista sen
+    /// kappaleiksi. Palauttaa moottorille syötettyjen tavujen määrän.
+    fn feed_record(
+        &mut self,
+        engine: &mut dyn Engine,
+        layout: Option<MediaLayout>,
+        data: &[u8],
+    ) -> usize {
+        if !self.boundaries.blank_lines || layout.is_some() {
+            return self.feed_segment(engine, layout, data);
+        }
+        let mut fed = 0;
+        let mut start = 0;
+        for cut in self.paragraphs.cuts(data) {
+            fed += self.feed_segment(engine, layout, &data[start..cut]);
+            engine.feed_boundary();
+            start = cut;
+        }
+        fed + self.feed_segment(engine, layout, &data[start..])
+    }
+
+    /// Syötä yksi tietueen osa moottoriin (valinnaisen muunnoksen kautta)
+    fn feed_segment(
+        &mut self,
+        engine: &mut dyn Engine,
+        layout: Option<MediaLayout>,
+        data: &[u8],
+    ) -> usize {
+        if data.is_empty() {
+            return 0;
+        }
+        let sample_align = layout.map_or(1, |l| l.sample_align);
+        let row_width = layout.and_then(|l| l.row_width);
+        let transformed;
+        let data = match self.transform.resolve(sample_align, row_width) {
+            Transform::None => data,
+            t => {
+                transformed = t.apply(data);
+                &transformed[..]
+            }
+        };
+
+        // Vieras pala karanteeniin; tietueraja estää pareja aukon yli
+        if let Some(quarantine) = self.quarantine.as_mut()
+            && quarantine.is_alien(engine, data)
+        {
+            if let Err(e) = quarantine.divert(data) {
+                warning!(
+                    "  ⚠️  Karanteeniin kirjoitus epäonnistui: {}",
+                    "  ⚠️  Failed to write quarantine: {}",
+                    e
+                );
+            }
+            engine.feed_boundary();
+            return 0;
+        }
+        engine.feed(data);
+        self.last_fed.extend_from_slice(data);
+        data.len()
+    }
+
+    /// Ota käyttöön vieraiden palojen karanteeni
+    pub fn set_quarantine(&mut self, quarantine: Option<Quarantine>) {
+        self.quarantine = quarantine;
+    }
+
+    pub fn quarantine(&self) -> Option<&Quarantine> {
+        self.quarantine.as_ref()
+    }
+
+    /// Palauta virtaan karanteenin palat, jotka ovat nyt tuttuja.
+    /// Palauttaa syötetyt tavut.
+    pub fn replay_quarantine(&mut self, engine: &mut dyn Engine) -> usize {
+        let Some(quarantine) = self.quarantine.as_mut() else {
+            return 0;
+        };
+        let ready = match quarantine.take_ready(|data| quarantine::familiarity(engine, data)) {
+            Ok(ready) => ready,
+            Err(e) => {
+                warning!(
+                    "  ⚠️  Karanteenin päivitys epäonnistui: {}",
+                    "  ⚠️  Failed to update quarantine: {}",
+                    e
+                );
+                return 0;
+            }
+        };
+        let mut fed = 0;
+        for data in ready {
+            engine.feed_boundary();
+            engine.feed(&data);
+            self.last_fed.extend_from_slice(&data);
+            fed += data.len();
+        }
+        if fed > 0 {
+            engine.feed_boundary();
+        }
+        fed
+    }
+
+    /// Seuraavan palan koko nykyisessä tiedostossa
+    fn chunk_len(&self) -> usize {
+        aligned_chunk_len(self.effective_rate(), self.layout, self.current_file_pos)
+    }
+
+    /// Syöttönopeus vastapaineen jälkeen. Ohitettaessa opittuja lohkoja
+    /// palat pyöristetään lohkon monikerroiksi, jotta lohkot pysyvät
+    /// tasattuina tiedoston alkuun.
+    fn effective_rate(&self) -> usize {
+        let rate = ((self.feed_rate as f64 * self.throttle) as usize).max(1);
+        if self.known_threshold.is_some() {
+            (rate / KNOWN_BLOCK).max(1) * KNOWN_BLOCK
+        } else {
+            rate
+        }
+    }
+
+    /// Vastapaine (0.0 - 1.0): 1.0 = täysi nopeus
+    pub fn throttle(&self) -> f64 {
+        self.throttle
+    }
+
+    /// Aseta vastapaine. Lukijat lukevat valmiiksi vain muutaman palan,
+    /// joten ne hidastuvat heti kun vihje laskee.
+    fn set_throttle(&mut self, hint: f64) {
+        let hint = hint.clamp(0.0, 1.0);
+        if hint != self.throttle {
+            self.throttle = hint;
+            if let Some(pool) = &self.pool {
+                pool.set_feed_rate(self.effective_rate());
+            }
+        }
+    }
+
     /// Tarkista, onko kaikki data syötetty
     pub fn is_depleted(&self) -> bool {
         self.is_depleted
     }
 
+    /// Skannaa datakansio uudelleen: uudet tiedostot lisätään jonon
+    /// loppuun, ja loppuun luettu tiedosto, joka on kasvanut, palautetaan
+    /// jonoon omalla indeksillään edellisestä lukukohdasta. Palauttaa
+    /// lisättyjen ja jatkettujen tiedostojen määrän; jos niitä on, Feeder
+    /// ei ole enää tyhjä.
+    pub fn rescan(&mut self) -> usize {
+        let Ok(listed) = Self::list_files(&self.data_dir, &self.extensions) else {
+            return 0;
+        };
+        let mut added = 0;
+        let mut requeued = Vec::new();
+        for path in listed {
+            match self.file_paths.iter().position(|p| *p == path) {
+                None => {
+                    detail!(
+                        "  📥 Feeder: Uusi tiedosto '{}'.",
+                        "  📥 Feeder: New file '{}'.",
+                        path.display()
+                    );
+                    self.file_paths.push(path);
+                    added += 1;
+                }
+                Some(index) => {
+                    let Some(&consumed) = self.consumed.get(&index) else {
+                        continue;
+                    };
+                    let Ok(len) = fs::metadata(&path).map(|m| m.len()) else {
+                        continue;
+                    };
+                    if len > consumed {
+                        detail!(
+                            "  📥 Feeder: Tiedostoa '{}' jatkettu kohdasta {}.",
+                            "  📥 Feeder: File '{}' appended after {}.",
+                            path.display(),
+                            consumed
+                        );
+                        self.consumed.remove(&index);
+                        self.progress.insert(index, consumed);
+                        if let Some(size) = self.file_sizes.get_mut(index) {
+                            *size = len;
+                        }
+                        requeued.push(index);
+                    }
+                }
+            }
+        }
+        // Jatkettu tiedosto on jo luettu: kelataan sen kohdalle ja merkitään
+        // välissä olevat valmiiksi, jolloin peräkkäinen lukija ohittaa ne
+        if let Some(&first) = requeued.iter().min()
+            && first < self.current_file_index
+        {
+            self.done_files.extend(first..self.current_file_index);
+            self.current_file_index = first;
+            self.current_file_pos = 0;
+        }
+        for index in &requeued {
+            self.done_files.remove(index);
+        }
+        added += requeued.len();
+        if added > 0 && self.is_depleted {
+            // Tyhjentyneet lukijat eivät näe uusia alkioita: jatka peräkkäin
+            self.pool = None;
+            self.is_depleted = false;
+        }
+        added
+    }
+
+    /// Seurantatila: odota, kunnes datakansioon tulee uutta luettavaa.
+    /// Kansion muutokset herättävät heti (notify); lisäksi kansio skannataan
+    /// `interval` välein siltä varalta, että ilmoituksia ei saada (esim.
+    /// verkkolevyt). Palauttaa lisätyt tiedostot, tai 0 heti kun `stop`
+    /// asetetaan (keskeytys), jolloin ajo päättyy normaalisti.
+    pub fn wait_for_data(&mut self, interval: Duration, stop: &AtomicBool) -> usize {
+        // Odotetaan lyhyissä paloissa, jotta keskeytys ei odota koko väliä
+        const SLICE: Duration = Duration::from_millis(100);
+        // Seuranta ennen ensimmäistä skannausta: välissä tullut muutos ei katoa
+        let (tx, rx) = mpsc::channel();
+        let watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+            if event.is_ok() {
+                let _ = tx.send(());
+            }
+        })
+        .and_then(|mut watcher| {
+            watcher.watch(Path::new(&self.data_dir), RecursiveMode::Recursive)?;
+            Ok(watcher)
+        });
+        if let Err(e) = &watcher {
+            detail!(
+                "  👀 Kansion seuranta ei käytössä ({}), skannataan {:.0} s välein.",
+                "  👀 Directory watching unavailable ({}), scanning every {:.0} s.",
+                e,
+                interval.as_secs_f64()
+            );
+        }
+        loop {
+            if stop.load(Ordering::SeqCst) {
+                return 0;
+            }
+            let added = self.rescan();
+            if added > 0 {
+                return added;
+            }
+            let mut waited = Duration::ZERO;
+            while waited < interval && !stop.load(Ordering::SeqCst) {
+                let step = SLICE.min(interval - waited);
+                if rx.recv_timeout(step).is_ok() {
+                    // Kirjoitus tuottaa useita ilmoituksia: yksi skannaus riittää
+                    while rx.try_recv().is_ok() {}
+                    break;
+                }
+                waited += step;
+            }
+        }
+    }
+
     /// Aseta syöttönopeus
-    #[allow(dead_code)]
     pub fn set_feed_rate(&mut self, rate: usize) {
         self.feed_rate = rate.max(1);
+        if let Some(pool) = &self.pool {
+            pool.set_feed_rate(self.effective_rate());
+        }
+    }
+
+    /// Aseta RAW-kuvien rivin leveys (tavuina)
+    pub fn set_row_hint(&mut self, row_width: Option<usize>) {
+        self.row_hint = row_width;
+    }
+
+    /// Tiedostot, joita ei ole vielä aloitettu
+    pub fn pending_files(&self) -> Vec<PathBuf> {
+        if let Some(pool) = &self.pool {
+            return pool
+                .queued()
+                .into_iter()
+                .map(|i| self.file_paths[i].clone())
+                .collect();
+        }
+        let next = if self.current_file_pos > 0 || self.current_file.is_some() {
+            self.current_file_index + 1
+        } else {
+            self.current_file_index
+        };
+        (next..self.file_paths.len())
+            .filter(|i| !self.done_files.contains(i) && !self.progress.contains_key(i))
+            .map(|i| self.file_paths[i].clone())
+            .collect()
     }
 
-    /// Palauta perusnopeus
-    #[allow(dead_code)]
-    pub fn reset_feed_rate(&mut self) {
-        self.feed_rate = self.base_feed_rate;
+    /// Viimeksi syötetty pala
+    pub fn last_chunk(&self) -> Option<&ChunkInfo> {
+        self.last_chunk.as_ref()
+    }
+
+    /// Viimeisimmän syöttökutsun tavut muunnosten jälkeen (vertailumalleille)
+    pub fn last_fed(&self) -> &[u8] {
+        &self.last_fed
+    }
+
+    /// Aseta entropiaraja (bittiä/tavu), None = ei rajaa
+    pub fn set_entropy_gate(&mut self, gate: Option<f64>) {
+        self.entropy_gate = gate;
+    }
+
+    /// Aseta tuttuusraja jo opittujen lohkojen ohitukselle (None = pois)
+    pub fn set_known_threshold(&mut self, threshold: Option<f64>) {
+        self.known_threshold = threshold;
+    }
+
+    /// Kirjaa viimeksi syötetyn palan tuttuus: rajan ylittäneen palan
+    /// lohkot muistetaan, ja niiden kopiot ohitetaan myöhemmin
+    pub fn note_familiarity(&mut self, familiarity: f64) {
+        if self.known_threshold.is_none_or(|t| familiarity < t) {
+            return;
+        }
+        for &hash in &self.last_hashes {
+            if self.known_blocks.len() >= MAX_KNOWN_BLOCKS {
+                break;
+            }
+            self.known_blocks.insert(hash);
+        }
+    }
+
+    /// Aseta tietuerajojen lisäys
+    pub fn set_boundaries(&mut self, boundaries: BoundaryPolicy) {
+        self.boundaries = boundaries;
+    }
+
+    /// Aseta HTML/XML-tagien käsittely
+    pub fn set_markup(&mut self, markup: MarkupMode) {
+        self.markup = markup;
+    }
+
+    /// Aseta esikäsittelymuunnos (esim. BWT+MTF)
+    pub fn set_transform(&mut self, transform: Transform) {
+        self.transform = transform;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::{BOUNDARY_TOKEN, Builder};
+    use std::thread;
+
+    /// Syötä kunnes data loppuu; palauttaa syötetyt tavut järjestyksessä
+    fn drain_bytes(feeder: &mut Feeder, engine: &mut Builder) -> Vec<u8> {
+        let mut fed = Vec::new();
+        while !feeder.is_depleted() {
+            feeder.feed_to_engine(engine).unwrap();
+            fed.extend_from_slice(feeder.last_fed());
+        }
+        fed
+    }
+
+    #[test]
+    fn test_known_blocks_are_skipped() {
+        let root = std::env::temp_dir().join(format!("petri_feeder_{}", std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+        let header: Vec<u8> = b"// Copyright (c) Petri. Licensed under MIT.\n"
+            .iter()
+            .cycle()
+            .take(2 * KNOWN_BLOCK)
+            .copied()
+            .collect();
+        fs::write(root.join("a.txt"), &header).unwrap();
+        fs::write(root.join("b.txt"), [&header[..], b"fn main() {}"].concat()).unwrap();
+
+        // Nopeus pyöristyy lohkon monikerraksi: 600 -> 512
+        let mut feeder =
+            Feeder::with_extensions(600, root.to_str().unwrap(), &["txt".into()]).unwrap();
+        feeder.set_known_threshold(Some(0.5));
+        assert_eq!(feeder.chunk_len(), 2 * KNOWN_BLOCK);
+        let mut engine = Builder::new(100);
+
+        // Ensimmäinen kopio syötetään ja todetaan tutuksi
+        feeder.feed_to_engine(&mut engine).unwrap();
+        assert_eq!(feeder.last_chunk().unwrap().engine_bytes, header.len());
+        feeder.note_familiarity(0.9);
+        let stream = engine.stream_len();
+
+        // Toinen kopio ohitetaan (vain tietueraja), mutta lasketaan syötetyksi
+        feeder.feed_to_engine(&mut engine).unwrap();
+        assert_eq!(feeder.last_chunk().unwrap().engine_bytes, 0);
+        assert_eq!(engine.stream_len(), stream + 1);
+        assert_eq!(feeder.known_bytes, header.len());
+        assert_eq!(feeder.total_fed, 2 * header.len());
+        assert_eq!(feeder.state().known_blocks.len(), 2);
+
+        // Uusi loppu syötetään normaalisti
+        feeder.feed_to_engine(&mut engine).unwrap();
+        assert_eq!(feeder.last_chunk().unwrap().engine_bytes, 12);
+
+        fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn test_boundaries_between_files_and_paragraphs() {
+        assert_eq!(
+            BoundaryPolicy::parse("blank, files").map(|p| p.name()),
+            Some("files,blank".to_string())
+        );
+        assert_eq!(BoundaryPolicy::parse("none").unwrap().name(), "none");
+        assert!(BoundaryPolicy::parse("pages").is_none());
+
+        let root = std::env::temp_dir().join(format!("petri_bounds_{}", std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+        // Tyhjä rivi osuu palojen (8 tavua) rajalle
+        fs::write(root.join("a.txt"), b"alpha 1\n \r\nbeta 2\n").unwrap();
+        fs::write(root.join("b.txt"), b"gamma\n").unwrap();
+        let run = |policy: &str| {
+            let mut feeder =
+                Feeder::with_extensions(8, root.to_str().unwrap(), &["txt".into()]).unwrap();
+            feeder.set_boundaries(BoundaryPolicy::parse(policy).unwrap());
+            let mut engine = Builder::new(100);
+            while !feeder.is_depleted() {
+                feeder.feed_to_engine(&mut engine).unwrap();
+            }
+            let stream = &engine.token_stream;
+            let cuts: Vec<usize> = (0..stream.len())
+                .filter(|&i| stream[i] == BOUNDARY_TOKEN)
+                .collect();
+            (cuts, stream.len())
+        };
+
+        // "alpha 1\n \r\n" | "beta 2\n" | "gamma\n" |
+        assert_eq!(run("files,blank"), (vec![11, 19, 26], 27));
+        assert_eq!(run("files"), (vec![18, 25], 26));
+        assert_eq!(run("none"), (vec![], 24));
+        fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn test_pool_resume_keeps_tag_state() {
+        let root = std::env::temp_dir().join(format!("petri_pool_tags_{}", std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+        let page = b"<p class=\"lead\">hei maailma</p><a href=\"/x\">linkki</a>\n".repeat(8);
+        fs::write(root.join("a.html"), &page).unwrap();
+        let open = || {
+            let mut feeder =
+                Feeder::with_extensions(7, root.to_str().unwrap(), &["html".into()]).unwrap();
+            feeder.set_markup(MarkupMode::Strip);
+            feeder
+        };
+
+        let mut sequential = Builder::new(100);
+        let mut feeder = open();
+        while !feeder.is_depleted() {
+            feeder.feed_to_engine(&mut sequential).unwrap();
+        }
+
+        // Rinnakkaisajo katkaistaan kesken tagin ja jatketaan kirjanmerkistä
+        let mut pooled = Builder::new(100);
+        let mut feeder = open();
+        feeder.start_readers(2);
+        let state = (0..page.len())
+            .find_map(|_| {
+                feeder.feed_to_engine(&mut pooled).unwrap();
+                Some(feeder.state()).filter(|s| s.markup_in_tag)
+            })
+            .expect("no chunk ended inside a tag");
+        drop(feeder);
+
+        let mut feeder = open();
+        feeder.restore_state(&state);
+        feeder.start_readers(2);
+        while !feeder.is_depleted() {
+            feeder.feed_to_engine(&mut pooled).unwrap();
+        }
+        assert_eq!(pooled.decode_stream(), sequential.decode_stream());
+
+        fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn test_rescan_finds_new_and_appended_files() {
+        let root = std::env::temp_dir().join(format!("petri_watch_{}", std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("a.txt"), b"alku").unwrap();
+
+        let mut feeder =
+            Feeder::with_extensions(100, root.to_str().unwrap(), &["txt".into()]).unwrap();
+        let mut engine = Builder::new(100);
+        let mut drain = |feeder: &mut Feeder| {
+            let mut fed = 0;
+            while !feeder.is_depleted() {
+                fed += feeder.feed_to_engine(&mut engine).unwrap();
+            }
+            fed
+        };
+        assert_eq!(drain(&mut feeder), 4);
+        assert_eq!(feeder.rescan(), 0);
+
+        // Jatkettu tiedosto luetaan edellisestä kohdasta, uusi alusta
+        fs::write(root.join("a.txt"), b"alku ja jatko").unwrap();
+        fs::write(root.join("b.txt"), b"uusi").unwrap();
+        assert_eq!(feeder.rescan(), 2);
+        assert!(!feeder.is_depleted());
+        // Jatkettu tiedosto pitää indeksinsä: polku ei kahdennu
+        assert_eq!(feeder.file_paths.len(), 2);
+        assert_eq!(drain(&mut feeder), 9 + 4);
+        assert_eq!(feeder.rescan(), 0);
+
+        // Keskeytys päättää odotuksen, vaikka uutta dataa ei tule
+        let stop = std::sync::Arc::new(AtomicBool::new(false));
+        let flag = std::sync::Arc::clone(&stop);
+        let waker = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(50));
+            flag.store(true, Ordering::SeqCst);
+        });
+        assert_eq!(feeder.wait_for_data(Duration::from_secs(60), &stop), 0);
+        waker.join().unwrap();
+
+        fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn test_appended_file_resumes_from_bookmark() {
+        let root = std::env::temp_dir().join(format!("petri_append_{}", std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("a.txt"), b"ensimmainen").unwrap();
+        fs::write(root.join("b.txt"), b"toinen").unwrap();
+        let dir = root.to_str().unwrap();
+        let extensions = ["txt".to_string()];
+
+        let mut feeder = Feeder::with_extensions(4, dir, &extensions).unwrap();
+        let mut engine = Builder::new(100);
+        assert_eq!(drain_bytes(&mut feeder, &mut engine), b"ensimmainentoinen");
+
+        // Ensimmäinen tiedosto kasvaa: kirjanmerkki kesken sen jatko-osan
+        fs::write(root.join("a.txt"), b"ensimmainen jatkuu pitkaan").unwrap();
+        assert_eq!(feeder.rescan(), 1);
+        feeder.feed_to_engine(&mut engine).unwrap();
+        assert_eq!(feeder.last_fed(), b" jat");
+        let state = feeder.state();
+        assert_eq!(state.current_file_index, 0);
+        assert_eq!(state.done_files, vec![1]);
+
+        // Kirjanmerkistä jatkettaessa luetaan vain loput, toista ei uudelleen
+        let mut resumed = Feeder::with_extensions(4, dir, &extensions).unwrap();
+        resumed.restore_state(&state);
+        assert_eq!(drain_bytes(&mut resumed, &mut engine), b"kuu pitkaan");
+
+        fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn test_rescan_switches_pool_to_sequential() {
+        let root = std::env::temp_dir().join(format!("petri_switch_{}", std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("a.txt"), b"aaaa").unwrap();
+        fs::write(root.join("b.txt"), b"bbbb").unwrap();
+
+        let mut feeder =
+            Feeder::with_extensions(2, root.to_str().unwrap(), &["txt".into()]).unwrap();
+        feeder.start_readers(2);
+        let mut engine = Builder::new(100);
+        let mut fed = drain_bytes(&mut feeder, &mut engine);
+        fed.sort();
+        assert_eq!(fed, b"aaaabbbb");
+
+        // Tyhjentyneet lukijat eivät näe uutta: jatketaan peräkkäin
+        fs::write(root.join("a.txt"), b"aaaaAA").unwrap();
+        fs::write(root.join("c.txt"), b"cc").unwrap();
+        assert_eq!(feeder.rescan(), 2);
+        assert!(feeder.pool.is_none());
+        assert_eq!(drain_bytes(&mut feeder, &mut engine), b"AAcc");
+        assert_eq!(feeder.file_paths.len(), 3);
+
+        fs::remove_dir_all(&root).ok();
+    }
+
+    #[test]
+    fn test_wait_for_data_wakes_on_new_file() {
+        let root = std::env::temp_dir().join(format!("petri_notify_{}", std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+        let mut feeder =
+            Feeder::with_extensions(100, root.to_str().unwrap(), &["txt".into()]).unwrap();
+        let mut engine = Builder::new(100);
+        assert!(drain_bytes(&mut feeder, &mut engine).is_empty());
+
+        // Pitkä väli: uusi tiedosto huomataan ilmoituksesta, ei skannauksesta
+        let path = root.join("uusi.txt");
+        let writer = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(200));
+            fs::write(path, b"uutta").unwrap();
+        });
+        let stop = AtomicBool::new(false);
+        let started = std::time::Instant::now();
+        assert_eq!(feeder.wait_for_data(Duration::from_secs(30), &stop), 1);
+        assert!(started.elapsed() < Duration::from_secs(10));
+        writer.join().unwrap();
+        assert_eq!(drain_bytes(&mut feeder, &mut engine), b"uutta");
+
+        fs::remove_dir_all(&root).ok();
     }
 }
@@ -0,0 +1,249 @@
+// src/heatmap.rs
+//
+// Heatmap: Tuttuus korpuksen sijainneittain.
+//
+// Globaali tuttuusluku kertoo vain, että jokin osa datasta on tuttua.
+// Tallentamalla jokaisen syöttöpalan tuttuuden (tiedosto + offset) nähdään
+// mitkä tiedostot ja niiden osat hierarkia oppi ja mitkä jäivät kohinaksi.
+//
+// Samoista kirjauksista kootaan tiedostokohtainen raportti: paljonko
+// tiedostoa syötettiin, montako mallia syntyi sen aikana ja miten tuttuus
+// kehittyi. Raportti kertoo mitkä dokumentit oikeasti opettivat jotain.
+
+use crate::feeder::ChunkInfo;
+use crate::term::say;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+/// Yhden syöttöpalan tuttuus
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChunkRecord {
+    /// Palan alku tiedostossa
+    pub offset: u64,
+    pub length: usize,
+    /// Sykli, jolla pala syötettiin
+    pub cycle: usize,
+    /// Palan tiivistyssuhde oppimissyklin jälkeen (0.0 - 1.0)
+    pub familiarity: f64,
+    /// Ohitettiinko pala (esim. entropiaportti)
+    pub skipped: bool,
+    /// Palan jälkeisessä syklissä syntyneet mallit
+    pub patterns_created: usize,
+}
+
+/// Yhden tiedoston oppimisyhteenveto
+#[derive(Debug, Clone, Serialize)]
+pub struct FileSummary {
+    pub file: String,
+    pub chunks: usize,
+    pub bytes_fed: usize,
+    pub bytes_skipped: usize,
+    pub patterns_created: usize,
+    /// Ensimmäisen ja viimeisen syötetyn palan tuttuus
+    pub first_familiarity: f64,
+    pub last_familiarity: f64,
+    /// Tavuilla painotettu keskiarvo
+    pub mean_familiarity: f64,
+}
+
+/// Tuttuuskartta: palat tiedostoittain syöttöjärjestyksessä
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FamiliarityMap {
+    pub files: BTreeMap<String, Vec<ChunkRecord>>,
+}
+
+impl FamiliarityMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Kirjaa syötetty pala
+    pub fn record(
+        &mut self,
+        chunk: &ChunkInfo,
+        cycle: usize,
+        familiarity: f64,
+        patterns_created: usize,
+    ) {
+        let skipped = chunk.engine_bytes == 0;
+        self.files
+            .entry(chunk.file.display().to_string())
+            .or_default()
+            .push(ChunkRecord {
+                offset: chunk.offset,
+                length: chunk.length,
+                cycle,
+                familiarity: if skipped { 0.0 } else { familiarity },
+                skipped,
+                patterns_created,
+            });
+    }
+
+    /// Tiedostokohtaiset yhteenvedot, eniten malleja tuottaneet ensin
+    pub fn file_summaries(&self) -> Vec<FileSummary> {
+        let mut summaries: Vec<FileSummary> = self
+            .files
+            .iter()
+            .map(|(file, records)| {
+                let fed: Vec<&ChunkRecord> = records.iter().filter(|r| !r.skipped).collect();
+                let bytes_fed: usize = fed.iter().map(|r| r.length).sum();
+                let weighted: f64 = fed.iter().map(|r| r.familiarity * r.length as f64).sum();
+                FileSummary {
+                    file: file.clone(),
+                    chunks: records.len(),
+                    bytes_fed,
+                    bytes_skipped: records.iter().filter(|r| r.skipped).map(|r| r.length).sum(),
+                    patterns_created: records.iter().map(|r| r.patterns_created).sum(),
+                    first_familiarity: fed.first().map_or(0.0, |r| r.familiarity),
+                    last_familiarity: fed.last().map_or(0.0, |r| r.familiarity),
+                    mean_familiarity: if bytes_fed > 0 {
+                        weighted / bytes_fed as f64
+                    } else {
+                        0.0
+                    },
+                }
+            })
+            .collect();
+        summaries.sort_by(|a, b| {
+            b.patterns_created
+                .cmp(&a.patterns_created)
+                .then_with(|| a.file.cmp(&b.file))
+        });
+        summaries
+    }
+
+    /// Kirjattujen palojen määrä
+    pub fn len(&self) -> usize {
+        self.files.values().map(|v| v.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// Kirjoita kartta CSV:ksi (rivi per pala) ja JSON:ksi (tiedostoittain)
+    /// (`<prefix>.csv` ja `<prefix>.json`)
+    pub fn export(&self, prefix: &str) -> io::Result<()> {
+        let mut csv = BufWriter::new(File::create(format!("{}.csv", prefix))?);
+        writeln!(
+            csv,
+            "file,offset,length,cycle,familiarity,skipped,patterns_created"
+        )?;
+        for (file, records) in &self.files {
+            for r in records {
+                writeln!(
+                    csv,
+                    "{},{},{},{},{:.4},{},{}",
+                    quote(file),
+                    r.offset,
+                    r.length,
+                    r.cycle,
+                    r.familiarity,
+                    r.skipped,
+                    r.patterns_created
+                )?;
+            }
+        }
+        csv.flush()?;
+
+        let json = serde_json::to_string_pretty(self).map_err(io::Error::other)?;
+        std::fs::write(format!("{}.json", prefix), json)
+    }
+}
+
+fn quote(field: &str) -> String {
+    format!("\"{}\"", field.replace('"', "\"\""))
+}
+
+/// Kirjoita tiedostoraportti CSV:ksi
+pub fn export_file_report(path: &str, summaries: &[FileSummary]) -> io::Result<()> {
+    let mut csv = BufWriter::new(File::create(path)?);
+    writeln!(
+        csv,
+        "file,chunks,bytes_fed,bytes_skipped,patterns_created,first_familiarity,last_familiarity,mean_familiarity"
+    )?;
+    for s in summaries {
+        writeln!(
+            csv,
+            "{},{},{},{},{},{:.4},{:.4},{:.4}",
+            quote(&s.file),
+            s.chunks,
+            s.bytes_fed,
+            s.bytes_skipped,
+            s.patterns_created,
+            s.first_familiarity,
+            s.last_familiarity,
+            s.mean_familiarity
+        )?;
+    }
+    csv.flush()
+}
+
+/// Tulosta opettavimmat tiedostot raporttiin
+pub fn print_file_report(summaries: &[FileSummary], top_n: usize) {
+    if summaries.is_empty() {
+        return;
+    }
+    say!(
+        "\n  📚 Opettavimmat tiedostot:",
+        "\n  📚 Most instructive files:"
+    );
+    for s in summaries.iter().take(top_n) {
+        say!(
+            "     {:>5} mallia  {:>9} tavua  Fam {:>5.1}% → {:>5.1}%  {}",
+            "     {:>5} patterns  {:>9} bytes  Fam {:>5.1}% → {:>5.1}%  {}",
+            s.patterns_created,
+            s.bytes_fed,
+            s.first_familiarity * 100.0,
+            s.last_familiarity * 100.0,
+            s.file
+        );
+    }
+    if summaries.len() > top_n {
+        say!(
+            "     ... ja {} muuta tiedostoa",
+            "     ... and {} more files",
+            summaries.len() - top_n
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn chunk(file: &str, offset: u64, engine_bytes: usize) -> ChunkInfo {
+        ChunkInfo {
+            file: PathBuf::from(file),
+            offset,
+            length: 100,
+            engine_bytes,
+        }
+    }
+
+    #[test]
+    fn test_records_grouped_per_file() {
+        let mut map = FamiliarityMap::new();
+        map.record(&chunk("b.txt", 0, 100), 1, 0.1, 5);
+        map.record(&chunk("a.txt", 0, 100), 2, 0.2, 1);
+        map.record(&chunk("b.txt", 100, 0), 3, 0.9, 2);
+
+        assert_eq!(map.len(), 3);
+        let b = &map.files["b.txt"];
+        assert_eq!(b.len(), 2);
+        assert_eq!(b[1].offset, 100);
+        // Ohitetulla palalla ei ole tuttuutta
+        assert!(b[1].skipped);
+        assert_eq!(b[1].familiarity, 0.0);
+
+        let summaries = map.file_summaries();
+        assert_eq!(summaries[0].file, "b.txt");
+        assert_eq!(summaries[0].patterns_created, 7);
+        assert_eq!(summaries[0].bytes_fed, 100);
+        assert_eq!(summaries[0].bytes_skipped, 100);
+        assert_eq!(summaries[0].last_familiarity, 0.1);
+    }
+}
@@ -0,0 +1,257 @@
+// src/huffman.rs
+//
+// Huffman-koodaus token-virralle.
+//
+// Evaluatorin bit_cost olettaa että jokainen token maksaa log2(mallien määrä)
+// bittiä. Todellisuudessa tokenien frekvenssit ovat hyvin vinoja, joten
+// kanoninen Huffman-koodi antaa todellisen, purettavissa olevan koon.
+//
+// Tiedostomuoto (kaikki kokonaisluvut varint-muodossa):
+//   [symbolien määrä]
+//   symbolit ID-järjestyksessä: [ID-erotus edelliseen][koodin pituus: u8]
+//   [tokenien määrä]
+//   [bittivirta, MSB ensin]
+
+use crate::bitio::{BitReader, BitWriter, read_varint, write_varint};
+use crate::i18n::tr;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::io;
+
+/// Kanoninen Huffman-koodi: token-ID -> (koodi, pituus)
+pub struct HuffmanCode {
+    codes: HashMap<u32, (u64, u8)>,
+}
+
+impl HuffmanCode {
+    /// Rakenna koodi tokenien frekvensseistä
+    pub fn from_tokens(tokens: &[u32]) -> Self {
+        let mut freqs: HashMap<u32, u64> = HashMap::new();
+        for &t in tokens {
+            *freqs.entry(t).or_insert(0) += 1;
+        }
+        let mut symbols: Vec<(u32, u64)> = freqs.into_iter().collect();
+        symbols.sort_by_key(|&(id, _)| id);
+
+        let lengths = code_lengths(&symbols);
+        let pairs: Vec<(u32, u8)> = symbols
+            .iter()
+            .zip(lengths)
+            .map(|(&(id, _), len)| (id, len))
+            .collect();
+        Self::from_lengths(&pairs)
+    }
+
+    /// Rakenna kanoninen koodi pelkistä pituuksista (purkajaa varten)
+    fn from_lengths(lengths: &[(u32, u8)]) -> Self {
+        let mut sorted = lengths.to_vec();
+        sorted.sort_by_key(|&(id, len)| (len, id));
+
+        let mut codes = HashMap::with_capacity(sorted.len());
+        let mut code = 0u64;
+        let mut prev_len = sorted.first().map(|&(_, len)| len).unwrap_or(0);
+        for (id, len) in sorted {
+            code <<= len - prev_len;
+            codes.insert(id, (code, len));
+            code += 1;
+            prev_len = len;
+        }
+        HuffmanCode { codes }
+    }
+
+    /// Koodattujen bittien määrä annetulle virralle
+    pub fn bit_len(&self, tokens: &[u32]) -> u64 {
+        tokens
+            .iter()
+            .map(|t| self.codes.get(t).map(|&(_, len)| len as u64).unwrap_or(0))
+            .sum()
+    }
+}
+
+/// Laske Huffman-koodin pituudet (symbolit ja frekvenssit samassa järjestyksessä)
+fn code_lengths(symbols: &[(u32, u64)]) -> Vec<u8> {
+    let n = symbols.len();
+    if n == 0 {
+        return Vec::new();
+    }
+    if n == 1 {
+        return vec![1];
+    }
+
+    // Solmut: 0..n lehtiä, n.. sisäsolmuja. parent[i] = vanhemman indeksi.
+    let mut parent = vec![usize::MAX; 2 * n - 1];
+    let mut heap: BinaryHeap<Reverse<(u64, usize)>> = symbols
+        .iter()
+        .enumerate()
+        .map(|(i, &(_, freq))| Reverse((freq, i)))
+        .collect();
+
+    let mut next = n;
+    while heap.len() > 1 {
+        let Reverse((f1, a)) = heap.pop().unwrap();
+        let Reverse((f2, b)) = heap.pop().unwrap();
+        parent[a] = next;
+        parent[b] = next;
+        heap.push(Reverse((f1 + f2, next)));
+        next += 1;
+    }
+
+    (0..n)
+        .map(|leaf| {
+            let mut depth = 0u8;
+            let mut node = leaf;
+            while parent[node] != usize::MAX {
+                node = parent[node];
+                depth += 1;
+            }
+            depth
+        })
+        .collect()
+}
+
+/// Koodaa token-virta: kooditaulukko + bittivirta
+pub fn encode(tokens: &[u32]) -> Vec<u8> {
+    let code = HuffmanCode::from_tokens(tokens);
+
+    let mut table: Vec<(u32, u8)> = code
+        .codes
+        .iter()
+        .map(|(&id, &(_, len))| (id, len))
+        .collect();
+    table.sort_by_key(|&(id, _)| id);
+
+    let mut out = Vec::new();
+    write_varint(&mut out, table.len() as u64);
+    let mut prev_id = 0u32;
+    for (id, len) in table {
+        write_varint(&mut out, (id - prev_id) as u64);
+        out.push(len);
+        prev_id = id;
+    }
+    write_varint(&mut out, tokens.len() as u64);
+
+    let mut bits = BitWriter::new();
+    for t in tokens {
+        let (c, len) = code.codes[t];
+        bits.write_bits(c, len);
+    }
+    out.extend(bits.finish());
+    out
+}
+
+/// Pura `encode`:n tuottama data takaisin token-virraksi
+pub fn decode(data: &[u8]) -> io::Result<Vec<u32>> {
+    let mut pos = 0;
+    let symbol_count = read_varint(data, &mut pos)? as usize;
+    // Jokainen taulukon rivi vie vähintään kaksi tavua: laskuri ei voi
+    // luvata enempää kuin syötettä on jäljellä
+    if symbol_count > (data.len() - pos) / 2 {
+        return Err(invalid(&tr!(
+            "katkennut kooditaulukko",
+            "truncated code table"
+        )));
+    }
+
+    let mut lengths = Vec::with_capacity(symbol_count);
+    let mut id = 0u32;
+    for _ in 0..symbol_count {
+        let delta = read_varint(data, &mut pos)?;
+        id = id
+            .checked_add(delta as u32)
+            .ok_or_else(|| invalid(&tr!("virheellinen symboli-ID", "invalid symbol ID")))?;
+        let len = *data
+            .get(pos)
+            .ok_or_else(|| invalid(&tr!("katkennut kooditaulukko", "truncated code table")))?;
+        pos += 1;
+        if len == 0 || len > 64 {
+            return Err(invalid(&tr!(
+                "virheellinen koodin pituus",
+                "invalid code length"
+            )));
+        }
+        lengths.push((id, len));
+    }
+    let token_count = read_varint(data, &mut pos)? as usize;
+    // Jokainen token vie vähintään yhden bitin
+    if token_count / 8 > data.len() - pos {
+        return Err(invalid(&tr!(
+            "katkennut bittivirta",
+            "truncated bit stream"
+        )));
+    }
+
+    let code = HuffmanCode::from_lengths(&lengths);
+    let lookup: HashMap<(u64, u8), u32> = code
+        .codes
+        .iter()
+        .map(|(&id, &(c, len))| ((c, len), id))
+        .collect();
+
+    let mut reader = BitReader::new(&data[pos..]);
+    let mut tokens = Vec::with_capacity(token_count);
+    for _ in 0..token_count {
+        let mut c = 0u64;
+        let mut len = 0u8;
+        loop {
+            let bit = reader
+                .read_bit()
+                .ok_or_else(|| invalid(&tr!("katkennut bittivirta", "truncated bit stream")))?;
+            c = (c << 1) | bit as u64;
+            len += 1;
+            if let Some(&id) = lookup.get(&(c, len)) {
+                tokens.push(id);
+                break;
+            }
+            if len >= 64 {
+                return Err(invalid(&tr!("tuntematon koodi", "unknown code")));
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_huffman_round_trip() {
+        let tokens = vec![97, 98, 97, 300, 97, 97, 98, 1000, 97, 300];
+        let encoded = encode(&tokens);
+        assert_eq!(decode(&encoded).unwrap(), tokens);
+    }
+
+    #[test]
+    fn test_huffman_edge_cases() {
+        assert_eq!(decode(&encode(&[])).unwrap(), Vec::<u32>::new());
+        assert_eq!(decode(&encode(&[42, 42, 42])).unwrap(), vec![42, 42, 42]);
+    }
+
+    #[test]
+    fn test_huffman_skewed_frequencies_are_short() {
+        let mut tokens = vec![1u32; 1000];
+        tokens.extend([2, 3, 4, 5]);
+        let code = HuffmanCode::from_tokens(&tokens);
+        // Yleisin token saa 1-bittisen koodin
+        assert_eq!(code.codes[&1].1, 1);
+        assert!(code.bit_len(&tokens) < 1100);
+    }
+
+    #[test]
+    fn test_huffman_rejects_oversized_counts() {
+        // Tyhjä taulukko ja 2^60 tokenia: ei saa varata muistia laskurin mukaan
+        let mut data = vec![0];
+        write_varint(&mut data, 1 << 60);
+        let err = decode(&data).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        let mut data = Vec::new();
+        write_varint(&mut data, 1 << 60);
+        let err = decode(&data).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,70 @@
+// src/i18n.rs
+//
+// I18n: Käyttäjälle näkyvien tekstien kieli.
+//
+// Jokainen tuloste muotoillaan `tr!`-makrolla (term-moduulin `say!` ja muut
+// tulostusmakrot kutsuvat sitä), joka saa saman viestin suomeksi ja
+// englanniksi. Molemmat versiot ovat muotoilumerkkijonoja samoilla
+// argumenteilla, joten kääntäjä tarkistaa, ettei käännöksestä puutu
+// arvoja. Kieli valitaan kerran ohjelman alussa (`--lang fi|en` tai
+// PETRI_LANG); oletus on suomi.
+
+use std::sync::OnceLock;
+
+/// Tulosteiden kieli
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lang {
+    Fi,
+    En,
+}
+
+impl Lang {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "fi" | "suomi" | "finnish" => Some(Lang::Fi),
+            "en" | "english" => Some(Lang::En),
+            _ => None,
+        }
+    }
+}
+
+static LANG: OnceLock<Lang> = OnceLock::new();
+
+/// Aseta kieli. Vain ensimmäinen kutsu on voimassa.
+pub fn set_lang(lang: Lang) {
+    let _ = LANG.set(lang);
+}
+
+/// Valittu kieli (oletus suomi)
+pub fn lang() -> Lang {
+    LANG.get().copied().unwrap_or(Lang::Fi)
+}
+
+/// Muotoile viesti valitulla kielellä: `tr!("suomeksi {}", "in English {}", x)`
+#[macro_export]
+macro_rules! tr {
+    ($fi:literal, $en:literal $(, $arg:expr)* $(,)?) => {
+        match $crate::i18n::lang() {
+            $crate::i18n::Lang::Fi => format!($fi $(, $arg)*),
+            $crate::i18n::Lang::En => format!($en $(, $arg)*),
+        }
+    };
+}
+
+pub use crate::tr;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lang_parse_and_default() {
+        assert_eq!(Lang::parse("EN"), Some(Lang::En));
+        assert_eq!(Lang::parse("fi"), Some(Lang::Fi));
+        assert_eq!(Lang::parse("sv"), None);
+
+        // Testit eivät aseta kieltä: oletus on suomi
+        let text = tr!("{} mallia", "{} patterns", 3);
+        assert_eq!(text, "3 mallia");
+    }
+}
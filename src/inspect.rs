@@ -0,0 +1,218 @@
+// src/inspect.rs
+//
+// Inspect: Aivotiedoston tarkastelu ilman oppimisajoa.
+//
+// Aivoista halutaan usein tietää vain "mitä tuolla on": mitkä mallit ovat
+// vahvimpia, mitä tasolla 5 on, mihin P_1234 purkautuu. Listaus suodattaa
+// opitut mallit tason, vahvuuden ja käyttökertojen mukaan ja järjestää ne
+// valitun avaimen mukaan. Yksittäisestä mallista näytetään tiedot, purettu
+// sisältö, hierarkia sekä mallit, jotka käyttävät sitä osanaan.
+
+use crate::builder::{Builder, PatternBank};
+use crate::render;
+use crate::term::{self, Level, say};
+
+/// Listauksen oletuspituus
+pub const DEFAULT_LIMIT: usize = 50;
+
+/// Listauksen järjestys (suurin ensin, paitsi ID)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SortKey {
+    #[default]
+    Strength,
+    Usage,
+    Saved,
+    Level,
+    Id,
+}
+
+impl SortKey {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "strength" | "str" => Some(SortKey::Strength),
+            "usage" | "used" => Some(SortKey::Usage),
+            "saved" | "savings" => Some(SortKey::Saved),
+            "level" => Some(SortKey::Level),
+            "id" => Some(SortKey::Id),
+            _ => None,
+        }
+    }
+}
+
+/// Listauksen suodatin; `None` ei rajaa
+#[derive(Debug, Clone, Default)]
+pub struct Filter {
+    pub min_level: Option<u8>,
+    pub max_level: Option<u8>,
+    pub min_strength: Option<f64>,
+    pub max_strength: Option<f64>,
+    pub min_usage: Option<u32>,
+    pub sort: SortKey,
+}
+
+/// Suodattimen läpäisevät opitut mallit järjestyksessä
+pub fn select(bank: &PatternBank, filter: &Filter) -> Vec<u32> {
+    let mut matches: Vec<_> = bank
+        .learned()
+        .filter(|(_, p)| {
+            filter.min_level.is_none_or(|l| p.complexity >= l)
+                && filter.max_level.is_none_or(|l| p.complexity <= l)
+                && filter.min_strength.is_none_or(|s| p.strength >= s)
+                && filter.max_strength.is_none_or(|s| p.strength <= s)
+                && filter.min_usage.is_none_or(|u| p.usage_count >= u)
+        })
+        .collect();
+    matches.sort_by(|(a_id, a), (b_id, b)| {
+        let order = match filter.sort {
+            SortKey::Strength => b.strength.total_cmp(&a.strength),
+            SortKey::Usage => b.usage_count.cmp(&a.usage_count),
+            SortKey::Saved => b.tokens_saved.cmp(&a.tokens_saved),
+            SortKey::Level => b.complexity.cmp(&a.complexity),
+            SortKey::Id => a_id.cmp(b_id),
+        };
+        order.then(a_id.cmp(b_id))
+    });
+    matches.into_iter().map(|(&id, _)| id).collect()
+}
+
+/// Yhteenveto: opittujen mallien määrä tasoittain
+pub fn print_summary(bank: &PatternBank) {
+    say!(
+        "\n  🔍 {} opittua mallia / kapasiteetti {} ({} leksemiä, tokenisointi {})",
+        "\n  🔍 {} learned patterns / capacity {} ({} lexemes, tokenization {})",
+        bank.combine_count(),
+        bank.capacity(),
+        bank.lexeme_count(),
+        bank.tokenization().name()
+    );
+    say!(
+        "     Taso  Malleja  Keskivahvuus  Säästöistä",
+        "     Level Patterns Mean strength Of savings"
+    );
+    for (level, stats) in bank.level_stats() {
+        term::emit(
+            Level::Info,
+            &format!(
+                "     {:>4} {:>8}  {:>13.3} {:>9.1}%",
+                level,
+                stats.count,
+                stats.mean_strength,
+                stats.coverage * 100.0
+            ),
+        );
+    }
+}
+
+/// Listaa mallit (enintään `limit`)
+pub fn print_list(bank: &PatternBank, ids: &[u32], limit: usize) {
+    say!(
+        "\n  📋 {} mallia täsmää (ID, taso, vahvuus, käyttö, säästi):",
+        "\n  📋 {} patterns match (ID, level, strength, usage, saved):",
+        ids.len()
+    );
+    for &id in ids.iter().take(limit) {
+        let Some(p) = bank.get(id) else {
+            continue;
+        };
+        term::emit(
+            Level::Info,
+            &format!(
+                "     P_{:<6} L{:<3} {:.2} {:>7} {:>8}  {}{}",
+                id,
+                p.complexity,
+                p.strength,
+                p.usage_count,
+                p.tokens_saved,
+                render::label(&bank.decode(id), render::PREVIEW_LEN),
+                if p.pinned { " 📌" } else { "" }
+            ),
+        );
+    }
+    if ids.len() > limit {
+        say!(
+            "     ... ja {} muuta (--limit N)",
+            "     ... and {} more (--limit N)",
+            ids.len() - limit
+        );
+    }
+}
+
+/// Yksittäisen mallin tiedot, sisältö, hierarkia ja käyttäjät
+pub fn print_pattern(builder: &Builder, id: u32) {
+    let bank = &builder.bank;
+    let Some(p) = bank.get(id) else {
+        return;
+    };
+    let bytes = bank.decode(id);
+    say!(
+        "\n  P_{}: {} tavua, taso {}, vahvuus {:.3}, käyttö {}, säästi {}, syntyi syklillä {}{}",
+        "\n  P_{}: {} bytes, level {}, strength {:.3}, usage {}, saved {}, born in cycle {}{}",
+        id,
+        bytes.len(),
+        p.complexity,
+        p.strength,
+        p.usage_count,
+        p.tokens_saved,
+        p.birth_cycle,
+        if p.pinned { " 📌" } else { "" }
+    );
+    term::emit(Level::Info, &format!("  \"{}\"", render::escape(&bytes)));
+    builder.print_hierarchy(id, 1);
+
+    let mut parents = bank.parents().remove(&id).unwrap_or_default();
+    parents.sort_unstable();
+    if !parents.is_empty() {
+        say!(
+            "  ⬆️  Osana {} mallissa:",
+            "  ⬆️  Part of {} patterns:",
+            parents.len()
+        );
+        for parent in parents.iter().take(DEFAULT_LIMIT) {
+            term::emit(
+                Level::Info,
+                &format!(
+                    "     P_{} {}",
+                    parent,
+                    render::label(&bank.decode(*parent), render::PREVIEW_LEN)
+                ),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_select_filters_and_sorts() {
+        let mut bank = PatternBank::new(100);
+        let ab = bank.create_combine(97, 98, 0).unwrap();
+        let abc = bank.create_combine(ab, 99, 0).unwrap();
+        let xy = bank.create_combine(120, 121, 0).unwrap();
+        for (id, strength, usage) in [(ab, 0.9, 5), (abc, 0.6, 12), (xy, 0.2, 1)] {
+            let p = bank.get_mut(id).unwrap();
+            p.strength = strength;
+            p.usage_count = usage;
+        }
+
+        assert_eq!(select(&bank, &Filter::default()), [ab, abc, xy]);
+        let level2 = Filter {
+            min_level: Some(2),
+            ..Filter::default()
+        };
+        assert_eq!(select(&bank, &level2), [abc]);
+        let used = Filter {
+            min_usage: Some(2),
+            sort: SortKey::Usage,
+            ..Filter::default()
+        };
+        assert_eq!(select(&bank, &used), [abc, ab]);
+        let weak = Filter {
+            max_strength: Some(0.5),
+            ..Filter::default()
+        };
+        assert_eq!(select(&bank, &weak), [xy]);
+        assert_eq!(SortKey::parse("Saved"), Some(SortKey::Saved));
+    }
+}
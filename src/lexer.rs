@@ -0,0 +1,140 @@
+// src/lexer.rs
+//
+// Lexer: Lähdekoodin pilkkominen atomisiksi siemenyksiköiksi.
+//
+// Tavutilassa hierarkia joutuu oppimaan ensin kirjainparit ja vasta
+// sitten sanat. Lähdekooditilassa tunnisteet, luvut, merkkijonot,
+// välilyöntiajot ja operaattorit syötetään valmiina yksiköinä
+// (dynaamiset literaalit), jolloin parit muodostuvat suoraan
+// API-idiomeista kuten `self` + `.` tai `unwrap` + `()`.
+
+use serde::{Deserialize, Serialize};
+
+/// Monimerkkiset operaattorit, pisimmät ensin
+const OPERATORS: &[&[u8]] = &[
+    b"<<=", b">>=", b"...", b"..=", b"===", b"!==", b"::", b"->", b"=>", b"==", b"!=", b"<=",
+    b">=", b"&&", b"||", b"+=", b"-=", b"*=", b"/=", b"%=", b"&=", b"|=", b"^=", b"<<", b">>",
+    b"++", b"--", b"..", b"()", b"[]", b"{}",
+];
+
+/// Miten raakadata jaetaan siemenyksiköiksi ennen oppimista
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Tokenization {
+    /// Jokainen tavu on oma literaalinsa (oletus)
+    #[default]
+    Bytes,
+    /// Lähdekoodin leksemit dynaamisina literaaleina
+    Source,
+}
+
+impl Tokenization {
+    /// Tulkitse tokenisoinnin nimi konfiguraatiosta
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "bytes" | "byte" | "" => Some(Tokenization::Bytes),
+            "source" | "code" => Some(Tokenization::Source),
+            _ => None,
+        }
+    }
+
+    /// Nimi raportteja varten
+    pub fn name(&self) -> &'static str {
+        match self {
+            Tokenization::Bytes => "bytes",
+            Tokenization::Source => "source",
+        }
+    }
+}
+
+fn is_ident_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_' || b >= 0x80
+}
+
+/// Pilko lähdekoodi leksemeiksi. Palan osat peräkkäin = alkuperäinen data.
+pub fn split_source(data: &[u8]) -> Vec<&[u8]> {
+    let mut units = Vec::new();
+    let mut pos = 0;
+
+    while pos < data.len() {
+        let start = pos;
+        let b = data[pos];
+
+        if is_ident_byte(b) && !b.is_ascii_digit() {
+            // Tunniste tai avainsana
+            while pos < data.len() && is_ident_byte(data[pos]) {
+                pos += 1;
+            }
+        } else if b.is_ascii_digit() {
+            // Luku (myös 0x1F, 1.5e3, 10_000u32)
+            while pos < data.len() && (is_ident_byte(data[pos]) || data[pos] == b'.') {
+                // Ei nielaista välimatkaoperaattoria (0..10)
+                if data[pos] == b'.' && data.get(pos + 1) == Some(&b'.') {
+                    break;
+                }
+                pos += 1;
+            }
+        } else if b == b'"' || b == b'\'' || b == b'`' {
+            // Merkkijono rivin loppuun asti, kenoviiva-escapet huomioiden
+            pos += 1;
+            while pos < data.len() && data[pos] != b && data[pos] != b'\n' {
+                pos += if data[pos] == b'\\' { 2 } else { 1 };
+            }
+            pos = (pos + 1).min(data.len());
+        } else if b.is_ascii_whitespace() {
+            // Välilyöntiajo (esim. rivinvaihto + sisennys)
+            while pos < data.len() && data[pos].is_ascii_whitespace() {
+                pos += 1;
+            }
+        } else {
+            let len = OPERATORS
+                .iter()
+                .find(|op| data[pos..].starts_with(op))
+                .map_or(1, |op| op.len());
+            pos += len;
+        }
+
+        units.push(&data[start..pos]);
+    }
+
+    units
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_source_units() {
+        let src = b"let x = foo::bar(42, \"a\\\"b\");\n    x->y";
+        let units: Vec<&str> = split_source(src)
+            .iter()
+            .map(|u| std::str::from_utf8(u).unwrap())
+            .collect();
+        assert_eq!(
+            units,
+            [
+                "let",
+                " ",
+                "x",
+                " ",
+                "=",
+                " ",
+                "foo",
+                "::",
+                "bar",
+                "(",
+                "42",
+                ",",
+                " ",
+                "\"a\\\"b\"",
+                ")",
+                ";",
+                "\n    ",
+                "x",
+                "->",
+                "y"
+            ]
+        );
+        assert_eq!(split_source(src).concat(), src);
+    }
+}
@@ -0,0 +1,76 @@
+// src/lib.rs
+//
+// Petri dish kirjastona: hierarkkinen oppija upotettavaksi muihin
+// sovelluksiin ilman komentorivisovellusta.
+//
+// Keskeiset tyypit ovat juuressa:
+//   - Builder: token-virta, oppimissykli (`live`) ja tiivistys,
+//   - PatternBank: opitut mallit, koodaus ja purku, tallennus ja lataus,
+//   - Pattern ja Operator: yksittäinen malli ja sen rakenne,
+//   - Feeder: tiedostojen syöttö paloina moottorille,
+//   - Evaluator: tiivistyksen ja koodatun koon mittarit.
+//
+// Tyypillinen käyttö:
+//   let mut builder = Builder::new(1000);
+//   builder.tokenize(data);
+//   for _ in 0..cycles { builder.live(); }
+//   builder.bank.save(path)?;
+//
+// Muut moduulit (arkistot, komentorivin raportit, vertailumoottorit) ovat
+// myös julkisia, sillä komentorivisovellus (main.rs) käyttää niitä tämän
+// kirjaston kautta.
+
+pub mod analysis;
+pub mod archive;
+pub mod atomic;
+pub mod bench;
+pub mod bitio;
+pub mod builder;
+pub mod checksum;
+pub mod cluster;
+pub mod dedup;
+pub mod detect;
+pub mod diff;
+pub mod drift;
+pub mod engine;
+pub mod entropy;
+pub mod evaluator;
+pub mod feeder;
+pub mod grammar;
+pub mod heatmap;
+pub mod huffman;
+pub mod i18n;
+pub mod inspect;
+pub mod lexer;
+pub mod lzw;
+pub mod markov;
+pub mod markup;
+pub mod media;
+pub mod operator;
+pub mod pattern;
+pub mod plot;
+pub mod prior;
+pub mod progress;
+pub mod quarantine;
+pub mod range_coder;
+pub mod reader_pool;
+pub mod recommend;
+pub mod render;
+pub mod repair;
+pub mod repl;
+pub mod segment;
+pub mod sequitur;
+pub mod snapshot;
+pub mod stop;
+pub mod survival;
+pub mod sweep;
+pub mod term;
+pub mod tournament;
+pub mod transform;
+pub mod wordlist;
+
+pub use builder::{Builder, BuilderStats, BuilderStatus, PatternBank};
+pub use evaluator::Evaluator;
+pub use feeder::Feeder;
+pub use operator::Operator;
+pub use pattern::Pattern;
@@ -0,0 +1,162 @@
+// src/lzw.rs
+//
+// Lzw: Klassinen inkrementaalinen sanakirja vertailukohdaksi.
+//
+// LZW jäsentää virran ahneesti: pisin sanakirjasta löytyvä fraasi w
+// tulostetaan, ja sanakirjaan lisätään w + seuraava symboli. Jokainen
+// merkintä on siis Combine(fraasi, siemen), joten sanakirja on suoraan
+// vasemmalle kasvava trie PatternBankissa. Tallennus, raportit ja
+// entropiakoodaus toimivat samoin kuin Builderilla.
+//
+// Viimeinen fraasi on virran lopussa keskeneräisenä: seuraava syöte jatkaa
+// sitä, joten palakoko ei vaikuta tulokseen. Kun pankki täyttyy, sanakirja
+// jäädytetään (ei unohdusta kuten Builderissa).
+
+use crate::builder::{BOUNDARY_TOKEN, Builder, BuilderStats};
+
+/// LZW-moottori. Pankki ja token-virta ovat Builderissa, jotta tuttuuden
+/// mittaus ja tulosteet ovat yhteisiä.
+pub struct Lzw {
+    pub(crate) inner: Builder,
+    /// Edellisen syklin jälkeen luodut merkinnät ja syötetyt siemenet
+    created: usize,
+    fed: usize,
+    /// Virran pituus edellisen syklin lopussa
+    last_len: usize,
+}
+
+impl Lzw {
+    pub fn new(inner: Builder) -> Self {
+        let last_len = inner.token_stream.len();
+        Lzw {
+            inner,
+            created: 0,
+            fed: 0,
+            last_len,
+        }
+    }
+
+    /// Jatka jäsennystä: virran viimeinen token on keskeneräinen fraasi
+    pub fn feed(&mut self, data: &[u8]) {
+        let seeds = self.inner.bank.seed_ids(data);
+        self.fed += seeds.len();
+        let mut current = match self.inner.token_stream.last() {
+            Some(&t) if t != BOUNDARY_TOKEN => self.inner.token_stream.pop(),
+            _ => None,
+        };
+
+        for seed in seeds {
+            let Some(w) = current else {
+                current = Some(seed);
+                continue;
+            };
+            if let Some(id) = self.inner.bank.get_pair_id(w, seed) {
+                current = Some(id);
+                continue;
+            }
+            self.emit(w);
+            let cycle = self.inner.cycle;
+            if let Some(id) = self.inner.bank.create_combine(w, seed, cycle) {
+                if let Some(p) = self.inner.bank.get_mut(id) {
+                    p.strength = 1.0;
+                }
+                self.created += 1;
+            }
+            current = Some(seed);
+        }
+        if let Some(w) = current {
+            self.inner.token_stream.push(w);
+        }
+    }
+
+    fn emit(&mut self, id: u32) {
+        self.inner.token_stream.push(id);
+        if let Some(p) = self.inner.bank.get_mut(id) {
+            p.usage_count += 1;
+        }
+    }
+
+    /// Jäsennä data nykyisellä sanakirjalla lisäämättä merkintöjä
+    pub fn parse(&self, data: &[u8]) -> Vec<u32> {
+        let bank = &self.inner.bank;
+        let mut tokens = Vec::new();
+        let mut current: Option<u32> = None;
+        for seed in bank.encode_seeds(data) {
+            current = match current {
+                None => Some(seed),
+                Some(w) => match bank.get_pair_id(w, seed) {
+                    Some(id) => Some(id),
+                    None => {
+                        tokens.push(w);
+                        Some(seed)
+                    }
+                },
+            };
+        }
+        tokens.extend(current);
+        tokens
+    }
+
+    /// Sykli vain raportoi: sanakirja kasvaa syötettäessä
+    pub fn step(&mut self) -> BuilderStats {
+        self.inner.cycle += 1;
+        let stream_before = self.last_len + self.fed;
+        let stream_after = self.inner.token_stream.len();
+        let patterns_total = self.inner.bank.combine_count();
+        let stats = BuilderStats {
+            cycle: self.inner.cycle,
+            stream_before,
+            stream_after,
+            patterns_created: self.created,
+            patterns_collapsed: 0,
+            patterns_forgotten: 0,
+            patterns_merged: 0,
+            patterns_oscillated: 0,
+            patterns_held: 0,
+            collapse_rejected: 0,
+            patterns_total,
+            compression_ratio: if stream_before > 0 {
+                1.0 - (stream_after as f64 / stream_before as f64)
+            } else {
+                0.0
+            },
+            patterns_before: patterns_total - self.created,
+            familiarity: self.inner.familiarity_profile(),
+        };
+        self.created = 0;
+        self.fed = 0;
+        self.last_len = stream_after;
+        stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chunked_feed_matches_single_feed() {
+        let text = b"abababababab the cat sat on the mat the cat sat".repeat(4);
+
+        let mut whole = Lzw::new(Builder::new(500));
+        whole.feed(&text);
+
+        let mut chunked = Lzw::new(Builder::new(500));
+        for chunk in text.chunks(7) {
+            chunked.feed(chunk);
+        }
+
+        assert_eq!(whole.inner.token_stream, chunked.inner.token_stream);
+        assert_eq!(whole.inner.decode_stream(), text);
+        assert!(whole.inner.token_stream.len() < text.len() / 2);
+
+        // Jäädytetty jäsennys tiivistää jo nähtyä dataa
+        let parsed = whole.parse(b"the cat sat");
+        assert!(parsed.len() < 6);
+        let decoded: Vec<u8> = parsed
+            .iter()
+            .flat_map(|&t| whole.inner.bank.decode(t))
+            .collect();
+        assert_eq!(decoded, b"the cat sat");
+    }
+}
@@ -1,24 +1,64 @@
-mod builder;
-mod evaluator;
-mod feeder;
-mod operator;
-mod pattern;
-
-use builder::{Builder, PatternBank};
-use evaluator::Evaluator;
-use feeder::Feeder;
-
+mod checkpoint;
+mod cli;
+mod commands;
+mod session;
+
+use petri_dish::{
+    atomic, builder, drift, engine, entropy, feeder, heatmap, i18n, lexer, markov, markup,
+    progress, segment, stop, term, transform,
+};
+
+use builder::PatternBank;
+use checkpoint::{AutosavePolicy, CheckpointPolicy};
+use cli::{check_args, check_values, take_switch, take_value, take_verbosity};
+use engine::{BuilderSettings, EngineKind};
+use entropy::Coder;
+use feeder::BoundaryPolicy;
+use i18n::{Lang, tr};
+use lexer::Tokenization;
+use markup::MarkupMode;
+use segment::CollapsePolicy;
+use serde::{Deserialize, Serialize};
+use stop::StopPolicy;
+use term::{Level, Output, detail, error, say, warning};
+use transform::Transform;
+
+use std::collections::HashMap;
 use std::env;
-use std::fs::File;
-use std::io::Write;
 use std::path::Path;
+use std::sync::OnceLock;
 
 /// Oletuspolku aivojen (PatternBank) tallennustiedostolle
 const BRAIN_FILE_PATH: &str = "brain.json";
 
+/// Oletuskansio, josta oppimisen data luetaan
+const DATA_DIR: &str = "./data";
+
 /// Oletuspolku feederin tilan (kirjanmerkki) tallennustiedostolle
 const FEEDER_STATE_PATH: &str = "feeder_state.json";
 
+/// Vieraiden palojen karanteenitiedosto
+const QUARANTINE_PATH: &str = "quarantine.bin";
+
+/// Oletuspolku ajon mittareiden CSV-tiedostolle
+const RESULTS_PATH: &str = "results.csv";
+
+/// Kansio, jonka alle nimetyt profiilit (`--profile nimi`) tallennetaan
+const PROFILES_DIR: &str = "profiles";
+
+/// Tiedoston polku profiilin kansiossa (ilman profiilia `file` sellaisenaan)
+fn profile_file(profile: Option<&str>, file: &str) -> String {
+    match profile {
+        Some(name) => Path::new(PROFILES_DIR)
+            .join(name)
+            .join(file)
+            .display()
+            .to_string(),
+        None => file.to_string(),
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 struct Config {
     /// Maksimi mallien määrä PatternBankissa (paitsi 256 literaalia ja esiluokat)
     pattern_capacity: usize,
@@ -30,10 +70,77 @@ struct Config {
     max_cycles: usize,
     /// Polku aivojen tallennustiedostolle
     brain_path: String,
+    /// Kansio, josta oppimisen data luetaan (tyhjä = DATA_DIR)
+    #[serde(default)]
+    data_dir: String,
+    /// Nimetty profiili: aivot, kirjanmerkki ja CSV kansiossa profiles/<nimi>/
+    #[serde(default)]
+    profile: Option<String>,
     /// Tylsistymiskynnys (0.0-1.0): yli tämän = tylsää, nopeutetaan
     boredom_threshold: f64,
     /// Uteliaisuuskynnys (0.0-1.0): alle tämän = vaikeaa, hidastetaan
     curiosity_threshold: f64,
+    /// Käytettävä oppimismoottori
+    engine: EngineKind,
+    /// Esikäsittelymuunnos ennen oppimista
+    transform: Transform,
+    /// Token-virran entropiakooderi (raportit ja tiedostomuodot)
+    coder: Coder,
+    /// Feederin luettavat tiedostopäätteet
+    extensions: Vec<String>,
+    /// Otsikottomien RAW-kuvien rivin leveys tavuina
+    row_width: Option<usize>,
+    /// Siemenyksiköiden muodostus (None = aivojen oma asetus)
+    tokenization: Option<Tokenization>,
+    /// HTML/XML-tagien käsittely syötössä
+    markup: MarkupMode,
+    /// Mihin syötön väliin lisätään tietueraja
+    #[serde(default)]
+    boundaries: BoundaryPolicy,
+    /// Palan entropiaraja (bittiä/tavu), None = ei ohiteta mitään
+    entropy_gate: Option<f64>,
+    /// Välitallennusten ajastus ja säilytys
+    #[serde(default)]
+    checkpoint: CheckpointPolicy,
+    /// Aivojen ja kirjanmerkin automaattitallennuksen väli
+    #[serde(default)]
+    autosave: AutosavePolicy,
+    /// Tavoitteisiin perustuvat pysäytysehdot
+    #[serde(default)]
+    stop: StopPolicy,
+    /// Rinnakkaisten tiedostonlukijoiden määrä (alle 2 = peräkkäin)
+    #[serde(default)]
+    readers: usize,
+    /// Tuttuusraja, jonka ylittäneiden palojen kopiot ohitetaan (None = pois)
+    #[serde(default)]
+    skip_familiar: Option<f64>,
+    /// Miten tunnetut mallit korvaavat virran tokeneita
+    #[serde(default)]
+    collapse: CollapsePolicy,
+    /// Tasokohtaiset parikynnykset (tyhjä = pair_threshold kaikille)
+    #[serde(default)]
+    level_thresholds: Vec<u32>,
+    /// Tasokohtaiset tiivistyskynnykset (tyhjä = totuuskynnys kaikille)
+    #[serde(default)]
+    collapse_thresholds: Vec<f64>,
+    /// Vahvuuden heikkeneminen syklissä (None = Builderin oletus)
+    #[serde(default)]
+    decay: Option<f64>,
+    /// Totuuskynnyksen vähimmäisviipymä sykleinä (None = Builderin oletus)
+    #[serde(default)]
+    min_dwell: Option<u64>,
+    /// Aivotiedoston kokobudjetti tavuina (None = vain mallien määrä)
+    #[serde(default)]
+    brain_budget: Option<usize>,
+    /// Datan muutoshälytyksen kynnys tuttuuden pudotuksena (None = pois)
+    #[serde(default)]
+    drift_threshold: Option<f64>,
+    /// Tuttuusraja, jota vieraammat palat eristetään karanteeniin (None = pois)
+    #[serde(default)]
+    quarantine: Option<f64>,
+    /// Toistettava ajo: siemen satunnaisuudelle (None = ei rajoituksia)
+    #[serde(default)]
+    seed: Option<u64>,
 }
 
 impl Config {
@@ -43,426 +150,951 @@ impl Config {
     const DEFAULT_MAX_CYCLES: usize = 200;
     const DEFAULT_BOREDOM_THRESHOLD: f64 = 0.70;
     const DEFAULT_CURIOSITY_THRESHOLD: f64 = 0.40;
+    const DEFAULT_ENTROPY_GATE: f64 = 7.5;
 
     fn load() -> Self {
-        let pattern_capacity = env::var("PETRI_PATTERN_CAPACITY")
+        let pattern_capacity = setting("PETRI_PATTERN_CAPACITY")
             .ok()
             .and_then(|v| v.parse().ok())
             .unwrap_or(Self::DEFAULT_PATTERN_CAPACITY);
 
-        let feed_rate = env::var("PETRI_FEED_RATE")
+        let feed_rate = setting("PETRI_FEED_RATE")
             .ok()
             .and_then(|v| v.parse().ok())
             .unwrap_or(Self::DEFAULT_FEED_RATE);
 
-        let pair_threshold = env::var("PETRI_PAIR_THRESHOLD")
+        let pair_threshold = setting("PETRI_PAIR_THRESHOLD")
             .ok()
             .and_then(|v| v.parse().ok())
             .unwrap_or(Self::DEFAULT_PAIR_THRESHOLD);
 
-        let max_cycles = env::var("PETRI_MAX_CYCLES")
+        // Pilkuilla eroteltu taulukko tasoille 1, 2, ...: "3,2,2,1"
+        let level_thresholds = setting("PETRI_LEVEL_THRESHOLDS")
+            .ok()
+            .and_then(|v| {
+                v.split(',')
+                    .map(|t| t.trim().parse().ok().filter(|&t: &u32| t > 0))
+                    .collect::<Option<Vec<u32>>>()
+            })
+            .unwrap_or_default();
+
+        // Tiivistyksen vahvuuskynnykset tasoille 1, 2, ...: "0.5,0.6,0.7"
+        let collapse_thresholds = setting("PETRI_COLLAPSE_THRESHOLDS")
+            .ok()
+            .and_then(|v| {
+                v.split(',')
+                    .map(|t| t.trim().parse().ok().filter(|&t: &f64| t > 0.0 && t <= 1.0))
+                    .collect::<Option<Vec<f64>>>()
+            })
+            .unwrap_or_default();
+
+        let decay = setting("PETRI_DECAY")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|&d: &f64| (0.0..1.0).contains(&d));
+
+        let min_dwell = setting("PETRI_MIN_DWELL").ok().and_then(|v| v.parse().ok());
+
+        let brain_budget = setting("PETRI_BRAIN_BUDGET")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|&b: &usize| b > 0);
+
+        let max_cycles = setting("PETRI_MAX_CYCLES")
             .ok()
             .and_then(|v| v.parse().ok())
             .unwrap_or(Self::DEFAULT_MAX_CYCLES);
 
-        let brain_path =
-            env::var("PETRI_BRAIN_PATH").unwrap_or_else(|_| BRAIN_FILE_PATH.to_string());
+        let profile = setting("PETRI_PROFILE")
+            .ok()
+            .filter(|name| !name.is_empty())
+            .and_then(|name| {
+                let valid = name != ".."
+                    && name != "."
+                    && !name.contains(['/', '\\'])
+                    && !Path::new(&name).is_absolute();
+                if !valid {
+                    warning!(
+                        "  ⚠️  Virheellinen profiilin nimi '{}', käytetään oletuspolkuja.",
+                        "  ⚠️  Invalid profile name '{}', using default paths.",
+                        name
+                    );
+                }
+                valid.then_some(name)
+            });
+
+        // Oma polku voittaa profiilin
+        let brain_path = setting("PETRI_BRAIN_PATH")
+            .unwrap_or_else(|_| profile_file(profile.as_deref(), BRAIN_FILE_PATH));
+
+        let data_dir = setting("PETRI_DATA_DIR").unwrap_or_else(|_| DATA_DIR.to_string());
 
-        let boredom_threshold = env::var("PETRI_BOREDOM_THRESHOLD")
+        let boredom_threshold = setting("PETRI_BOREDOM_THRESHOLD")
             .ok()
             .and_then(|v| v.parse().ok())
             .unwrap_or(Self::DEFAULT_BOREDOM_THRESHOLD);
 
-        let curiosity_threshold = env::var("PETRI_CURIOSITY_THRESHOLD")
+        let curiosity_threshold = setting("PETRI_CURIOSITY_THRESHOLD")
             .ok()
             .and_then(|v| v.parse().ok())
             .unwrap_or(Self::DEFAULT_CURIOSITY_THRESHOLD);
 
-        Config {
-            pattern_capacity,
-            feed_rate,
-            pair_threshold,
-            max_cycles,
-            brain_path,
-            boredom_threshold,
-            curiosity_threshold,
-        }
-    }
-}
-
-/// Lataa PatternBank tiedostosta tai luo uusi
-fn load_or_create_brain(config: &Config) -> PatternBank {
-    let path = Path::new(&config.brain_path);
+        let engine = match setting("PETRI_ENGINE") {
+            Ok(name) => EngineKind::parse(&name).unwrap_or_else(|| {
+                warning!(
+                    "  ⚠️  Tuntematon moottori '{}', käytetään oletusta (builder).",
+                    "  ⚠️  Unknown engine '{}', using default (builder).",
+                    name
+                );
+                EngineKind::Builder
+            }),
+            Err(_) => EngineKind::Builder,
+        };
 
-    if path.exists() {
-        println!("  🧠 Ladataan aivot tiedostosta '{}'...", config.brain_path);
-        match PatternBank::load(path) {
-            Ok(bank) => {
-                println!("  ✅ Aivot ladattu! {} mallia muistissa.", bank.len());
-                return bank;
+        // Esiasetus: oletukset tietylle datatyypille (yksittäiset muuttujat voittavat)
+        let preset = setting("PETRI_PRESET").unwrap_or_default();
+        let (preset_transform, mut extensions) = match preset.as_str() {
+            "audio" => (Transform::Delta { stride: 0 }, vec!["wav".to_string()]),
+            "image" => (
+                Transform::RowDelta,
+                ["pgm", "ppm", "pnm", "raw"].map(String::from).to_vec(),
+            ),
+            "code" => (
+                Transform::None,
+                ["rs", "py", "js", "ts", "c", "h", "cpp", "java", "go"]
+                    .map(String::from)
+                    .to_vec(),
+            ),
+            "web" => (
+                Transform::None,
+                ["html", "htm", "xml"].map(String::from).to_vec(),
+            ),
+            other => {
+                if !other.is_empty() && other != "text" {
+                    warning!(
+                        "  ⚠️  Tuntematon esiasetus '{}', käytetään tekstiä.",
+                        "  ⚠️  Unknown preset '{}', using text.",
+                        other
+                    );
+                }
+                (Transform::None, vec!["txt".to_string()])
             }
-            Err(e) => {
-                println!("  ⚠️  Aivojen lataus epäonnistui: {}", e);
-                println!("     Aloitetaan tyhjästä...");
+        };
+
+        if let Ok(list) = setting("PETRI_EXTENSIONS") {
+            let parsed: Vec<String> = list
+                .split(',')
+                .map(|e| e.trim().trim_start_matches('.').to_ascii_lowercase())
+                .filter(|e| !e.is_empty())
+                .collect();
+            if !parsed.is_empty() {
+                extensions = parsed;
             }
         }
-    } else {
-        println!(
-            "  🧠 Aivotiedostoa '{}' ei löytynyt, aloitetaan tyhjästä.",
-            config.brain_path
-        );
-    }
 
-    PatternBank::new(config.pattern_capacity)
-}
+        let row_width = setting("PETRI_ROW_WIDTH")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|&w: &usize| w > 0);
+
+        let transform = match setting("PETRI_TRANSFORM") {
+            Ok(name) => Transform::parse(&name).unwrap_or_else(|| {
+                warning!(
+                    "  ⚠️  Tuntematon muunnos '{}', ei muunnosta.",
+                    "  ⚠️  Unknown transform '{}', no transform.",
+                    name
+                );
+                Transform::None
+            }),
+            Err(_) => preset_transform,
+        };
 
-/// Tallenna PatternBank tiedostoon
-fn save_brain(bank: &PatternBank, path: &str) {
-    let path = Path::new(path);
-    match bank.save(path) {
-        Ok(()) => println!("  💾 Aivot tallennettu tiedostoon '{}'.", path.display()),
-        Err(e) => println!("  ⚠️  Aivojen tallennus epäonnistui: {}", e),
-    }
-}
+        let tokenization = match setting("PETRI_TOKENIZER") {
+            Ok(name) => Some(Tokenization::parse(&name).unwrap_or_else(|| {
+                warning!(
+                    "  ⚠️  Tuntematon tokenisointi '{}', käytetään tavuja.",
+                    "  ⚠️  Unknown tokenization '{}', using bytes.",
+                    name
+                );
+                Tokenization::Bytes
+            })),
+            Err(_) if preset == "code" => Some(Tokenization::Source),
+            Err(_) => None,
+        };
 
-fn main() {
-    println!("=== Petrimalja Älykkyyelle: HIERARKKINEN TIEDONRAKENNUSKONE ===\n");
-    println!("Ydinfilosofia: \"Totuus on pysyvä yhteys kahden asian välillä.\"\n");
-    println!("Petri Dish 2.0: \"Ikuinen Oppija\" - Pysyvä muisti + Adaptiivinen oppiminen.\n");
+        let markup = match setting("PETRI_MARKUP") {
+            Ok(name) => MarkupMode::parse(&name).unwrap_or_else(|| {
+                warning!(
+                    "  ⚠️  Tuntematon merkintätila '{}', tagit säilytetään.",
+                    "  ⚠️  Unknown markup mode '{}', keeping tags.",
+                    name
+                );
+                MarkupMode::Keep
+            }),
+            Err(_) if preset == "web" => MarkupMode::Split,
+            Err(_) => MarkupMode::Keep,
+        };
+
+        // Esim. "files,blank"; "none" = ei tietuerajoja
+        let boundaries = match setting("PETRI_BOUNDARIES") {
+            Ok(spec) => BoundaryPolicy::parse(&spec).unwrap_or_else(|| {
+                warning!(
+                    "  ⚠️  Tuntematon tietuerajojen asetus '{}', käytetään oletusta.",
+                    "  ⚠️  Unknown boundary setting '{}', using the default.",
+                    spec
+                );
+                BoundaryPolicy::default()
+            }),
+            Err(_) => BoundaryPolicy::default(),
+        };
 
-    let config = Config::load();
+        // "off" tai 0 poistaa rajan käytöstä
+        let entropy_gate = match setting("PETRI_ENTROPY_GATE") {
+            Ok(v) => v.parse::<f64>().ok().filter(|&g| g > 0.0),
+            Err(_) => Some(Self::DEFAULT_ENTROPY_GATE),
+        };
 
-    // Lataa olemassa olevat aivot tai luo uudet
-    let brain = load_or_create_brain(&config);
+        let mut checkpoint = CheckpointPolicy {
+            every_cycles: setting("PETRI_CHECKPOINT_EVERY")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|&n: &usize| n > 0),
+            every_minutes: setting("PETRI_CHECKPOINT_MINUTES")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|&m: &f64| m > 0.0),
+            ..CheckpointPolicy::default()
+        };
+        if let Some(keep) = setting("PETRI_CHECKPOINT_KEEP")
+            .ok()
+            .and_then(|v| v.parse().ok())
+        {
+            checkpoint.keep_last = keep;
+        }
+        // Oma kansio voittaa profiilin
+        checkpoint.dir = setting("PETRI_CHECKPOINT_DIR")
+            .unwrap_or_else(|_| profile_file(profile.as_deref(), checkpoint::DEFAULT_DIR));
+
+        let autosave = AutosavePolicy {
+            every_cycles: setting("PETRI_AUTOSAVE_EVERY")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|&n: &usize| n > 0),
+            every_bytes: setting("PETRI_AUTOSAVE_BYTES")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|&n: &usize| n > 0),
+        };
 
-    // Luo Builder ladatulla PatternBankilla
-    let mut builder = Builder::with_bank(brain);
-    builder.pair_threshold = config.pair_threshold;
+        let stop = StopPolicy {
+            target_ratio: setting("PETRI_STOP_RATIO")
+                .ok()
+                .and_then(|v| v.parse().ok()),
+            target_holdout_bpb: setting("PETRI_STOP_HOLDOUT_BPB")
+                .ok()
+                .and_then(|v| v.parse().ok()),
+            stable_cycles: setting("PETRI_STOP_STABLE_CYCLES")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|&n: &usize| n > 0),
+            byte_budget: setting("PETRI_STOP_BYTES")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|&n: &usize| n > 0),
+        };
 
-    // Luo Feeder ja lataa edellinen tila (kirjanmerkki)
-    let feeder_result = Feeder::new(config.feed_rate, "./data");
+        // "off" tai 0 poistaa muutoshälytykset käytöstä
+        let drift_threshold = match setting("PETRI_DRIFT_THRESHOLD") {
+            Ok(v) => v.parse().ok().filter(|&t: &f64| t > 0.0 && t <= 1.0),
+            Err(_) => Some(drift::DEFAULT_THRESHOLD),
+        };
 
-    let mut feeder = match feeder_result {
-        Ok(mut f) => {
-            // Yritä ladata vanha tila
-            f.load_state(FEEDER_STATE_PATH);
-            f
-        }
-        Err(e) => {
-            println!("⚠️  Datakansio './data' ei löydy tai on tyhjä: {}", e);
-            println!("    Luodaan esimerkkidata demonstraatiota varten...\n");
-
-            // Luo esimerkkidata suoraan Builderiin
-            let sample_text = b"funktio on joka funktio on joka funktio on joka \
-                               tama on esimerkki tama on esimerkki tama on esimerkki \
-                               alku alku alku loppu loppu loppu \
-                               aabbaabbaabb ccddccddccdd";
-
-            builder.tokenize(sample_text);
-
-            println!("Aloitustilanne (esimerkkidata):");
-            println!("  Syötetty: {} tavua", sample_text.len());
-            println!("  Token-virta: {} tokenia", builder.stream_len());
-            println!(
-                "  PatternBank: {} mallia (256 literaalia + 3 luokkaa)",
-                builder.bank.len()
-            );
-            println!("\n--- Aloitetaan hierarkkinen oppiminen ---\n");
-
-            // Aja oppimissyklit
-            let evaluator = Evaluator::new();
-
-            for _ in 0..config.max_cycles {
-                let stats = builder.live();
-
-                if stats.patterns_created > 0 || stats.patterns_collapsed > 0 {
-                    stats.print();
-                }
+        let seed = setting("PETRI_SEED").ok().and_then(|v| v.parse().ok());
 
-                // Lopeta jos virta ei enää tiivisty
-                if stats.stream_before == stats.stream_after && stats.patterns_created == 0 {
-                    break;
-                }
-            }
+        let quarantine = setting("PETRI_QUARANTINE")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|&t: &f64| t > 0.0 && t < 1.0);
 
-            println!("\n=== LOPPUTILANNE ===");
-            evaluator.print_analysis(&builder);
+        let readers = setting("PETRI_READERS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(1);
 
-            // Tulosta muutama esimerkki opituista malleista
-            println!("\n  🧬 Opitut hierarkkiset mallit:");
-            let mut patterns: Vec<_> = builder
-                .bank
-                .iter()
-                .filter(|(_, p)| !p.is_literal() && !p.op.is_class() && p.strength >= 0.5)
-                .collect();
-            patterns.sort_by(|a, b| b.1.usage_count.cmp(&a.1.usage_count));
-
-            for (id, pattern) in patterns.iter().take(10) {
-                let decoded = builder.bank.decode(**id);
-                let decoded_str = String::from_utf8_lossy(&decoded);
-                println!(
-                    "     P_{}: \"{}\" [taso {}, käyttö {}, vahvuus {:.2}, viittauksia {}]",
-                    id,
-                    decoded_str,
-                    pattern.complexity,
-                    pattern.usage_count,
-                    pattern.strength,
-                    pattern.ref_count
+        // Prosentteina tai osuutena: "95" ja "0.95" ovat sama raja
+        let skip_familiar = setting("PETRI_SKIP_FAMILIAR")
+            .ok()
+            .and_then(|v| v.trim_end_matches('%').parse::<f64>().ok())
+            .map(|t| if t > 1.0 { t / 100.0 } else { t })
+            .filter(|&t| t > 0.0 && t <= 1.0);
+
+        let coder = match setting("PETRI_CODER") {
+            Ok(name) => Coder::parse(&name).unwrap_or_else(|| {
+                warning!(
+                    "  ⚠️  Tuntematon kooderi '{}', käytetään Huffmania.",
+                    "  ⚠️  Unknown coder '{}', using Huffman.",
+                    name
                 );
-            }
+                Coder::Huffman
+            }),
+            Err(_) => Coder::Huffman,
+        };
 
-            // Tallenna aivot
-            save_brain(&builder.bank, &config.brain_path);
+        let collapse = match setting("PETRI_COLLAPSE") {
+            Ok(name) => CollapsePolicy::parse(&name).unwrap_or_else(|| {
+                warning!(
+                    "  ⚠️  Tuntematon jäsennyspolitiikka '{}', käytetään parittaista.",
+                    "  ⚠️  Unknown collapse policy '{}', using pairwise.",
+                    name
+                );
+                CollapsePolicy::Pairwise
+            }),
+            Err(_) => CollapsePolicy::Pairwise,
+        };
 
-            println!("\n✅ Demonstraatio valmis!");
-            return;
+        Config {
+            pattern_capacity,
+            feed_rate,
+            pair_threshold,
+            max_cycles,
+            brain_path,
+            data_dir,
+            profile,
+            boredom_threshold,
+            curiosity_threshold,
+            engine,
+            transform,
+            coder,
+            extensions,
+            row_width,
+            tokenization,
+            markup,
+            boundaries,
+            entropy_gate,
+            checkpoint,
+            autosave,
+            stop,
+            readers,
+            skip_familiar,
+            drift_threshold,
+            quarantine,
+            seed,
+            collapse,
+            level_thresholds,
+            collapse_thresholds,
+            decay,
+            min_dwell,
+            brain_budget,
         }
-    };
+    }
 
-    // Luo Evaluator
-    let evaluator = Evaluator::new();
+    /// Datakansio (vanhoissa istunnoissa kenttää ei ole)
+    fn data_dir(&self) -> &str {
+        if self.data_dir.is_empty() {
+            DATA_DIR
+        } else {
+            &self.data_dir
+        }
+    }
 
-    println!("\nAloitustilanne:");
-    println!(
-        "  PatternBank kapasiteetti: {} mallia",
-        builder.bank.capacity()
-    );
-    println!(
-        "  Olemassa olevia malleja: {} (256 literaalia + 3 luokkaa + {} combine)",
-        builder.bank.len(),
-        builder.bank.combine_count()
-    );
-    println!("  Feeder nopeus: {} tavua/sykli (perus)", config.feed_rate);
-    println!("  Parin kynnys: {} esiintymää", config.pair_threshold);
-    println!("  Maksimi syklit: {}", config.max_cycles);
-    println!("  Aivojen tallennuspolku: {}", config.brain_path);
-    println!(
-        "  Tylsistymiskynnys: {:.0}%",
-        config.boredom_threshold * 100.0
-    );
-    println!(
-        "  Uteliaisuuskynnys: {:.0}%",
-        config.curiosity_threshold * 100.0
-    );
+    /// Profiilin tiedosto (ilman profiilia `file` sellaisenaan)
+    fn profile_path(&self, file: &str) -> String {
+        profile_file(self.profile.as_deref(), file)
+    }
 
-    // Avaa CSV-tiedosto
-    let mut csv_file = File::create("results.csv").expect("CSV-tiedoston luonti epäonnistui");
-    writeln!(
-        csv_file,
-        "cycle,stream_len,original_len,patterns_count,compression_ratio,patterns_created,patterns_collapsed,familiarity,mode"
-    )
-    .expect("CSV-otsikkojen kirjoitus epäonnistui");
-
-    println!("\n--- Aloitetaan hierarkkinen oppiminen (Adaptiivinen moodi) ---\n");
-
-    // Pääsilmukka - ADAPTIIVINEN VERSIO
-    let mut cycle = 0;
-    let mut last_stream_len = 0;
-    let mut stagnant_cycles = 0;
-    let base_rate = config.feed_rate;
-
-    while cycle < config.max_cycles {
-        cycle += 1;
-
-        // 1. MITTAA: Kuinka hyvin ymmärsimme edellisen kierroksen?
-        // Katsotaan viimeistä 1000 tokenia
-        let familiarity = builder.assess_familiarity(1000);
-
-        // 2. SÄÄDÄ: Päätä nopeus ja strategia tuttuuden perusteella
-        let (new_rate, do_explore, mode_str) = if familiarity > config.boredom_threshold {
-            // TYLSÄÄ: Juokse läpi!
-            // 5x nopeus, ei uusien etsimistä (säästää aikaa), vain vanhan käyttöä
-            (base_rate * 5, false, "SPEED ⏩")
-        } else if familiarity < config.curiosity_threshold {
-            // VAIKEAA: Hidasta ja tutki!
-            // 0.5x nopeus, etsi aggressiivisesti uusia malleja
-            (((base_rate as f64) * 0.5) as usize, true, "FOCUS 🔍")
-        } else {
-            // NORMAALI
-            (base_rate, true, "NORMAL 📖")
+    /// Jatketun istunnon konfiguraatio: tallennettu pohjaksi, mutta
+    /// komentorivillä tai ympäristössä annetut asetukset (`explicit` lisäksi
+    /// valitsimet, joilla ei ole muuttujaa) voittavat. Istunnon tilaan
+    /// sidotut asetukset pysyvät ennallaan varoituksen kera.
+    fn resume_from(&self, saved: Config, explicit: &[&str]) -> Config {
+        let given = |var: &str| explicit.contains(&var) || setting(var).is_ok();
+        let (Ok(mut merged), Ok(current)) =
+            (serde_json::to_value(&saved), serde_json::to_value(self))
+        else {
+            return saved;
         };
 
-        // Aseta uusi nopeus
-        feeder.set_feed_rate(new_rate);
-
-        // 3. SYÖTÄ: Hae uutta dataa
-        let fed = match feeder.feed_to_builder(&mut builder) {
-            Ok(fed) => {
-                if fed == 0 && feeder.is_depleted() {
-                    println!("  ✓ Kaikki data käsitelty.");
-                    break;
-                }
-                // Tulosta aina tilannekatsaus
-                if fed > 0 {
-                    println!(
-                        "  {} Sykli {}: Fam {:.1}%, Rate {}, +{} tavua",
-                        mode_str,
-                        cycle,
-                        familiarity * 100.0,
-                        new_rate,
-                        fed
-                    );
-                }
-                fed
+        for &(var, pointer) in RESUME_FIELDS {
+            let value = current.pointer(pointer);
+            if !given(var) || value == merged.pointer(pointer) {
+                continue;
             }
-            Err(e) => {
-                println!("❌ Virhe: {}", e);
-                break;
+            if let (Some(slot), Some(value)) = (merged.pointer_mut(pointer), value) {
+                *slot = value.clone();
+                detail!(
+                    "     {} ohittaa istunnon arvon: {}",
+                    "     {} overrides the session value: {}",
+                    var,
+                    value
+                );
             }
-        };
-
-        // 4. OPPIMISSYKLI (Kustomoitu explore-kontrollilla)
-        builder.cycle += 1;
-
-        // Aina: Unohda turhat (tee tilaa)
-        let forgotten = builder.forget(0);
-
-        // Uusien etsiminen vain jos ollaan "uteliaita" tai "normaaleja"
-        let mut created = 0;
-        if do_explore {
-            created = builder.explore();
         }
-
-        // Aina: Tiivistä sillä mitä tiedät (tämä on nopeaa)
-        let mut collapsed = 0;
-        loop {
-            let n = builder.collapse();
-            if n == 0 {
-                break;
+        for &(var, pointer) in RESUME_FIXED {
+            if given(var) && current.pointer(pointer) != merged.pointer(pointer) {
+                warning!(
+                    "  ⚠️  {} ei muutu jatketussa istunnossa, käytetään istunnon arvoa.",
+                    "  ⚠️  {} cannot change in a resumed session, keeping the session value.",
+                    var
+                );
             }
-            collapsed += n;
         }
 
-        // Decay
-        builder.decay(0.01);
-
-        // Tulosta tilastot
-        if created > 0 || collapsed > 0 || forgotten > 0 {
-            println!(
-                "     📊 Virta: {} tok, Malleja: {} (+{} -{}) Tiiv: {}",
-                builder.stream_len(),
-                builder.bank.combine_count(),
-                created,
-                forgotten,
-                collapsed
-            );
+        let mut config: Config = serde_json::from_value(merged).unwrap_or(saved);
+        // Siemen vaatii peräkkäisen lukemisen myös jatketussa ajossa
+        if config.seed.is_some() {
+            config.readers = config.readers.min(1);
         }
+        config
+    }
 
-        // Kirjoita CSV
-        writeln!(
-            csv_file,
-            "{},{},{},{},{:.4},{},{},{:.4},{}",
-            cycle,
-            builder.stream_len(),
-            builder.original_len(),
-            builder.bank.combine_count(),
-            evaluator.compression_ratio(&builder),
-            created,
-            collapsed,
-            familiarity,
-            if do_explore { "explore" } else { "speed" }
-        )
-        .expect("CSV-rivin kirjoitus epäonnistui");
-
-        // Tarkista stagnaatio
-        if builder.stream_len() == last_stream_len && fed == 0 {
-            stagnant_cycles += 1;
-        } else {
-            stagnant_cycles = 0;
-        }
-        last_stream_len = builder.stream_len();
-
-        // Lopeta jos feeder on tyhjä ja stagnaatio jatkuu
-        if feeder.is_depleted() && stagnant_cycles >= 5 {
-            println!(
-                "\n  ✓ Oppiminen saturoitunut ({} sykliä ilman muutosta)",
-                stagnant_cycles
-            );
-            break;
+    /// Builder-moottorin oppimisasetukset
+    fn builder_settings(&self) -> BuilderSettings {
+        BuilderSettings {
+            pair_threshold: self.pair_threshold,
+            level_thresholds: self.level_thresholds.clone(),
+            collapse: self.collapse,
+            collapse_thresholds: self.collapse_thresholds.clone(),
+            decay_rate: self.decay,
+            min_dwell: self.min_dwell,
+            size_budget: self.brain_budget,
         }
     }
+}
 
-    // Loppuraportti
-    println!("\n=== LOPPUTILANNE ===");
+/// Lataa PatternBank tiedostosta tai luo uusi, ja aseta tokenisointitila
+fn load_or_create_brain(config: &Config) -> PatternBank {
+    let mut bank = load_brain_file(config);
+    if let Some(tokenization) = config.tokenization {
+        bank.set_tokenization(tokenization);
+    }
+    bank
+}
+
+fn load_brain_file(config: &Config) -> PatternBank {
+    let path = Path::new(&config.brain_path);
 
-    if feeder.is_depleted() {
-        println!("✅ Kaikki data käsitelty!");
+    if path.exists() {
+        say!(
+            "  🧠 Ladataan aivot tiedostosta '{}'...",
+            "  🧠 Loading brain from '{}'...",
+            config.brain_path
+        );
+        let loaded = progress::with_spinner(tr!("Ladataan aivoja", "Loading brain"), || {
+            PatternBank::load(path)
+        });
+        match loaded {
+            Ok(bank) => {
+                say!(
+                    "  ✅ Aivot ladattu! {} mallia muistissa.",
+                    "  ✅ Brain loaded! {} patterns in memory.",
+                    bank.len()
+                );
+                return bank;
+            }
+            Err(e) => {
+                warning!(
+                    "  ⚠️  Aivojen lataus epäonnistui: {}",
+                    "  ⚠️  Failed to load brain: {}",
+                    e
+                );
+                say!(
+                    "     Aloitetaan tyhjästä...",
+                    "     Starting from scratch..."
+                );
+            }
+        }
     } else {
-        println!(
-            "⚠️  Keskeytettiin syklien maksimirajalla ({}).",
-            config.max_cycles
+        say!(
+            "  🧠 Aivotiedostoa '{}' ei löytynyt, aloitetaan tyhjästä.",
+            "  🧠 Brain file '{}' not found, starting from scratch.",
+            config.brain_path
         );
     }
 
-    evaluator.print_analysis(&builder);
-
-    println!("\n  📊 Tilastot:");
-    println!("     Syklit: {}", cycle);
-    println!("     Syötetty: {} tavua", feeder.total_fed);
-    println!("     Token-virta: {} tokenia", builder.stream_len());
-    println!("     Combine-malleja: {}", builder.bank.combine_count());
+    PatternBank::new(config.pattern_capacity)
+}
 
-    // Tulosta hierarkkiset mallit
-    println!("\n  🧬 Opitut hierarkkiset mallit (TOP 20):");
-    let mut patterns: Vec<_> = builder
-        .bank
+/// Oppimisen asetukset komentoriviltä: valitsin, vastaava ympäristömuuttuja
+/// (käytetään, jos valitsinta ei anneta) ja kuvaus ohjeeseen
+const SETTINGS: &[(&str, &str, &str, &str)] = &[
+    ("--data", "PETRI_DATA_DIR", "datakansio", "data directory"),
+    (
+        "--profile",
+        "PETRI_PROFILE",
+        "nimetty profiili (profiles/<nimi>/)",
+        "named profile (profiles/<name>/)",
+    ),
+    (
+        "--capacity",
+        "PETRI_PATTERN_CAPACITY",
+        "mallien enimmäismäärä",
+        "maximum pattern count",
+    ),
+    (
+        "--feed-rate",
+        "PETRI_FEED_RATE",
+        "syöttönopeus tavuina/sykli",
+        "feed rate in bytes/cycle",
+    ),
+    (
+        "--max-cycles",
+        "PETRI_MAX_CYCLES",
+        "syklien enimmäismäärä",
+        "maximum cycles",
+    ),
+    (
+        "--pair-threshold",
+        "PETRI_PAIR_THRESHOLD",
+        "parin esiintymiskynnys",
+        "pair occurrence threshold",
+    ),
+    (
+        "--level-thresholds",
+        "PETRI_LEVEL_THRESHOLDS",
+        "parikynnykset tasoittain (3,2,1)",
+        "pair thresholds per level (3,2,1)",
+    ),
+    (
+        "--brain-budget",
+        "PETRI_BRAIN_BUDGET",
+        "aivotiedoston enimmäiskoko tavuina",
+        "maximum brain file size in bytes",
+    ),
+    (
+        "--collapse-thresholds",
+        "PETRI_COLLAPSE_THRESHOLDS",
+        "tiivistyskynnykset tasoittain (0.5,0.6)",
+        "collapse thresholds per level (0.5,0.6)",
+    ),
+    (
+        "--collapse",
+        "PETRI_COLLAPSE",
+        "pairwise | greedy | optimal",
+        "pairwise | greedy | optimal",
+    ),
+    (
+        "--decay",
+        "PETRI_DECAY",
+        "vahvuuden heikkeneminen syklissä",
+        "strength decay per cycle",
+    ),
+    (
+        "--min-dwell",
+        "PETRI_MIN_DWELL",
+        "totuuskynnyksen viipymä sykleinä",
+        "truth threshold dwell in cycles",
+    ),
+    (
+        "--boredom",
+        "PETRI_BOREDOM_THRESHOLD",
+        "tylsistymiskynnys (0-1)",
+        "boredom threshold (0-1)",
+    ),
+    (
+        "--curiosity",
+        "PETRI_CURIOSITY_THRESHOLD",
+        "uteliaisuuskynnys (0-1)",
+        "curiosity threshold (0-1)",
+    ),
+    (
+        "--engine",
+        "PETRI_ENGINE",
+        "builder | sequitur | lzw",
+        "builder | sequitur | lzw",
+    ),
+    (
+        "--preset",
+        "PETRI_PRESET",
+        "text | code | web | ...",
+        "text | code | web | ...",
+    ),
+    (
+        "--extensions",
+        "PETRI_EXTENSIONS",
+        "tiedostopäätteet (txt,md)",
+        "file extensions (txt,md)",
+    ),
+    (
+        "--row-width",
+        "PETRI_ROW_WIDTH",
+        "RAW-kuvan rivin leveys tavuina",
+        "RAW image row width in bytes",
+    ),
+    (
+        "--tokenizer",
+        "PETRI_TOKENIZER",
+        "bytes | source",
+        "bytes | source",
+    ),
+    (
+        "--transform",
+        "PETRI_TRANSFORM",
+        "none | bwt | delta | row-delta",
+        "none | bwt | delta | row-delta",
+    ),
+    ("--coder", "PETRI_CODER", "entropiakooderi", "entropy coder"),
+    (
+        "--markup",
+        "PETRI_MARKUP",
+        "keep | strip | split",
+        "keep | strip | split",
+    ),
+    (
+        "--boundaries",
+        "PETRI_BOUNDARIES",
+        "feeds,files,blank | none",
+        "feeds,files,blank | none",
+    ),
+    (
+        "--readers",
+        "PETRI_READERS",
+        "rinnakkaiset lukijat",
+        "parallel readers",
+    ),
+    (
+        "--entropy-gate",
+        "PETRI_ENTROPY_GATE",
+        "palan entropiaraja (bittiä/tavu)",
+        "chunk entropy gate (bits/byte)",
+    ),
+    (
+        "--skip-familiar",
+        "PETRI_SKIP_FAMILIAR",
+        "tuttujen kopioiden ohitusraja",
+        "familiar copy skip threshold",
+    ),
+    (
+        "--quarantine",
+        "PETRI_QUARANTINE",
+        "vieraiden palojen karanteeniraja",
+        "alien chunk quarantine threshold",
+    ),
+    (
+        "--drift-threshold",
+        "PETRI_DRIFT_THRESHOLD",
+        "datan muutoshälytyksen kynnys",
+        "data drift alert threshold",
+    ),
+    (
+        "--autosave-every",
+        "PETRI_AUTOSAVE_EVERY",
+        "aivojen ja kirjanmerkin tallennus N syklin välein",
+        "save brain and bookmark every N cycles",
+    ),
+    (
+        "--autosave-bytes",
+        "PETRI_AUTOSAVE_BYTES",
+        "aivojen ja kirjanmerkin tallennus N tavun välein",
+        "save brain and bookmark every N bytes",
+    ),
+    (
+        "--checkpoint-every",
+        "PETRI_CHECKPOINT_EVERY",
+        "välitallennus N syklin välein",
+        "checkpoint every N cycles",
+    ),
+    (
+        "--checkpoint-minutes",
+        "PETRI_CHECKPOINT_MINUTES",
+        "välitallennus M minuutin välein",
+        "checkpoint every M minutes",
+    ),
+    (
+        "--checkpoint-keep",
+        "PETRI_CHECKPOINT_KEEP",
+        "säilytettävät välitallennukset",
+        "checkpoints to keep",
+    ),
+    (
+        "--checkpoint-dir",
+        "PETRI_CHECKPOINT_DIR",
+        "välitallennusten kansio",
+        "checkpoint directory",
+    ),
+    (
+        "--stop-ratio",
+        "PETRI_STOP_RATIO",
+        "pysäytä tiivistyssuhteessa",
+        "stop at compression ratio",
+    ),
+    (
+        "--stop-holdout-bpb",
+        "PETRI_STOP_HOLDOUT_BPB",
+        "pysäytä holdoutin bittiä/tavu -arvossa",
+        "stop at holdout bits/byte",
+    ),
+    (
+        "--stop-stable-cycles",
+        "PETRI_STOP_STABLE_CYCLES",
+        "pysäytä, kun vakaa N sykliä",
+        "stop when stable for N cycles",
+    ),
+    (
+        "--stop-bytes",
+        "PETRI_STOP_BYTES",
+        "pysäytä N syötetyn tavun jälkeen",
+        "stop after N bytes fed",
+    ),
+];
+
+/// Yleiset arvolliset valitsimet (kaikki komennot)
+const GLOBAL_VALUES: &[&str] = &["--brain", "--seed", "--lang", "--log-format"];
+
+/// Oppimisajon arvolliset valitsimet
+const LEARN_VALUES: &[&str] = &[
+    "--session",
+    "--resume",
+    "--save-stream",
+    "--watch-interval",
+    "--holdout",
+    "--snapshots",
+    "--snapshot-every",
+    "--seed-grammar",
+    "--seed-words",
+    "--seed-strength",
+    "--bigram-prior",
+    "--prior-bytes",
+    "--token-stats",
+    "--file-report",
+    "--grammar",
+    "--grammar-min-strength",
+    "--heatmap",
+];
+
+/// Oppimisajon kytkimet (`--recommend` voi saada arvon)
+const LEARN_SWITCHES: &[&str] = &["--watch", "--pin-words", "--grammar-stable-ids"];
+
+/// Komentoriviltä annetut asetukset (ks. SETTINGS)
+static SETTING_OVERRIDES: OnceLock<HashMap<&'static str, String>> = OnceLock::new();
+
+/// Jatketussa istunnossa annettu asetus korvaa tallennetun kentän
+/// (JSON-osoitin Configin sarjallistettuun muotoon)
+const RESUME_FIELDS: &[(&str, &str)] = &[
+    ("PETRI_DATA_DIR", "/data_dir"),
+    ("PETRI_BRAIN_PATH", "/brain_path"),
+    ("PETRI_SEED", "/seed"),
+    ("PETRI_FEED_RATE", "/feed_rate"),
+    ("PETRI_MAX_CYCLES", "/max_cycles"),
+    ("PETRI_PAIR_THRESHOLD", "/pair_threshold"),
+    ("PETRI_LEVEL_THRESHOLDS", "/level_thresholds"),
+    ("PETRI_BRAIN_BUDGET", "/brain_budget"),
+    ("PETRI_COLLAPSE_THRESHOLDS", "/collapse_thresholds"),
+    ("PETRI_COLLAPSE", "/collapse"),
+    ("PETRI_DECAY", "/decay"),
+    ("PETRI_MIN_DWELL", "/min_dwell"),
+    ("PETRI_BOREDOM_THRESHOLD", "/boredom_threshold"),
+    ("PETRI_CURIOSITY_THRESHOLD", "/curiosity_threshold"),
+    ("PETRI_EXTENSIONS", "/extensions"),
+    ("PETRI_ROW_WIDTH", "/row_width"),
+    ("PETRI_CODER", "/coder"),
+    ("PETRI_MARKUP", "/markup"),
+    ("PETRI_BOUNDARIES", "/boundaries"),
+    ("PETRI_READERS", "/readers"),
+    ("PETRI_ENTROPY_GATE", "/entropy_gate"),
+    ("PETRI_SKIP_FAMILIAR", "/skip_familiar"),
+    ("PETRI_QUARANTINE", "/quarantine"),
+    ("PETRI_DRIFT_THRESHOLD", "/drift_threshold"),
+    ("PETRI_AUTOSAVE_EVERY", "/autosave/every_cycles"),
+    ("PETRI_AUTOSAVE_BYTES", "/autosave/every_bytes"),
+    ("PETRI_CHECKPOINT_EVERY", "/checkpoint/every_cycles"),
+    ("PETRI_CHECKPOINT_MINUTES", "/checkpoint/every_minutes"),
+    ("PETRI_CHECKPOINT_KEEP", "/checkpoint/keep_last"),
+    ("PETRI_CHECKPOINT_DIR", "/checkpoint/dir"),
+    ("PETRI_STOP_RATIO", "/stop/target_ratio"),
+    ("PETRI_STOP_HOLDOUT_BPB", "/stop/target_holdout_bpb"),
+    ("PETRI_STOP_STABLE_CYCLES", "/stop/stable_cycles"),
+    ("PETRI_STOP_BYTES", "/stop/byte_budget"),
+];
+
+/// Istunnon tilaan sidotut asetukset: pankin koko, moottori, siemenyksiköt,
+/// virran muunnos ja profiilin kansio tulevat aina istunnosta
+const RESUME_FIXED: &[(&str, &str)] = &[
+    ("PETRI_PATTERN_CAPACITY", "/pattern_capacity"),
+    ("PETRI_ENGINE", "/engine"),
+    ("PETRI_PRESET", "/transform"),
+    ("PETRI_TOKENIZER", "/tokenization"),
+    ("PETRI_TRANSFORM", "/transform"),
+    ("PETRI_PROFILE", "/profile"),
+];
+
+/// Poista asetusvalitsimet argumenteista ennen konfiguraation latausta
+fn take_settings(args: &mut Vec<String>) {
+    let overrides = SETTINGS
         .iter()
-        .filter(|(_, p)| !p.is_literal() && !p.op.is_class())
+        .filter_map(|&(flag, var, _, _)| Some((var, take_value(args, flag)?)))
         .collect();
-    patterns.sort_by(|a, b| {
-        // Lajittele: ensin tason mukaan (korkein ensin), sitten käytön mukaan
-        let level_cmp = b.1.complexity.cmp(&a.1.complexity);
-        if level_cmp == std::cmp::Ordering::Equal {
-            b.1.usage_count.cmp(&a.1.usage_count)
-        } else {
-            level_cmp
-        }
-    });
+    let _ = SETTING_OVERRIDES.set(overrides);
+}
 
-    for (id, pattern) in patterns.iter().take(20) {
-        let decoded = builder.bank.decode(**id);
-        let decoded_str = String::from_utf8_lossy(&decoded);
-        let preview = if decoded_str.len() > 30 {
-            format!("{}...", &decoded_str[..30])
-        } else {
-            decoded_str.to_string()
+/// Asetuksen arvo: komentorivi ensin, sitten ympäristömuuttuja
+fn setting(var: &str) -> Result<String, env::VarError> {
+    match SETTING_OVERRIDES.get().and_then(|o| o.get(var)) {
+        Some(value) => Ok(value.clone()),
+        None => env::var(var),
+    }
+}
+
+/// Virheellinen komentorivi: ilmoitus, vihje ohjeeseen ja poistuminen
+fn exit_usage(e: cli::ArgError) -> ! {
+    error!("  ❌ {}", "  ❌ {}", e);
+    say!(
+        "  Ohje: petri_dish help, alikomennon ohje: petri_dish <alikomento> --help",
+        "  Help: petri_dish help, subcommand help: petri_dish <subcommand> --help"
+    );
+    std::process::exit(2);
+}
+
+/// `help`: alikomennot ja asetukset
+fn print_help() {
+    say!(
+        "Käyttö: petri_dish [learn] [asetukset]    oppiminen ./data-kansiosta (oletus)\n        petri_dish <alikomento> [...]\n\nAlikomennot:\n  learn        oppiminen (sama kuin ilman alikomentoa)\n  inspect      aivojen mallit ja tasot\n  stats        ajon mittarit results.csv:stä\n  decode       mallien sisältö tai arkiston purku\n  repl         aivojen tutkiminen komento kerrallaan\n  archive, extract, encode-file, repair, diff, cluster, detect,\n  tournament, history, sweep, bench, plot, survival\n  (alikomennon ohje: petri_dish <alikomento> --help)\n\nYleiset valitsimet: --brain polku, --seed N, --lang fi|en, -q, -v, -vv,\n  --no-color, --plain, --log-format text|json\nOppiminen: --session polku, --resume polku, --save-stream virta.petri\n\nAsetukset (valitsin tai ympäristömuuttuja):",
+        "Usage: petri_dish [learn] [settings]    learn from ./data (default)\n       petri_dish <subcommand> [...]\n\nSubcommands:\n  learn        learning (same as no subcommand)\n  inspect      patterns and levels of a brain\n  stats        run metrics from results.csv\n  decode       pattern contents or archive extraction\n  repl         explore a brain one command at a time\n  archive, extract, encode-file, repair, diff, cluster, detect,\n  tournament, history, sweep, bench, plot, survival\n  (subcommand help: petri_dish <subcommand> --help)\n\nCommon options: --brain path, --seed N, --lang fi|en, -q, -v, -vv,\n  --no-color, --plain, --log-format text|json\nLearning: --session path, --resume path, --save-stream stream.petri\n\nSettings (option or environment variable):"
+    );
+    for &(flag, var, fi, en) in SETTINGS {
+        let text = match i18n::lang() {
+            Lang::Fi => fi,
+            Lang::En => en,
         };
-        println!(
-            "     P_{}: \"{}\" [L{}, käyttö {}, str {:.2}, refs {}]",
-            id,
-            preview,
-            pattern.complexity,
-            pattern.usage_count,
-            pattern.strength,
-            pattern.ref_count
-        );
+        term::emit(Level::Info, &format!("  {:<22} {:<27} {}", flag, var, text));
     }
+}
 
-    // Tulosta hierarkiaesimerkki korkeimman tason mallista
-    if let Some((id, _)) = patterns.first() {
-        println!("\n  🌳 Hierarkiaesimerkki (P_{}):", id);
-        builder.print_hierarchy(**id, 2);
+/// Valitse tulosteiden kieli (`--lang fi|en` tai PETRI_LANG) ja poista
+/// lippu argumenteista, jotta alikomentojen paikkaparametrit eivät siirry
+fn select_lang(args: &mut Vec<String>) {
+    let Some(name) = take_value(args, "--lang").or_else(|| env::var("PETRI_LANG").ok()) else {
+        return;
+    };
+    match Lang::parse(&name) {
+        Some(lang) => i18n::set_lang(lang),
+        None => warning!(
+            "  ⚠️  Tuntematon kieli '{}', käytetään suomea (fi|en).",
+            "  ⚠️  Unknown language '{}', using Finnish (fi|en).",
+            name
+        ),
     }
+}
 
-    // === TALLENNA TILA ===
-    println!("\n=== TALLENNETAAN TILA ===");
+/// Tulosteiden taso, värit, emojit ja muoto (`--quiet`, `--verbose`, `-vv`,
+/// `--no-color`, `--plain`, `--log-format`; vastaavat PETRI_VERBOSITY,
+/// NO_COLOR, PETRI_PLAIN ja PETRI_LOG_FORMAT)
+fn select_output(args: &mut Vec<String>) {
+    let verbosity = take_verbosity(args);
+    let no_color = take_switch(args, &["--no-color"]);
+    let plain = take_switch(args, &["--plain"]) || env::var_os("PETRI_PLAIN").is_some();
+    let format_name =
+        take_value(args, "--log-format").or_else(|| env::var("PETRI_LOG_FORMAT").ok());
+    let format = format_name.as_deref().map(term::Format::parse);
+
+    let level = verbosity.unwrap_or_else(|| match env::var("PETRI_VERBOSITY").as_deref() {
+        Ok("quiet") => Level::Warn,
+        Ok("verbose") => Level::Detail,
+        Ok("trace") => Level::Trace,
+        _ => Level::Info,
+    });
+    term::configure(Output {
+        level,
+        color: !no_color && !plain && term::color_supported(),
+        emoji: !plain,
+        format: format.flatten().unwrap_or_default(),
+    });
+    if let (Some(name), Some(None)) = (&format_name, format) {
+        warning!(
+            "  ⚠️  Tuntematon lokimuoto '{}' (text, json), käytetään tekstiä.",
+            "  ⚠️  Unknown log format '{}' (text, json), using text.",
+            name
+        );
+    }
+}
 
-    // 1. Tallenna aivot
-    save_brain(&builder.bank, &config.brain_path);
+fn main() {
+    // Tulosteasetukset ja kieli ensin: konfiguraation varoituksetkin
+    // tulostuvat valitulla tavalla
+    let mut args: Vec<String> = env::args().collect();
+    // Puuttuvat arvot tarkistetaan ennen poimintaa, ilmoitetaan valinnan jälkeen
+    let global: Vec<&str> = GLOBAL_VALUES
+        .iter()
+        .copied()
+        .chain(SETTINGS.iter().map(|&(flag, ..)| flag))
+        .collect();
+    let checked = check_values(&args, &global);
+    select_output(&mut args);
+    select_lang(&mut args);
+    if let Err(e) = checked {
+        exit_usage(e);
+    }
+    take_settings(&mut args);
+    let mut config = Config::load();
+    // Valitsimet ilman asetusriviä: jatkettu istunto ei saa ohittaa niitä
+    let mut explicit = Vec::new();
+    // Aivot muualta kuin PETRI_BRAIN_PATH:sta (kaikki komennot)
+    if let Some(path) = take_value(&mut args, "--brain") {
+        config.brain_path = path;
+        explicit.push("PETRI_BRAIN_PATH");
+    }
+    // Toistettava ajo (`--seed N`): sama siemen ja data = samat syklit
+    if let Some(value) = take_value(&mut args, "--seed") {
+        match value.parse() {
+            Ok(seed) => {
+                config.seed = Some(seed);
+                explicit.push("PETRI_SEED");
+            }
+            Err(_) => warning!(
+                "  ⚠️  Virheellinen siemen '{}', ohitetaan.",
+                "  ⚠️  Invalid seed '{}', ignoring.",
+                value
+            ),
+        }
+    }
+    if config.seed.is_some() && config.readers > 1 {
+        // Rinnakkaisten lukijoiden lomitus riippuu säikeiden ajoituksesta
+        warning!(
+            "  ⚠️  Siemen annettu: tiedostot luetaan peräkkäin (PETRI_READERS={} ohitetaan).",
+            "  ⚠️  Seed given: files are read sequentially (ignoring PETRI_READERS={}).",
+            config.readers
+        );
+        config.readers = 1;
+    }
 
-    // 2. Tallenna feederin tila (kirjanmerkki)
-    if let Err(e) = feeder.save_state(FEEDER_STATE_PATH) {
-        println!("  ⚠️  Feederin tilan tallennus epäonnistui: {}", e);
-    } else {
-        println!("  🔖 Kirjanmerkki tallennettu: {}", FEEDER_STATE_PATH);
+    // `<alikomento> --help`: alikomennon ohje, muuten yleinen ohje
+    if cli::wants_help(&args[1..]) {
+        match args.get(1) {
+            Some(command) if cli::usage(command).is_some() => cli::print_usage(command),
+            _ => print_help(),
+        }
+        return;
     }
 
-    println!("\n=== HIERARKKINEN TIEDONRAKENNUSKONE VALMIS ===");
-    println!("\n📊 Analyysi:");
-    println!("  • CSV tallennettu: results.csv");
-    println!("  • Aivot tallennettu: {}", config.brain_path);
-    println!("  • Kirjanmerkki tallennettu: {}", FEEDER_STATE_PATH);
-    println!("  • Järjestelmä oppi kielen rakenteita hierarkkisesti");
-    println!("  • Kirjaimista → tavuihin → sanoihin → lauseisiin");
-    println!("\n✅ \"Totuus on pysyvä yhteys kahden asian välillä.\"");
+    // Alikomennot (commands/), oppimisajo oletuksena
+    match args.get(1).map(String::as_str) {
+        Some("archive") => return commands::archive::run(&config, &args[2..]),
+        Some("extract") => return commands::extract::run(&config, &args[2..]),
+        Some("decode") => return commands::decode::run(&config, &args[2..]),
+        Some("encode-file") => return commands::encode_file::run(&config, &args[2..]),
+        Some("repair") => return commands::repair::run(&config, &args[2..]),
+        Some("diff") => return commands::diff::run(&config, &args[2..]),
+        Some("cluster") => return commands::cluster::run(&config, &args[2..]),
+        Some("detect") => return commands::detect::run(&args[2..]),
+        Some("tournament") => return commands::tournament::run(&config, &args[2..]),
+        Some("history") => return commands::history::run(&args[2..]),
+        Some("inspect") => return commands::inspect::run(&config, &args[2..]),
+        Some("repl") => return commands::repl::run(&config, &args[2..]),
+        Some("sweep") => return commands::sweep::run(&config, &args[2..]),
+        Some("bench") => return commands::bench::run(&config, &args[2..]),
+        Some("plot") => return commands::plot::run(&config, &args[2..]),
+        Some("survival") => return commands::survival::run(&args[2..]),
+        Some("stats") => return commands::stats::run(&config, &args[2..]),
+        Some("help" | "--help" | "-h") => return print_help(),
+        // `learn` on oletus: sama kuin ilman alikomentoa
+        Some("learn") => {
+            args.remove(1);
+        }
+        _ => {}
+    }
+    // Oppimisajo lukee valitsimensa paikallaan: kirjoitusvirhe ei saa
+    // kadota hiljaa
+    if let Err(e) = check_args(&args[1..], LEARN_VALUES, LEARN_SWITCHES, &["--recommend"]) {
+        exit_usage(e);
+    }
+    commands::learn::run(config, &explicit, &args);
 }
@@ -0,0 +1,96 @@
+// src/markup.rs
+//
+// Markup: HTML/XML-rakenteen erottaminen tekstisisällöstä.
+//
+// Verkkosivuilla tagit (<div class="...">) hallitsevat paritilastoja,
+// jolloin hierarkia oppii lähinnä tagien palasia. Suodatin jakaa
+// syötteen tagi- ja tekstiosiin, jotka joko syötetään erillisinä
+// tietueina tai tagit jätetään kokonaan pois.
+//
+// Suodatin on tilallinen: syöttöpala voi katketa kesken tagin.
+
+use serde::{Deserialize, Serialize};
+
+/// Miten merkintäkieltä käsitellään
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum MarkupMode {
+    /// Data syötetään sellaisenaan (oletus)
+    #[default]
+    Keep,
+    /// Tagit poistetaan, vain tekstisisältö syötetään
+    Strip,
+    /// Tagit ja teksti syötetään erillisinä tietueina
+    Split,
+}
+
+impl MarkupMode {
+    /// Tulkitse tila konfiguraatiosta
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "keep" | "none" | "" => Some(MarkupMode::Keep),
+            "strip" => Some(MarkupMode::Strip),
+            "split" => Some(MarkupMode::Split),
+            _ => None,
+        }
+    }
+
+    /// Nimi raportteja varten
+    pub fn name(&self) -> &'static str {
+        match self {
+            MarkupMode::Keep => "keep",
+            MarkupMode::Strip => "strip",
+            MarkupMode::Split => "split",
+        }
+    }
+}
+
+/// Tilallinen tagien erottelija
+#[derive(Debug, Default)]
+pub struct MarkupFilter {
+    /// Ollaanko edellisen palan lopussa kesken tagin
+    pub in_tag: bool,
+}
+
+impl MarkupFilter {
+    /// Jaa pala tageihin ja tekstiin. Tagin tilalle tekstiin jää
+    /// yksi välilyönti, jotta sanat eivät liimaudu yhteen.
+    pub fn split(&mut self, data: &[u8]) -> (Vec<u8>, Vec<u8>) {
+        let mut tags = Vec::new();
+        let mut text = Vec::with_capacity(data.len());
+
+        for &b in data {
+            if self.in_tag {
+                tags.push(b);
+                if b == b'>' {
+                    self.in_tag = false;
+                }
+            } else if b == b'<' {
+                self.in_tag = true;
+                tags.push(b);
+                if text.last().is_some_and(|c: &u8| !c.is_ascii_whitespace()) {
+                    text.push(b' ');
+                }
+            } else {
+                text.push(b);
+            }
+        }
+
+        (tags, text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_across_chunks() {
+        let mut filter = MarkupFilter::default();
+        let (tags1, text1) = filter.split(b"<p class=\"x");
+        let (tags2, text2) = filter.split(b"\">Hei</p><p>maailma</p>");
+
+        assert_eq!([tags1, tags2].concat(), b"<p class=\"x\"></p><p></p>");
+        assert!(text1.is_empty());
+        assert_eq!(text2, b"Hei maailma ");
+    }
+}
@@ -0,0 +1,208 @@
+// src/media.rs
+//
+// Media: Ei-tekstimuotoisten lähteiden otsikoiden tulkinta.
+//
+// Feeder syöttää tiedostoista vain varsinaisen näytedatan (ei otsikoita),
+// ja tasaa syöttöpalat näytteen (kuvissa rivin) rajoille, jotta samat
+// näytteet näkyvät virrassa aina samassa tavujärjestyksessä.
+//
+// Tuetut muodot:
+// - WAV (RIFF PCM): näytekoko fmt-lohkon block_align-kentästä
+// - PNM (P5 harmaasävy, P6 RGB): rivin leveys otsikosta
+// - RAW: otsikoton data, rivin leveys annetaan vihjeenä
+
+use crate::i18n::tr;
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// Tiedoston raakadatan sijainti ja rakenne
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MediaLayout {
+    /// Ensimmäisen datatavun sijainti tiedostossa
+    pub data_start: u64,
+    /// Datan pituus tavuina
+    pub data_len: u64,
+    /// Yhden näytteen koko (kaikki kanavat) tavuina
+    pub sample_align: usize,
+    /// Kuvan rivin leveys tavuina (None = ei kaksiulotteista rakennetta)
+    pub row_width: Option<usize>,
+}
+
+impl MediaLayout {
+    /// Datan loppu tiedostossa
+    pub fn data_end(&self) -> u64 {
+        self.data_start + self.data_len
+    }
+
+    /// Syöttöpalojen tasaus: kokonainen rivi tai näyte
+    pub fn chunk_align(&self) -> usize {
+        self.row_width.unwrap_or(self.sample_align).max(1)
+    }
+}
+
+/// Tulkitse tiedoston rakenne tiedostopäätteen perusteella.
+/// Palauttaa None tavalliselle (teksti)tiedostolle.
+///
+/// `row_hint` on otsikottoman RAW-kuvan rivin leveys tavuina.
+pub fn probe(
+    path: &Path,
+    file: &mut File,
+    row_hint: Option<usize>,
+) -> io::Result<Option<MediaLayout>> {
+    let ext = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default();
+
+    let layout = match ext.as_str() {
+        "wav" => Some(parse_wav(file)?),
+        "pgm" | "ppm" | "pnm" => Some(parse_pnm(file)?),
+        "raw" => Some(MediaLayout {
+            data_start: 0,
+            data_len: file.metadata()?.len(),
+            sample_align: 1,
+            row_width: row_hint.filter(|&w| w > 0),
+        }),
+        _ => None,
+    };
+    file.seek(SeekFrom::Start(0))?;
+    Ok(layout)
+}
+
+/// Lue WAV (RIFF) -otsikko: etsi "fmt "- ja "data"-lohkot
+fn parse_wav(file: &mut File) -> io::Result<MediaLayout> {
+    let mut riff = [0u8; 12];
+    file.read_exact(&mut riff)?;
+    if &riff[0..4] != b"RIFF" || &riff[8..12] != b"WAVE" {
+        return Err(invalid(&tr!("ei WAV-tiedosto", "not a WAV file")));
+    }
+
+    let mut block_align = 1usize;
+    let mut pos = 12u64;
+    loop {
+        let mut header = [0u8; 8];
+        file.read_exact(&mut header).map_err(|_| {
+            invalid(&tr!(
+                "WAV-tiedostosta puuttuu data-lohko",
+                "WAV file has no data chunk"
+            ))
+        })?;
+        let size = u32::from_le_bytes([header[4], header[5], header[6], header[7]]) as u64;
+        pos += 8;
+
+        match &header[0..4] {
+            b"fmt " => {
+                let mut fmt = vec![0u8; size.min(64) as usize];
+                file.read_exact(&mut fmt)?;
+                if fmt.len() >= 14 {
+                    block_align = u16::from_le_bytes([fmt[12], fmt[13]]).max(1) as usize;
+                }
+            }
+            b"data" => {
+                let file_len = file.metadata()?.len();
+                return Ok(MediaLayout {
+                    data_start: pos,
+                    data_len: size.min(file_len.saturating_sub(pos)),
+                    sample_align: block_align,
+                    row_width: None,
+                });
+            }
+            _ => {}
+        }
+
+        // Lohkot on täytetty parilliseen kokoon
+        pos += size + (size & 1);
+        file.seek(SeekFrom::Start(pos))?;
+    }
+}
+
+/// Lue binäärinen PNM-otsikko: "P5|P6 <leveys> <korkeus> <maksimi>\n"
+fn parse_pnm(file: &mut File) -> io::Result<MediaLayout> {
+    let mut head = Vec::with_capacity(512);
+    file.by_ref().take(512).read_to_end(&mut head)?;
+
+    let channels = match head.get(..2) {
+        Some(b"P5") => 1,
+        Some(b"P6") => 3,
+        _ => {
+            return Err(invalid(&tr!(
+                "ei tuettu PNM-tiedosto (vain P5/P6)",
+                "unsupported PNM file (P5/P6 only)"
+            )));
+        }
+    };
+
+    // Kolme lukua välilyöntien ja #-kommenttien seassa
+    let mut pos = 2;
+    let mut fields = [0usize; 3];
+    for field in fields.iter_mut() {
+        loop {
+            match head.get(pos) {
+                Some(b'#') => {
+                    while head.get(pos).is_some_and(|&c| c != b'\n') {
+                        pos += 1;
+                    }
+                }
+                Some(c) if c.is_ascii_whitespace() => pos += 1,
+                _ => break,
+            }
+        }
+        let start = pos;
+        while head.get(pos).is_some_and(|c| c.is_ascii_digit()) {
+            pos += 1;
+        }
+        *field = std::str::from_utf8(&head[start..pos])
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .ok_or_else(|| invalid(&tr!("virheellinen PNM-otsikko", "invalid PNM header")))?;
+    }
+    // Täsmälleen yksi välilyönti ennen dataa
+    pos += 1;
+
+    let [width, height, maxval] = fields;
+    if width == 0 || maxval == 0 || maxval > 65535 {
+        return Err(invalid(&tr!(
+            "virheellinen PNM-otsikko",
+            "invalid PNM header"
+        )));
+    }
+    let sample_align = channels * if maxval < 256 { 1 } else { 2 };
+    let row_width = width * sample_align;
+
+    let data_start = pos as u64;
+    let available = file.metadata()?.len().saturating_sub(data_start);
+    Ok(MediaLayout {
+        data_start,
+        data_len: ((row_width * height) as u64).min(available),
+        sample_align,
+        row_width: Some(row_width),
+    })
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_probe_pnm_row_width() {
+        let path = std::env::temp_dir().join(format!("petri_media_{}.ppm", std::process::id()));
+        let mut data = b"P6\n# kommentti\n4 2\n255\n".to_vec();
+        let header_len = data.len() as u64;
+        data.extend(std::iter::repeat_n(7u8, 4 * 2 * 3));
+        std::fs::write(&path, &data).unwrap();
+
+        let mut file = File::open(&path).unwrap();
+        let layout = probe(&path, &mut file, None).unwrap().unwrap();
+        assert_eq!(layout.data_start, header_len);
+        assert_eq!(layout.data_len, 24);
+        assert_eq!(layout.sample_align, 3);
+        assert_eq!(layout.row_width, Some(12));
+
+        std::fs::remove_file(&path).ok();
+    }
+}
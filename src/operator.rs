@@ -1,3 +1,4 @@
+use crate::render;
 use serde::{Deserialize, Serialize};
 use std::fmt;
 
@@ -23,14 +24,28 @@ pub enum Operator {
     /// PatternID:t viittaavat PatternBankissa oleviin malleihin.
     Combine(u32, u32),
 
+    /// Dynaaminen literaali: atominen monitavuinen siemenyksikkö
+    /// (esim. lähdekoodin tunniste `unwrap` tai operaattori `::`).
+    /// Luodaan lennossa, ei kiinteästä 256 tavun taulukosta.
+    Lexeme(Vec<u8>),
+
     /// Abstrakti luokka, joka tunnistaa kokonaisen joukon symboleita.
     /// Esim: kaikki numerot 0-9 voivat kuulua samaan luokkaan.
     Class(u32),
+
+    /// Aukollinen pari "A _ B": A ja B yhden mielivaltaisen tokenin
+    /// päässä toisistaan (esim. "error: " _ " at line"). Abstrakti kuten
+    /// Class: ei esiinny virrassa, vaan collapse sitoo aukon luomalla
+    /// kohtaan konkreettisen mallin.
+    Gap(u32, u32),
 }
 
+/// `{}` näyttää literaalin tavuna ('a' tai 0x0A) ja leksemin tekstinä;
+/// `{:#}` nimeää operaattorin ja escapoi tavun (Literal('\n')).
 impl fmt::Display for Operator {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
+            Operator::Literal(b) if f.alternate() => write!(f, "Literal({})", render::byte(*b)),
             Operator::Literal(b) => {
                 if b.is_ascii_graphic() || *b == b' ' {
                     write!(f, "'{}'", *b as char)
@@ -38,48 +53,39 @@ impl fmt::Display for Operator {
                     write!(f, "0x{:02X}", b)
                 }
             }
+            Operator::Lexeme(bytes) => {
+                let label = render::label(bytes, render::PREVIEW_LEN);
+                if f.alternate() {
+                    write!(f, "Lexeme({})", label)
+                } else {
+                    f.write_str(&label)
+                }
+            }
             Operator::Combine(l, r) => write!(f, "Combine(P_{}, P_{})", l, r),
             Operator::Class(id) => write!(f, "CLASS_{}", id),
+            Operator::Gap(l, r) => write!(f, "Gap(P_{}, _, P_{})", l, r),
         }
     }
 }
 
 impl Operator {
-    /// Palauttaa hierarkiatason (complexity).
-    /// - Literal = 0
-    /// - Combine kahdesta Literalista = 1
-    /// - Combine jossa toinen on Combine = max(left, right) + 1
-    ///
-    /// Huom: Täydellinen kompleksisuuslaskenta vaatii pääsyn PatternBankiin,
-    /// joten tämä on yksinkertaistettu versio.
-    #[allow(dead_code)]
-    pub fn base_complexity(&self) -> u8 {
-        match self {
-            Operator::Literal(_) => 0,
-            Operator::Combine(_, _) => 1, // Minimikompleksisuus yhdistelmälle
-            Operator::Class(_) => 0,
-        }
-    }
-
-    /// Tarkistaa onko tämä Literal-operaattori
+    /// Tarkistaa onko tämä perusyksikkö (Literal tai dynaaminen Lexeme)
     pub fn is_literal(&self) -> bool {
-        matches!(self, Operator::Literal(_))
+        matches!(self, Operator::Literal(_) | Operator::Lexeme(_))
     }
 
-    /// Palauttaa Literal-tavun jos kyseessä on Literal
-    #[allow(dead_code)]
-    pub fn as_literal(&self) -> Option<u8> {
+    /// Palauttaa Combine-parin jos kyseessä on Combine
+    pub fn as_combine(&self) -> Option<(u32, u32)> {
         match self {
-            Operator::Literal(b) => Some(*b),
+            Operator::Combine(left, right) => Some((*left, *right)),
             _ => None,
         }
     }
 
-    /// Palauttaa Combine-parin jos kyseessä on Combine
-    #[allow(dead_code)]
-    pub fn as_combine(&self) -> Option<(u32, u32)> {
+    /// Palauttaa aukon reunat jos kyseessä on Gap
+    pub fn as_gap(&self) -> Option<(u32, u32)> {
         match self {
-            Operator::Combine(left, right) => Some((*left, *right)),
+            Operator::Gap(left, right) => Some((*left, *right)),
             _ => None,
         }
     }
@@ -89,3 +95,22 @@ impl Operator {
         matches!(self, Operator::Class(_))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display_plain_and_alternate() {
+        assert_eq!(Operator::Literal(b'a').to_string(), "'a'");
+        assert_eq!(Operator::Literal(b'\n').to_string(), "0x0A");
+        assert_eq!(format!("{:#}", Operator::Literal(b'\n')), "Literal('\\n')");
+        let lexeme = Operator::Lexeme(b"unwrap".to_vec());
+        assert_eq!(lexeme.to_string(), "\"unwrap\"");
+        assert_eq!(format!("{:#}", lexeme), "Lexeme(\"unwrap\")");
+        assert_eq!(
+            format!("{:#}", Operator::Combine(1, 2)),
+            "Combine(P_1, P_2)"
+        );
+    }
+}
@@ -1,5 +1,39 @@
 use crate::operator::Operator;
 use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+/// Montako viimeisintä strength-näytettä historiaan säilytetään
+pub const STRENGTH_HISTORY_LEN: usize = 8;
+
+/// Totuuskynnyksen ylitysten seuranta syklien lopussa.
+///
+/// Vahvistus, heikennys ja vanheneminen voivat heilutella mallia kynnyksen
+/// yli ja ali joka syklillä: malli on vuorotellen käytössä ja poissa, eikä
+/// virta asetu. Seuranta laskee suunnanvaihdot, ja vähimmäisviipymä pitää
+/// mallin uudella puolella, kunnes se on ollut siellä tarpeeksi kauan.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct TruthState {
+    /// Puoli edellisen syklin lopussa (None = ei vielä seurattu)
+    pub side: Option<bool>,
+    /// Sykli, jolla puoli viimeksi vaihtui
+    pub since: u64,
+    /// Viipymä kesken: käyttö seuraa `side`-arvoa eikä vahvuutta
+    pub held: bool,
+    /// Puolen vaihdot yhteensä
+    pub flips: u32,
+}
+
+/// Syklin lopun tarkistuksen tulos
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TruthChange {
+    Unchanged,
+    /// Ensimmäinen ylitys tai pudotus
+    Crossed,
+    /// Suunta kääntyi aiemman vaihdon jälkeen (heilahtelu)
+    Oscillated,
+    /// Vaihto estettiin: vähimmäisviipymä ei ole täynnä
+    Held,
+}
 
 /// Pattern (Malli): Elävä hypoteesi hierarkkisessa oppimissysteemissä.
 ///
@@ -12,7 +46,8 @@ pub struct Pattern {
     /// Uniikki tunniste PatternBankissa
     pub id: u32,
 
-    /// Operaattori: Literal(u8), Combine(u32, u32) tai Class(u32)
+    /// Operaattori: Literal(u8), Lexeme(tavut), Combine(u32, u32), Class(u32)
+    /// tai Gap(u32, u32)
     pub op: Operator,
 
     /// "Totuusarvo": 0.0 - 1.0
@@ -38,6 +73,26 @@ pub struct Pattern {
     /// Viittauslaskuri: Kuinka moni muu malli viittaa tähän malliin.
     /// Mallia ei saa poistaa jos ref_count > 0.
     pub ref_count: u32,
+
+    /// Luontisykli (literaaleilla ja luokilla 0)
+    #[serde(default)]
+    pub birth_cycle: u64,
+
+    /// Viimeisimmät strength-arvot syklien lopussa (vanhin ensin)
+    #[serde(default)]
+    pub strength_history: VecDeque<f64>,
+
+    /// Kuinka monta tokenia malli on yhteensä säästänyt virrasta
+    #[serde(default)]
+    pub tokens_saved: u64,
+
+    /// Kiinnitetty malli ei heikkene eikä unohdu (esim. sanaston sanat)
+    #[serde(default)]
+    pub pinned: bool,
+
+    /// Totuuskynnyksen ylitysten seuranta (ks. settle_truth)
+    #[serde(default)]
+    pub truth: TruthState,
 }
 
 impl Pattern {
@@ -51,6 +106,29 @@ impl Pattern {
             complexity: 0,
             usage_count: 0,
             ref_count: 0,
+            birth_cycle: 0,
+            strength_history: VecDeque::new(),
+            tokens_saved: 0,
+            pinned: false,
+            truth: TruthState::default(),
+        }
+    }
+
+    /// Luo uusi dynaaminen literaali (taso 0)
+    pub fn new_lexeme(id: u32, bytes: Vec<u8>) -> Self {
+        Pattern {
+            id,
+            op: Operator::Lexeme(bytes),
+            strength: 1.0,
+            last_used: 0,
+            complexity: 0,
+            usage_count: 0,
+            ref_count: 0,
+            birth_cycle: 0,
+            strength_history: VecDeque::new(),
+            tokens_saved: 0,
+            pinned: false,
+            truth: TruthState::default(),
         }
     }
 
@@ -82,6 +160,33 @@ impl Pattern {
             complexity,
             usage_count: 0,
             ref_count: 0,
+            birth_cycle: cycle,
+            strength_history: VecDeque::new(),
+            tokens_saved: 0,
+            pinned: false,
+            truth: TruthState::default(),
+        }
+    }
+
+    /// Luo uusi aukollinen malli "left _ right" (taso kuten Combinella)
+    pub fn new_gap(
+        id: u32,
+        left_id: u32,
+        right_id: u32,
+        left_complexity: u8,
+        right_complexity: u8,
+        cycle: u64,
+    ) -> Self {
+        Pattern {
+            op: Operator::Gap(left_id, right_id),
+            ..Pattern::new_combine(
+                id,
+                left_id,
+                right_id,
+                left_complexity,
+                right_complexity,
+                cycle,
+            )
         }
     }
 
@@ -92,25 +197,103 @@ impl Pattern {
         self.usage_count += 1;
     }
 
+    /// Tallenna syklin lopun strength historiaan
+    pub fn record_strength(&mut self) {
+        if self.strength_history.len() == STRENGTH_HISTORY_LEN {
+            self.strength_history.pop_front();
+        }
+        self.strength_history.push_back(self.strength);
+    }
+
+    /// Onko malli "tosi" eli käytettävissä tiivistykseen. Viipymän aikana
+    /// malli pysyy puolella, jolle se viimeksi siirtyi.
+    pub fn is_true(&self, threshold: f64) -> bool {
+        match self.truth.side {
+            Some(side) if self.truth.held => side,
+            _ => self.strength >= threshold,
+        }
+    }
+
+    /// Syklin lopun tarkistus: vaihtuiko puoli kynnykseen nähden. Vaihto
+    /// hyväksytään vasta, kun edellisestä on kulunut `min_dwell` sykliä
+    /// (0 = ei viipymää, käyttö seuraa aina vahvuutta).
+    pub fn settle_truth(&mut self, threshold: f64, cycle: u64, min_dwell: u64) -> TruthChange {
+        let above = self.strength >= threshold;
+        let truth = &mut self.truth;
+        let Some(side) = truth.side else {
+            *truth = TruthState {
+                side: Some(above),
+                since: cycle,
+                ..TruthState::default()
+            };
+            return TruthChange::Unchanged;
+        };
+        if above == side {
+            truth.held = false;
+            return TruthChange::Unchanged;
+        }
+        if truth.flips > 0 && cycle.saturating_sub(truth.since) < min_dwell {
+            truth.held = true;
+            return TruthChange::Held;
+        }
+        truth.side = Some(above);
+        truth.since = cycle;
+        truth.held = false;
+        truth.flips += 1;
+        if truth.flips > 1 {
+            TruthChange::Oscillated
+        } else {
+            TruthChange::Crossed
+        }
+    }
+
+    /// Kuinka monta kertaa suunta on kääntynyt (ensimmäinen vaihto ei ole
+    /// heilahtelua)
+    pub fn oscillations(&self) -> u32 {
+        self.truth.flips.saturating_sub(1)
+    }
+
     /// Heikennä mallin "totuusarvoa" kun ennustus epäonnistuu
     pub fn weaken(&mut self, amount: f64) {
         self.strength = (self.strength - amount).max(0.0);
     }
 
-    /// Tarkista onko malli "kuollut" (liian heikko)
-    #[allow(dead_code)]
-    pub fn is_dead(&self, threshold: f64) -> bool {
-        self.strength < threshold
-    }
-
     /// Tarkista onko tämä Literal-malli
     pub fn is_literal(&self) -> bool {
         self.op.is_literal()
     }
 
     /// Palauttaa Combine-parin jos kyseessä on Combine
-    #[allow(dead_code)]
     pub fn as_combine(&self) -> Option<(u32, u32)> {
         self.op.as_combine()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dwell_damps_oscillation() {
+        // Vahvuus heiluu kynnyksen ympärillä joka syklillä
+        let swing = |min_dwell: u64| {
+            let mut pattern = Pattern::new_combine(300, 97, 98, 0, 0, 0);
+            let mut used = Vec::new();
+            for cycle in 1..=8 {
+                pattern.strength = if cycle % 2 == 0 { 0.45 } else { 0.55 };
+                pattern.settle_truth(0.5, cycle, min_dwell);
+                used.push(pattern.is_true(0.5));
+            }
+            (used, pattern.oscillations())
+        };
+
+        let (used, oscillations) = swing(0);
+        assert_eq!(used, [true, false, true, false, true, false, true, false]);
+        assert_eq!(oscillations, 6);
+
+        // Viipymä pitää mallin uudella puolellaan vähintään kolme sykliä
+        let (used, oscillations) = swing(3);
+        assert_eq!(used, [true, false, false, false, true, true, true, false]);
+        assert_eq!(oscillations, 2);
+    }
+}
@@ -0,0 +1,322 @@
+// src/plot.rs
+//
+// Plot: Ajon mittarit SVG-kuvaajiksi.
+//
+// results.csv luetaan otsikkorivin perusteella, joten vanhemmat (vähemmän
+// sarakkeita sisältävät) tiedostot toimivat: puuttuvat sarjat jätetään
+// pois. Kuvaajat piirretään suoraan SVG:ksi ilman riippuvuuksia, yksi
+// paneeli kullekin mittarille päällekkäin:
+//   - tiivistyssuhde,
+//   - tuttuus (kokonais ja horisontit 1k / 10k / 100k),
+//   - mallien määrä.
+
+use crate::i18n::tr;
+use std::fmt::Write;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Kuvan leveys ja yhden paneelin korkeus (pikseleinä)
+const WIDTH: f64 = 800.0;
+const PANEL_HEIGHT: f64 = 240.0;
+
+/// Piirtoalueen reunukset paneelin sisällä: vasen, oikea, ylä, ala
+const MARGIN: (f64, f64, f64, f64) = (70.0, 20.0, 36.0, 40.0);
+
+/// Akselien jakoviivojen tavoitemäärä
+const TICKS: usize = 5;
+
+/// Sarjojen värit järjestyksessä
+const COLORS: [&str; 4] = ["#1f77b4", "#d62728", "#2ca02c", "#9467bd"];
+
+/// CSV-tiedoston numeeriset sarakkeet
+pub struct Metrics {
+    columns: Vec<String>,
+    rows: Vec<Vec<f64>>,
+}
+
+impl Metrics {
+    /// Jäsennä CSV; ei-numeeriset arvot ovat NaN
+    pub fn parse(text: &str) -> Self {
+        let mut lines = text.lines().filter(|l| !l.trim().is_empty());
+        let columns = lines
+            .next()
+            .map(|h| h.split(',').map(|c| c.trim().to_string()).collect())
+            .unwrap_or_default();
+        let rows = lines
+            .map(|line| {
+                line.split(',')
+                    .map(|v| v.trim().parse().unwrap_or(f64::NAN))
+                    .collect()
+            })
+            .collect();
+        Metrics { columns, rows }
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        Ok(Self::parse(&fs::read_to_string(path)?))
+    }
+
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    /// Sarjan pisteet (sykli, arvo); `None`, jos saraketta ei ole
+    pub fn series(&self, column: &str, scale: f64) -> Option<Vec<(f64, f64)>> {
+        let x = self.columns.iter().position(|c| c == "cycle")?;
+        let y = self.columns.iter().position(|c| c == column)?;
+        Some(
+            self.rows
+                .iter()
+                .filter_map(|row| Some((*row.get(x)?, *row.get(y)? * scale)))
+                .filter(|(x, y)| x.is_finite() && y.is_finite())
+                .collect(),
+        )
+    }
+}
+
+/// Yksi nimetty viiva
+pub struct Series {
+    pub name: String,
+    pub points: Vec<(f64, f64)>,
+}
+
+/// Yksi kuvaaja
+pub struct Panel {
+    pub title: String,
+    pub series: Vec<Series>,
+}
+
+/// Oletuspaneelit: tiivistys, tuttuus ja mallien määrä
+pub fn default_panels(metrics: &Metrics) -> Vec<Panel> {
+    let panel = |title: String, columns: &[(&str, &str)], scale: f64| Panel {
+        title,
+        series: columns
+            .iter()
+            .filter_map(|&(column, name)| {
+                Some(Series {
+                    name: name.to_string(),
+                    points: metrics.series(column, scale)?,
+                })
+            })
+            .collect(),
+    };
+    vec![
+        panel(
+            tr!("Tiivistyssuhde (%)", "Compression ratio (%)"),
+            &[("compression_ratio", "ratio")],
+            100.0,
+        ),
+        panel(
+            tr!("Tuttuus (%)", "Familiarity (%)"),
+            &[
+                ("familiarity", "familiarity"),
+                ("fam_1k", "1k"),
+                ("fam_10k", "10k"),
+                ("fam_100k", "100k"),
+            ],
+            100.0,
+        ),
+        panel(
+            tr!("Malleja", "Patterns"),
+            &[("patterns_count", "patterns")],
+            1.0,
+        ),
+    ]
+}
+
+/// "Siistit" jakoviivat välille [min, max]: askel 1, 2 tai 5 × 10^n
+fn ticks(min: f64, max: f64) -> Vec<f64> {
+    let range = (max - min).max(f64::EPSILON);
+    let raw = range / TICKS as f64;
+    let magnitude = 10f64.powf(raw.log10().floor());
+    let step = [1.0, 2.0, 5.0, 10.0]
+        .iter()
+        .map(|m| m * magnitude)
+        .find(|&s| s >= raw)
+        .unwrap_or(raw);
+    let mut out = Vec::new();
+    let mut tick = (min / step).ceil() * step;
+    while tick <= max + step * 1e-9 {
+        out.push(tick);
+        tick += step;
+    }
+    out
+}
+
+/// Luvun lyhyt esitys akselille
+fn format_tick(value: f64) -> String {
+    if value.abs() >= 10_000.0 {
+        format!("{:.0}k", value / 1000.0)
+    } else if value.fract().abs() < 1e-9 {
+        format!("{:.0}", value)
+    } else {
+        format!("{:.1}", value)
+    }
+}
+
+/// Piirrä yksi paneeli kohtaan `top`
+fn render_panel(svg: &mut String, panel: &Panel, top: f64) {
+    let (left, right, margin_top, margin_bottom) = MARGIN;
+    let (x0, x1) = (left, WIDTH - right);
+    let (y0, y1) = (top + PANEL_HEIGHT - margin_bottom, top + margin_top);
+
+    let points = panel.series.iter().flat_map(|s| s.points.iter());
+    let (mut min_x, mut max_x, mut min_y, mut max_y) = (
+        f64::INFINITY,
+        f64::NEG_INFINITY,
+        f64::INFINITY,
+        f64::NEG_INFINITY,
+    );
+    for &(x, y) in points {
+        min_x = min_x.min(x);
+        max_x = max_x.max(x);
+        min_y = min_y.min(y);
+        max_y = max_y.max(y);
+    }
+
+    let _ = writeln!(
+        svg,
+        r#"<text x="{}" y="{}" font-size="15" font-weight="bold">{}</text>"#,
+        x0,
+        top + 22.0,
+        escape(&panel.title)
+    );
+    if !min_x.is_finite() {
+        let _ = writeln!(
+            svg,
+            r##"<text x="{}" y="{}" fill="#888">{}</text>"##,
+            x0,
+            (y0 + y1) / 2.0,
+            tr!("(ei dataa)", "(no data)")
+        );
+        return;
+    }
+    // Y-akseli alkaa nollasta, jos arvot ovat positiivisia
+    min_y = min_y.min(0.0);
+    if max_y <= min_y {
+        max_y = min_y + 1.0;
+    }
+    if max_x <= min_x {
+        max_x = min_x + 1.0;
+    }
+    let sx = |x: f64| x0 + (x - min_x) / (max_x - min_x) * (x1 - x0);
+    let sy = |y: f64| y0 - (y - min_y) / (max_y - min_y) * (y0 - y1);
+
+    for tick in ticks(min_y, max_y) {
+        let y = sy(tick);
+        let _ = writeln!(
+            svg,
+            r##"<line x1="{x0}" y1="{y:.1}" x2="{x1}" y2="{y:.1}" stroke="#e0e0e0"/><text x="{:.1}" y="{:.1}" text-anchor="end">{}</text>"##,
+            x0 - 6.0,
+            y + 4.0,
+            format_tick(tick)
+        );
+    }
+    for tick in ticks(min_x, max_x) {
+        let x = sx(tick);
+        let _ = writeln!(
+            svg,
+            r#"<text x="{x:.1}" y="{:.1}" text-anchor="middle">{}</text>"#,
+            y0 + 16.0,
+            format_tick(tick)
+        );
+    }
+    let _ = writeln!(
+        svg,
+        r##"<rect x="{x0}" y="{y1}" width="{}" height="{}" fill="none" stroke="#444"/>"##,
+        x1 - x0,
+        y0 - y1
+    );
+    let _ = writeln!(
+        svg,
+        r#"<text x="{}" y="{}" text-anchor="middle">{}</text>"#,
+        (x0 + x1) / 2.0,
+        y0 + 32.0,
+        tr!("sykli", "cycle")
+    );
+
+    for (i, series) in panel.series.iter().enumerate() {
+        let color = COLORS[i % COLORS.len()];
+        let coords: Vec<String> = series
+            .points
+            .iter()
+            .map(|&(x, y)| format!("{:.1},{:.1}", sx(x), sy(y)))
+            .collect();
+        let _ = writeln!(
+            svg,
+            r#"<polyline fill="none" stroke="{}" stroke-width="1.5" points="{}"/>"#,
+            color,
+            coords.join(" ")
+        );
+        // Selite oikeaan yläkulmaan
+        let ly = top + 16.0 + i as f64 * 14.0;
+        let _ = writeln!(
+            svg,
+            r#"<line x1="{:.1}" y1="{ly:.1}" x2="{:.1}" y2="{ly:.1}" stroke="{color}" stroke-width="3"/><text x="{:.1}" y="{:.1}">{}</text>"#,
+            x1 - 110.0,
+            x1 - 90.0,
+            x1 - 84.0,
+            ly + 4.0,
+            escape(&series.name)
+        );
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Piirrä paneelit päällekkäin yhdeksi SVG-kuvaksi
+pub fn render(panels: &[Panel]) -> String {
+    let height = PANEL_HEIGHT * panels.len().max(1) as f64;
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{WIDTH}" height="{height}" viewBox="0 0 {WIDTH} {height}" font-family="sans-serif" font-size="11">"#
+    );
+    let _ = writeln!(svg, r#"<rect width="100%" height="100%" fill="white"/>"#);
+    for (i, panel) in panels.iter().enumerate() {
+        render_panel(&mut svg, panel, i as f64 * PANEL_HEIGHT);
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_metrics_to_svg() {
+        // Vanha muoto: ei horisonttisarakkeita, lopussa tekstisarake
+        let csv = "cycle,patterns_count,compression_ratio,familiarity,mode\n\
+                   1,840,0.5480,0.0000,explore\n\
+                   2,758,0.5507,0.5480,explore\n\
+                   3,790,0.6000,0.7000,speed\n";
+        let metrics = Metrics::parse(csv);
+        assert_eq!(metrics.len(), 3);
+        assert_eq!(
+            metrics.series("compression_ratio", 100.0).unwrap()[2],
+            (3.0, 60.0)
+        );
+        assert!(metrics.series("fam_1k", 1.0).is_none());
+
+        let panels = default_panels(&metrics);
+        assert_eq!(panels.len(), 3);
+        assert_eq!(panels[1].series.len(), 1);
+
+        let svg = render(&panels);
+        assert!(svg.starts_with("<svg"));
+        assert_eq!(svg.matches("<polyline").count(), 3);
+        assert!(svg.trim_end().ends_with("</svg>"));
+
+        assert_eq!(ticks(0.0, 100.0), [0.0, 20.0, 40.0, 60.0, 80.0, 100.0]);
+    }
+}
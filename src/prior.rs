@@ -0,0 +1,123 @@
+// src/prior.rs
+//
+// Prior: Parifrekvenssien esiskannaus ennen oppimista.
+//
+// Suuren korpuksen alussa pankki on tyhjä, ja ensimmäiset sadat syklit
+// kuluvat yleisimpien parien ("e ", "th", "in") löytämiseen. Nopea
+// esiskannaus laskee siemenyksiköiden (tavujen tai leksemien) parit
+// otoksesta ja luo K yleisintä valmiiksi Combine-malleiksi. Vahvuus on
+// verrannollinen frekvenssiin, joten harvinaisemmat ennakkomallit unohtuvat
+// ensin, jos varsinainen data ei tue niitä.
+//
+// Otos otetaan jokaisen tiedoston alusta tasaisena osuutena tavubudjetista,
+// jotta yksi suuri tiedosto ei hallitse tilastoa. Tiedostorajan yli ei
+// muodosteta pareja.
+
+use crate::builder::{PairStats, PatternBank};
+use crate::i18n::tr;
+use crate::progress;
+use std::fs::File;
+use std::io::Read;
+use std::path::PathBuf;
+
+/// Esiskannauksen oletusbudjetti tavuina
+pub const DEFAULT_SCAN_BYTES: usize = 16 * 1024 * 1024;
+
+/// Yleisimmän parin vahvuus; muut suhteessa frekvenssiin
+const MAX_PRIOR_STRENGTH: f64 = 0.9;
+
+/// Harvinaisimpienkin ennakkomallien vähimmäisvahvuus
+const MIN_PRIOR_STRENGTH: f64 = 0.1;
+
+/// Kertaalleen nähty pari ei ole tilasto
+const MIN_PRIOR_COUNT: u32 = 2;
+
+/// Esiskannauksen tulos
+pub struct PriorSummary {
+    pub scanned: usize,
+    pub created: usize,
+}
+
+/// Laske siemenparien frekvenssit tiedostojen alusta (yhteensä enintään
+/// `max_bytes`). Palauttaa tilaston ja luettujen tavujen määrän.
+pub fn scan(bank: &mut PatternBank, files: &[PathBuf], max_bytes: usize) -> (PairStats, usize) {
+    let share = (max_bytes / files.len().max(1)).max(1);
+    let mut stats = PairStats::new();
+    let mut scanned = 0;
+    let bar = progress::bar(files.len() as u64, tr!("Esiskannaus", "Pre-scan"));
+    for path in files {
+        let mut data = Vec::new();
+        if let Ok(file) = File::open(path) {
+            let _ = file.take(share as u64).read_to_end(&mut data);
+        }
+        scanned += data.len();
+        for pair in bank.seed_ids(&data).windows(2) {
+            stats.record(pair[0], pair[1]);
+        }
+        bar.inc(1);
+    }
+    bar.finish_and_clear();
+    (stats, scanned)
+}
+
+/// Luo `top_k` yleisintä paria Combine-malleiksi. Olemassa olevat parit
+/// ohitetaan; luonti loppuu, kun pankki täyttyy.
+pub fn seed(bank: &mut PatternBank, stats: &PairStats, top_k: usize) -> usize {
+    let pairs = stats.get_top_pairs(MIN_PRIOR_COUNT, top_k);
+    let Some(&(_, top)) = pairs.first() else {
+        return 0;
+    };
+    let mut created = 0;
+    for ((left, right), count) in pairs {
+        if bank.has_pair(left, right) {
+            continue;
+        }
+        let Some(id) = bank.create_combine(left, right, 0) else {
+            break;
+        };
+        if let Some(pattern) = bank.get_mut(id) {
+            pattern.strength =
+                (MAX_PRIOR_STRENGTH * count as f64 / top as f64).max(MIN_PRIOR_STRENGTH);
+        }
+        created += 1;
+    }
+    created
+}
+
+/// Esiskannaa tiedostot ja luo ennakkomallit
+pub fn apply(
+    bank: &mut PatternBank,
+    files: &[PathBuf],
+    top_k: usize,
+    max_bytes: usize,
+) -> PriorSummary {
+    let (stats, scanned) = scan(bank, files, max_bytes);
+    PriorSummary {
+        scanned,
+        created: seed(bank, &stats, top_k),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_top_pairs_seeded_by_frequency() {
+        let mut bank = PatternBank::new(100);
+        let mut stats = PairStats::new();
+        for pair in bank.seed_ids(b"thethethe x").windows(2) {
+            stats.record(pair[0], pair[1]);
+        }
+        let existing = bank.create_combine(b'h' as u32, b'e' as u32, 0).unwrap();
+
+        // "th" ja "he" (3) sekä "et" (2); "he" on jo pankissa
+        assert_eq!(seed(&mut bank, &stats, 3), 2);
+        let th = bank.get_pair_id(b't' as u32, b'h' as u32).unwrap();
+        let et = bank.get_pair_id(b'e' as u32, b't' as u32).unwrap();
+        assert_eq!(bank.get(th).unwrap().strength, MAX_PRIOR_STRENGTH);
+        assert!((bank.get(et).unwrap().strength - MAX_PRIOR_STRENGTH * 2.0 / 3.0).abs() < 1e-9);
+        assert_eq!(bank.get(existing).unwrap().strength, 0.5);
+        assert!(!bank.has_pair(b' ' as u32, b'x' as u32));
+    }
+}
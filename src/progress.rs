@@ -0,0 +1,150 @@
+// src/progress.rs
+//
+// Progress: Edistymispalkit pitkille operaatioille.
+//
+// Offline-työkalut (repair, cluster, detect, archive) ja suurten aivojen
+// lataus ja tallennus voivat kestää minuutteja ilman yhtään tulostetta.
+// Palkit piirretään stderr:iin ja piilotetaan automaattisesti, kun se ei
+// ole pääte, joten putkitettu tai tiedostoon ohjattu tuloste pysyy siistinä.
+// --quiet piilottaa palkit kokonaan.
+// Valmis palkki poistetaan: lopputulos tulostetaan tavalliseen tapaan.
+//
+// Oppimissilmukka tulostaa joka syklillä omat rivinsä, joten sille ei
+// piirretä palkkia vaan Eta arvioi jäljellä olevan ajan korpuksen
+// luetusta osuudesta, ja arvio tulostetaan syklin tilannekatsauksessa.
+
+use crate::term;
+use indicatif::{ProgressBar, ProgressDrawTarget, ProgressStyle};
+use std::time::{Duration, Instant};
+
+/// Pyörivän ilmaisimen päivitysväli
+const TICK_INTERVAL: Duration = Duration::from_millis(120);
+
+/// Palkki tunnetun pituiselle työlle: sijainti ja arvioitu jäljellä oleva aika
+pub fn bar(len: u64, message: String) -> ProgressBar {
+    if term::is_quiet() {
+        return ProgressBar::with_draw_target(Some(len), ProgressDrawTarget::hidden());
+    }
+    let style =
+        ProgressStyle::with_template("  {spinner} {msg} [{bar:30}] {pos}/{len} (ETA {eta})")
+            .expect("kiinteä mallipohja")
+            .progress_chars("=> ");
+    let bar = ProgressBar::new(len)
+        .with_style(style)
+        .with_message(message);
+    bar.enable_steady_tick(TICK_INTERVAL);
+    bar
+}
+
+/// Pyörivä ilmaisin työlle, jonka kestoa ei tiedetä
+pub fn spinner(message: String) -> ProgressBar {
+    if term::is_quiet() {
+        return ProgressBar::hidden();
+    }
+    let style =
+        ProgressStyle::with_template("  {spinner} {msg} ({elapsed})").expect("kiinteä mallipohja");
+    let spinner = ProgressBar::new_spinner()
+        .with_style(style)
+        .with_message(message);
+    spinner.enable_steady_tick(TICK_INTERVAL);
+    spinner
+}
+
+/// Jäljellä olevan ajan arvio: nopeus mitataan arvioinnin alusta, joten
+/// jatketun ajon jo luettu osa ei vääristä sitä
+pub struct Eta {
+    started: Instant,
+    start_done: u64,
+}
+
+impl Eta {
+    /// `done` = jo valmis määrä arvioinnin alkaessa
+    pub fn new(done: u64) -> Self {
+        Eta {
+            started: Instant::now(),
+            start_done: done,
+        }
+    }
+
+    /// Arvio, kun `done` / `total` on valmiina (None = ei vielä edistystä)
+    pub fn remaining(&self, done: u64, total: u64) -> Option<Duration> {
+        estimate(
+            self.started.elapsed(),
+            done.saturating_sub(self.start_done),
+            total.saturating_sub(done),
+        )
+    }
+}
+
+/// Jäljellä oleva aika samalla nopeudella
+fn estimate(elapsed: Duration, progressed: u64, left: u64) -> Option<Duration> {
+    if progressed == 0 {
+        return None;
+    }
+    Some(elapsed.mul_f64(left as f64 / progressed as f64))
+}
+
+/// Kesto lyhyesti: "45s", "3m 12s", "2h 05m"
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    match (secs / 3600, secs / 60 % 60, secs % 60) {
+        (0, 0, s) => format!("{}s", s),
+        (0, m, s) => format!("{}m {:02}s", m, s),
+        (h, m, _) => format!("{}h {:02}m", h, m),
+    }
+}
+
+/// Tavumäärä luettavana: "512 B", "3.4 KiB", "12.0 MiB", "1.5 GiB"
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 3] = ["KiB", "MiB", "GiB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit + 1 < UNITS.len() {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
+/// Aja `work` pyörivän ilmaisimen kanssa ja poista ilmaisin lopuksi
+pub fn with_spinner<T>(message: String, work: impl FnOnce() -> T) -> T {
+    let spinner = spinner(message);
+    let result = work();
+    spinner.finish_and_clear();
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bar_counts_and_spinner_returns_result() {
+        let progress = bar(3, "testi".to_string());
+        progress.inc(2);
+        assert_eq!(progress.position(), 2);
+        assert_eq!(progress.length(), Some(3));
+        progress.finish_and_clear();
+
+        assert_eq!(with_spinner("laske".to_string(), || 6 * 7), 42);
+    }
+
+    #[test]
+    fn test_eta_estimate_and_format() {
+        // Neljännes 30 sekunnissa: kolme neljännestä jäljellä
+        let left = estimate(Duration::from_secs(30), 250, 750).unwrap();
+        assert_eq!(left, Duration::from_secs(90));
+        assert_eq!(estimate(Duration::from_secs(5), 0, 100), None);
+        assert_eq!(Eta::new(40).remaining(40, 100), None);
+
+        assert_eq!(format_duration(Duration::from_secs(45)), "45s");
+        assert_eq!(format_duration(left), "1m 30s");
+        assert_eq!(format_duration(Duration::from_secs(7500)), "2h 05m");
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(1536), "1.5 KiB");
+        assert_eq!(format_bytes(3 * 1024 * 1024), "3.0 MiB");
+    }
+}
@@ -0,0 +1,189 @@
+// src/quarantine.rs
+//
+// Quarantine: Vieraan datan eristys ja myöhempi palautus.
+//
+// Yksittäinen vieras tiedosto (binääri, toinen kieli, rikkinäinen dump)
+// täyttää paritilastot pareilla, joita ei enää koskaan nähdä, ja pankki
+// unohtaa niiden tieltä hyödyllisiä malleja. Kun aivot jo tuntevat
+// tavallisen datansa, jokainen moottorille menossa oleva pala arvioidaan
+// jäädytetyillä aivoilla ennen syöttöä. Jos tuttuus jää kynnyksen alle,
+// pala siirretään karanteeniin (levylle) eikä virtaan.
+//
+// Karanteeni käydään läpi säännöllisesti: kun aiheeseen liittyviä malleja
+// on myöhemmin syntynyt muusta datasta, palan tuttuus nousee kynnyksen yli
+// ja se palautetaan virtaan. Loput säilyvät tiedostossa seuraavaan ajoon.
+//
+// Tiedostomuoto: peräkkäisiä tietueita [pituus u32 LE][tavut].
+
+use crate::builder::BOUNDARY_TOKEN;
+use crate::engine::Engine;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Karanteenin läpikäynti tämän syklimäärän välein
+pub const REPLAY_INTERVAL: usize = 20;
+
+/// Karanteenin enimmäiskoko tavuina; täydestä karanteenista pala hylätään
+pub const DEFAULT_MAX_BYTES: usize = 8 * 1024 * 1024;
+
+/// Tätä lyhyempiä paloja ei arvioida
+const MIN_SCORED_LEN: usize = 64;
+
+/// Eristys vasta, kun virran lopun tuttuus on vähintään tämä: tyhjät
+/// aivot eivät tunne mitään, eikä kaikkea saa eristää
+const MIN_CONTEXT_FAMILIARITY: f64 = 0.3;
+
+/// Virran lopun ikkuna (tokeneina) yllä olevaan ehtoon
+const CONTEXT_LOOKBACK: usize = 1000;
+
+/// Palan tuttuus jäädytetyillä aivoilla (1.0 - tokeneja / tavuja)
+pub fn familiarity(engine: &dyn Engine, data: &[u8]) -> f64 {
+    if data.is_empty() {
+        return 0.0;
+    }
+    let tokens = engine
+        .encode(data)
+        .iter()
+        .filter(|&&t| t != BOUNDARY_TOKEN)
+        .count();
+    1.0 - tokens as f64 / data.len() as f64
+}
+
+/// Levylle tallentuva karanteeni
+pub struct Quarantine {
+    path: PathBuf,
+    threshold: f64,
+    max_bytes: usize,
+    entries: Vec<Vec<u8>>,
+    /// Karanteeniin siirretyt tavut tässä ajossa
+    pub diverted: usize,
+    /// Virtaan palautetut tavut tässä ajossa
+    pub replayed: usize,
+    /// Täydestä karanteenista hylätyt tavut
+    pub dropped: usize,
+}
+
+impl Quarantine {
+    /// Avaa karanteeni; aiemman ajon tietueet luetaan mukaan
+    pub fn open(path: &Path, threshold: f64, max_bytes: usize) -> io::Result<Self> {
+        let entries = match fs::read(path) {
+            Ok(bytes) => parse_entries(&bytes),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e),
+        };
+        Ok(Quarantine {
+            path: path.to_path_buf(),
+            threshold,
+            max_bytes,
+            entries,
+            diverted: 0,
+            replayed: 0,
+            dropped: 0,
+        })
+    }
+
+    /// Karanteenin tietueet
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Karanteenissa olevat tavut
+    pub fn bytes(&self) -> usize {
+        self.entries.iter().map(Vec::len).sum()
+    }
+
+    /// Onko pala niin vieras, että se pitää eristää
+    pub fn is_alien(&self, engine: &dyn Engine, data: &[u8]) -> bool {
+        data.len() >= MIN_SCORED_LEN
+            && engine.familiarity(CONTEXT_LOOKBACK) >= MIN_CONTEXT_FAMILIARITY
+            && familiarity(engine, data) < self.threshold
+    }
+
+    /// Siirrä pala karanteeniin (tiedoston loppuun). Täydestä karanteenista
+    /// pala hylätään.
+    pub fn divert(&mut self, data: &[u8]) -> io::Result<()> {
+        if self.bytes() + data.len() > self.max_bytes {
+            self.dropped += data.len();
+            return Ok(());
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        file.write_all(&(data.len() as u32).to_le_bytes())?;
+        file.write_all(data)?;
+        self.entries.push(data.to_vec());
+        self.diverted += data.len();
+        Ok(())
+    }
+
+    /// Ota karanteenista palat, joiden tuttuus on nyt kynnyksen yli.
+    /// Tiedosto kirjoitetaan uudelleen vain, jos jotain poistui.
+    pub fn take_ready(&mut self, score: impl Fn(&[u8]) -> f64) -> io::Result<Vec<Vec<u8>>> {
+        let (ready, rest): (Vec<_>, Vec<_>) = std::mem::take(&mut self.entries)
+            .into_iter()
+            .partition(|data| score(data) >= self.threshold);
+        self.entries = rest;
+        if !ready.is_empty() {
+            let mut out = Vec::with_capacity(self.bytes() + 4 * self.entries.len());
+            for data in &self.entries {
+                out.extend_from_slice(&(data.len() as u32).to_le_bytes());
+                out.extend_from_slice(data);
+            }
+            fs::write(&self.path, out)?;
+            self.replayed += ready.iter().map(Vec::len).sum::<usize>();
+        }
+        Ok(ready)
+    }
+}
+
+/// Jäsennä tietueet; keskeneräinen viimeinen tietue ohitetaan
+fn parse_entries(mut bytes: &[u8]) -> Vec<Vec<u8>> {
+    let mut entries = Vec::new();
+    while let Some((len, rest)) = bytes.split_first_chunk::<4>() {
+        let len = u32::from_le_bytes(*len) as usize;
+        if rest.len() < len {
+            break;
+        }
+        entries.push(rest[..len].to_vec());
+        bytes = &rest[len..];
+    }
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_divert_persist_and_replay() {
+        let path =
+            std::env::temp_dir().join(format!("petri_quarantine_{}.bin", std::process::id()));
+        fs::remove_file(&path).ok();
+        let mut quarantine = Quarantine::open(&path, 0.2, 100).unwrap();
+        quarantine.divert(b"outoa dataa").unwrap();
+        quarantine.divert(b"lisaa outoa").unwrap();
+        quarantine.divert(&[0; 90]).unwrap();
+        assert_eq!((quarantine.len(), quarantine.dropped), (2, 90));
+
+        // Tietueet säilyvät ajojen yli
+        let mut reopened = Quarantine::open(&path, 0.2, 100).unwrap();
+        assert_eq!(reopened.bytes(), 22);
+        let ready = reopened
+            .take_ready(|data| if data.starts_with(b"lisaa") { 0.5 } else { 0.0 })
+            .unwrap();
+        assert_eq!(ready, [b"lisaa outoa".to_vec()]);
+        assert_eq!(reopened.replayed, 11);
+        assert_eq!(
+            Quarantine::open(&path, 0.2, 100).unwrap().entries,
+            [b"outoa dataa".to_vec()]
+        );
+
+        fs::remove_file(&path).ok();
+    }
+}
@@ -0,0 +1,484 @@
+// src/range_coder.rs
+//
+// Adaptiivinen range-kooderi token-virralle.
+//
+// Toisin kuin Huffman, range-kooderi ei tarvitse erillistä kooditaulukkoa:
+// frekvenssit opitaan lennossa sekä koodatessa että purkaessa.
+//
+// Mallit:
+// - Order-0: kaikkien tokenien frekvenssit
+// - Order-1 (valinnainen): frekvenssit edellisen tokenin kontekstissa
+// Uusi symboli koodataan ESCAPE-symbolilla ja putoaa alemmalle tasolle;
+// order-0:sta pudotessa ID kirjoitetaan suoraan neljänä tavuna.
+//
+// Tiedostomuoto:
+//   [tokenien määrä: varint][liput: u8][range-kooderin tavut]
+
+use crate::bitio::{read_varint, write_varint};
+use crate::i18n::tr;
+use std::collections::HashMap;
+use std::io;
+
+/// Normalisointiraja: range pidetään aina yli 2^24
+const TOP: u32 = 1 << 24;
+
+/// Frekvenssien kokonaissumman yläraja (skaalataan puoleen kun ylittyy)
+const MAX_TOTAL: u32 = 1 << 16;
+
+/// Frekvenssin kasvatus jokaisella esiintymällä
+const INCREMENT: u32 = 24;
+
+/// ESCAPE-symbolin kiinteä frekvenssi
+const ESCAPE_FREQ: u32 = 8;
+
+/// Lippu: order-1 konteksti käytössä
+const FLAG_CONTEXT: u8 = 1;
+
+// ============================================================================
+// RANGE-KOODERI (LZMA-tyylinen, carry-käsittelyllä)
+// ============================================================================
+
+struct Encoder {
+    low: u64,
+    range: u32,
+    cache: u8,
+    cache_size: u64,
+    out: Vec<u8>,
+}
+
+impl Encoder {
+    fn new() -> Self {
+        Encoder {
+            low: 0,
+            range: u32::MAX,
+            cache: 0,
+            cache_size: 1,
+            out: Vec::new(),
+        }
+    }
+
+    fn encode(&mut self, cum: u32, freq: u32, total: u32) {
+        let r = self.range / total;
+        self.low += r as u64 * cum as u64;
+        self.range = r * freq;
+        while self.range < TOP {
+            self.range <<= 8;
+            self.shift_low();
+        }
+    }
+
+    fn shift_low(&mut self) {
+        if self.low < 0xFF00_0000 || self.low > 0xFFFF_FFFF {
+            let carry = (self.low >> 32) as u8;
+            let mut temp = self.cache;
+            loop {
+                self.out.push(temp.wrapping_add(carry));
+                temp = 0xFF;
+                self.cache_size -= 1;
+                if self.cache_size == 0 {
+                    break;
+                }
+            }
+            self.cache = ((self.low >> 24) & 0xFF) as u8;
+        }
+        self.cache_size += 1;
+        self.low = (self.low & 0x00FF_FFFF) << 8;
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        for _ in 0..5 {
+            self.shift_low();
+        }
+        self.out
+    }
+}
+
+struct Decoder<'a> {
+    code: u32,
+    range: u32,
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Decoder<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        let mut dec = Decoder {
+            code: 0,
+            range: u32::MAX,
+            data,
+            pos: 0,
+        };
+        for _ in 0..5 {
+            dec.code = (dec.code << 8) | dec.next_byte() as u32;
+        }
+        dec
+    }
+
+    fn next_byte(&mut self) -> u8 {
+        // Datan lopun jälkeen luetaan nollia (kooderi täyttää samoin)
+        let b = self.data.get(self.pos).copied().unwrap_or(0);
+        self.pos += 1;
+        b
+    }
+
+    fn get_freq(&self, total: u32) -> u32 {
+        (self.code / (self.range / total)).min(total - 1)
+    }
+
+    fn decode(&mut self, cum: u32, freq: u32, total: u32) {
+        let r = self.range / total;
+        self.code -= r * cum;
+        self.range = r * freq;
+        while self.range < TOP {
+            self.code = (self.code << 8) | self.next_byte() as u32;
+            self.range <<= 8;
+        }
+    }
+}
+
+// ============================================================================
+// ADAPTIIVINEN FREKVENSSIMALLI
+// ============================================================================
+
+/// Symbolin paikka mallissa: ESCAPE tai tunnettu symboli
+enum Slot {
+    Escape,
+    Symbol(u32),
+}
+
+/// Adaptiivinen malli: ESCAPE ensin (cum 0), sitten symbolit lisäysjärjestyksessä.
+///
+/// Kumulatiiviset frekvenssit pidetään Fenwick-puussa, joten sekä
+/// koodauksen (`range_of`) että purun (`find`) haku on O(log n) eikä
+/// lineaarinen sanaston koon suhteen.
+struct AdaptiveModel {
+    symbols: Vec<u32>,
+    freqs: Vec<u32>,
+    /// Fenwick-puu frekvensseistä: solmu k (1..=n) on paikassa k - 1 ja
+    /// kattaa symbolit (k - lowbit(k), k]
+    tree: Vec<u32>,
+    index: HashMap<u32, usize>,
+    total: u32,
+}
+
+impl Default for AdaptiveModel {
+    fn default() -> Self {
+        AdaptiveModel {
+            symbols: Vec::new(),
+            freqs: Vec::new(),
+            tree: Vec::new(),
+            index: HashMap::new(),
+            total: ESCAPE_FREQ,
+        }
+    }
+}
+
+/// Alin asetettu bitti (Fenwick-puun solmun leveys)
+fn lowbit(k: usize) -> usize {
+    k & k.wrapping_neg()
+}
+
+impl AdaptiveModel {
+    /// (cum, freq) symbolille, None jos symboli on tuntematon
+    fn range_of(&self, symbol: u32) -> Option<(u32, u32)> {
+        let idx = *self.index.get(&symbol)?;
+        Some((ESCAPE_FREQ + self.prefix(idx), self.freqs[idx]))
+    }
+
+    /// Etsi symboli kumulatiivisen arvon perusteella
+    fn find(&self, target: u32) -> (Slot, u32, u32) {
+        if target < ESCAPE_FREQ {
+            return (Slot::Escape, 0, ESCAPE_FREQ);
+        }
+        // Laskeudu puussa: suurin `pos`, jonka etuliitesumma <= rem
+        let mut rem = target - ESCAPE_FREQ;
+        let mut pos = 0;
+        let mut step = match self.tree.len() {
+            0 => 0,
+            n => 1 << n.ilog2(),
+        };
+        while step > 0 {
+            let next = pos + step;
+            if next <= self.tree.len() && self.tree[next - 1] <= rem {
+                pos = next;
+                rem -= self.tree[next - 1];
+            }
+            step >>= 1;
+        }
+        match self.freqs.get(pos) {
+            Some(&f) => (Slot::Symbol(self.symbols[pos]), target - rem, f),
+            // Ei pitäisi tapahtua eheällä datalla
+            None => (Slot::Escape, 0, ESCAPE_FREQ),
+        }
+    }
+
+    fn update(&mut self, symbol: u32) {
+        match self.index.get(&symbol) {
+            Some(&idx) => {
+                self.freqs[idx] += INCREMENT;
+                self.add(idx, INCREMENT);
+            }
+            None => {
+                self.index.insert(symbol, self.symbols.len());
+                self.symbols.push(symbol);
+                self.freqs.push(INCREMENT);
+                // Uusi solmu k kattaa aiemmat symbolit (k - lowbit(k), k - 1]
+                let k = self.tree.len() + 1;
+                let covered = self.prefix(k - 1) - self.prefix(k - lowbit(k));
+                self.tree.push(INCREMENT + covered);
+            }
+        }
+        self.total += INCREMENT;
+
+        if self.total > MAX_TOTAL {
+            self.total = ESCAPE_FREQ;
+            for f in self.freqs.iter_mut() {
+                *f = (*f / 2).max(1);
+                self.total += *f;
+            }
+            self.rebuild();
+        }
+    }
+
+    /// Ensimmäisten `count` symbolin frekvenssien summa
+    fn prefix(&self, count: usize) -> u32 {
+        let mut k = count;
+        let mut sum = 0;
+        while k > 0 {
+            sum += self.tree[k - 1];
+            k -= lowbit(k);
+        }
+        sum
+    }
+
+    fn add(&mut self, idx: usize, delta: u32) {
+        let mut k = idx + 1;
+        while k <= self.tree.len() {
+            self.tree[k - 1] += delta;
+            k += lowbit(k);
+        }
+    }
+
+    /// Rakenna puu frekvensseistä (skaalauksen jälkeen), O(n)
+    fn rebuild(&mut self) {
+        self.tree.clone_from(&self.freqs);
+        for k in 1..=self.tree.len() {
+            let parent = k + lowbit(k);
+            if parent <= self.tree.len() {
+                self.tree[parent - 1] += self.tree[k - 1];
+            }
+        }
+    }
+}
+
+// ============================================================================
+// JULKINEN RAJAPINTA
+// ============================================================================
+
+/// Koodaa token-virta adaptiivisella range-kooderilla.
+///
+/// Jos `context` on true, käytetään order-1 mallia (edellinen token).
+pub fn encode(tokens: &[u32], context: bool) -> Vec<u8> {
+    let mut out = Vec::new();
+    write_varint(&mut out, tokens.len() as u64);
+    out.push(if context { FLAG_CONTEXT } else { 0 });
+
+    let mut enc = Encoder::new();
+    let mut order0 = AdaptiveModel::default();
+    let mut order1: HashMap<u32, AdaptiveModel> = HashMap::new();
+    let mut prev: Option<u32> = None;
+
+    for &token in tokens {
+        let mut coded = false;
+
+        if let Some(p) = prev.filter(|_| context) {
+            let model = order1.entry(p).or_default();
+            match model.range_of(token) {
+                Some((cum, freq)) => {
+                    enc.encode(cum, freq, model.total);
+                    coded = true;
+                }
+                None => enc.encode(0, ESCAPE_FREQ, model.total),
+            }
+            model.update(token);
+        }
+
+        if !coded {
+            match order0.range_of(token) {
+                Some((cum, freq)) => enc.encode(cum, freq, order0.total),
+                None => {
+                    enc.encode(0, ESCAPE_FREQ, order0.total);
+                    for byte in token.to_le_bytes() {
+                        enc.encode(byte as u32, 1, 256);
+                    }
+                }
+            }
+        }
+        order0.update(token);
+        prev = Some(token);
+    }
+
+    out.extend(enc.finish());
+    out
+}
+
+/// Pura `encode`:n tuottama data takaisin token-virraksi
+pub fn decode(data: &[u8]) -> io::Result<Vec<u32>> {
+    let mut pos = 0;
+    let count = read_varint(data, &mut pos)? as usize;
+    let flags = *data.get(pos).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            tr!("katkennut otsikko", "truncated header"),
+        )
+    })?;
+    pos += 1;
+    let context = flags & FLAG_CONTEXT != 0;
+
+    // ESCAPE pitää jokaisen symbolin todennäköisyyden alle 1 - 8/MAX_TOTAL,
+    // joten token vie yli 1/8192 bittiä: tätä suurempi määrä on korruptoitunut
+    let remaining = data.len() - pos;
+    if count / 8 / (MAX_TOTAL / ESCAPE_FREQ) as usize > remaining {
+        return Err(invalid(&tr!(
+            "tokenien määrä ylittää datan",
+            "token count exceeds the data"
+        )));
+    }
+
+    let mut dec = Decoder::new(&data[pos..]);
+    let mut order0 = AdaptiveModel::default();
+    let mut order1: HashMap<u32, AdaptiveModel> = HashMap::new();
+    let mut prev: Option<u32> = None;
+    let mut tokens = Vec::with_capacity(count.min(remaining * 8));
+
+    for _ in 0..count {
+        let mut decoded = None;
+
+        if let Some(p) = prev.filter(|_| context) {
+            let model = order1.entry(p).or_default();
+            let target = dec.get_freq(model.total);
+            let (slot, cum, freq) = model.find(target);
+            dec.decode(cum, freq, model.total);
+            if let Slot::Symbol(s) = slot {
+                decoded = Some(s);
+            }
+        }
+
+        let token = match decoded {
+            Some(t) => t,
+            None => {
+                let target = dec.get_freq(order0.total);
+                let (slot, cum, freq) = order0.find(target);
+                dec.decode(cum, freq, order0.total);
+                match slot {
+                    Slot::Symbol(s) => s,
+                    Slot::Escape => {
+                        let mut bytes = [0u8; 4];
+                        for b in bytes.iter_mut() {
+                            let v = dec.get_freq(256);
+                            dec.decode(v, 1, 256);
+                            *b = v as u8;
+                        }
+                        u32::from_le_bytes(bytes)
+                    }
+                }
+            }
+        };
+
+        if let Some(p) = prev.filter(|_| context)
+            && let Some(model) = order1.get_mut(&p)
+        {
+            model.update(token);
+        }
+        order0.update(token);
+        tokens.push(token);
+        prev = Some(token);
+    }
+
+    Ok(tokens)
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_tokens() -> Vec<u32> {
+        let mut tokens = Vec::new();
+        for i in 0..2000u32 {
+            tokens.push(97 + (i % 3));
+            tokens.push(300 + (i % 7) * (i % 2));
+            if i % 97 == 0 {
+                tokens.push(1_000_000 + i);
+            }
+        }
+        tokens
+    }
+
+    #[test]
+    fn test_range_round_trip_order0() {
+        let tokens = sample_tokens();
+        let encoded = encode(&tokens, false);
+        assert_eq!(decode(&encoded).unwrap(), tokens);
+    }
+
+    #[test]
+    fn test_range_round_trip_order1() {
+        let tokens = sample_tokens();
+        let encoded = encode(&tokens, true);
+        assert_eq!(decode(&encoded).unwrap(), tokens);
+    }
+
+    #[test]
+    fn test_model_matches_linear_cumulative_frequencies() {
+        // Monta symbolia ja skaalauksia: puun haut vastaavat suoraa summaa
+        let mut model = AdaptiveModel::default();
+        for i in 0..20_000u32 {
+            model.update((i * i) % 37 + (i % 5) * 1000);
+            if i % 101 != 0 {
+                continue;
+            }
+            let mut cum = ESCAPE_FREQ;
+            for (idx, (&symbol, &freq)) in model.symbols.iter().zip(&model.freqs).enumerate() {
+                assert_eq!(model.range_of(symbol), Some((cum, freq)), "{}", idx);
+                for target in [cum, cum + freq - 1] {
+                    let (slot, found_cum, found_freq) = model.find(target);
+                    assert!(matches!(slot, Slot::Symbol(s) if s == symbol));
+                    assert_eq!((found_cum, found_freq), (cum, freq));
+                }
+                cum += freq;
+            }
+            assert_eq!(cum, model.total);
+            assert!(matches!(model.find(0).0, Slot::Escape));
+        }
+    }
+
+    #[test]
+    fn test_range_edge_cases() {
+        assert_eq!(decode(&encode(&[], true)).unwrap(), Vec::<u32>::new());
+        assert_eq!(decode(&encode(&[7], false)).unwrap(), vec![7]);
+        assert_eq!(
+            decode(&encode(&[u32::MAX, 0], true)).unwrap(),
+            vec![u32::MAX, 0]
+        );
+    }
+
+    #[test]
+    fn test_context_helps_on_predictable_stream() {
+        // Vuorotteleva virta: order-1 ennustaa seuraavan tokenin täydellisesti
+        let tokens: Vec<u32> = (0..4000).map(|i| [10, 20, 30, 40][i % 4]).collect();
+        assert!(encode(&tokens, true).len() < encode(&tokens, false).len());
+    }
+
+    #[test]
+    fn test_range_rejects_corrupt_count() {
+        let mut data = Vec::new();
+        write_varint(&mut data, 1 << 60);
+        data.extend([FLAG_CONTEXT, 0, 0, 0, 0, 0]);
+        let err = decode(&data).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,176 @@
+// src/reader_pool.rs
+//
+// ReaderPool: Usean tiedoston rinnakkainen luku yhteen Builderiin.
+//
+// I/O-sidotulla korpuksella (verkkolevy, paljon pieniä tiedostoja) Builder
+// odottaa levyä ja prosessori on tyhjäkäynnillä. Lukijasäikeet ottavat
+// tiedostoja yhteisestä jonosta ja lähettävät paloja kanavaan etukäteen;
+// Builder itse pysyy yksisäikeisenä ja kuluttaa palat Feederin kautta.
+//
+// Yksittäisen tiedoston palat saapuvat aina järjestyksessä (yksi säie lukee
+// sen alusta loppuun), mutta eri tiedostojen palat lomittuvat. Lomitus ei
+// ole deterministinen, joten rinnakkaisajo ei toistu tavulleen samana.
+
+use crate::feeder::aligned_chunk_len;
+use crate::media::{self, MediaLayout};
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/// Montako palaa kukin lukija saa lukea etukäteen
+const CHUNKS_AHEAD_PER_READER: usize = 2;
+
+/// Luettu pala
+pub struct ReadChunk {
+    pub file_index: usize,
+    /// Palan alku tiedostossa
+    pub offset: u64,
+    pub data: Vec<u8>,
+    /// Tiedoston rakenne (mediatiedostot)
+    pub layout: Option<MediaLayout>,
+}
+
+/// Lukijasäikeen viesti Feederille
+pub enum ReaderMessage {
+    Chunk(ReadChunk),
+    /// Tiedosto luettu loppuun
+    FileDone(usize),
+    /// Tiedostoa ei voitu lukea, se ohitetaan
+    Failed {
+        file_index: usize,
+        error: String,
+    },
+}
+
+/// Lukijasäikeet ja niiden yhteinen työjono
+pub struct ReaderPool {
+    rx: Receiver<ReaderMessage>,
+    /// Aloittamattomat tiedostot (indeksi, aloituskohta)
+    queue: Arc<Mutex<VecDeque<(usize, u64)>>>,
+    feed_rate: Arc<AtomicUsize>,
+}
+
+impl ReaderPool {
+    /// Käynnistä `readers` lukijaa annetulle työlistalle
+    pub fn spawn(
+        paths: Vec<PathBuf>,
+        work: Vec<(usize, u64)>,
+        readers: usize,
+        feed_rate: usize,
+        row_hint: Option<usize>,
+    ) -> Self {
+        let readers = readers.max(1);
+        let (tx, rx) = mpsc::sync_channel(readers * CHUNKS_AHEAD_PER_READER);
+        let paths = Arc::new(paths);
+        let queue = Arc::new(Mutex::new(VecDeque::from(work)));
+        let feed_rate = Arc::new(AtomicUsize::new(feed_rate.max(1)));
+
+        for _ in 0..readers {
+            let tx = tx.clone();
+            let paths = Arc::clone(&paths);
+            let queue = Arc::clone(&queue);
+            let feed_rate = Arc::clone(&feed_rate);
+            thread::spawn(move || read_loop(&paths, &queue, &feed_rate, row_hint, &tx));
+        }
+
+        ReaderPool {
+            rx,
+            queue,
+            feed_rate,
+        }
+    }
+
+    /// Seuraava viesti; None kun kaikki lukijat ovat valmiita
+    pub fn recv(&self) -> Option<ReaderMessage> {
+        self.rx.recv().ok()
+    }
+
+    /// Uusi palakoko koskee seuraavaksi luettavia paloja
+    pub fn set_feed_rate(&self, rate: usize) {
+        self.feed_rate.store(rate.max(1), Ordering::Relaxed);
+    }
+
+    /// Vielä aloittamattomien tiedostojen indeksit
+    pub fn queued(&self) -> Vec<usize> {
+        match self.queue.lock() {
+            Ok(queue) => queue.iter().map(|&(index, _)| index).collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+}
+
+/// Lukijasäie: ota tiedosto jonosta ja lähetä se paloina, kunnes jono on tyhjä
+/// tai vastaanottaja on poistunut
+fn read_loop(
+    paths: &[PathBuf],
+    queue: &Mutex<VecDeque<(usize, u64)>>,
+    feed_rate: &AtomicUsize,
+    row_hint: Option<usize>,
+    tx: &SyncSender<ReaderMessage>,
+) {
+    loop {
+        let Some((file_index, start)) = queue.lock().ok().and_then(|mut q| q.pop_front()) else {
+            return;
+        };
+
+        let message = match read_file(
+            file_index,
+            &paths[file_index],
+            start,
+            feed_rate,
+            row_hint,
+            tx,
+        ) {
+            Ok(true) => ReaderMessage::FileDone(file_index),
+            Ok(false) => return,
+            Err(e) => ReaderMessage::Failed {
+                file_index,
+                error: e.to_string(),
+            },
+        };
+        if tx.send(message).is_err() {
+            return;
+        }
+    }
+}
+
+/// Lue yksi tiedosto. Palauttaa false, jos vastaanottaja on poistunut.
+fn read_file(
+    file_index: usize,
+    path: &Path,
+    start: u64,
+    feed_rate: &AtomicUsize,
+    row_hint: Option<usize>,
+    tx: &SyncSender<ReaderMessage>,
+) -> std::io::Result<bool> {
+    let mut file = File::open(path)?;
+    let layout = media::probe(path, &mut file, row_hint)?;
+    let mut pos = layout.map_or(start, |l| start.max(l.data_start));
+    file.seek(SeekFrom::Start(pos))?;
+    let mut reader = BufReader::new(file);
+
+    loop {
+        let len = aligned_chunk_len(feed_rate.load(Ordering::Relaxed), layout, pos);
+        let mut data = Vec::with_capacity(len);
+        (&mut reader).take(len as u64).read_to_end(&mut data)?;
+        if data.is_empty() {
+            return Ok(true);
+        }
+
+        let chunk = ReadChunk {
+            file_index,
+            offset: pos,
+            data,
+            layout,
+        };
+        pos += chunk.data.len() as u64;
+        if tx.send(ReaderMessage::Chunk(chunk)).is_err() {
+            return Ok(false);
+        }
+    }
+}
@@ -0,0 +1,160 @@
+// src/recommend.rs
+//
+// Recommend: Mitä dataa kannattaisi syöttää seuraavaksi?
+//
+// Uteliaisuusmekanismi säätää nopeutta vasta kun data on jo syötetty.
+// Suosittelija arvioi syöttämättömät (tai erilliset "holdout") tiedostot
+// etukäteen: jokaisesta luetaan muutama otosikkuna, jotka koodataan
+// jäädytetyllä mallilla. Oppimisarvo on suurin, kun otos on
+//   - vierasta (malli ei tiivistä sitä), mutta
+//   - rakenteellista (matala tavuentropia; kohinasta ei opi mitään).
+//
+// Mediamuunnoksia (delta jne.) ei sovelleta otoksiin, joten pisteet ovat
+// vertailukelpoisia vain saman tyyppisten tiedostojen kesken.
+
+use crate::engine::Engine;
+use crate::entropy::byte_entropy;
+use crate::term::say;
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+/// Otosikkunoiden määrä tiedostoa kohden
+const SAMPLE_WINDOWS: u64 = 4;
+/// Yhden otosikkunan pituus tavuina
+const SAMPLE_WINDOW_LEN: u64 = 1024;
+
+/// Yhden tiedoston arvio
+#[derive(Debug, Clone)]
+pub struct Recommendation {
+    pub path: PathBuf,
+    pub sample_bytes: usize,
+    /// Otoksen tiivistyssuhde nykyisellä mallilla (0.0 - 1.0)
+    pub familiarity: f64,
+    /// Otoksen tavuentropia (bittiä / tavu)
+    pub entropy: f64,
+    /// Odotettu oppimisarvo (0.0 - 1.0)
+    pub score: f64,
+}
+
+/// Lue tiedostosta tasavälein otosikkunat
+fn sample_file(path: &Path) -> io::Result<Vec<u8>> {
+    let mut file = File::open(path)?;
+    let len = file.metadata()?.len();
+    let window_len = SAMPLE_WINDOW_LEN.min(len);
+    let stride = (len - window_len) / (SAMPLE_WINDOWS - 1);
+
+    let mut sample = Vec::new();
+    for i in 0..SAMPLE_WINDOWS {
+        file.seek(SeekFrom::Start(i * stride))?;
+        (&mut file).take(window_len).read_to_end(&mut sample)?;
+        if stride == 0 {
+            // Pieni tiedosto: yksi ikkuna kattaa sen kokonaan
+            break;
+        }
+    }
+    Ok(sample)
+}
+
+/// Arvioi otoksen tuttuus, entropia ja oppimisarvo
+pub fn assess_sample(engine: &dyn Engine, sample: &[u8]) -> (f64, f64, f64) {
+    if sample.is_empty() {
+        return (0.0, 0.0, 0.0);
+    }
+    let tokens = engine.encode(sample).len();
+    let familiarity = 1.0 - tokens as f64 / sample.len() as f64;
+    let entropy = byte_entropy(sample);
+    let score = (1.0 - familiarity) * (1.0 - entropy / 8.0);
+    (familiarity, entropy, score)
+}
+
+/// Järjestä tiedostot odotetun oppimisarvon mukaan (paras ensin).
+/// Lukukelvottomat tiedostot ohitetaan.
+pub fn rank(engine: &dyn Engine, paths: &[PathBuf]) -> Vec<Recommendation> {
+    let mut ranked: Vec<Recommendation> = paths
+        .iter()
+        .filter_map(|path| {
+            let sample = sample_file(path).ok()?;
+            let (familiarity, entropy, score) = assess_sample(engine, &sample);
+            Some(Recommendation {
+                path: path.clone(),
+                sample_bytes: sample.len(),
+                familiarity,
+                entropy,
+                score,
+            })
+        })
+        .collect();
+    ranked.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then_with(|| a.path.cmp(&b.path))
+    });
+    ranked
+}
+
+/// Tulosta "syötä nämä seuraavaksi" -lista
+pub fn print_recommendations(ranked: &[Recommendation], top_n: usize) {
+    if ranked.is_empty() {
+        say!(
+            "\n  🎯 Ei arvioitavia tiedostoja suosituksiin.",
+            "\n  🎯 No files to evaluate for recommendations."
+        );
+        return;
+    }
+    say!("\n  🎯 Syötä nämä seuraavaksi:", "\n  🎯 Feed these next:");
+    for r in ranked.iter().take(top_n) {
+        say!(
+            "     Arvo {:.3}  Fam {:>5.1}%  H {:.2} b/t  ({} t otos)  {}",
+            "     Value {:.3}  Fam {:>5.1}%  H {:.2} b/B  ({} B sample)  {}",
+            r.score,
+            r.familiarity * 100.0,
+            r.entropy,
+            r.sample_bytes,
+            r.path.display()
+        );
+    }
+    if ranked.len() > top_n {
+        say!(
+            "     ... ja {} muuta tiedostoa",
+            "     ... and {} more files",
+            ranked.len() - top_n
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::Builder;
+
+    #[test]
+    fn test_novel_structure_ranks_above_known_and_noise() {
+        let mut builder = Builder::new(500);
+        builder.tokenize(&b"the cat sat on the mat ".repeat(40));
+        for _ in 0..30 {
+            builder.live();
+        }
+
+        let known = b"the cat sat on the mat ".repeat(8);
+        let novel = b"xylophone quartz jig vex ".repeat(8);
+        let mut state = 0x9e37_79b9u32;
+        let noise: Vec<u8> = (0..known.len())
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                state as u8
+            })
+            .collect();
+
+        let (known_fam, _, known_score) = assess_sample(&builder, &known);
+        let (_, _, novel_score) = assess_sample(&builder, &novel);
+        let (_, noise_entropy, noise_score) = assess_sample(&builder, &noise);
+
+        assert!(known_fam > 0.5);
+        assert!(noise_entropy > 7.0);
+        assert!(novel_score > known_score);
+        assert!(novel_score > noise_score);
+    }
+}
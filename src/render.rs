@@ -0,0 +1,235 @@
+// src/render.rs
+//
+// Render: Mallien tavujen esittäminen tulosteissa ja raporteissa.
+//
+// Mallit ovat mielivaltaisia tavujonoja: niissä on rivinvaihtoja,
+// ohjausmerkkejä, katkenneita UTF-8-sekvenssejä ja binääridataa.
+// Kaikki tulosteet kulkevat tämän moduulin kautta, jotta
+//   - ohjausmerkit ja virheelliset tavut näkyvät escapeina (\n, \xFF),
+//   - lyhennys ei koskaan katkaise merkkiä, escapea tai yhdistelmämerkkiä,
+//   - binääridata näytetään heksana ASCII-rinnakkaisnäkymän kanssa.
+
+use crate::i18n::tr;
+
+/// Oletuspituus esikatseluille (näkyviä yksiköitä)
+pub const PREVIEW_LEN: usize = 30;
+
+/// Lyhennyksen merkki
+const ELLIPSIS: &str = "...";
+
+/// Kuinka suuri osa merkeistä saa olla ohjausmerkkejä tai virheellisiä
+/// tavuja, jotta data näytetään vielä tekstinä
+const MAX_NON_TEXT_RATIO: f64 = 0.25;
+
+/// Yhdistelmämerkki tai liitos, joka kuuluu edelliseen merkkiin
+fn is_combining(c: char) -> bool {
+    matches!(c as u32,
+        0x0300..=0x036F
+        | 0x1AB0..=0x1AFF
+        | 0x1DC0..=0x1DFF
+        | 0x20D0..=0x20FF
+        | 0xFE00..=0xFE0F
+        | 0xFE20..=0xFE2F
+        | 0x200D
+        | 0x1F3FB..=0x1F3FF
+        | 0xE0100..=0xE01EF)
+}
+
+fn escape_char(c: char, out: &mut String) {
+    match c {
+        '\n' => out.push_str("\\n"),
+        '\r' => out.push_str("\\r"),
+        '\t' => out.push_str("\\t"),
+        '\\' => out.push_str("\\\\"),
+        '"' => out.push_str("\\\""),
+        c if c.is_control() => {
+            // \xNN vain ASCII-alueelle: se on varattu yksittäisille tavuille
+            if (c as u32) < 0x80 {
+                out.push_str(&format!("\\x{:02X}", c as u32));
+            } else {
+                out.push_str(&format!("\\u{{{:X}}}", c as u32));
+            }
+        }
+        c => out.push(c),
+    }
+}
+
+/// Jaa tavut näkyviin yksiköihin: merkki yhdistelmämerkkeineen, escape
+/// tai virheellinen tavu. Yksikköä ei koskaan katkaista.
+fn units(bytes: &[u8]) -> Vec<String> {
+    let mut units: Vec<String> = Vec::new();
+    for chunk in bytes.utf8_chunks() {
+        for c in chunk.valid().chars() {
+            match units.last_mut() {
+                Some(last) if is_combining(c) && !last.starts_with('\\') => last.push(c),
+                _ => {
+                    let mut unit = String::new();
+                    escape_char(c, &mut unit);
+                    units.push(unit);
+                }
+            }
+        }
+        for b in chunk.invalid() {
+            units.push(format!("\\x{:02X}", b));
+        }
+    }
+    units
+}
+
+/// Escapoi tavut kokonaisuudessaan
+pub fn escape(bytes: &[u8]) -> String {
+    units(bytes).concat()
+}
+
+/// `escape`:n käänteinen: \n \r \t \\ \" \xNN (raakatavu) ja \u{X}
+pub fn unescape(text: &str) -> Result<Vec<u8>, String> {
+    let mut out = Vec::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            let mut buf = [0u8; 4];
+            out.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push(b'\n'),
+            Some('r') => out.push(b'\r'),
+            Some('t') => out.push(b'\t'),
+            Some('\\') => out.push(b'\\'),
+            Some('"') => out.push(b'"'),
+            Some('x') => {
+                let hex: String = chars.by_ref().take(2).collect();
+                let b = u8::from_str_radix(&hex, 16).map_err(|_| {
+                    tr!(
+                        "virheellinen \\x-escape '{}'",
+                        "invalid \\x escape '{}'",
+                        hex
+                    )
+                })?;
+                out.push(b);
+            }
+            Some('u') => {
+                let rest: String = chars.by_ref().take_while(|&c| c != '}').collect();
+                let c = rest
+                    .strip_prefix('{')
+                    .and_then(|h| u32::from_str_radix(h, 16).ok())
+                    .and_then(char::from_u32)
+                    .ok_or_else(|| {
+                        tr!(
+                            "virheellinen \\u-escape '{}'",
+                            "invalid \\u escape '{}'",
+                            rest
+                        )
+                    })?;
+                let mut buf = [0u8; 4];
+                out.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+            }
+            other => {
+                return Err(tr!(
+                    "tuntematon escape '\\{}'",
+                    "unknown escape '\\{}'",
+                    other.unwrap_or(' ')
+                ));
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// Escapoitu esikatselu enintään `max_units` näkyvällä yksiköllä
+pub fn preview(bytes: &[u8], max_units: usize) -> String {
+    let units = units(bytes);
+    if units.len() <= max_units {
+        units.concat()
+    } else {
+        format!("{}{}", units[..max_units].concat(), ELLIPSIS)
+    }
+}
+
+/// Onko data pääosin tulostettavaa tekstiä
+pub fn is_text(bytes: &[u8]) -> bool {
+    if bytes.is_empty() {
+        return true;
+    }
+    let mut total = 0usize;
+    let mut non_text = 0usize;
+    for chunk in bytes.utf8_chunks() {
+        for c in chunk.valid().chars() {
+            total += 1;
+            if c.is_control() && !matches!(c, '\n' | '\r' | '\t') {
+                non_text += 1;
+            }
+        }
+        total += chunk.invalid().len();
+        non_text += chunk.invalid().len();
+    }
+    (non_text as f64) <= total as f64 * MAX_NON_TEXT_RATIO
+}
+
+/// Heksa- ja ASCII-rinnakkaisnäkymä yhdellä rivillä: `48 65 0A  |He.|`
+pub fn hex_ascii(bytes: &[u8], max_bytes: usize) -> String {
+    let shown = &bytes[..bytes.len().min(max_bytes)];
+    let hex: Vec<String> = shown.iter().map(|b| format!("{:02X}", b)).collect();
+    let ascii: String = shown
+        .iter()
+        .map(|&b| {
+            if b.is_ascii_graphic() || b == b' ' {
+                b as char
+            } else {
+                '.'
+            }
+        })
+        .collect();
+    let more = if bytes.len() > shown.len() {
+        ELLIPSIS
+    } else {
+        ""
+    };
+    format!("{}{}  |{}|", hex.join(" "), more, ascii)
+}
+
+/// Mallin nimiö: lainausmerkeissä oleva teksti tai heksanäkymä binäärille
+pub fn label(bytes: &[u8], max_units: usize) -> String {
+    if is_text(bytes) {
+        format!("\"{}\"", preview(bytes, max_units))
+    } else {
+        format!("[{}]", hex_ascii(bytes, max_units / 2))
+    }
+}
+
+/// Yksittäinen tavu heittomerkeissä: 'a', '\n', '\xFF'
+pub fn byte(b: u8) -> String {
+    format!("'{}'", escape(&[b]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_preview_never_splits_units() {
+        // Monitavuiset merkit ja yhdistelmämerkki (e + akuutti)
+        let text = "äöe\u{0301}x".as_bytes();
+        assert_eq!(preview(text, 3), "äöe\u{0301}...");
+        assert_eq!(preview(text, 10), "äöe\u{0301}x");
+
+        // Ohjausmerkit ja katkennut UTF-8 escapoidaan
+        assert_eq!(escape(b"a\n\t\"\x01"), "a\\n\\t\\\"\\x01");
+        assert_eq!(escape(&[b'a', 0xC3]), "a\\xC3");
+        assert_eq!(preview(b"\x01\x02\x03", 2), "\\x01\\x02...");
+
+        assert_eq!(byte(b'\n'), "'\\n'");
+
+        // Escape kulkee edestakaisin myös C1-ohjausmerkeillä
+        let tricky = "a\n\u{85}\"\\ä"
+            .as_bytes()
+            .iter()
+            .chain(&[0xFF])
+            .copied()
+            .collect::<Vec<u8>>();
+        assert_eq!(unescape(&escape(&tricky)).unwrap(), tricky);
+        assert_eq!(hex_ascii(b"He\n", 8), "48 65 0A  |He.|");
+        assert!(label(&[0, 1, 2, 3, 200, 201], 30).starts_with('['));
+        assert_eq!(label(b"the cat", 30), "\"the cat\"");
+    }
+}
@@ -0,0 +1,223 @@
+// src/repair.rs
+//
+// RePair: Offline-vertailukohta staattiselle korpukselle.
+//
+// Re-Pair (Larsson & Moffat 2000) näkee koko datan kerralla ja korvaa aina
+// globaalisti yleisimmän parin uudella säännöllä, kunnes mikään pari ei
+// toistu. Online-oppija (Builder) näkee datan paloina ja rajallisella
+// muistilla, joten Re-Pairin tulos on sille laatukatto: kuinka lähelle
+// samaa korpusta koodattuna päästään.
+//
+// Virta on kaksoislinkitetty lista (poistetut kohdat ohitetaan), parien
+// esiintymät pidetään listoina ja yleisin pari haetaan keosta. Kekoon ei
+// päivitetä vanhoja merkintöjä: jokainen lukumäärän muutos lisää uuden,
+// ja vanhentuneet ohitetaan nostettaessa.
+
+use crate::builder::{BOUNDARY_TOKEN, PatternBank};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+type Pair = (u32, u32);
+
+/// Re-Pairin tulos
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RePairStats {
+    /// Luodut säännöt (Combine-mallit)
+    pub rules: usize,
+    /// Korvatut parit yhteensä
+    pub replacements: usize,
+}
+
+/// Virta linkitettynä listana
+struct Sequence {
+    tokens: Vec<u32>,
+    prev: Vec<usize>,
+    next: Vec<usize>,
+    alive: Vec<bool>,
+}
+
+const NONE: usize = usize::MAX;
+
+impl Sequence {
+    fn new(tokens: Vec<u32>) -> Self {
+        let n = tokens.len();
+        Sequence {
+            prev: (0..n).map(|i| i.checked_sub(1).unwrap_or(NONE)).collect(),
+            next: (0..n)
+                .map(|i| if i + 1 < n { i + 1 } else { NONE })
+                .collect(),
+            alive: vec![true; n],
+            tokens,
+        }
+    }
+
+    /// Kohdasta alkava pari (ei rajojen yli)
+    fn pair_at(&self, i: usize) -> Option<Pair> {
+        let j = self.next[i];
+        if !self.alive[i] || j == NONE {
+            return None;
+        }
+        let pair = (self.tokens[i], self.tokens[j]);
+        (pair.0 != BOUNDARY_TOKEN && pair.1 != BOUNDARY_TOKEN).then_some(pair)
+    }
+
+    fn into_tokens(self) -> Vec<u32> {
+        self.tokens
+            .into_iter()
+            .zip(self.alive)
+            .filter_map(|(t, alive)| alive.then_some(t))
+            .collect()
+    }
+}
+
+/// Parien lukumäärät, esiintymät ja keko
+struct PairIndex {
+    counts: HashMap<Pair, usize>,
+    positions: HashMap<Pair, Vec<usize>>,
+    heap: BinaryHeap<(usize, Reverse<Pair>)>,
+}
+
+impl PairIndex {
+    fn add(&mut self, pair: Pair, pos: usize) {
+        let count = self.counts.entry(pair).or_insert(0);
+        *count += 1;
+        if *count >= 2 {
+            self.heap.push((*count, Reverse(pair)));
+        }
+        self.positions.entry(pair).or_default().push(pos);
+    }
+
+    fn remove(&mut self, pair: Pair) {
+        if let Some(count) = self.counts.get_mut(&pair) {
+            *count = count.saturating_sub(1);
+            if *count >= 2 {
+                self.heap.push((*count, Reverse(pair)));
+            }
+        }
+    }
+
+    /// Yleisin pari, jolla on vähintään kaksi esiintymää
+    fn pop_most_frequent(&mut self) -> Option<Pair> {
+        while let Some((count, Reverse(pair))) = self.heap.pop() {
+            if self.counts.get(&pair) == Some(&count) {
+                return Some(pair);
+            }
+        }
+        None
+    }
+}
+
+/// Tiivistä virta Re-Pairilla. Säännöt luodaan pankkiin (kapasiteetti
+/// kasvaa tarvittaessa) ja virta korvataan tiivistetyllä.
+pub fn compress(bank: &mut PatternBank, stream: &mut Vec<u32>) -> RePairStats {
+    let mut seq = Sequence::new(std::mem::take(stream));
+    let mut index = PairIndex {
+        counts: HashMap::new(),
+        positions: HashMap::new(),
+        heap: BinaryHeap::new(),
+    };
+    // Lukumäärät sisältävät päällekkäiset esiintymät (aaa = 2), jotta
+    // lisäys ja poisto pysyvät symmetrisinä; korvattavat rajataan erikseen
+    for i in 0..seq.tokens.len() {
+        if let Some(pair) = seq.pair_at(i) {
+            index.add(pair, i);
+        }
+    }
+
+    let mut stats = RePairStats::default();
+    while let Some(pair) = index.pop_most_frequent() {
+        // Voimassa olevat, toisiaan leikkaamattomat esiintymät
+        let mut current = index.positions.remove(&pair).unwrap_or_default();
+        current.retain(|&i| seq.pair_at(i) == Some(pair));
+        current.sort_unstable();
+        current.dedup();
+        let mut valid = Vec::with_capacity(current.len());
+        let mut blocked = NONE;
+        for &i in &current {
+            if i != blocked {
+                valid.push(i);
+                blocked = seq.next[i];
+            }
+        }
+        if valid.len() < 2 {
+            // Vain päällekkäisiä (aaa): odotetaan uutta esiintymää
+            index.positions.insert(pair, current);
+            continue;
+        }
+
+        bank.reserve(1);
+        let Some(id) = bank.create_combine(pair.0, pair.1, stats.rules as u64) else {
+            break;
+        };
+        if let Some(p) = bank.get_mut(id) {
+            p.strength = 1.0;
+            p.usage_count = valid.len() as u32;
+        }
+        stats.rules += 1;
+
+        for i in valid {
+            let j = seq.next[i];
+            let (p, n) = (seq.prev[i], seq.next[j]);
+            if p != NONE
+                && let Some(left) = seq.pair_at(p)
+            {
+                index.remove(left);
+            }
+            if let Some(right) = seq.pair_at(j) {
+                index.remove(right);
+            }
+            index.remove(pair);
+
+            seq.tokens[i] = id;
+            seq.alive[j] = false;
+            seq.next[i] = n;
+            if n != NONE {
+                seq.prev[n] = i;
+            }
+            stats.replacements += 1;
+
+            if p != NONE
+                && let Some(left) = seq.pair_at(p)
+            {
+                index.add(left, p);
+            }
+            if let Some(right) = seq.pair_at(i) {
+                index.add(right, i);
+            }
+        }
+        index.counts.remove(&pair);
+    }
+
+    *stream = seq.into_tokens();
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_no_pair_repeats_and_stream_decodes() {
+        let text = b"abcabcabcabc xyxyxy aaaaaa abcabc".to_vec();
+        let mut bank = PatternBank::new(10);
+        let mut stream = bank.seed_ids(&text);
+        stream.push(BOUNDARY_TOKEN);
+        stream.extend(bank.seed_ids(b"abcabc"));
+
+        let stats = compress(&mut bank, &mut stream);
+        assert!(stats.rules > 0);
+
+        let decoded: Vec<u8> = stream.iter().flat_map(|&t| bank.decode(t)).collect();
+        assert_eq!(decoded, [text.as_slice(), b"abcabc"].concat());
+
+        // Mikään pari ei toistu (päällekkäisiä ei lasketa)
+        let mut seen = HashSet::new();
+        for (i, w) in stream.windows(2).enumerate() {
+            if w.contains(&BOUNDARY_TOKEN) || (i > 0 && stream[i - 1] == w[0] && w[0] == w[1]) {
+                continue;
+            }
+            assert!(seen.insert((w[0], w[1])), "pari {:?} toistuu", w);
+        }
+    }
+}
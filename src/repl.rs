@@ -0,0 +1,259 @@
+// src/repl.rs
+//
+// Repl: Opetettujen aivojen vuorovaikutteinen tutkiminen.
+//
+// Aivot ladataan kerran, ja komentoja luetaan rivi kerrallaan:
+//
+//     decode 1234          mallin tiedot, sisältö ja hierarkia
+//     encode "hello world" tekstin tokenit opituilla malleilla
+//     top 20               vahvimmat mallit
+//     predict "the qu"     todennäköisin jatko (ketjutetut ennustukset)
+//     levels               mallit tasoittain
+//
+// Tekstin voi antaa lainausmerkeissä escapeineen (render-moduulin säännöt)
+// tai sellaisenaan rivin loppuna. Kun syöte ei ole pääte (putki, tiedosto),
+// kehotetta ei tulosteta, joten komennot voi ajaa myös skriptinä.
+
+use crate::builder::{BOUNDARY_TOKEN, Builder};
+use crate::i18n::tr;
+use crate::inspect;
+use crate::render;
+use crate::term::{self, Level, error, say};
+use std::io::{self, BufRead, IsTerminal, Write};
+
+/// `top`-komennon oletuspituus
+const DEFAULT_TOP: usize = 20;
+
+/// Ennustuksen enimmäispituus tokeneina
+const PREDICT_STEPS: usize = 8;
+
+/// Tulkkikomento
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    Decode(Vec<u32>),
+    Encode(Vec<u8>),
+    Top(usize),
+    Predict(Vec<u8>),
+    Levels,
+    Help,
+    Quit,
+}
+
+impl Command {
+    /// Jäsennä rivi. `Ok(None)` tyhjälle riville.
+    pub fn parse(line: &str) -> Result<Option<Self>, String> {
+        let line = line.trim();
+        if line.is_empty() {
+            return Ok(None);
+        }
+        let (name, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let rest = rest.trim();
+        let command = match name.to_ascii_lowercase().as_str() {
+            "decode" | "d" => Command::Decode(parse_ids(rest)?),
+            "encode" | "e" => Command::Encode(parse_text(rest)?),
+            "top" | "t" => Command::Top(match rest {
+                "" => DEFAULT_TOP,
+                n => n
+                    .parse()
+                    .map_err(|_| tr!("virheellinen määrä '{}'", "invalid count '{}'", n))?,
+            }),
+            "predict" | "p" => Command::Predict(parse_text(rest)?),
+            "levels" | "l" => Command::Levels,
+            "help" | "h" | "?" => Command::Help,
+            "quit" | "exit" | "q" => Command::Quit,
+            _ => {
+                return Err(tr!(
+                    "tuntematon komento '{}' (help)",
+                    "unknown command '{}' (help)",
+                    name
+                ));
+            }
+        };
+        Ok(Some(command))
+    }
+}
+
+/// Mallien ID:t (`1234` tai `P_1234`)
+fn parse_ids(rest: &str) -> Result<Vec<u32>, String> {
+    let ids: Vec<u32> = rest
+        .split_whitespace()
+        .map(|arg| {
+            arg.trim_start_matches("P_")
+                .parse()
+                .map_err(|_| tr!("virheellinen ID '{}'", "invalid ID '{}'", arg))
+        })
+        .collect::<Result<_, _>>()?;
+    if ids.is_empty() {
+        return Err(tr!("anna mallin ID", "give a pattern ID"));
+    }
+    Ok(ids)
+}
+
+/// Teksti lainausmerkeissä (escapeineen) tai sellaisenaan
+fn parse_text(rest: &str) -> Result<Vec<u8>, String> {
+    let text = match rest.strip_prefix('"') {
+        Some(quoted) => render::unescape(
+            quoted
+                .strip_suffix('"')
+                .ok_or_else(|| tr!("päättymätön merkkijono", "unterminated string"))?,
+        )?,
+        None => rest.as_bytes().to_vec(),
+    };
+    if text.is_empty() {
+        return Err(tr!("anna teksti", "give some text"));
+    }
+    Ok(text)
+}
+
+/// Todennäköisin jatko: viimeisestä tokenista ketjutetut ennustukset
+pub fn predict(builder: &Builder, prefix: &[u8]) -> Vec<u32> {
+    let bank = &builder.bank;
+    let mut continuation = Vec::new();
+    let Some(mut last) = bank
+        .encode(prefix)
+        .into_iter()
+        .rfind(|&t| t != BOUNDARY_TOKEN)
+    else {
+        return continuation;
+    };
+    while continuation.len() < PREDICT_STEPS
+        && let Some(next) = bank.predict_next(last)
+    {
+        continuation.push(next);
+        last = next;
+    }
+    continuation
+}
+
+/// Suorita komento. Palauttaa `false`, kun tulkki lopetetaan.
+pub fn execute(builder: &Builder, command: Command) -> bool {
+    let bank = &builder.bank;
+    match command {
+        Command::Decode(ids) => {
+            for id in ids {
+                if bank.get(id).is_some() {
+                    inspect::print_pattern(builder, id);
+                } else {
+                    error!(
+                        "  ❌ P_{} ei ole aivojen malli.",
+                        "  ❌ P_{} is not a pattern in the brain.", id
+                    );
+                }
+            }
+        }
+        Command::Encode(text) => {
+            let tokens: Vec<u32> = bank
+                .encode(&text)
+                .into_iter()
+                .filter(|&t| t != BOUNDARY_TOKEN)
+                .collect();
+            say!(
+                "  {} tavua → {} tokenia:",
+                "  {} bytes → {} tokens:",
+                text.len(),
+                tokens.len()
+            );
+            for &token in &tokens {
+                term::emit(
+                    Level::Info,
+                    &format!(
+                        "     P_{:<6} L{:<3} {}",
+                        token,
+                        bank.complexity(token),
+                        render::label(&bank.decode(token), render::PREVIEW_LEN)
+                    ),
+                );
+            }
+        }
+        Command::Top(limit) => {
+            let ids = inspect::select(bank, &inspect::Filter::default());
+            inspect::print_list(bank, &ids, limit);
+        }
+        Command::Predict(prefix) => {
+            let continuation = predict(builder, &prefix);
+            if continuation.is_empty() {
+                say!(
+                    "  Aivot eivät tunne jatkoa tälle.",
+                    "  The brain knows no continuation for this."
+                );
+            } else {
+                say!(
+                    "  \"{}\" → \"{}\"",
+                    "  \"{}\" → \"{}\"",
+                    render::escape(&prefix),
+                    render::escape(&bank.decode_parts(&continuation))
+                );
+            }
+        }
+        Command::Levels => inspect::print_summary(bank),
+        Command::Help => say!(
+            "  Komennot: decode <id...>, encode <teksti>, top [N], predict <alku>, levels, help, quit\n  Teksti lainausmerkeissä escapeineen (\"a\\tb\") tai sellaisenaan.",
+            "  Commands: decode <id...>, encode <text>, top [N], predict <prefix>, levels, help, quit\n  Text in quotes with escapes (\"a\\tb\") or as is."
+        ),
+        Command::Quit => return false,
+    }
+    true
+}
+
+/// Lue ja suorita komentoja syötteen loppuun tai `quit`-komentoon asti
+pub fn run(builder: &Builder, input: impl BufRead) {
+    let interactive = io::stdin().is_terminal();
+    let prompt = || {
+        if interactive {
+            print!("petri> ");
+            let _ = io::stdout().flush();
+        }
+    };
+    prompt();
+    for line in input.lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        match Command::parse(&line) {
+            Ok(Some(command)) => {
+                if !execute(builder, command) {
+                    return;
+                }
+            }
+            Ok(None) => {}
+            Err(e) => error!("  ❌ {}", "  ❌ {}", e),
+        }
+        prompt();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::PatternBank;
+
+    #[test]
+    fn test_parse_commands_and_predict() {
+        assert_eq!(
+            Command::parse("decode P_300 301"),
+            Ok(Some(Command::Decode(vec![300, 301])))
+        );
+        assert_eq!(
+            Command::parse("encode \"a\\tb\""),
+            Ok(Some(Command::Encode(b"a\tb".to_vec())))
+        );
+        assert_eq!(
+            Command::parse("p the qu"),
+            Ok(Some(Command::Predict(b"the qu".to_vec())))
+        );
+        assert_eq!(Command::parse("top"), Ok(Some(Command::Top(DEFAULT_TOP))));
+        assert_eq!(Command::parse("  "), Ok(None));
+        assert!(Command::parse("top many").is_err());
+        assert!(Command::parse("fly").is_err());
+
+        // "qu" -> "i" -> "ck": ennustukset ketjutetaan
+        let mut bank = PatternBank::new(100);
+        let qu = bank.create_combine(b'q' as u32, b'u' as u32, 0).unwrap();
+        bank.create_combine(qu, b'i' as u32, 0).unwrap();
+        let ck = bank.create_combine(b'c' as u32, b'k' as u32, 0).unwrap();
+        bank.create_combine(b'i' as u32, ck, 0).unwrap();
+        let builder = Builder::with_bank(bank);
+        let continuation = predict(&builder, b"the qu");
+        assert_eq!(builder.bank.decode_parts(&continuation), b"ick");
+    }
+}
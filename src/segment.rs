@@ -0,0 +1,250 @@
+// src/segment.rs
+//
+// Segment: Virran jäsennyspolitiikat tiivistyksessä.
+//
+// Builderin collapse yhdistää vierekkäisiä pareja vasemmalta oikealle.
+// Se ei näe kuin kaksi tokenia kerrallaan, joten aikainen pari voi estää
+// pidemmän mallin käytön: jos pankissa on "ab" ja "a"+"bc", virta "abc"
+// jää muotoon "ab" "c". Vaihtoehtoiset politiikat jäsentävät virran koko
+// pankkia vasten:
+//   - pairwise: alkuperäinen parittainen collapse (oletus),
+//   - greedy: jokaisessa kohdassa pisin (tavuina) vahva malli, jonka
+//     rakenne vastaa virran seuraavia tokeneita,
+//   - optimal: dynaaminen ohjelmointi lyhyissä ikkunoissa; ikkunan sisällä
+//     tokeneita jää vähiten mahdollinen määrä.
+//
+// Malli "vastaa" kohtaa, jos sen Combine-puun voi purkaa virran tokeneiksi:
+// token joko on alipuun malli itse tai alipuu jatkaa purkamista. Näin jo
+// tiivistyneet osat (esim. "ab") kelpaavat suurempien mallien osiksi.
+
+use crate::builder::{PatternBank, TRUTH_THRESHOLD};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Optimaalisen jäsennyksen ikkunan pituus tokeneina
+const OPTIMAL_WINDOW: usize = 64;
+
+/// Miten tunnetut mallit korvaavat virran tokeneita
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum CollapsePolicy {
+    /// Vierekkäiset parit vasemmalta oikealle (oletus)
+    #[default]
+    Pairwise,
+    /// Pisin vastaava malli kussakin kohdassa
+    Greedy,
+    /// Vähiten tokeneita ikkunoittain (dynaaminen ohjelmointi)
+    Optimal,
+}
+
+impl CollapsePolicy {
+    /// Kaikki politiikat vertailua varten
+    pub const ALL: [CollapsePolicy; 3] = [
+        CollapsePolicy::Pairwise,
+        CollapsePolicy::Greedy,
+        CollapsePolicy::Optimal,
+    ];
+
+    /// Tulkitse politiikan nimi konfiguraatiosta
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "pairwise" | "pair" | "" => Some(CollapsePolicy::Pairwise),
+            "greedy" | "longest" => Some(CollapsePolicy::Greedy),
+            "optimal" | "dp" => Some(CollapsePolicy::Optimal),
+            _ => None,
+        }
+    }
+
+    /// Nimi raportteja varten
+    pub fn name(&self) -> &'static str {
+        match self {
+            CollapsePolicy::Pairwise => "pairwise",
+            CollapsePolicy::Greedy => "greedy",
+            CollapsePolicy::Optimal => "optimal",
+        }
+    }
+}
+
+/// Vahvat Combine-mallit vasemman reunansa tokenien mukaan.
+///
+/// Malli on jokaisen vasemman haaransa solmun alla: "(ab)c" löytyy sekä
+/// tokenilla "a" että "ab". Ehdokkaat ovat pisin (tavuina) ensin.
+struct MatchIndex<'a> {
+    bank: &'a PatternBank,
+    by_first: HashMap<u32, Vec<u32>>,
+}
+
+impl<'a> MatchIndex<'a> {
+    fn new(bank: &'a PatternBank, threshold: &dyn Fn(u8) -> f64) -> Self {
+        let mut candidates: Vec<(u32, usize)> = bank
+            .iter()
+            .filter(|(_, p)| p.is_true(threshold(p.complexity)) && p.op.as_combine().is_some())
+            .map(|(&id, _)| (id, bank.pattern_length(id)))
+            .collect();
+        candidates.sort_unstable_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+
+        let mut by_first: HashMap<u32, Vec<u32>> = HashMap::new();
+        for (id, _) in candidates {
+            let mut node = id;
+            while let Some((left, _)) = bank.get(node).and_then(|p| p.op.as_combine()) {
+                by_first.entry(left).or_default().push(id);
+                node = left;
+            }
+        }
+        MatchIndex { bank, by_first }
+    }
+
+    /// Kohdassa `i` alkavat mallit ja niiden kattamat tokenit, pisin ensin
+    fn matches_at<'s>(
+        &'s self,
+        stream: &'s [u32],
+        i: usize,
+    ) -> impl Iterator<Item = (u32, usize)> + 's {
+        self.by_first
+            .get(&stream[i])
+            .into_iter()
+            .flatten()
+            .filter_map(move |&id| Some((id, self.match_len(id, stream, i)?)))
+    }
+
+    /// Kuinka monta virran tokenia malli kattaa kohdasta `i`
+    fn match_len(&self, id: u32, stream: &[u32], i: usize) -> Option<usize> {
+        let &token = stream.get(i)?;
+        if token == id {
+            return Some(1);
+        }
+        let (left, right) = self.bank.get(id)?.op.as_combine()?;
+        let left_len = self.match_len(left, stream, i)?;
+        let right_len = self.match_len(right, stream, i + left_len)?;
+        Some(left_len + right_len)
+    }
+}
+
+/// Jäsennä virta politiikan mukaan. Palauttaa uuden virran sekä käytetyt
+/// mallit ja niiden korvaamien tokenien määrät. `Pairwise` ei muuta virtaa
+/// (parittainen vaihe kuuluu kutsujalle).
+pub fn segment(
+    bank: &PatternBank,
+    stream: &[u32],
+    policy: CollapsePolicy,
+) -> (Vec<u32>, Vec<(u32, usize)>) {
+    segment_with(bank, stream, policy, &|_| TRUTH_THRESHOLD)
+}
+
+/// Kuten `segment`, mutta mallin käyttökynnys riippuu sen tasosta
+pub fn segment_with(
+    bank: &PatternBank,
+    stream: &[u32],
+    policy: CollapsePolicy,
+    threshold: &dyn Fn(u8) -> f64,
+) -> (Vec<u32>, Vec<(u32, usize)>) {
+    match policy {
+        CollapsePolicy::Pairwise => (stream.to_vec(), Vec::new()),
+        CollapsePolicy::Greedy => greedy(&MatchIndex::new(bank, threshold), stream),
+        CollapsePolicy::Optimal => optimal(&MatchIndex::new(bank, threshold), stream),
+    }
+}
+
+fn greedy(index: &MatchIndex, stream: &[u32]) -> (Vec<u32>, Vec<(u32, usize)>) {
+    let mut out = Vec::with_capacity(stream.len());
+    let mut used = Vec::new();
+    let mut i = 0;
+    while i < stream.len() {
+        match index.matches_at(stream, i).next() {
+            Some((id, len)) => {
+                out.push(id);
+                used.push((id, len));
+                i += len;
+            }
+            None => {
+                out.push(stream[i]);
+                i += 1;
+            }
+        }
+    }
+    (out, used)
+}
+
+fn optimal(index: &MatchIndex, stream: &[u32]) -> (Vec<u32>, Vec<(u32, usize)>) {
+    let mut out = Vec::with_capacity(stream.len());
+    let mut used = Vec::new();
+    for start in (0..stream.len()).step_by(OPTIMAL_WINDOW) {
+        let window = &stream[..(start + OPTIMAL_WINDOW).min(stream.len())];
+        let n = window.len() - start;
+
+        // cost[k] = vähin tokenimäärä ikkunan k ensimmäiselle tokenille,
+        // choice[k] = siihen päättyvä malli (None = token sellaisenaan)
+        let mut cost = vec![usize::MAX; n + 1];
+        let mut choice: Vec<Option<(u32, usize)>> = vec![None; n + 1];
+        cost[0] = 0;
+        for j in 0..n {
+            let next = cost[j] + 1;
+            if next < cost[j + 1] {
+                cost[j + 1] = next;
+                choice[j + 1] = None;
+            }
+            for (id, len) in index.matches_at(window, start + j) {
+                if next < cost[j + len] {
+                    cost[j + len] = next;
+                    choice[j + len] = Some((id, len));
+                }
+            }
+        }
+
+        let mut segments = Vec::with_capacity(cost[n]);
+        let mut k = n;
+        while k > 0 {
+            match choice[k] {
+                Some((id, len)) => {
+                    segments.push(id);
+                    used.push((id, len));
+                    k -= len;
+                }
+                None => {
+                    segments.push(window[start + k - 1]);
+                    k -= 1;
+                }
+            }
+        }
+        out.extend(segments.into_iter().rev());
+    }
+    (out, used)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pair(bank: &mut PatternBank, left: u32, right: u32) -> u32 {
+        bank.create_combine(left, right, 0).unwrap()
+    }
+
+    #[test]
+    fn test_policies_segment_differently() {
+        let [a, b, c, d, e, f] = [b'a', b'b', b'c', b'd', b'e', b'f'].map(|x| x as u32);
+
+        // "ab" estää parittaiselta mallin "a"+"bc"
+        let mut bank = PatternBank::new(100);
+        pair(&mut bank, a, b);
+        let bc = pair(&mut bank, b, c);
+        let abc = pair(&mut bank, a, bc);
+        let counts = CollapsePolicy::ALL.map(|p| bank.encode_with(b"abc", p));
+        assert_eq!(counts.each_ref().map(|s| s.len()), [2, 1, 1]);
+        assert_eq!(counts[1], [abc]);
+
+        // Ahne "abc" pilkkoo loput; optimaalinen löytää "ab" + "cdef"
+        let mut bank = PatternBank::new(100);
+        let ab = pair(&mut bank, a, b);
+        pair(&mut bank, ab, c);
+        let cd = pair(&mut bank, c, d);
+        let cde = pair(&mut bank, cd, e);
+        let cdef = pair(&mut bank, cde, f);
+        let streams = CollapsePolicy::ALL.map(|p| bank.encode_with(b"abcdef", p));
+        assert_eq!(streams.each_ref().map(|s| s.len()), [2, 4, 2]);
+        assert_eq!(streams[2], [ab, cdef]);
+        for stream in &streams {
+            let bytes: Vec<u8> = stream.iter().flat_map(|&t| bank.decode(t)).collect();
+            assert_eq!(bytes, b"abcdef");
+        }
+        assert_eq!(CollapsePolicy::parse("DP"), Some(CollapsePolicy::Optimal));
+    }
+}
@@ -0,0 +1,659 @@
+// src/sequitur.rs
+//
+// Sequitur: Hierarkian rakentaminen yhdellä läpikäynnillä.
+//
+// Builder kerää paritilastot koko virrasta ja luo malleja kynnyksen
+// ylittäville pareille erissä. Sequitur (Nevill-Manning & Witten 1997)
+// toimii virtaavasti: jokainen syötetty symboli käsitellään heti, ja
+// kielioppi pitää koko ajan voimassa kaksi invarianttia:
+//
+//   1. Digrammien yksikäsitteisyys: sama vierekkäinen pari ei esiinny
+//      kielioppissa kahdesti. Toinen esiintymä korvataan säännöllä.
+//   2. Sääntöjen hyödyllisyys: jokaista sääntöä käytetään vähintään
+//      kahdesti. Kerran käytetty sääntö puretaan takaisin paikalleen.
+//
+// Säännöt ovat kaksoislinkitettyjä listoja (vartijasolmu kummassakin
+// päässä), joten korvaus ja purku ovat vakioaikaisia. Säännön runko voi
+// olla pidempi kuin kaksi symbolia; PatternBankiin se kirjoitetaan
+// vasemmalle kasvavana Combine-ketjuna, jolloin raportit, kielioppivienti
+// ja tallennus toimivat kuten Builderilla.
+
+use crate::builder::{BOUNDARY_TOKEN, Builder, BuilderStats, PatternBank};
+use crate::term::say;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Juurisääntö: koko syötetty virta
+const ROOT: u32 = 0;
+
+/// Linkittämätön solmu
+const NIL: usize = usize::MAX;
+
+/// Kieliopin symboli
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Symbol {
+    /// Pankin siemen-ID (literaali tai leksemi)
+    Term(u32),
+    /// Viittaus sääntöön
+    Rule(u32),
+    /// Tietueraja: ei muodosta digrammeja
+    Boundary,
+}
+
+struct Node {
+    symbol: Symbol,
+    /// Vartijasolmu: säännön alku ja loppu (symbol = Rule(oma id))
+    guard: bool,
+    prev: usize,
+    next: usize,
+}
+
+struct RuleEntry {
+    guard: usize,
+    uses: usize,
+}
+
+/// Sequiturin kielioppi
+pub struct Grammar {
+    nodes: Vec<Node>,
+    free: Vec<usize>,
+    rules: HashMap<u32, RuleEntry>,
+    digrams: HashMap<(Symbol, Symbol), usize>,
+    next_rule: u32,
+    /// Luodut ja puretut säännöt sekä korvaukset (tilastoja varten)
+    created: usize,
+    expanded: usize,
+    substitutions: usize,
+}
+
+impl Default for Grammar {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Grammar {
+    pub fn new() -> Self {
+        let mut grammar = Grammar {
+            nodes: Vec::new(),
+            free: Vec::new(),
+            rules: HashMap::new(),
+            digrams: HashMap::new(),
+            next_rule: ROOT,
+            created: 0,
+            expanded: 0,
+            substitutions: 0,
+        };
+        grammar.new_rule();
+        grammar
+    }
+
+    /// Lisää symboli juuren loppuun ja palauta invariantit voimaan
+    pub fn push(&mut self, symbol: Symbol) {
+        let node = self.alloc(symbol, false);
+        let last = self.last(ROOT);
+        self.insert_after(last, node);
+        let prev = self.nodes[node].prev;
+        if !self.nodes[prev].guard {
+            self.check(prev);
+        }
+    }
+
+    /// Juuren viimeinen symboli
+    pub fn last_symbol(&self) -> Option<Symbol> {
+        let last = self.last(ROOT);
+        (!self.nodes[last].guard).then(|| self.nodes[last].symbol)
+    }
+
+    /// Säännön runko
+    pub fn body(&self, rule: u32) -> Vec<Symbol> {
+        let guard = self.rules[&rule].guard;
+        let mut symbols = Vec::new();
+        let mut node = self.nodes[guard].next;
+        while node != guard {
+            symbols.push(self.nodes[node].symbol);
+            node = self.nodes[node].next;
+        }
+        symbols
+    }
+
+    /// Säännöt (ei juurta) nousevassa järjestyksessä
+    pub fn rule_ids(&self) -> Vec<u32> {
+        let mut ids: Vec<u32> = self.rules.keys().copied().filter(|&r| r != ROOT).collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    pub fn uses(&self, rule: u32) -> usize {
+        self.rules.get(&rule).map_or(0, |r| r.uses)
+    }
+
+    /// Sääntöjen määrä (ei juurta)
+    pub fn rule_count(&self) -> usize {
+        self.rules.len() - 1
+    }
+
+    // --- Solmut ja linkitys -------------------------------------------------
+
+    fn alloc(&mut self, symbol: Symbol, guard: bool) -> usize {
+        if !guard && let Symbol::Rule(r) = symbol {
+            self.rules.get_mut(&r).expect("tuntematon sääntö").uses += 1;
+        }
+        let node = Node {
+            symbol,
+            guard,
+            prev: NIL,
+            next: NIL,
+        };
+        match self.free.pop() {
+            Some(i) => {
+                self.nodes[i] = node;
+                i
+            }
+            None => {
+                self.nodes.push(node);
+                self.nodes.len() - 1
+            }
+        }
+    }
+
+    fn new_rule(&mut self) -> u32 {
+        let id = self.next_rule;
+        self.next_rule += 1;
+        let guard = self.alloc(Symbol::Rule(id), true);
+        self.nodes[guard].prev = guard;
+        self.nodes[guard].next = guard;
+        self.rules.insert(id, RuleEntry { guard, uses: 0 });
+        id
+    }
+
+    fn first(&self, rule: u32) -> usize {
+        self.nodes[self.rules[&rule].guard].next
+    }
+
+    fn last(&self, rule: u32) -> usize {
+        self.nodes[self.rules[&rule].guard].prev
+    }
+
+    /// Digrammi, joka alkaa solmusta (ei vartijoiden eikä rajojen yli)
+    fn key(&self, node: usize) -> Option<(Symbol, Symbol)> {
+        let next = self.nodes[node].next;
+        if next == NIL || self.nodes[node].guard || self.nodes[next].guard {
+            return None;
+        }
+        let (a, b) = (self.nodes[node].symbol, self.nodes[next].symbol);
+        (a != Symbol::Boundary && b != Symbol::Boundary).then_some((a, b))
+    }
+
+    /// Sama symboli kolmessa peräkkäisessä solmussa (aaa): päällekkäiset
+    /// digrammit, joista vain ensimmäinen on taulussa
+    fn is_triple(&self, node: usize) -> bool {
+        let (prev, next) = (self.nodes[node].prev, self.nodes[node].next);
+        prev != NIL
+            && next != NIL
+            && ![prev, node, next].iter().any(|&n| self.nodes[n].guard)
+            && self.nodes[prev].symbol == self.nodes[node].symbol
+            && self.nodes[next].symbol == self.nodes[node].symbol
+    }
+
+    fn delete_digram(&mut self, node: usize) {
+        if let Some(key) = self.key(node)
+            && self.digrams.get(&key) == Some(&node)
+        {
+            self.digrams.remove(&key);
+        }
+    }
+
+    fn join(&mut self, left: usize, right: usize) {
+        if self.nodes[left].next != NIL {
+            self.delete_digram(left);
+            // Päällekkäisen digrammin toinen esiintymä jää muuten taulusta
+            if self.is_triple(right)
+                && let Some(key) = self.key(right)
+            {
+                self.digrams.insert(key, right);
+            }
+            let left_prev = self.nodes[left].prev;
+            if self.is_triple(left)
+                && let Some(key) = self.key(left_prev)
+            {
+                self.digrams.insert(key, left_prev);
+            }
+        }
+        self.nodes[left].next = right;
+        self.nodes[right].prev = left;
+    }
+
+    fn insert_after(&mut self, at: usize, node: usize) {
+        let next = self.nodes[at].next;
+        self.join(node, next);
+        self.join(at, node);
+    }
+
+    /// Poista solmu listasta ja vapauta se
+    fn remove_node(&mut self, node: usize) {
+        let (prev, next) = (self.nodes[node].prev, self.nodes[node].next);
+        self.join(prev, next);
+        self.delete_digram(node);
+        if let Symbol::Rule(r) = self.nodes[node].symbol
+            && let Some(rule) = self.rules.get_mut(&r)
+        {
+            rule.uses -= 1;
+        }
+        self.free.push(node);
+    }
+
+    // --- Invariantit --------------------------------------------------------
+
+    /// Tarkista solmusta alkava digrammi. Palauttaa true, jos se oli jo
+    /// kieliopissa (ja korvattiin säännöllä).
+    fn check(&mut self, node: usize) -> bool {
+        let Some(key) = self.key(node) else {
+            return false;
+        };
+        match self.digrams.get(&key).copied() {
+            None => {
+                self.digrams.insert(key, node);
+                false
+            }
+            Some(found) if found == node => false,
+            Some(found) => {
+                // Päällekkäiset esiintymät (aaa) eivät ole toisto
+                if self.nodes[found].next != node {
+                    self.match_digram(node, found);
+                }
+                true
+            }
+        }
+    }
+
+    /// Digrammi esiintyy solmuissa `new` ja `found`: korvaa molemmat säännöllä
+    fn match_digram(&mut self, new: usize, found: usize) {
+        let found_prev = self.nodes[found].prev;
+        let found_end = self.nodes[self.nodes[found].next].next;
+
+        let rule = if self.nodes[found_prev].guard && self.nodes[found_end].guard {
+            // Esiintymä on jo kokonainen sääntö
+            let Symbol::Rule(rule) = self.nodes[found_prev].symbol else {
+                unreachable!("vartijan symboli on aina sääntö");
+            };
+            self.substitute(new, rule);
+            rule
+        } else {
+            let rule = self.new_rule();
+            self.created += 1;
+            let (a, b) = (
+                self.nodes[new].symbol,
+                self.nodes[self.nodes[new].next].symbol,
+            );
+            for symbol in [a, b] {
+                let node = self.alloc(symbol, false);
+                let last = self.last(rule);
+                self.insert_after(last, node);
+            }
+            self.substitute(found, rule);
+            self.substitute(new, rule);
+            let first = self.first(rule);
+            if let Some(key) = self.key(first) {
+                self.digrams.insert(key, first);
+            }
+            rule
+        };
+
+        // Hyödyllisyys: korvaus on voinut jättää alisäännön yhteen käyttöön
+        let first = self.first(rule);
+        if let Symbol::Rule(inner) = self.nodes[first].symbol
+            && self.uses(inner) == 1
+        {
+            self.expand(first);
+        }
+    }
+
+    /// Korvaa solmusta alkava digrammi viittauksella sääntöön
+    fn substitute(&mut self, node: usize, rule: u32) {
+        self.substitutions += 1;
+        let prev = self.nodes[node].prev;
+        for _ in 0..2 {
+            let next = self.nodes[prev].next;
+            self.remove_node(next);
+        }
+        let reference = self.alloc(Symbol::Rule(rule), false);
+        self.insert_after(prev, reference);
+        if !self.check(prev) {
+            let next = self.nodes[prev].next;
+            self.check(next);
+        }
+    }
+
+    /// Pura kerran käytetty sääntö viittauksensa paikalle
+    fn expand(&mut self, node: usize) {
+        let Symbol::Rule(rule) = self.nodes[node].symbol else {
+            return;
+        };
+        self.expanded += 1;
+        let (left, right) = (self.nodes[node].prev, self.nodes[node].next);
+        let (first, last) = (self.first(rule), self.last(rule));
+        let guard = self.rules[&rule].guard;
+
+        self.delete_digram(node);
+        self.rules.remove(&rule);
+        self.join(left, first);
+        self.join(last, right);
+        if let Some(key) = self.key(last) {
+            self.digrams.insert(key, last);
+        }
+        self.free.push(node);
+        self.free.push(guard);
+    }
+}
+
+/// Kieliopin sarjallistettava muoto (istuntotiedosto)
+#[derive(Serialize, Deserialize)]
+pub struct GrammarState {
+    pub root: Vec<Symbol>,
+    pub rules: Vec<(u32, Vec<Symbol>)>,
+    pub next_rule: u32,
+}
+
+impl Grammar {
+    pub fn state(&self) -> GrammarState {
+        GrammarState {
+            root: self.body(ROOT),
+            rules: self
+                .rule_ids()
+                .into_iter()
+                .map(|r| (r, self.body(r)))
+                .collect(),
+            next_rule: self.next_rule,
+        }
+    }
+
+    /// Rakenna kielioppi tilasta. Invariantit ovat jo voimassa, joten
+    /// digrammit vain rekisteröidään uudelleen.
+    pub fn from_state(state: GrammarState) -> Self {
+        let mut grammar = Grammar::new();
+        for &(id, _) in &state.rules {
+            let guard = grammar.alloc(Symbol::Rule(id), true);
+            grammar.nodes[guard].prev = guard;
+            grammar.nodes[guard].next = guard;
+            grammar.rules.insert(id, RuleEntry { guard, uses: 0 });
+        }
+        grammar.next_rule = state.next_rule;
+
+        let bodies = std::iter::once((ROOT, state.root)).chain(state.rules);
+        for (id, body) in bodies {
+            for symbol in body {
+                let node = grammar.alloc(symbol, false);
+                let last = grammar.last(id);
+                // Linkitys ilman join-kirjanpitoa: taulu täytetään lopuksi
+                let guard = grammar.rules[&id].guard;
+                grammar.nodes[last].next = node;
+                grammar.nodes[node].prev = last;
+                grammar.nodes[node].next = guard;
+                grammar.nodes[guard].prev = node;
+            }
+            let guard = grammar.rules[&id].guard;
+            let mut node = grammar.nodes[guard].next;
+            while node != guard {
+                if let Some(key) = grammar.key(node) {
+                    grammar.digrams.entry(key).or_insert(node);
+                }
+                node = grammar.nodes[node].next;
+            }
+        }
+        grammar
+    }
+}
+
+/// Sequitur-moottori: kielioppi sekä sen näkymä PatternBankina
+pub struct Sequitur {
+    grammar: Grammar,
+    /// Literaalit, luokat ja leksemit (siemen-ID:t)
+    base: PatternBank,
+    /// Kielioppi Combine-ketjuina ja juuri token-virtana; päivitetään
+    /// syklin lopussa, jotta raportit ja tuttuus toimivat kuten Builderilla
+    pub(crate) view: Builder,
+    /// Syklin aikana syötetyt symbolit
+    pending: usize,
+    dirty: bool,
+}
+
+impl Sequitur {
+    pub fn new(base: PatternBank) -> Self {
+        Self::with_grammar(base, Grammar::new(), 0)
+    }
+
+    pub fn with_grammar(base: PatternBank, grammar: Grammar, cycle: u64) -> Self {
+        let mut sequitur = Sequitur {
+            grammar,
+            view: Builder::with_bank(base.clone()),
+            base,
+            pending: 0,
+            dirty: true,
+        };
+        sequitur.view.cycle = cycle;
+        sequitur.refresh();
+        sequitur
+    }
+
+    pub fn grammar(&self) -> &Grammar {
+        &self.grammar
+    }
+
+    pub fn base(&self) -> &PatternBank {
+        &self.base
+    }
+
+    pub fn feed(&mut self, data: &[u8]) {
+        for id in self.base.seed_ids(data) {
+            self.grammar.push(Symbol::Term(id));
+            self.pending += 1;
+        }
+        self.dirty = true;
+    }
+
+    /// Tietueraja (ei peräkkäisiä eikä virran alkuun)
+    pub fn push_boundary(&mut self) {
+        if self
+            .grammar
+            .last_symbol()
+            .is_some_and(|s| s != Symbol::Boundary)
+        {
+            self.grammar.push(Symbol::Boundary);
+            self.dirty = true;
+        }
+    }
+
+    /// Päivitä näkymä ja palauta syklin tilastot. Kielioppi on jo ajan
+    /// tasalla (työ tehdään syötettäessä), joten `explore` ei vaikuta.
+    pub fn step(&mut self) -> BuilderStats {
+        self.view.cycle += 1;
+        let stream_before = self.view.token_stream.len() + self.pending;
+        let patterns_before = self.view.bank.combine_count();
+        let (created, expanded, substitutions) = (
+            self.grammar.created,
+            self.grammar.expanded,
+            self.grammar.substitutions,
+        );
+        self.refresh();
+        self.pending = 0;
+        self.grammar.created = 0;
+        self.grammar.expanded = 0;
+        self.grammar.substitutions = 0;
+
+        let stream_after = self.view.token_stream.len();
+        BuilderStats {
+            cycle: self.view.cycle,
+            stream_before,
+            stream_after,
+            patterns_created: created,
+            patterns_collapsed: substitutions,
+            patterns_forgotten: expanded,
+            patterns_merged: 0,
+            patterns_oscillated: 0,
+            patterns_held: 0,
+            collapse_rejected: 0,
+            patterns_total: self.view.bank.combine_count(),
+            compression_ratio: if stream_before > 0 {
+                1.0 - (stream_after as f64 / stream_before as f64)
+            } else {
+                0.0
+            },
+            patterns_before,
+            familiarity: self.view.familiarity_profile(),
+        }
+    }
+
+    /// Kirjoita säännöt pankkiin Combine-ketjuina ja juuri virraksi
+    fn refresh(&mut self) {
+        if !self.dirty {
+            return;
+        }
+        let rules = self.grammar.rule_ids();
+        let mut bank = self.base.clone();
+        bank.reserve(rules.iter().map(|&r| self.grammar.body(r).len()).sum());
+
+        let mut ids: HashMap<u32, u32> = HashMap::new();
+        for &rule in &rules {
+            // Alisäännöt ensin (eksplisiittinen pino: sisäkkäisyys voi olla syvä)
+            let mut stack = vec![rule];
+            while let Some(&top) = stack.last() {
+                if ids.contains_key(&top) {
+                    stack.pop();
+                    continue;
+                }
+                let body = self.grammar.body(top);
+                let missing: Vec<u32> = body
+                    .iter()
+                    .filter_map(|s| match s {
+                        Symbol::Rule(r) if !ids.contains_key(r) => Some(*r),
+                        _ => None,
+                    })
+                    .collect();
+                if !missing.is_empty() {
+                    stack.extend(missing);
+                    continue;
+                }
+                let id = self.chain(&mut bank, &body, &ids, self.grammar.uses(top));
+                ids.insert(top, id);
+                stack.pop();
+            }
+        }
+
+        self.view.token_stream = self
+            .grammar
+            .body(ROOT)
+            .into_iter()
+            .map(|s| match s {
+                Symbol::Term(id) => id,
+                Symbol::Rule(r) => ids[&r],
+                Symbol::Boundary => BOUNDARY_TOKEN,
+            })
+            .collect();
+        self.view.bank = bank;
+        self.dirty = false;
+    }
+
+    /// Säännön runko vasemmalle kasvavana ketjuna
+    fn chain(
+        &self,
+        bank: &mut PatternBank,
+        body: &[Symbol],
+        ids: &HashMap<u32, u32>,
+        uses: usize,
+    ) -> u32 {
+        let mut tokens = body.iter().map(|s| match s {
+            Symbol::Term(id) => *id,
+            Symbol::Rule(r) => ids[r],
+            Symbol::Boundary => unreachable!("raja ei ole koskaan säännössä"),
+        });
+        let mut acc = tokens.next().expect("säännössä on aina symboleja");
+        for token in tokens {
+            acc = bank
+                .create_combine(acc, token, self.view.cycle)
+                .expect("kapasiteetti varattu etukäteen");
+            if let Some(p) = bank.get_mut(acc) {
+                p.strength = 1.0;
+            }
+        }
+        if let Some(p) = bank.get_mut(acc) {
+            p.usage_count = p.usage_count.max(uses as u32);
+        }
+        acc
+    }
+
+    pub fn print_model(&self, top_n: usize) {
+        say!(
+            "\n  📜 Sequitur-kielioppi: {} sääntöä, juuressa {} symbolia",
+            "\n  📜 Sequitur grammar: {} rules, {} symbols in root",
+            self.grammar.rule_count(),
+            self.view.token_stream.len()
+        );
+        self.view.print_top_patterns(top_n);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    /// Pura sääntö tavuiksi suoraan kieliopista
+    fn expand_all(grammar: &Grammar, rule: u32, bank: &PatternBank) -> Vec<u8> {
+        grammar
+            .body(rule)
+            .into_iter()
+            .flat_map(|s| match s {
+                Symbol::Term(id) => bank.decode(id),
+                Symbol::Rule(r) => expand_all(grammar, r, bank),
+                Symbol::Boundary => Vec::new(),
+            })
+            .collect()
+    }
+
+    fn assert_invariants(grammar: &Grammar) {
+        let mut seen = HashSet::new();
+        for rule in std::iter::once(ROOT).chain(grammar.rule_ids()) {
+            let body = grammar.body(rule);
+            if rule != ROOT {
+                assert!(body.len() >= 2, "sääntö {} liian lyhyt", rule);
+                assert!(grammar.uses(rule) >= 2, "sääntöä {} käytetään kerran", rule);
+            }
+            for (i, pair) in body.windows(2).enumerate() {
+                if pair.contains(&Symbol::Boundary) {
+                    continue;
+                }
+                // Päällekkäinen aaa sallitaan
+                let overlapping = i > 0 && body[i - 1] == pair[0] && pair[0] == pair[1];
+                assert!(
+                    seen.insert((pair[0], pair[1])) || overlapping,
+                    "digrammi {:?} toistuu",
+                    pair
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_invariants_hold_and_stream_decodes() {
+        let text = b"abcdbcabcdbc the cat sat on the mat, the cat sat on the hat. aaaaaaaa";
+        let mut sequitur = Sequitur::new(PatternBank::new(100));
+        sequitur.feed(&text[..30]);
+        sequitur.push_boundary();
+        sequitur.feed(&text[30..]);
+        sequitur.step();
+
+        assert_invariants(sequitur.grammar());
+        assert!(sequitur.grammar().rule_count() > 0);
+        assert_eq!(expand_all(sequitur.grammar(), ROOT, sequitur.base()), text);
+        assert_eq!(sequitur.view.decode_stream(), text);
+
+        // Tallennettu tila palautuu samaksi virraksi
+        let restored = Sequitur::with_grammar(
+            sequitur.base().clone(),
+            Grammar::from_state(sequitur.grammar().state()),
+            1,
+        );
+        assert_eq!(restored.view.token_stream, sequitur.view.token_stream);
+    }
+}
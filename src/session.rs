@@ -0,0 +1,117 @@
+// src/session.rs
+//
+// Session (Istunto): Koko ajon tila yhdessä tiedostossa.
+//
+// Pelkät aivot + kirjanmerkki eivät riitä tarkkaan jatkamiseen: token-virta,
+// säätimen tila ja konfiguraatio katoavat, jolloin "jatkettu" ajo on
+// todellisuudessa eri ajo. `.petri-session` sisältää kaiken tarvittavan,
+// ja se kirjoitetaan atomisesti (väliaikaistiedosto + rename), jotta
+// keskeytetty tallennus ei koskaan jätä rikkinäistä istuntoa.
+//
+// Paritilastot lasketaan aina uudelleen token-virrasta, joten niitä ei
+// tarvitse tallentaa erikseen. Raportoinnin tila (Markov-vertailumalli,
+// tuttuuskartta, pysäytysehtojen eteneminen) tallentuu mukana: muuten
+// jatketun ajon CSV ja loppuraportti poikkeaisivat keskeyttämättömästä.
+// Liukuluvut kirjoitetaan tarkasti (serde_json `float_roundtrip`).
+//
+// Token-virta on istunnon suurin osa: kymmenien miljoonien tokenien virta
+// JSON-lukulistana hallitsisi tiedoston kokoa ja kirjoitusaikaa. Virta
+// pakataan (ks. engine::packed_stream): jokainen ajo (sama token peräkkäin)
+// kirjoitetaan varinttina `id << 1 | toistuu`, ja toistuvan ajon perään
+// sen pituus - 1. Tavut tallennetaan Base64-merkkijonona. Vanhat
+// istunnot, joissa virta on lukulista, latautuvat edelleen.
+
+use crate::Config;
+use crate::atomic;
+use crate::drift::DriftDetector;
+use crate::engine::EngineState;
+use crate::feeder::FeederState;
+use crate::heatmap::FamiliarityMap;
+use crate::i18n::tr;
+use crate::markov::MarkovBaseline;
+use crate::stop::{StopReason, StopState};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{self, BufReader};
+use std::path::Path;
+
+/// Istuntotiedoston muodon versio
+const SESSION_VERSION: u32 = 1;
+
+/// Adaptiivisen säätimen (pääsilmukan) tila
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct RegulatorState {
+    pub cycle: usize,
+    pub last_stream_len: usize,
+    pub stagnant_cycles: usize,
+    /// Saapuvan datan muutoksen seuranta
+    #[serde(default)]
+    pub drift: DriftDetector,
+    /// Pysäytysehtojen eteneminen
+    #[serde(default)]
+    pub stop: StopState,
+}
+
+/// Tallennettu istunto
+#[derive(Serialize, Deserialize)]
+pub struct Session {
+    pub version: u32,
+    pub config: Config,
+    pub engine: EngineState,
+    pub feeder: FeederState,
+    pub regulator: RegulatorState,
+    /// Vertailumalli ja tuttuuskartta (vanhoissa istunnoissa tyhjät)
+    #[serde(default)]
+    pub markov: MarkovBaseline,
+    #[serde(default)]
+    pub heatmap: FamiliarityMap,
+    /// Miksi ajo pysähtyi (None = välitallennus kesken ajon)
+    #[serde(default)]
+    pub stop_reason: Option<StopReason>,
+}
+
+impl Session {
+    pub fn new(
+        config: Config,
+        engine: EngineState,
+        feeder: FeederState,
+        regulator: RegulatorState,
+        markov: MarkovBaseline,
+        heatmap: FamiliarityMap,
+    ) -> Self {
+        Session {
+            version: SESSION_VERSION,
+            config,
+            engine,
+            feeder,
+            regulator,
+            markov,
+            heatmap,
+            stop_reason: None,
+        }
+    }
+
+    /// Kirjoita istunto atomisesti
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        atomic::write_atomic(path, |writer| {
+            serde_json::to_writer(writer, self).map_err(io::Error::other)
+        })
+    }
+
+    /// Lue istunto tiedostosta
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let reader = BufReader::new(File::open(path)?);
+        let session: Session = serde_json::from_reader(reader).map_err(io::Error::other)?;
+        if session.version != SESSION_VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                tr!(
+                    "tuntematon istuntoversio {}",
+                    "unknown session version {}",
+                    session.version
+                ),
+            ));
+        }
+        Ok(session)
+    }
+}
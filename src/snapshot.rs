@@ -0,0 +1,214 @@
+// src/snapshot.rs
+//
+// Snapshot: Pankin kevyet tilannekuvat ajon aikana ("aikamatkustus").
+//
+// Kun oppiminen junnaa, loppuaivoista ei näe, miksi: syntyikö malli ja
+// unohtuiko se, vai eikö sen vahvuus koskaan noussut totuuskynnyksen yli?
+// Tilannekuvaan tallennetaan N syklin välein jokaisen opitun mallin ID,
+// vahvuus ja käyttökerrat sekä virran pituus. Kuvat kirjoitetaan yhteen
+// ajotiedostoon JSON-riveinä, joten tiedosto kasvaa vain lopusta ja
+// keskeytynytkin ajo on luettavissa.
+//
+// `history`-komento näyttää yhden mallin kehityksen ajon yli tai, ilman
+// ID:tä, koko pankin yhteenvedon tilannekuvittain.
+
+use crate::builder::PatternBank;
+use crate::term::{self, Level, say};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+/// Oletusväli sykleinä
+pub const DEFAULT_EVERY: usize = 10;
+
+/// Vahvuuspalkin leveys merkkeinä
+const BAR_WIDTH: usize = 20;
+
+/// Yksi tilannekuva
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub cycle: usize,
+    pub stream_len: usize,
+    /// (ID, vahvuus, käyttökerrat) ID-järjestyksessä; vain opitut mallit
+    pub patterns: Vec<(u32, f32, u32)>,
+}
+
+impl Snapshot {
+    pub fn capture(bank: &PatternBank, cycle: usize, stream_len: usize) -> Self {
+        let mut patterns: Vec<(u32, f32, u32)> = bank
+            .learned()
+            .map(|(&id, p)| (id, p.strength as f32, p.usage_count))
+            .collect();
+        patterns.sort_unstable_by_key(|p| p.0);
+        Snapshot {
+            cycle,
+            stream_len,
+            patterns,
+        }
+    }
+
+    /// Mallin (vahvuus, käyttökerrat) tässä kuvassa
+    pub fn get(&self, id: u32) -> Option<(f32, u32)> {
+        self.patterns
+            .binary_search_by_key(&id, |p| p.0)
+            .ok()
+            .map(|i| (self.patterns[i].1, self.patterns[i].2))
+    }
+}
+
+/// Tilannekuvien kirjoitus ajotiedostoon N syklin välein
+pub struct SnapshotWriter {
+    out: BufWriter<File>,
+    every: usize,
+}
+
+impl SnapshotWriter {
+    /// Avaa ajotiedosto; jatketussa istunnossa kirjoitetaan perään
+    pub fn create(path: &Path, every: usize, append: bool) -> io::Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(append)
+            .truncate(!append)
+            .open(path)?;
+        Ok(SnapshotWriter {
+            out: BufWriter::new(file),
+            every: every.max(1),
+        })
+    }
+
+    /// Kirjaa kuva, jos sykli on välin monikerta. Palauttaa, kirjattiinko.
+    pub fn record(
+        &mut self,
+        bank: &PatternBank,
+        cycle: usize,
+        stream_len: usize,
+    ) -> io::Result<bool> {
+        if !cycle.is_multiple_of(self.every) {
+            return Ok(false);
+        }
+        let snapshot = Snapshot::capture(bank, cycle, stream_len);
+        serde_json::to_writer(&mut self.out, &snapshot).map_err(io::Error::other)?;
+        self.out.write_all(b"\n")?;
+        self.out.flush()?;
+        Ok(true)
+    }
+}
+
+/// Lue ajotiedoston kuvat. Keskeneräinen viimeinen rivi ohitetaan.
+pub fn load(path: &Path) -> io::Result<Vec<Snapshot>> {
+    Ok(fs::read_to_string(path)?
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+/// Tulosta yhden mallin kehitys ajon yli
+pub fn print_history(snapshots: &[Snapshot], id: u32) {
+    let present: Vec<&Snapshot> = snapshots.iter().filter(|s| s.get(id).is_some()).collect();
+    let (Some(first), Some(last)) = (present.first(), present.last()) else {
+        say!(
+            "\n  P_{}: ei yhdessäkään tilannekuvassa.",
+            "\n  P_{}: not in any snapshot.",
+            id
+        );
+        return;
+    };
+    say!(
+        "\n  🕰️  P_{}: nähty sykleillä {}-{} ({}/{} kuvassa)",
+        "\n  🕰️  P_{}: seen in cycles {}-{} ({}/{} snapshots)",
+        id,
+        first.cycle,
+        last.cycle,
+        present.len(),
+        snapshots.len()
+    );
+    for snapshot in snapshots {
+        let line = match snapshot.get(id) {
+            Some((strength, usage)) => {
+                let filled = (strength.clamp(0.0, 1.0) * BAR_WIDTH as f32).round() as usize;
+                format!(
+                    "     {:>7} {:>9}  {:.2} {:<width$}  {}",
+                    snapshot.cycle,
+                    snapshot.stream_len,
+                    strength,
+                    "#".repeat(filled),
+                    usage,
+                    width = BAR_WIDTH
+                )
+            }
+            None => format!("     {:>7} {:>9}  -", snapshot.cycle, snapshot.stream_len),
+        };
+        term::emit(Level::Info, &line);
+    }
+    if let Some(after) = snapshots.iter().find(|s| s.cycle > last.cycle) {
+        say!(
+            "  🗑️  Unohdettu syklien {} ja {} välillä.",
+            "  🗑️  Forgotten between cycles {} and {}.",
+            last.cycle,
+            after.cycle
+        );
+    }
+}
+
+/// Tulosta koko pankin yhteenveto kuvittain
+pub fn print_overview(snapshots: &[Snapshot]) {
+    say!(
+        "\n  🕰️  {} tilannekuvaa (sykli, virta, malleja, keskivahvuus):",
+        "\n  🕰️  {} snapshots (cycle, stream, patterns, mean strength):",
+        snapshots.len()
+    );
+    for s in snapshots {
+        let mean = if s.patterns.is_empty() {
+            0.0
+        } else {
+            s.patterns.iter().map(|p| p.1 as f64).sum::<f64>() / s.patterns.len() as f64
+        };
+        term::emit(
+            Level::Info,
+            &format!(
+                "     {:>7} {:>9} {:>7}  {:.3}",
+                s.cycle,
+                s.stream_len,
+                s.patterns.len(),
+                mean
+            ),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::Builder;
+
+    #[test]
+    fn test_snapshots_written_and_tracked() {
+        let path = std::env::temp_dir().join(format!("petri_snap_{}.jsonl", std::process::id()));
+        let mut builder = Builder::new(100);
+        builder.tokenize(&b"abab".repeat(20));
+
+        let mut writer = SnapshotWriter::create(&path, 2, false).unwrap();
+        for cycle in 1..=4 {
+            builder.live();
+            writer
+                .record(&builder.bank, cycle, builder.token_stream.len())
+                .unwrap();
+        }
+        drop(writer);
+
+        let snapshots = load(&path).unwrap();
+        assert_eq!(
+            snapshots.iter().map(|s| s.cycle).collect::<Vec<_>>(),
+            [2, 4]
+        );
+        let ab = builder.bank.get_pair_id(97, 98).unwrap();
+        let (strength, usage) = snapshots[1].get(ab).unwrap();
+        assert_eq!(strength, builder.bank.get(ab).unwrap().strength as f32);
+        assert!(usage > 0);
+        assert_eq!(snapshots[0].get(97), None);
+
+        fs::remove_file(&path).ok();
+    }
+}
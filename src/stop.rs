@@ -0,0 +1,251 @@
+// src/stop.rs
+//
+// Stop: Tavoitteisiin perustuvat pysäytysehdot.
+//
+// Syklien enimmäismäärä on huono pysäytysehto: sopiva arvo riippuu
+// korpuksesta. Ajo voidaan pysäyttää myös, kun
+//   - tiivistyssuhde saavuttaa tavoitteen,
+//   - erillisen holdout-joukon bittiä/tavu laskee tavoitteeseen,
+//   - pankin koko on pysynyt vakaana N sykliä (oppiminen on asettunut), tai
+//   - syötetyn datan tavubudjetti on käytetty.
+//
+// Ajosilmukka kysyy ehtoja joka sykli; pysäytyksen syy tallennetaan
+// istuntoon ja tulostetaan loppuraporttiin.
+
+use crate::engine::Engine;
+use crate::entropy::Coder;
+use crate::i18n::tr;
+use crate::term::say;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+
+/// Holdout-joukon koodaus on kallis: arvioidaan tämän syklimäärän välein
+const HOLDOUT_CHECK_INTERVAL: usize = 10;
+
+/// Pankki on "vakaa", kun koko pysyy tämän suhteellisen vaihtelun sisällä
+const STABLE_TOLERANCE: f64 = 0.01;
+
+/// Holdout-joukosta käytetään enintään näin monta tavua
+const HOLDOUT_MAX_BYTES: usize = 64 * 1024;
+
+/// Pysäytysehdot (None = ehto ei käytössä)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StopPolicy {
+    /// Tiivistyssuhde (0.0 - 1.0)
+    pub target_ratio: Option<f64>,
+    /// Holdout-joukon bittiä/tavu (vaatii `--holdout`-kansion)
+    pub target_holdout_bpb: Option<f64>,
+    /// Montako sykliä pankin koon pitää pysyä vakaana
+    pub stable_cycles: Option<usize>,
+    /// Syötettyjen tavujen budjetti
+    pub byte_budget: Option<usize>,
+}
+
+/// Miksi ajo pysähtyi
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum StopReason {
+    MaxCycles,
+    /// Data loppui ja oppiminen saturoitui
+    Depleted,
+    Error(String),
+    TargetRatio(f64),
+    TargetHoldoutBpb(f64),
+    BankStable(usize),
+    ByteBudget(usize),
+    /// Käyttäjä keskeytti (Ctrl-C tai SIGTERM)
+    Interrupted,
+}
+
+impl fmt::Display for StopReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = match self {
+            StopReason::MaxCycles => tr!("syklien maksimiraja", "maximum cycle limit"),
+            StopReason::Depleted => tr!("kaikki data käsitelty", "all data processed"),
+            StopReason::Error(e) => tr!("virhe: {}", "error: {}", e),
+            StopReason::TargetRatio(r) => tr!(
+                "tiivistyssuhde {:.1}%",
+                "compression ratio {:.1}%",
+                r * 100.0
+            ),
+            StopReason::TargetHoldoutBpb(b) => {
+                tr!("holdout {:.3} bittiä/tavu", "holdout {:.3} bits/byte", b)
+            }
+            StopReason::BankStable(n) => {
+                tr!("pankki vakaa {} sykliä", "bank stable for {} cycles", n)
+            }
+            StopReason::ByteBudget(n) => tr!("tavubudjetti {} käytetty", "byte budget {} used", n),
+            StopReason::Interrupted => tr!("käyttäjä keskeytti", "interrupted by the user"),
+        };
+        f.write_str(&text)
+    }
+}
+
+/// Lue holdout-tiedostot yhdeksi näytteeksi (enintään HOLDOUT_MAX_BYTES)
+pub fn load_holdout(files: &[PathBuf]) -> Vec<u8> {
+    let mut data = Vec::new();
+    for path in files {
+        if data.len() >= HOLDOUT_MAX_BYTES {
+            break;
+        }
+        if let Ok(bytes) = fs::read(path) {
+            let take = bytes.len().min(HOLDOUT_MAX_BYTES - data.len());
+            data.extend_from_slice(&bytes[..take]);
+        }
+    }
+    data
+}
+
+/// Vakausehdon eteneminen (tallentuu istuntoon)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct StopState {
+    /// Vakauden vertailukoko ja syklit sen jälkeen
+    stable_base: usize,
+    stable_for: usize,
+}
+
+/// Ehtojen seuranta ajon aikana
+pub struct StopMonitor {
+    policy: StopPolicy,
+    holdout: Vec<u8>,
+    coder: Coder,
+    state: StopState,
+}
+
+impl StopMonitor {
+    /// `holdout` on holdout-joukon data (tyhjä = ehto ohitetaan)
+    pub fn new(policy: StopPolicy, holdout: Vec<u8>, coder: Coder) -> Self {
+        StopMonitor {
+            policy,
+            holdout,
+            coder,
+            state: StopState::default(),
+        }
+    }
+
+    /// Jatka tallennetusta tilasta
+    pub fn restore(&mut self, state: StopState) {
+        self.state = state;
+    }
+
+    pub fn state(&self) -> StopState {
+        self.state
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        let p = &self.policy;
+        p.target_ratio.is_some()
+            || (p.target_holdout_bpb.is_some() && !self.holdout.is_empty())
+            || p.stable_cycles.is_some()
+            || p.byte_budget.is_some()
+    }
+
+    /// Holdout-joukon bittiä/tavu nykyisellä mallilla
+    pub fn holdout_bpb(&self, engine: &dyn Engine) -> Option<f64> {
+        if self.holdout.is_empty() {
+            return None;
+        }
+        let coded = self.coder.encode(&engine.encode(&self.holdout)).len();
+        Some(coded as f64 * 8.0 / self.holdout.len() as f64)
+    }
+
+    /// Tarkista ehdot syklin lopussa
+    pub fn check(
+        &mut self,
+        engine: &dyn Engine,
+        cycle: usize,
+        ratio: f64,
+        total_fed: usize,
+    ) -> Option<StopReason> {
+        if let Some(budget) = self.policy.byte_budget
+            && total_fed >= budget
+        {
+            return Some(StopReason::ByteBudget(budget));
+        }
+        if let Some(target) = self.policy.target_ratio
+            && ratio >= target
+        {
+            return Some(StopReason::TargetRatio(ratio));
+        }
+
+        if let Some(needed) = self.policy.stable_cycles {
+            let size = engine.model_size();
+            let state = &mut self.state;
+            let tolerance = (state.stable_base as f64 * STABLE_TOLERANCE).max(1.0);
+            if size.abs_diff(state.stable_base) as f64 <= tolerance {
+                state.stable_for += 1;
+            } else {
+                state.stable_base = size;
+                state.stable_for = 0;
+            }
+            if state.stable_for >= needed {
+                return Some(StopReason::BankStable(state.stable_for));
+            }
+        }
+
+        if let Some(target) = self.policy.target_holdout_bpb
+            && cycle.is_multiple_of(HOLDOUT_CHECK_INTERVAL)
+            && let Some(bpb) = self.holdout_bpb(engine)
+        {
+            say!(
+                "     🧪 Holdout: {:.3} bittiä/tavu",
+                "     🧪 Holdout: {:.3} bits/byte",
+                bpb
+            );
+            if bpb <= target {
+                return Some(StopReason::TargetHoldoutBpb(bpb));
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::Builder;
+
+    #[test]
+    fn test_stop_conditions() {
+        let mut engine = Builder::new(100);
+        engine.tokenize(b"abcabcabc");
+
+        let policy = StopPolicy {
+            byte_budget: Some(1000),
+            stable_cycles: Some(3),
+            ..StopPolicy::default()
+        };
+        let mut monitor = StopMonitor::new(policy, Vec::new(), Coder::Huffman);
+        assert!(monitor.is_enabled());
+        assert_eq!(monitor.check(&engine, 1, 0.0, 10), None);
+        assert_eq!(monitor.check(&engine, 2, 0.0, 10), None);
+        assert_eq!(
+            monitor.check(&engine, 3, 0.0, 10),
+            Some(StopReason::BankStable(3))
+        );
+        assert_eq!(
+            monitor.check(&engine, 4, 0.0, 1000),
+            Some(StopReason::ByteBudget(1000))
+        );
+
+        // Holdout: ehto ohitetaan ilman dataa, muuten tarkistetaan välein
+        let policy = StopPolicy {
+            target_holdout_bpb: Some(100.0),
+            target_ratio: Some(0.5),
+            ..StopPolicy::default()
+        };
+        let mut monitor = StopMonitor::new(policy.clone(), Vec::new(), Coder::Huffman);
+        assert_eq!(monitor.check(&engine, 10, 0.1, 0), None);
+        assert_eq!(
+            monitor.check(&engine, 11, 0.6, 0),
+            Some(StopReason::TargetRatio(0.6))
+        );
+        let mut monitor = StopMonitor::new(policy, b"abcabc".to_vec(), Coder::Huffman);
+        assert_eq!(monitor.check(&engine, 9, 0.1, 0), None);
+        assert!(matches!(
+            monitor.check(&engine, 10, 0.1, 0),
+            Some(StopReason::TargetHoldoutBpb(_))
+        ));
+    }
+}
@@ -0,0 +1,302 @@
+// src/survival.rs
+//
+// Survival: Mallien elinajat ja eloonjäämisanalyysi tapahtumalokista.
+//
+// Kapasiteetti, heikkeneminen ja parikynnys säädetään usein tuntumalla.
+// Tapahtumaloki (`--log-format json -vv`) kertoo jokaisen mallin synnyn ja
+// kuoleman, joten elinajat voi mitata: kuinka kauan tason 3 malli elää,
+// kuolevatko ajon alussa syntyneet nopeammin ja kuoleeko malli siksi, että
+// se heikkeni (decay) vai siksi, että pankki oli täynnä (capacity).
+//
+// Eloonjäämiskäyrä lasketaan Kaplan-Meier-estimaattorilla: lokin lopussa
+// elossa olevat mallit ovat sensuroituja (elinaika vähintään havaittu).
+// Mediaanielinaika on ensimmäinen ikä, jolla käyrä putoaa puoleen; jos
+// yli puolet ryhmästä on yhä elossa, mediaania ei tunneta.
+//
+// Kuolinsyyt:
+//   - decay: unohdettu, kun vahvuus oli jo totuuskynnyksen alla,
+//   - capacity: unohdettu vahvana, koska pankki oli täynnä,
+//   - dependent: poistettu unohdetun osansa mukana,
+//   - merged: yhdistetty lähes samaan malliin.
+
+use crate::i18n::tr;
+use crate::term::{self, Level, say};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::io::{self, BufRead};
+
+/// Eloonjäämisosuus raportoidaan näissä iässä (sykleinä)
+pub const CHECKPOINTS: [u64; 3] = [10, 100, 1000];
+
+/// Ajon vaiheet syntymän mukaan (kolmannekset)
+const PHASES: usize = 3;
+
+/// Kuolinsyy
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Cause {
+    Decay,
+    Capacity,
+    Dependent,
+    Merged,
+}
+
+impl Cause {
+    pub const ALL: [Cause; 4] = [
+        Cause::Decay,
+        Cause::Capacity,
+        Cause::Dependent,
+        Cause::Merged,
+    ];
+
+    fn parse(name: &str) -> Self {
+        match name {
+            "decay" => Cause::Decay,
+            "dependent" => Cause::Dependent,
+            "merged" => Cause::Merged,
+            _ => Cause::Capacity,
+        }
+    }
+}
+
+/// Yhden mallin elinkaari lokissa
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lifetime {
+    pub id: u32,
+    pub level: u8,
+    pub born: u64,
+    /// Kuolinsykli ja -syy; `None` = elossa lokin lopussa
+    pub died: Option<(u64, Cause)>,
+}
+
+/// Luettu loki: elinkaaret ID-järjestyksessä ja viimeinen havaittu sykli
+#[derive(Debug, Clone, Default)]
+pub struct SurvivalLog {
+    pub lifetimes: Vec<Lifetime>,
+    pub first_cycle: u64,
+    pub last_cycle: u64,
+}
+
+/// Lue JSON-tapahtumaloki. Muut kuin JSON-rivit ja tuntemattomat
+/// tapahtumat ohitetaan.
+pub fn read_log(reader: impl BufRead) -> io::Result<SurvivalLog> {
+    let mut lifetimes: HashMap<u32, Lifetime> = HashMap::new();
+    let (mut first_cycle, mut last_cycle) = (u64::MAX, 0);
+    for line in reader.lines() {
+        let Ok(event) = serde_json::from_str::<Value>(&line?) else {
+            continue;
+        };
+        let field = |name: &str| event.get(name).and_then(Value::as_u64);
+        let Some(cycle) = field("cycle") else {
+            continue;
+        };
+        first_cycle = first_cycle.min(cycle);
+        last_cycle = last_cycle.max(cycle);
+        let id = field("id").unwrap_or(0) as u32;
+        let level = field("level").unwrap_or(0) as u8;
+        match event.get("event").and_then(Value::as_str) {
+            Some("pattern_born") => {
+                lifetimes.insert(
+                    id,
+                    Lifetime {
+                        id,
+                        level,
+                        born: cycle,
+                        died: None,
+                    },
+                );
+            }
+            Some(kind @ ("pattern_forgotten" | "pattern_merged")) => {
+                let cause = if kind == "pattern_merged" {
+                    Cause::Merged
+                } else {
+                    Cause::parse(event.get("cause").and_then(Value::as_str).unwrap_or(""))
+                };
+                // Ennen lokin alkua syntyneet: syntymä kuolintapahtumasta
+                let born = field("born").unwrap_or(cycle);
+                first_cycle = first_cycle.min(born);
+                lifetimes
+                    .entry(id)
+                    .or_insert(Lifetime {
+                        id,
+                        level,
+                        born,
+                        died: None,
+                    })
+                    .died = Some((cycle, cause));
+            }
+            _ => {}
+        }
+    }
+    let mut lifetimes: Vec<Lifetime> = lifetimes.into_values().collect();
+    lifetimes.sort_unstable_by_key(|l| l.id);
+    Ok(SurvivalLog {
+        lifetimes,
+        first_cycle: first_cycle.min(last_cycle),
+        last_cycle,
+    })
+}
+
+/// Kaplan-Meier-käyrä: (ikä, eloonjäämisosuus) jokaisella kuolinhetkellä
+pub fn kaplan_meier(lifetimes: &[&Lifetime], last_cycle: u64) -> Vec<(u64, f64)> {
+    let mut ages: Vec<(u64, bool)> = lifetimes
+        .iter()
+        .map(|l| match l.died {
+            Some((cycle, _)) => (cycle.saturating_sub(l.born), true),
+            None => (last_cycle.saturating_sub(l.born), false),
+        })
+        .collect();
+    ages.sort_unstable();
+
+    let mut curve = Vec::new();
+    let mut survival = 1.0;
+    let mut at_risk = ages.len();
+    let mut i = 0;
+    while i < ages.len() {
+        let age = ages[i].0;
+        let same = ages[i..].iter().take_while(|a| a.0 == age);
+        let (count, deaths) = same.fold((0, 0), |(n, d), a| (n + 1, d + a.1 as usize));
+        if deaths > 0 {
+            survival *= 1.0 - deaths as f64 / at_risk as f64;
+            curve.push((age, survival));
+        }
+        at_risk -= count;
+        i += count;
+    }
+    curve
+}
+
+/// Eloonjäämisosuus iässä `age`
+pub fn survival_at(curve: &[(u64, f64)], age: u64) -> f64 {
+    curve
+        .iter()
+        .take_while(|(a, _)| *a <= age)
+        .last()
+        .map_or(1.0, |(_, s)| *s)
+}
+
+/// Mediaanielinaika: ensimmäinen ikä, jolla käyrä on enintään 0.5
+pub fn median(curve: &[(u64, f64)]) -> Option<u64> {
+    curve.iter().find(|(_, s)| *s <= 0.5).map(|(a, _)| *a)
+}
+
+/// Tulosta ryhmän rivi
+fn print_group(name: &str, group: &[&Lifetime], last_cycle: u64) {
+    let curve = kaplan_meier(group, last_cycle);
+    let died = group.iter().filter(|l| l.died.is_some()).count();
+    let causes: Vec<String> = Cause::ALL
+        .iter()
+        .map(|&cause| {
+            let n = group
+                .iter()
+                .filter(|l| l.died.is_some_and(|(_, c)| c == cause))
+                .count();
+            format!("{:>8}", n)
+        })
+        .collect();
+    let checkpoints: Vec<String> = CHECKPOINTS
+        .iter()
+        .map(|&age| format!("{:>6.0}%", survival_at(&curve, age) * 100.0))
+        .collect();
+    term::emit(
+        Level::Info,
+        &format!(
+            "     {:<10} {:>7} {:>7} {:>9} {} {}",
+            name,
+            group.len(),
+            died,
+            median(&curve).map_or("-".to_string(), |m| m.to_string()),
+            checkpoints.join(""),
+            causes.join("")
+        ),
+    );
+}
+
+/// Eloonjäämisraportti tasoittain ja ajon vaiheittain
+pub fn print_report(log: &SurvivalLog) {
+    let SurvivalLog {
+        lifetimes,
+        first_cycle,
+        last_cycle,
+    } = log;
+    say!(
+        "\n  ⏳ Mallien elinajat: {} mallia, syklit {}-{}",
+        "\n  ⏳ Pattern lifetimes: {} patterns, cycles {}-{}",
+        lifetimes.len(),
+        first_cycle,
+        last_cycle
+    );
+    say!(
+        "     Ryhmä       Syntyi   Kuoli  Mediaani   S(10) S(100) S(1000) Heikkeni   Täysi   Osana Yhdist.",
+        "     Group         Born    Died    Median   S(10) S(100) S(1000)    Decay Capacity    Part  Merged"
+    );
+
+    let all: Vec<&Lifetime> = lifetimes.iter().collect();
+    print_group(&tr!("kaikki", "all"), &all, *last_cycle);
+
+    let mut levels: BTreeMap<u8, Vec<&Lifetime>> = BTreeMap::new();
+    for l in lifetimes {
+        levels.entry(l.level).or_default().push(l);
+    }
+    for (level, group) in &levels {
+        print_group(&format!("L{}", level), group, *last_cycle);
+    }
+
+    let span = (last_cycle - first_cycle + 1).div_ceil(PHASES as u64);
+    let mut phases: Vec<Vec<&Lifetime>> = vec![Vec::new(); PHASES];
+    for l in lifetimes {
+        let phase = ((l.born.saturating_sub(*first_cycle)) / span) as usize;
+        phases[phase.min(PHASES - 1)].push(l);
+    }
+    for (phase, group) in phases.iter().enumerate() {
+        let start = first_cycle + phase as u64 * span;
+        print_group(
+            &format!("@{}-{}", start, (start + span - 1).min(*last_cycle)),
+            group,
+            *last_cycle,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_log_lifetimes_and_kaplan_meier() {
+        let log = [
+            r#"{"event":"pattern_born","cycle":1,"id":300,"level":1}"#,
+            r#"{"event":"pattern_born","cycle":1,"id":301,"level":1}"#,
+            "  ei JSONia",
+            r#"{"event":"pattern_born","cycle":2,"id":302,"level":2}"#,
+            r#"{"event":"pattern_forgotten","cycle":5,"id":300,"cause":"decay"}"#,
+            r#"{"event":"pattern_merged","cycle":6,"id":302,"level":2,"born":2}"#,
+            r#"{"event":"pattern_forgotten","cycle":7,"id":280,"level":1,"born":0,"cause":"capacity"}"#,
+            r#"{"event":"cycle","cycle":10}"#,
+        ]
+        .join("\n");
+        let log = read_log(log.as_bytes()).unwrap();
+        assert_eq!((log.first_cycle, log.last_cycle), (0, 10));
+        let summary: Vec<_> = log
+            .lifetimes
+            .iter()
+            .map(|l| (l.id, l.born, l.died))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (280, 0, Some((7, Cause::Capacity))),
+                (300, 1, Some((5, Cause::Decay))),
+                (301, 1, None),
+                (302, 2, Some((6, Cause::Merged))),
+            ]
+        );
+
+        // Iät 7, 4, 9 (sensuroitu), 4: kaksi neljästä kuolee iässä 4
+        let all: Vec<&Lifetime> = log.lifetimes.iter().collect();
+        let curve = kaplan_meier(&all, log.last_cycle);
+        assert_eq!(curve, [(4, 0.5), (7, 0.25)]);
+        assert_eq!(median(&curve), Some(4));
+        assert_eq!(survival_at(&curve, 3), 1.0);
+        assert_eq!(survival_at(&curve, 8), 0.25);
+    }
+}
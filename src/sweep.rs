@@ -0,0 +1,507 @@
+// src/sweep.rs
+//
+// Sweep: Hyperparametrien automaattinen vertailu (A/B-haku).
+//
+// Parikynnyksen, kapasiteetin, heikkenemisen ja tasokynnysten vaikutusta
+// on vaikea arvata, ja täysi ajo kestää tunteja. Sweep kouluttaa jokaisella
+// ehdokasasetuksella lyhyen, rajatun ajon samalla korpusnäytteellä ja
+// mittaa tuloksen näytteen sivuun jätetyllä osalla (holdout) bitteinä
+// tavua kohden. Pienin luku voittaa.
+//
+// Ehdokkaat annetaan akseleina:
+//   - `nimi=a,b,c`: ruudukko; kaikki yhdistelmät kokeillaan,
+//   - `nimi=lo..hi`: väli; mukana olevat akselit arvotaan (satunnaishaku).
+// Tasokynnysten taulukko kirjoitetaan kaksoispistein: `levels=3:2:1,2:1`.
+//
+// Kukin tiedosto antaa näytteeseen yhtä suuren osuuden; osuuden viimeinen
+// kymmenesosa on holdoutia, joten arvio mittaa yleistymistä eikä ulkoa
+// opettelua. Ehdokkaat ovat toisistaan riippumattomia ja voivat ajaa
+// rinnakkain omissa säikeissään.
+
+use crate::builder::{Builder, DEFAULT_DECAY_RATE};
+use crate::entropy::Coder;
+use crate::i18n::tr;
+use crate::lexer::Tokenization;
+use crate::term::{self, Level, say};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::VecDeque;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::Instant;
+
+/// Oletussyklit ehdokasta kohden
+pub const DEFAULT_CYCLES: usize = 40;
+
+/// Korpusnäytteen oletuskoko tavuina
+pub const DEFAULT_SAMPLE_BYTES: usize = 256 * 1024;
+
+/// Satunnaishaun oletusehdokkaat
+pub const DEFAULT_SAMPLES: usize = 12;
+
+/// Tiedoston osuudesta holdoutiksi jäävä prosentti
+const HOLDOUT_PERCENT: usize = 10;
+
+/// Säädettävä hyperparametri
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Param {
+    PairThreshold,
+    Capacity,
+    Decay,
+    LevelThresholds,
+}
+
+impl Param {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "pair_threshold" | "pair" => Some(Param::PairThreshold),
+            "capacity" | "pattern_capacity" => Some(Param::Capacity),
+            "decay" => Some(Param::Decay),
+            "level_thresholds" | "levels" => Some(Param::LevelThresholds),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Param::PairThreshold => "pair_threshold",
+            Param::Capacity => "capacity",
+            Param::Decay => "decay",
+            Param::LevelThresholds => "level_thresholds",
+        }
+    }
+
+    /// Aseta ruudukon arvo ehdokkaaseen
+    fn set(&self, candidate: &mut Candidate, value: &str) -> Option<()> {
+        let value = value.trim();
+        match self {
+            Param::PairThreshold => {
+                candidate.pair_threshold = value.parse().ok().filter(|&t| t > 0)?
+            }
+            Param::Capacity => candidate.capacity = value.parse().ok().filter(|&c| c > 0)?,
+            Param::Decay => {
+                candidate.decay = value.parse().ok().filter(|d| (0.0..1.0).contains(d))?
+            }
+            Param::LevelThresholds => {
+                candidate.level_thresholds = if value.is_empty() || value == "-" {
+                    Vec::new()
+                } else {
+                    value
+                        .split(':')
+                        .map(|t| t.trim().parse().ok().filter(|&t: &u32| t > 0))
+                        .collect::<Option<_>>()?
+                }
+            }
+        }
+        Some(())
+    }
+
+    /// Aseta välistä arvottu luku (kokonaisluvut pyöristetään, heikkeneminen
+    /// neljään desimaaliin)
+    fn set_sampled(&self, candidate: &mut Candidate, x: f64) {
+        match self {
+            Param::PairThreshold => candidate.pair_threshold = (x.round() as u32).max(1),
+            Param::Capacity => candidate.capacity = (x.round() as usize).max(1),
+            Param::Decay => candidate.decay = ((x * 1e4).round() / 1e4).clamp(0.0, 0.999),
+            Param::LevelThresholds => {}
+        }
+    }
+}
+
+/// Akselin arvot
+#[derive(Debug, Clone, PartialEq)]
+pub enum Values {
+    /// Luetellut arvot (ruudukko)
+    Grid(Vec<String>),
+    /// Suljettu väli, josta arvotaan
+    Range(f64, f64),
+}
+
+/// Yksi haun akseli: parametri ja sen arvot
+#[derive(Debug, Clone, PartialEq)]
+pub struct Axis {
+    pub param: Param,
+    pub values: Values,
+}
+
+impl Axis {
+    /// Tulkitse `nimi=a,b,c` tai `nimi=lo..hi`
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let (name, values) = spec.split_once('=').ok_or_else(|| {
+            tr!(
+                "'{}': odotettiin nimi=arvot",
+                "'{}': expected name=values",
+                spec
+            )
+        })?;
+        let param = Param::parse(name)
+            .ok_or_else(|| tr!("tuntematon parametri '{}'", "unknown parameter '{}'", name))?;
+        let invalid = |value: &str| {
+            tr!(
+                "{}: virheellinen arvo '{}'",
+                "{}: invalid value '{}'",
+                param.name(),
+                value
+            )
+        };
+
+        if let Some((lo, hi)) = values.split_once("..") {
+            if param == Param::LevelThresholds {
+                return Err(tr!(
+                    "{}: väli ei käy, luettele taulukot",
+                    "{}: ranges are not supported, list the tables",
+                    param.name()
+                ));
+            }
+            let (Ok(lo), Ok(hi)) = (lo.trim().parse::<f64>(), hi.trim().parse::<f64>()) else {
+                return Err(invalid(values));
+            };
+            if !(lo.is_finite() && hi.is_finite() && lo <= hi && lo >= 0.0) {
+                return Err(invalid(values));
+            }
+            return Ok(Axis {
+                param,
+                values: Values::Range(lo, hi),
+            });
+        }
+
+        let grid: Vec<String> = values.split(',').map(|v| v.trim().to_string()).collect();
+        let mut probe = Candidate::default();
+        for value in &grid {
+            param.set(&mut probe, value).ok_or_else(|| invalid(value))?;
+        }
+        Ok(Axis {
+            param,
+            values: Values::Grid(grid),
+        })
+    }
+}
+
+/// Yksi kokeiltava asetusyhdistelmä
+#[derive(Debug, Clone, PartialEq)]
+pub struct Candidate {
+    pub pair_threshold: u32,
+    pub capacity: usize,
+    pub decay: f64,
+    pub level_thresholds: Vec<u32>,
+}
+
+impl Default for Candidate {
+    fn default() -> Self {
+        Candidate {
+            pair_threshold: 2,
+            capacity: 1000,
+            decay: DEFAULT_DECAY_RATE,
+            level_thresholds: Vec::new(),
+        }
+    }
+}
+
+impl Candidate {
+    /// Lyhyt kuvaus tulostauluun
+    pub fn label(&self) -> String {
+        let mut label = format!(
+            "pair={} cap={} decay={}",
+            self.pair_threshold, self.capacity, self.decay
+        );
+        if !self.level_thresholds.is_empty() {
+            label.push_str(&format!(" levels={}", self.levels(":")));
+        }
+        label
+    }
+
+    /// Ympäristömuuttujat, joilla asetus otetaan käyttöön oikeassa ajossa
+    pub fn env(&self) -> String {
+        let mut env = format!(
+            "PETRI_PAIR_THRESHOLD={} PETRI_PATTERN_CAPACITY={} PETRI_DECAY={}",
+            self.pair_threshold, self.capacity, self.decay
+        );
+        if !self.level_thresholds.is_empty() {
+            env.push_str(&format!(" PETRI_LEVEL_THRESHOLDS={}", self.levels(",")));
+        }
+        env
+    }
+
+    /// Tyhjä rakentaja tällä asetuksella
+    pub fn builder(&self, tokenization: Option<Tokenization>) -> Builder {
+        let mut builder = Builder::new(self.capacity);
+        if let Some(tokenization) = tokenization {
+            builder.bank.set_tokenization(tokenization);
+        }
+        builder.pair_threshold = self.pair_threshold;
+        builder.level_thresholds = self.level_thresholds.clone();
+        builder.decay_rate = self.decay;
+        builder
+    }
+
+    fn levels(&self, separator: &str) -> String {
+        let table: Vec<String> = self
+            .level_thresholds
+            .iter()
+            .map(|t| t.to_string())
+            .collect();
+        table.join(separator)
+    }
+}
+
+/// Muodosta ehdokkaat: ruudukon kaikki yhdistelmät, tai `samples`
+/// satunnaista, jos se on annettu tai jokin akseli on väli.
+pub fn candidates(
+    base: &Candidate,
+    axes: &[Axis],
+    samples: Option<usize>,
+    seed: u64,
+) -> Vec<Candidate> {
+    let random = samples.is_some() || axes.iter().any(|a| matches!(a.values, Values::Range(..)));
+    let mut out: Vec<Candidate> = Vec::new();
+    if random {
+        let mut rng = StdRng::seed_from_u64(seed);
+        for _ in 0..samples.unwrap_or(DEFAULT_SAMPLES) {
+            let mut candidate = base.clone();
+            for axis in axes {
+                match &axis.values {
+                    Values::Grid(values) => {
+                        let value = &values[rng.gen_range(0..values.len())];
+                        axis.param.set(&mut candidate, value);
+                    }
+                    Values::Range(lo, hi) => axis
+                        .param
+                        .set_sampled(&mut candidate, rng.gen_range(*lo..=*hi)),
+                }
+            }
+            if !out.contains(&candidate) {
+                out.push(candidate);
+            }
+        }
+        return out;
+    }
+
+    out.push(base.clone());
+    for axis in axes {
+        let Values::Grid(values) = &axis.values else {
+            continue;
+        };
+        out = out
+            .iter()
+            .flat_map(|c| {
+                values.iter().map(move |value| {
+                    let mut next = c.clone();
+                    axis.param.set(&mut next, value);
+                    next
+                })
+            })
+            .collect();
+    }
+    out.dedup();
+    out
+}
+
+/// Korpusnäyte: opetusosat tiedostoittain ja yhteinen holdout
+pub struct Sample {
+    pub train: Vec<Vec<u8>>,
+    pub holdout: Vec<u8>,
+}
+
+impl Sample {
+    /// Lue kustakin tiedostosta yhtä suuri osuus `budget` tavusta
+    pub fn load(files: &[PathBuf], budget: usize) -> Self {
+        let share = budget / files.len().max(1);
+        let data = files
+            .iter()
+            .filter_map(|path| fs::read(path).ok())
+            .map(|mut bytes| {
+                bytes.truncate(share);
+                bytes
+            })
+            .collect();
+        Self::split(data)
+    }
+
+    /// Jaa kunkin osuuden loppu holdoutiksi
+    pub fn split(data: Vec<Vec<u8>>) -> Self {
+        let mut train = Vec::new();
+        let mut holdout = Vec::new();
+        for mut bytes in data {
+            let cut = bytes.len() - bytes.len() * HOLDOUT_PERCENT / 100;
+            holdout.extend_from_slice(&bytes[cut..]);
+            bytes.truncate(cut);
+            if !bytes.is_empty() {
+                train.push(bytes);
+            }
+        }
+        Sample { train, holdout }
+    }
+
+    pub fn train_bytes(&self) -> usize {
+        self.train.iter().map(Vec::len).sum()
+    }
+}
+
+/// Yhden ehdokkaan tulos
+#[derive(Debug, Clone)]
+pub struct Trial {
+    pub candidate: Candidate,
+    pub holdout_bpb: f64,
+    pub patterns: usize,
+    pub seconds: f64,
+}
+
+/// Syötä opetusdata tasaisesti `cycles` syklin kesken, tiedostojen väliin
+/// tietueraja kuten Feederissä. Palauttaa syötettyjen tokenien määrän.
+pub fn train(builder: &mut Builder, train: &[Vec<u8>], cycles: usize) -> usize {
+    let cycles = cycles.max(1);
+    let total: usize = train.iter().map(Vec::len).sum();
+    let chunk = total.div_ceil(cycles).max(1);
+    let mut pending: VecDeque<&[u8]> = train.iter().map(Vec::as_slice).collect();
+    let mut tokens = 0;
+    for _ in 0..cycles {
+        let mut budget = chunk;
+        while budget > 0
+            && let Some(front) = pending.front_mut()
+        {
+            let take = budget.min(front.len());
+            let before = builder.token_stream.len();
+            builder.tokenize(&front[..take]);
+            tokens += builder.token_stream.len() - before;
+            *front = &front[take..];
+            budget -= take;
+            if front.is_empty() {
+                pending.pop_front();
+                builder.push_boundary();
+            }
+        }
+        builder.live();
+    }
+    tokens
+}
+
+/// Kouluta ehdokas `cycles` syklin ajan ja mittaa holdout.
+pub fn run_trial(
+    candidate: &Candidate,
+    sample: &Sample,
+    cycles: usize,
+    tokenization: Option<Tokenization>,
+    coder: Coder,
+) -> Trial {
+    let started = Instant::now();
+    let mut builder = candidate.builder(tokenization);
+    train(&mut builder, &sample.train, cycles);
+
+    let coded = coder.encode(&builder.bank.encode(&sample.holdout)).len();
+    Trial {
+        candidate: candidate.clone(),
+        holdout_bpb: coded as f64 * 8.0 / sample.holdout.len().max(1) as f64,
+        patterns: builder.bank.combine_count(),
+        seconds: started.elapsed().as_secs_f64(),
+    }
+}
+
+/// Aja kaikki ehdokkaat `jobs` säikeellä. Tulokset parhaasta alkaen;
+/// `on_done` kutsutaan jokaisen valmistuneen ehdokkaan jälkeen.
+pub fn run_all(
+    candidates: &[Candidate],
+    sample: &Sample,
+    cycles: usize,
+    tokenization: Option<Tokenization>,
+    coder: Coder,
+    jobs: usize,
+    on_done: impl Fn() + Sync,
+) -> Vec<Trial> {
+    let next = AtomicUsize::new(0);
+    let trials = Mutex::new(Vec::with_capacity(candidates.len()));
+    thread::scope(|scope| {
+        for _ in 0..jobs.clamp(1, candidates.len().max(1)) {
+            scope.spawn(|| {
+                while let Some(candidate) = candidates.get(next.fetch_add(1, Ordering::Relaxed)) {
+                    let trial = run_trial(candidate, sample, cycles, tokenization, coder);
+                    trials.lock().expect("sweep-tulokset").push(trial);
+                    on_done();
+                }
+            });
+        }
+    });
+    let mut trials = trials.into_inner().expect("sweep-tulokset");
+    trials.sort_by(|a, b| {
+        a.holdout_bpb
+            .total_cmp(&b.holdout_bpb)
+            .then(a.candidate.label().cmp(&b.candidate.label()))
+    });
+    trials
+}
+
+/// Tulosta tulostaulu; nykyinen asetus merkitään, paras annetaan
+/// ympäristömuuttujina
+pub fn print_report(trials: &[Trial], current: &Candidate) {
+    say!(
+        "\n  🧪 Tulokset (holdout bittiä/tavu, malleja, aika):",
+        "\n  🧪 Results (holdout bits/byte, patterns, time):"
+    );
+    for (i, trial) in trials.iter().enumerate() {
+        term::emit(
+            Level::Info,
+            &format!(
+                "     {:>3}. {:.4} {:>7} {:>6.1}s  {}{}",
+                i + 1,
+                trial.holdout_bpb,
+                trial.patterns,
+                trial.seconds,
+                trial.candidate.label(),
+                if &trial.candidate == current {
+                    "  ◀"
+                } else {
+                    ""
+                }
+            ),
+        );
+    }
+    let Some(best) = trials.first() else {
+        return;
+    };
+    if let Some(baseline) = trials.iter().find(|t| &t.candidate == current)
+        && baseline.holdout_bpb > 0.0
+    {
+        say!(
+            "\n  Nykyinen asetus (◀): {:.4} bittiä/tavu, paras {:+.2}%",
+            "\n  Current settings (◀): {:.4} bits/byte, best {:+.2}%",
+            baseline.holdout_bpb,
+            (best.holdout_bpb / baseline.holdout_bpb - 1.0) * 100.0
+        );
+    }
+    say!("\n  🏆 Paras: {}", "\n  🏆 Best: {}", best.candidate.env());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_axes_expand_and_trial_runs() {
+        let axes: Vec<Axis> = ["pair=2,3", "levels=3:2,-", "decay=0.01"]
+            .iter()
+            .map(|s| Axis::parse(s).unwrap())
+            .collect();
+        let grid = candidates(&Candidate::default(), &axes, None, 0);
+        assert_eq!(grid.len(), 4);
+        assert_eq!(grid[0].level_thresholds, [3, 2]);
+        assert_eq!(grid[3].pair_threshold, 3);
+        assert!(grid[3].level_thresholds.is_empty());
+        assert!(grid[0].env().contains("PETRI_LEVEL_THRESHOLDS=3,2"));
+
+        let range = [Axis::parse("capacity=100..200").unwrap()];
+        let random = candidates(&Candidate::default(), &range, Some(5), 7);
+        assert!(!random.is_empty());
+        assert!(random.iter().all(|c| (100..=200).contains(&c.capacity)));
+        assert!(Axis::parse("levels=1..3").is_err());
+        assert!(Axis::parse("pair=0").is_err());
+        assert!(Axis::parse("speed=1").is_err());
+
+        let sample = Sample::split(vec![b"kissa istuu, kissa istuu, ".repeat(20)]);
+        assert_eq!(sample.holdout.len(), 52);
+        let trial = run_trial(&grid[1], &sample, 5, None, Coder::Huffman);
+        assert!(trial.patterns > 0);
+        assert!(trial.holdout_bpb > 0.0 && trial.holdout_bpb < 8.0);
+    }
+}
@@ -0,0 +1,571 @@
+// src/term.rs
+//
+// Term: Konsolitulosteiden taso, värit ja emojit.
+//
+// Kaikki tulosteet kulkevat `emit`-funktion kautta (makrot `say!`, `warning!`,
+// `error!`, `detail!` ja `trace!`) tracing-tapahtumina, ja `configure`
+// asentaa tilaajan (TermLayer), joka muotoilee ne. Yksi asetus ohjaa siis
+// koko ohjelmaa:
+//   - taso: -q näyttää vain varoitukset ja virheet, oletus syklien
+//     yhteenvedot, -v lisää yksityiskohdat (avatut ja luetut tiedostot) ja
+//     -vv jokaisen syntyneen, unohdetun ja yhdistetyn mallin,
+//   - värit: vain päätteelle, ja NO_COLOR tai --no-color poistaa ne,
+//   - emojit: --plain (tai PETRI_PLAIN) poistaa ne lokitiedostoja varten,
+//   - muoto: --log-format json (tai PETRI_LOG_FORMAT) tulostaa jokaisen
+//     rivin JSON-oliona jq:lle tai lokikeräimelle.
+//
+// JSON-muodossa tavalliset viestit ovat `message`-tapahtumia (taso ja
+// teksti ilman emojeja). Syklin tilastot, mallien synty ja unohdus sekä
+// feederin tiedostot tulostetaan omina tapahtuminaan (`event`) tekstirivien
+// sijaan; taso rajaa niitä kuten tekstiäkin (mallit vain -vv).
+//
+// Tasot vastaavat tracingin tasoja (Detail = DEBUG). Spanien kentät
+// liitetään JSON-riveihin (esim. pääsilmukan `cycle`-span antaa jokaiselle
+// syklin aikana tulostetulle riville syklin numeron); tekstirivit pysyvät
+// ennallaan.
+//
+// Asetukset valitaan kerran ohjelman alussa; oletus on normaali taso,
+// värit päätteellä ja emojit päällä.
+
+use serde_json::{Map, Value};
+use std::fmt;
+use std::io::IsTerminal;
+use std::sync::OnceLock;
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+use tracing_subscriber::registry::LookupSpan;
+
+/// Viestin taso
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Error,
+    Warn,
+    Info,
+    Detail,
+    /// Jokainen malli (pitkissä ajoissa tuhansia rivejä)
+    Trace,
+}
+
+impl Level {
+    /// Nimi JSON-tapahtumia varten
+    pub fn name(&self) -> &'static str {
+        match self {
+            Level::Error => "error",
+            Level::Warn => "warn",
+            Level::Info => "info",
+            Level::Detail => "detail",
+            Level::Trace => "trace",
+        }
+    }
+
+    /// Vastaava tracing-taso
+    pub fn to_tracing(self) -> tracing::Level {
+        match self {
+            Level::Error => tracing::Level::ERROR,
+            Level::Warn => tracing::Level::WARN,
+            Level::Info => tracing::Level::INFO,
+            Level::Detail => tracing::Level::DEBUG,
+            Level::Trace => tracing::Level::TRACE,
+        }
+    }
+
+    fn from_tracing(level: &tracing::Level) -> Self {
+        match *level {
+            tracing::Level::ERROR => Level::Error,
+            tracing::Level::WARN => Level::Warn,
+            tracing::Level::INFO => Level::Info,
+            tracing::Level::DEBUG => Level::Detail,
+            _ => Level::Trace,
+        }
+    }
+}
+
+/// Tulosteen muoto
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Format {
+    /// Luettava teksti (oletus)
+    #[default]
+    Text,
+    /// Yksi JSON-olio riviä kohden
+    Json,
+}
+
+impl Format {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "text" | "plain" => Some(Format::Text),
+            "json" => Some(Format::Json),
+            _ => None,
+        }
+    }
+}
+
+/// Tulosteasetukset
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Output {
+    /// Näytettävä taso (tätä yksityiskohtaisemmat ohitetaan)
+    pub level: Level,
+    pub color: bool,
+    pub emoji: bool,
+    pub format: Format,
+}
+
+impl Default for Output {
+    fn default() -> Self {
+        Output {
+            level: Level::Info,
+            color: false,
+            emoji: true,
+            format: Format::Text,
+        }
+    }
+}
+
+static OUTPUT: OnceLock<Output> = OnceLock::new();
+
+/// Aseta tulosteasetukset ja asenna tilaaja. Vain ensimmäinen kutsu on
+/// voimassa.
+pub fn configure(output: Output) {
+    if OUTPUT.set(output).is_ok() {
+        let subscriber = tracing_subscriber::registry()
+            .with(LevelFilter::from_level(output.level.to_tracing()))
+            .with(TermLayer::new(output, |line| println!("{}", line)));
+        let _ = tracing::subscriber::set_global_default(subscriber);
+    }
+}
+
+/// Voimassa olevat asetukset
+pub fn output() -> Output {
+    OUTPUT.get().copied().unwrap_or_default()
+}
+
+/// Tulostetaanko JSON-tapahtumia (tekstirivien sijaan)
+pub fn is_json() -> bool {
+    output().format == Format::Json
+}
+
+/// Näytetäänkö taso (makrot eivät muotoile piilotettuja viestejä)
+pub fn enabled(level: Level) -> bool {
+    level <= output().level
+}
+
+/// Onko hiljainen tila päällä (tavalliset viestit piilotetaan)
+pub fn is_quiet() -> bool {
+    output().level < Level::Info
+}
+
+/// Värit ovat käytössä vain päätteelle ilman NO_COLOR-muuttujaa
+pub fn color_supported() -> bool {
+    std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none_or(|v| v.is_empty())
+}
+
+/// Onko merkki emoji tai sen osa (vaihtoehtovalitsin, yhdistäjä)
+fn is_emoji(c: char) -> bool {
+    matches!(
+        c as u32,
+        0x1F000..=0x1FAFF | 0x2300..=0x23FF | 0x2600..=0x27BF | 0x2B00..=0x2BFF | 0xFE0F | 0x200D
+    )
+}
+
+/// Poista emojit; emojin jälkeinen välilyönti poistetaan samalla
+pub fn strip_emoji(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if is_emoji(c) {
+            while chars.peek().is_some_and(|&n| is_emoji(n)) {
+                chars.next();
+            }
+            if chars.peek() == Some(&' ') {
+                chars.next();
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// Muotoile rivi asetusten mukaan. `None`, jos taso on piilotettu.
+pub fn render(output: &Output, level: Level, text: &str) -> Option<String> {
+    render_in(output, level, text, Map::new())
+}
+
+/// Kuten `render`; JSON-riville lisätään spanien kentät `context`
+fn render_in(
+    output: &Output,
+    level: Level,
+    text: &str,
+    context: Map<String, Value>,
+) -> Option<String> {
+    if level > output.level {
+        return None;
+    }
+    if output.format == Format::Json {
+        let text = strip_emoji(text);
+        let text = text.trim();
+        if text.is_empty() {
+            return None;
+        }
+        let mut fields = context;
+        fields.insert("level".to_string(), Value::from(level.name()));
+        fields.insert("text".to_string(), Value::from(text));
+        return render_event(output, level, "message", Value::Object(fields));
+    }
+    let text = if output.emoji {
+        text.to_string()
+    } else {
+        strip_emoji(text)
+    };
+    let color = match level {
+        _ if !output.color => None,
+        Level::Error => Some("31"),
+        Level::Warn => Some("33"),
+        Level::Detail | Level::Trace => Some("2"),
+        Level::Info => None,
+    };
+    Some(match color {
+        // Väri vasta alun rivinvaihtojen jälkeen, jotta tyhjät rivit pysyvät tyhjinä
+        Some(code) => {
+            let body = text.trim_start_matches('\n');
+            let breaks = &text[..text.len() - body.len()];
+            format!("{}\x1b[{}m{}\x1b[0m", breaks, code, body)
+        }
+        None => text,
+    })
+}
+
+/// Lähetä tracing-tapahtuma annetulla tasolla (tasot ovat tracingissa
+/// vakioita, joten jokaiselle oma kutsukohta)
+macro_rules! dispatch {
+    ($level:expr, $($fields:tt)*) => {
+        match $level {
+            Level::Error => tracing::error!($($fields)*),
+            Level::Warn => tracing::warn!($($fields)*),
+            Level::Info => tracing::info!($($fields)*),
+            Level::Detail => tracing::debug!($($fields)*),
+            Level::Trace => tracing::trace!($($fields)*),
+        }
+    };
+}
+
+/// Tulosta rivi annetulla tasolla
+pub fn emit(level: Level, text: &str) {
+    if !tracing::dispatcher::has_been_set() {
+        // Ennen configurea (esim. kirjaston testeissä) tulostetaan suoraan
+        if let Some(line) = render(&output(), level, text) {
+            println!("{}", line);
+        }
+        return;
+    }
+    dispatch!(level, text = text);
+}
+
+/// Muotoile tapahtuma: `{"event": nimi, ...kentät}`. `None`, jos taso on
+/// piilotettu tai muoto ei ole JSON.
+pub fn render_event(output: &Output, level: Level, name: &str, fields: Value) -> Option<String> {
+    if level > output.level || output.format != Format::Json {
+        return None;
+    }
+    let mut object = Map::new();
+    object.insert("event".to_string(), Value::from(name));
+    if let Value::Object(fields) = fields {
+        object.extend(fields);
+    }
+    Some(Value::Object(object).to_string())
+}
+
+/// Tulosta rakenteinen tapahtuma (vain JSON-muodossa)
+pub fn event(level: Level, name: &str, fields: Value) {
+    if !is_json() {
+        return;
+    }
+    if !tracing::dispatcher::has_been_set() {
+        if let Some(line) = render_event(&output(), level, name, fields) {
+            println!("{}", line);
+        }
+        return;
+    }
+    let fields = fields.to_string();
+    dispatch!(level, event = name, fields = fields.as_str());
+}
+
+/// Tapahtuman tai spanin kentät
+#[derive(Default)]
+struct Fields {
+    /// Viestin teksti (`emit`)
+    text: Option<String>,
+    /// Rakenteisen tapahtuman nimi ja kentät JSON-tekstinä (`event`)
+    event: Option<String>,
+    fields: Option<String>,
+    /// Muut kentät (spanit)
+    other: Map<String, Value>,
+}
+
+impl Visit for Fields {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        match field.name() {
+            "text" => self.text = Some(value.to_string()),
+            "event" => self.event = Some(value.to_string()),
+            "fields" => self.fields = Some(value.to_string()),
+            name => {
+                self.other.insert(name.to_string(), Value::from(value));
+            }
+        }
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.other
+            .insert(field.name().to_string(), Value::from(value));
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.other
+            .insert(field.name().to_string(), Value::from(value));
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.other
+            .insert(field.name().to_string(), Value::from(value));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.other
+            .insert(field.name().to_string(), Value::from(value));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.record_str(field, &format!("{:?}", value));
+    }
+}
+
+/// Spanin kentät tilaajan laajennuksissa
+struct SpanFields(Map<String, Value>);
+
+/// Tilaajan kerros: muotoilee tapahtumat asetusten mukaan ja antaa rivit
+/// `write`-funktiolle (ohjelmassa stdout)
+pub struct TermLayer<W> {
+    output: Output,
+    write: W,
+}
+
+impl<W: Fn(&str) + Send + Sync + 'static> TermLayer<W> {
+    pub fn new(output: Output, write: W) -> Self {
+        TermLayer { output, write }
+    }
+}
+
+impl<S, W> Layer<S> for TermLayer<W>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    W: Fn(&str) + Send + Sync + 'static,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let mut fields = Fields::default();
+        attrs.record(&mut fields);
+        if let Some(span) = ctx.span(id) {
+            span.extensions_mut().insert(SpanFields(fields.other));
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let level = Level::from_tracing(event.metadata().level());
+        let mut fields = Fields::default();
+        event.record(&mut fields);
+        // Spanien kentät uloimmasta sisimpään; sisempi voittaa
+        let mut context = Map::new();
+        if self.output.format == Format::Json
+            && let Some(scope) = ctx.event_scope(event)
+        {
+            for span in scope.from_root() {
+                if let Some(SpanFields(values)) = span.extensions().get::<SpanFields>() {
+                    context.extend(values.clone());
+                }
+            }
+        }
+        let line = match fields.event {
+            Some(name) => {
+                let mut values: Map<String, Value> = fields
+                    .fields
+                    .and_then(|f| serde_json::from_str(&f).ok())
+                    .unwrap_or_default();
+                // Tapahtuman omat kentät voittavat spanien kentät
+                for (key, value) in context {
+                    values.entry(key).or_insert(value);
+                }
+                render_event(&self.output, level, &name, Value::Object(values))
+            }
+            None => render_in(
+                &self.output,
+                level,
+                fields.text.as_deref().unwrap_or_default(),
+                context,
+            ),
+        };
+        if let Some(line) = line {
+            (self.write)(&line);
+        }
+    }
+}
+
+/// Tavallinen viesti valitulla kielellä: `say!("suomeksi", "in English", args...)`
+#[macro_export]
+macro_rules! say {
+    ($($t:tt)*) => {
+        if $crate::term::enabled($crate::term::Level::Info) {
+            $crate::term::emit($crate::term::Level::Info, &$crate::i18n::tr!($($t)*))
+        }
+    };
+}
+
+/// Varoitus (näkyy myös --quiet-tilassa)
+#[macro_export]
+macro_rules! warning {
+    ($($t:tt)*) => {
+        if $crate::term::enabled($crate::term::Level::Warn) {
+            $crate::term::emit($crate::term::Level::Warn, &$crate::i18n::tr!($($t)*))
+        }
+    };
+}
+
+/// Virhe (näkyy aina)
+#[macro_export]
+macro_rules! error {
+    ($($t:tt)*) => {
+        if $crate::term::enabled($crate::term::Level::Error) {
+            $crate::term::emit($crate::term::Level::Error, &$crate::i18n::tr!($($t)*))
+        }
+    };
+}
+
+/// Yksityiskohta (-v)
+#[macro_export]
+macro_rules! detail {
+    ($($t:tt)*) => {
+        if $crate::term::enabled($crate::term::Level::Detail) {
+            $crate::term::emit($crate::term::Level::Detail, &$crate::i18n::tr!($($t)*))
+        }
+    };
+}
+
+/// Mallikohtainen jälki (-vv)
+#[macro_export]
+macro_rules! trace {
+    ($($t:tt)*) => {
+        if $crate::term::enabled($crate::term::Level::Trace) {
+            $crate::term::emit($crate::term::Level::Trace, &$crate::i18n::tr!($($t)*))
+        }
+    };
+}
+
+pub use crate::detail;
+pub use crate::error;
+pub use crate::say;
+pub use crate::trace;
+pub use crate::warning;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_levels_color_and_plain_output() {
+        let quiet = Output {
+            level: Level::Warn,
+            ..Output::default()
+        };
+        assert_eq!(render(&quiet, Level::Info, "x"), None);
+        assert_eq!(render(&quiet, Level::Warn, "x").as_deref(), Some("x"));
+
+        let normal = Output::default();
+        assert_eq!(render(&normal, Level::Detail, "x"), None);
+        let verbose = Output {
+            level: Level::Detail,
+            ..Output::default()
+        };
+        assert_eq!(render(&verbose, Level::Detail, "x").as_deref(), Some("x"));
+        assert_eq!(render(&verbose, Level::Trace, "x"), None);
+        assert_eq!(render(&normal, Level::Info, "x").as_deref(), Some("x"));
+
+        let colored = Output {
+            color: true,
+            ..Output::default()
+        };
+        assert_eq!(
+            render(&colored, Level::Error, "\nvirhe").as_deref(),
+            Some("\n\x1b[31mvirhe\x1b[0m")
+        );
+        assert_eq!(render(&colored, Level::Info, "ok").as_deref(), Some("ok"));
+
+        assert_eq!(strip_emoji("  ⚠️  Varoitus"), "   Varoitus");
+        assert_eq!(strip_emoji("  📥 Feeder: a → b"), "  Feeder: a → b");
+        assert_eq!(strip_emoji("🗺️ kartta"), "kartta");
+
+        let json = Output {
+            format: Format::Json,
+            ..Output::default()
+        };
+        assert_eq!(
+            render(&json, Level::Warn, "\n  ⚠️  Varoitus").as_deref(),
+            Some(r#"{"event":"message","level":"warn","text":"Varoitus"}"#)
+        );
+        assert_eq!(render(&json, Level::Info, "\n"), None);
+        assert_eq!(
+            render_event(&json, Level::Info, "cycle", json!({"cycle": 3})).as_deref(),
+            Some(r#"{"cycle":3,"event":"cycle"}"#)
+        );
+        assert_eq!(render_event(&json, Level::Detail, "x", json!({})), None);
+        assert_eq!(render_event(&normal, Level::Info, "x", json!({})), None);
+    }
+
+    #[test]
+    fn test_layer_formats_events_with_span_fields() {
+        let lines = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&lines);
+        let output = Output {
+            level: Level::Detail,
+            format: Format::Json,
+            ..Output::default()
+        };
+        let subscriber = tracing_subscriber::registry().with(TermLayer::new(output, move |line| {
+            sink.lock().unwrap().push(line.to_string())
+        }));
+        tracing::subscriber::with_default(subscriber, || {
+            let _span = tracing::info_span!("cycle", cycle = 7u64).entered();
+            dispatch!(Level::Warn, text = "  ⚠️  Varoitus");
+            dispatch!(Level::Trace, text = "piilotettu");
+            let fields = json!({"cycle": 3, "id": 1}).to_string();
+            dispatch!(
+                Level::Detail,
+                event = "pattern_born",
+                fields = fields.as_str()
+            );
+        });
+        assert_eq!(
+            *lines.lock().unwrap(),
+            [
+                r#"{"cycle":7,"event":"message","level":"warn","text":"Varoitus"}"#,
+                r#"{"cycle":3,"event":"pattern_born","id":1}"#,
+            ]
+        );
+
+        // Tekstimuodossa spanit eivät näy
+        let lines = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&lines);
+        let subscriber = tracing_subscriber::registry()
+            .with(TermLayer::new(Output::default(), move |line| {
+                sink.lock().unwrap().push(line.to_string())
+            }));
+        tracing::subscriber::with_default(subscriber, || {
+            let _span = tracing::info_span!("cycle", cycle = 7u64).entered();
+            dispatch!(Level::Info, text = "rivi");
+            dispatch!(Level::Detail, text = "piilotettu");
+        });
+        assert_eq!(*lines.lock().unwrap(), ["rivi"]);
+    }
+}
@@ -0,0 +1,183 @@
+// src/tournament.rs
+//
+// Tournament: Usean aivotiedoston vertailu samalla vertailukorpuksella.
+//
+// Kokeiluista jää helposti kymmeniä aivotiedostoja, joiden keskinäistä
+// järjestystä ei näe ajojen omista tilastoista (eri data, eri ajat).
+// Turnauksessa jokainen aivotiedosto koodaa saman korpuksen jäädytettynä:
+//   - bittiä/tavu: entropiakoodatun virran todellinen koko,
+//   - kattavuus: kuinka suuri osa tavuista koodautui opituilla malleilla
+//     (ei literaaleina tai leksemeinä).
+//
+// Tulostaulu järjestetään bittiä/tavu -luvun mukaan (tasapelissä suurempi
+// kattavuus voittaa), ja tiedostokohtainen erittely näyttää, missä kukin
+// aivotiedosto on vahva.
+
+use crate::builder::PatternBank;
+use crate::entropy::Coder;
+use crate::term::{self, Level, say};
+
+/// Yhden tiedoston tulos yksillä aivoilla
+#[derive(Debug, Clone)]
+pub struct FileScore {
+    pub bytes: usize,
+    pub coded_bytes: usize,
+    /// Opituilla malleilla koodatut tavut
+    pub covered_bytes: usize,
+}
+
+impl FileScore {
+    pub fn bits_per_byte(&self) -> f64 {
+        if self.bytes == 0 {
+            return 0.0;
+        }
+        self.coded_bytes as f64 * 8.0 / self.bytes as f64
+    }
+
+    pub fn coverage(&self) -> f64 {
+        if self.bytes == 0 {
+            return 0.0;
+        }
+        self.covered_bytes as f64 / self.bytes as f64
+    }
+}
+
+/// Yhden aivotiedoston tulos koko korpuksella
+#[derive(Debug, Clone)]
+pub struct Contestant {
+    pub name: String,
+    pub patterns: usize,
+    /// Tiedostokohtaiset tulokset korpuksen järjestyksessä
+    pub files: Vec<FileScore>,
+}
+
+impl Contestant {
+    fn total(&self) -> FileScore {
+        self.files.iter().fold(
+            FileScore {
+                bytes: 0,
+                coded_bytes: 0,
+                covered_bytes: 0,
+            },
+            |acc, f| FileScore {
+                bytes: acc.bytes + f.bytes,
+                coded_bytes: acc.coded_bytes + f.coded_bytes,
+                covered_bytes: acc.covered_bytes + f.covered_bytes,
+            },
+        )
+    }
+
+    pub fn bits_per_byte(&self) -> f64 {
+        self.total().bits_per_byte()
+    }
+
+    pub fn coverage(&self) -> f64 {
+        self.total().coverage()
+    }
+}
+
+/// Koodaa yksi tiedosto jäädytetyillä aivoilla
+pub fn score_file(bank: &PatternBank, data: &[u8], coder: Coder) -> FileScore {
+    let tokens = bank.encode(data);
+    let covered_bytes = tokens
+        .iter()
+        .filter(|&&t| bank.get(t).is_some_and(|p| p.op.as_combine().is_some()))
+        .map(|&t| bank.pattern_length(t))
+        .sum();
+    FileScore {
+        bytes: data.len(),
+        coded_bytes: coder.encode(&tokens).len(),
+        covered_bytes,
+    }
+}
+
+/// Järjestä tulostaulu: pienin bittiä/tavu ensin, tasapelissä kattavuus
+pub fn rank(contestants: &mut [Contestant]) {
+    contestants.sort_by(|a, b| {
+        a.bits_per_byte()
+            .total_cmp(&b.bits_per_byte())
+            .then(b.coverage().total_cmp(&a.coverage()))
+            .then(a.name.cmp(&b.name))
+    });
+}
+
+/// Tulosta tulostaulu ja tiedostokohtainen erittely (bittiä/tavu)
+pub fn print_leaderboard(contestants: &[Contestant], files: &[String]) {
+    say!("\n  🏆 Tulostaulu:", "\n  🏆 Leaderboard:");
+    for (i, c) in contestants.iter().enumerate() {
+        say!(
+            "     {:>2}. {:<24} {:.3} bittiä/tavu  kattavuus {:>5.1}%  ({} mallia)",
+            "     {:>2}. {:<24} {:.3} bits/byte  coverage {:>5.1}%  ({} patterns)",
+            i + 1,
+            c.name,
+            c.bits_per_byte(),
+            c.coverage() * 100.0,
+            c.patterns
+        );
+    }
+
+    say!(
+        "\n  📄 Tiedostoittain (bittiä/tavu, paras merkitty *):",
+        "\n  📄 Per file (bits/byte, best marked *):"
+    );
+    let mut header = format!("     {:<32}", "");
+    for c in contestants {
+        header.push_str(&format!(" {:>12}", truncate(&c.name, 12)));
+    }
+    term::emit(Level::Info, &header);
+    for (i, file) in files.iter().enumerate() {
+        let scores: Vec<f64> = contestants
+            .iter()
+            .map(|c| c.files[i].bits_per_byte())
+            .collect();
+        let best = scores.iter().copied().fold(f64::INFINITY, f64::min);
+        let mut row = format!("     {:<32}", truncate(file, 32));
+        for score in scores {
+            let mark = if score == best { "*" } else { " " };
+            row.push_str(&format!(" {:>11.3}{}", score, mark));
+        }
+        term::emit(Level::Info, &row);
+    }
+}
+
+/// Lyhennä nimi sarakkeeseen (loppu säilyy: tiedostonimi on tärkein)
+fn truncate(text: &str, width: usize) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    if chars.len() <= width {
+        return text.to_string();
+    }
+    let tail: String = chars[chars.len() - (width - 1)..].iter().collect();
+    format!("…{}", tail)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::Builder;
+
+    #[test]
+    fn test_trained_brain_wins() {
+        let text = b"funktio on joka funktio on joka funktio on joka";
+        let mut trained = Builder::new(100);
+        trained.tokenize(text);
+        for _ in 0..5 {
+            trained.live();
+        }
+        let empty = PatternBank::new(100);
+
+        let mut contestants: Vec<Contestant> = [("empty", &empty), ("trained", &trained.bank)]
+            .into_iter()
+            .map(|(name, bank)| Contestant {
+                name: name.to_string(),
+                patterns: bank.combine_count(),
+                files: vec![score_file(bank, text, Coder::Huffman)],
+            })
+            .collect();
+        rank(&mut contestants);
+
+        assert_eq!(contestants[0].name, "trained");
+        assert!(contestants[0].coverage() > 0.5);
+        assert_eq!(contestants[1].coverage(), 0.0);
+        assert_eq!(truncate("data/pitka_nimi.txt", 8), "…imi.txt");
+    }
+}
@@ -0,0 +1,362 @@
+// src/transform.rs
+//
+// Transform (Muunnos): Valinnainen, käännettävä esikäsittelyvaihe.
+//
+// Burrows–Wheeler + move-to-front ryhmittelee toistuvan tekstin
+// samanlaisiin merkkeihin ja pieniin lukuihin, jolloin parit
+// toistuvat useammin ja hierarkia oppii nopeammin.
+//
+// Delta korvaa jokaisen tavun erotuksella `stride` tavun takaiseen
+// tavuun. Jaksollinen näytedata (ääni) muuttuu toistuviksi pieniksi
+// erotuksiksi, joista parit on helppo oppia. Kuvissa rivi-delta käyttää
+// strideä rivin leveys, jolloin pikseliä verrataan yläpuolella olevaan.
+//
+// Jokainen syöttöpala muunnetaan omana lohkonaan:
+//   BWT:   [pituus: u32 LE][primääri-indeksi: u32 LE][MTF(BWT(lohko))]
+//   Delta: [pituus: u32 LE][stride: u16 LE][erotukset]
+// Lohkot ovat itserajaavia, joten koko virta voidaan palauttaa
+// lukemalla lohkot järjestyksessä.
+
+use crate::i18n::tr;
+use serde::{Deserialize, Serialize};
+use std::io;
+
+/// BWT-lohkon otsikon koko tavuina (pituus + primääri-indeksi)
+const BLOCK_HEADER_LEN: usize = 8;
+
+/// Delta-lohkon otsikon koko tavuina (pituus + stride)
+const DELTA_HEADER_LEN: usize = 6;
+
+/// Käytettävä muunnos ennen oppimista
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Transform {
+    /// Ei muunnosta: data syötetään sellaisenaan
+    #[default]
+    None,
+    /// Burrows–Wheeler + move-to-front, lohkoittain
+    BwtMtf,
+    /// Tavukohtainen erotus `stride` tavun päähän (0 = lähteen näytekoko)
+    Delta { stride: usize },
+    /// Delta rivin leveyden päähän (kuvan pystysuuntainen erotus)
+    RowDelta,
+}
+
+impl Transform {
+    /// Tulkitse muunnoksen nimi konfiguraatiosta
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "none" | "" => Some(Transform::None),
+            "bwt" | "bwt-mtf" => Some(Transform::BwtMtf),
+            "delta" => Some(Transform::Delta { stride: 0 }),
+            "row-delta" | "delta2d" => Some(Transform::RowDelta),
+            other => {
+                let stride = other.strip_prefix("delta:")?.parse().ok()?;
+                Some(Transform::Delta { stride })
+            }
+        }
+    }
+
+    /// Nimi raportteja varten
+    pub fn name(&self) -> &'static str {
+        match self {
+            Transform::None => "none",
+            Transform::BwtMtf => "bwt-mtf",
+            Transform::Delta { .. } => "delta",
+            Transform::RowDelta => "row-delta",
+        }
+    }
+
+    /// Kiinnitä automaattinen stride lähteen näytekokoon tai rivin leveyteen
+    pub fn resolve(&self, sample_align: usize, row_width: Option<usize>) -> Transform {
+        match *self {
+            Transform::Delta { stride: 0 } => Transform::Delta {
+                stride: sample_align.max(1),
+            },
+            Transform::RowDelta => Transform::Delta {
+                stride: row_width.unwrap_or(sample_align).max(1),
+            },
+            t => t,
+        }
+    }
+
+    /// Muunna yksi syöttöpala (lohko)
+    pub fn apply(&self, data: &[u8]) -> Vec<u8> {
+        match self {
+            Transform::None => data.to_vec(),
+            Transform::BwtMtf => {
+                let (last_column, primary) = bwt_forward(data);
+                let mut out = Vec::with_capacity(BLOCK_HEADER_LEN + data.len());
+                out.extend_from_slice(&(data.len() as u32).to_le_bytes());
+                out.extend_from_slice(&(primary as u32).to_le_bytes());
+                out.extend(mtf_encode(&last_column));
+                out
+            }
+            Transform::Delta { stride } => delta_block(data, *stride),
+            // Ratkaisematon rivi-delta (ei rivin leveyttä): tavallinen delta
+            Transform::RowDelta => delta_block(data, 1),
+        }
+    }
+
+    /// Palauta alkuperäinen data muunnetusta virrasta (peräkkäiset lohkot)
+    pub fn invert(&self, data: &[u8]) -> io::Result<Vec<u8>> {
+        match self {
+            Transform::None => Ok(data.to_vec()),
+            Transform::BwtMtf => {
+                let mut out = Vec::with_capacity(data.len());
+                let mut pos = 0;
+                while pos < data.len() {
+                    if pos + BLOCK_HEADER_LEN > data.len() {
+                        return Err(invalid(&tr!(
+                            "katkennut lohko-otsikko",
+                            "truncated block header"
+                        )));
+                    }
+                    let len = read_u32(&data[pos..]) as usize;
+                    let primary = read_u32(&data[pos + 4..]) as usize;
+                    pos += BLOCK_HEADER_LEN;
+
+                    if pos + len > data.len() || (len > 0 && primary >= len) {
+                        return Err(invalid(&tr!("virheellinen lohko", "invalid block")));
+                    }
+                    let last_column = mtf_decode(&data[pos..pos + len]);
+                    out.extend(bwt_inverse(&last_column, primary));
+                    pos += len;
+                }
+                Ok(out)
+            }
+            Transform::Delta { .. } | Transform::RowDelta => {
+                let mut out = Vec::with_capacity(data.len());
+                let mut pos = 0;
+                while pos < data.len() {
+                    if pos + DELTA_HEADER_LEN > data.len() {
+                        return Err(invalid(&tr!(
+                            "katkennut lohko-otsikko",
+                            "truncated block header"
+                        )));
+                    }
+                    let len = read_u32(&data[pos..]) as usize;
+                    let stride = u16::from_le_bytes([data[pos + 4], data[pos + 5]]) as usize;
+                    pos += DELTA_HEADER_LEN;
+
+                    if pos + len > data.len() || stride == 0 {
+                        return Err(invalid(&tr!("virheellinen lohko", "invalid block")));
+                    }
+                    out.extend(delta_decode(&data[pos..pos + len], stride));
+                    pos += len;
+                }
+                Ok(out)
+            }
+        }
+    }
+}
+
+fn read_u32(bytes: &[u8]) -> u32 {
+    u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.to_string())
+}
+
+/// Burrows–Wheeler: palauttaa lajiteltujen kiertojen viimeisen sarakkeen
+/// ja alkuperäisen merkkijonon rivin (primääri-indeksi).
+///
+/// Kierrot lajitellaan prefiksin tuplauksella: O(n log² n).
+pub fn bwt_forward(data: &[u8]) -> (Vec<u8>, usize) {
+    let n = data.len();
+    if n == 0 {
+        return (Vec::new(), 0);
+    }
+
+    let mut order: Vec<usize> = (0..n).collect();
+    let mut rank: Vec<usize> = data.iter().map(|&b| b as usize).collect();
+    let mut next_rank = vec![0usize; n];
+    let mut k = 1;
+
+    loop {
+        let key = |i: usize| (rank[i], rank[(i + k) % n]);
+        order.sort_by_key(|&i| key(i));
+
+        next_rank[order[0]] = 0;
+        for w in 1..n {
+            let bump = usize::from(key(order[w]) != key(order[w - 1]));
+            next_rank[order[w]] = next_rank[order[w - 1]] + bump;
+        }
+        std::mem::swap(&mut rank, &mut next_rank);
+
+        if rank[order[n - 1]] == n - 1 || k >= n {
+            break;
+        }
+        k *= 2;
+    }
+
+    let primary = order.iter().position(|&i| i == 0).unwrap_or(0);
+    let last_column = order.iter().map(|&i| data[(i + n - 1) % n]).collect();
+    (last_column, primary)
+}
+
+/// Käänteinen Burrows–Wheeler (LF-kuvaus)
+pub fn bwt_inverse(last_column: &[u8], primary: usize) -> Vec<u8> {
+    let n = last_column.len();
+    if n == 0 {
+        return Vec::new();
+    }
+
+    // Montako pienempää merkkiä ennen kutakin tavua (ensimmäinen sarake)
+    let mut counts = [0usize; 256];
+    for &b in last_column {
+        counts[b as usize] += 1;
+    }
+    let mut starts = [0usize; 256];
+    let mut sum = 0;
+    for (byte, count) in counts.iter().enumerate() {
+        starts[byte] = sum;
+        sum += count;
+    }
+
+    // LF[i] = rivi, jonka alussa on last_column[i]
+    let mut seen = [0usize; 256];
+    let mut lf = vec![0usize; n];
+    for (i, &b) in last_column.iter().enumerate() {
+        lf[i] = starts[b as usize] + seen[b as usize];
+        seen[b as usize] += 1;
+    }
+
+    let mut out = vec![0u8; n];
+    let mut row = primary;
+    for slot in out.iter_mut().rev() {
+        *slot = last_column[row];
+        row = lf[row];
+    }
+    out
+}
+
+/// Delta-lohko: [pituus][stride][erotukset]
+fn delta_block(data: &[u8], stride: usize) -> Vec<u8> {
+    let stride = stride.clamp(1, u16::MAX as usize);
+    let mut out = Vec::with_capacity(DELTA_HEADER_LEN + data.len());
+    out.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    out.extend_from_slice(&(stride as u16).to_le_bytes());
+    out.extend(delta_encode(data, stride));
+    out
+}
+
+/// Delta: jokainen tavu miinus `stride` tavun takainen tavu (wrapping)
+pub fn delta_encode(data: &[u8], stride: usize) -> Vec<u8> {
+    data.iter()
+        .enumerate()
+        .map(|(i, &b)| {
+            if i < stride {
+                b
+            } else {
+                b.wrapping_sub(data[i - stride])
+            }
+        })
+        .collect()
+}
+
+/// Käänteinen delta
+pub fn delta_decode(data: &[u8], stride: usize) -> Vec<u8> {
+    let mut out: Vec<u8> = Vec::with_capacity(data.len());
+    for (i, &d) in data.iter().enumerate() {
+        let b = if i < stride {
+            d
+        } else {
+            d.wrapping_add(out[i - stride])
+        };
+        out.push(b);
+    }
+    out
+}
+
+/// Move-to-front: usein toistuvat tavut muuttuvat pieniksi luvuiksi
+pub fn mtf_encode(data: &[u8]) -> Vec<u8> {
+    let mut table: Vec<u8> = (0..=255).collect();
+    data.iter()
+        .map(|&b| {
+            let idx = table.iter().position(|&t| t == b).unwrap_or(0);
+            table.remove(idx);
+            table.insert(0, b);
+            idx as u8
+        })
+        .collect()
+}
+
+/// Käänteinen move-to-front
+pub fn mtf_decode(data: &[u8]) -> Vec<u8> {
+    let mut table: Vec<u8> = (0..=255).collect();
+    data.iter()
+        .map(|&idx| {
+            let b = table.remove(idx as usize);
+            table.insert(0, b);
+            b
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bwt_known_example() {
+        let (last, primary) = bwt_forward(b"banana");
+        assert_eq!(last, b"nnbaaa");
+        assert_eq!(bwt_inverse(&last, primary), b"banana");
+    }
+
+    #[test]
+    fn test_mtf_round_trip() {
+        let data = b"aaabbbaaaccc";
+        let encoded = mtf_encode(data);
+        assert_eq!(&encoded[..3], &[b'a', 0, 0]);
+        assert_eq!(mtf_decode(&encoded), data);
+    }
+
+    #[test]
+    fn test_transform_round_trip_blocks() {
+        let t = Transform::BwtMtf;
+        let blocks: [&[u8]; 4] = [b"funktio on joka funktio on joka ", b"", b"x", b"aaaaaaaa"];
+
+        let mut stream = Vec::new();
+        let mut original = Vec::new();
+        for block in blocks {
+            stream.extend(t.apply(block));
+            original.extend_from_slice(block);
+        }
+
+        assert_eq!(t.invert(&stream).unwrap(), original);
+    }
+
+    #[test]
+    fn test_delta_round_trip_with_stride() {
+        // 16-bittinen stereo: stride 4
+        let samples: Vec<u8> = (0..400u32).map(|i| ((i * 7) % 251) as u8).collect();
+        let t = Transform::Delta { stride: 0 }.resolve(4, None);
+        assert_eq!(t, Transform::Delta { stride: 4 });
+
+        let mut stream = t.apply(&samples[..200]);
+        stream.extend(t.apply(&samples[200..]));
+        assert_eq!(t.invert(&stream).unwrap(), samples);
+    }
+
+    #[test]
+    fn test_row_delta_uses_row_width() {
+        assert_eq!(
+            Transform::RowDelta.resolve(3, Some(12)),
+            Transform::Delta { stride: 12 }
+        );
+        assert_eq!(
+            Transform::RowDelta.resolve(3, None),
+            Transform::Delta { stride: 3 }
+        );
+    }
+
+    #[test]
+    fn test_transform_rejects_truncated_stream() {
+        let t = Transform::BwtMtf;
+        let mut stream = t.apply(b"hello world");
+        stream.truncate(stream.len() - 1);
+        assert!(t.invert(&stream).is_err());
+    }
+}
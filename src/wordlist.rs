@@ -0,0 +1,96 @@
+// src/wordlist.rs
+//
+// Wordlist: Sanaston opetus pankkiin ennen oppimista.
+//
+// Toimialan sanasto (termit, tunnisteet, vakiolausekkeet) on tiedossa jo
+// ennen ajoa, mutta tilastojen keruu löytää sen vasta tuntien päästä.
+// Sanastotiedostossa on yksi sana tai lauseke riviltä; tyhjät rivit ja
+// `#`-alkuiset kommentit ohitetaan. Jokainen rivi jaetaan siemenyksiköiksi
+// (tavut tai lähdekooditilassa leksemit) ja rakennetaan vasemmalle
+// kasvavaksi Combine-ketjuksi, kuten kieliopin tuonnissa.
+//
+// Uudet mallit saavat annetun vahvuuden. Kiinnitetyt (`--pin-words`)
+// mallit eivät heikkene eivätkä unohdu, joten sanasto säilyy myös pitkän
+// hiljaisen jakson yli.
+
+use crate::builder::PatternBank;
+
+/// Sanaston opetuksen tulos
+pub struct TeachSummary {
+    /// Opetetut rivit
+    pub entries: usize,
+    /// Uudet mallit
+    pub created: usize,
+    /// Rivit, jotka jäivät pois pankin täytyttyä
+    pub skipped: usize,
+}
+
+/// Opeta sanaston rivit pankkiin
+pub fn teach(bank: &mut PatternBank, text: &str, strength: f64, pin: bool) -> TeachSummary {
+    let mut summary = TeachSummary {
+        entries: 0,
+        created: 0,
+        skipped: 0,
+    };
+    let entries = text
+        .lines()
+        .map(|l| l.trim_end_matches('\r'))
+        .filter(|l| !l.trim().is_empty() && !l.starts_with('#'));
+    for entry in entries {
+        match teach_entry(bank, entry.as_bytes(), strength, pin) {
+            Some(created) => {
+                summary.entries += 1;
+                summary.created += created;
+            }
+            None => summary.skipped += 1,
+        }
+    }
+    summary
+}
+
+/// Rakenna yhden rivin ketju. `None`, jos pankki täyttyi kesken.
+fn teach_entry(bank: &mut PatternBank, entry: &[u8], strength: f64, pin: bool) -> Option<usize> {
+    let ids = bank.seed_ids(entry);
+    let (&first, rest) = ids.split_first()?;
+    let mut created = 0;
+    let mut acc = first;
+    for &next in rest {
+        let existed = bank.has_pair(acc, next);
+        acc = bank.create_combine(acc, next, 0)?;
+        let pattern = bank.get_mut(acc)?;
+        if !existed {
+            pattern.strength = strength;
+            created += 1;
+        }
+        pattern.pinned |= pin;
+    }
+    Some(created)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::Builder;
+
+    #[test]
+    fn test_wordlist_taught_and_pinned() {
+        let mut builder = Builder::new(100);
+        let summary = teach(&mut builder.bank, "# termit\nabc\r\n\nabd\n", 0.7, true);
+        assert_eq!(
+            (summary.entries, summary.created, summary.skipped),
+            (2, 3, 0)
+        );
+
+        // "ab" on yhteinen: abc = (ab, c), abd = (ab, d)
+        let ab = builder.bank.get_pair_id(b'a' as u32, b'b' as u32).unwrap();
+        let abc = builder.bank.get_pair_id(ab, b'c' as u32).unwrap();
+        assert_eq!(builder.bank.decode(abc), b"abc");
+        assert_eq!(builder.bank.get(abc).unwrap().strength, 0.7);
+
+        // Kiinnitetyt eivät heikkene eivätkä unohdu
+        builder.decay(0.5);
+        builder.forget(10);
+        assert_eq!(builder.bank.get(abc).unwrap().strength, 0.7);
+        assert!(builder.bank.get(ab).unwrap().pinned);
+    }
+}
@@ -0,0 +1,66 @@
+// tests/resume.rs
+//
+// Istunnon jatkaminen: keskeytetty ja `--resume`-jatkettu ajo tuottaa samat
+// aivot, token-virran, CSV:n ja tuttuuskartan kuin keskeyttämätön ajo.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const DATA_FILES: [&str; 4] = ["markov.rs", "stop.rs", "heatmap.rs", "dedup.rs"];
+
+/// Kansio, jossa on oma kopio datasta
+fn workdir(name: &str) -> PathBuf {
+    let root = std::env::temp_dir().join(format!("petri_resume_{}_{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    let data = root.join("data");
+    fs::create_dir_all(&data).unwrap();
+    let src = Path::new(env!("CARGO_MANIFEST_DIR")).join("src");
+    for file in DATA_FILES {
+        fs::copy(src.join(file), data.join(file).with_extension("txt")).unwrap();
+    }
+    root
+}
+
+fn run(dir: &Path, args: &[&str]) {
+    let output = Command::new(env!("CARGO_BIN_EXE_petri_dish"))
+        .args([
+            "-q",
+            "--seed",
+            "1",
+            "--save-stream",
+            "stream.bin",
+            "--heatmap",
+            "heat",
+        ])
+        .args(args)
+        .current_dir(dir)
+        .env_clear()
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{:?}: {}",
+        args,
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+#[test]
+fn test_resumed_run_matches_uninterrupted() {
+    let straight = workdir("straight");
+    run(&straight, &["--max-cycles", "30"]);
+
+    let resumed = workdir("resumed");
+    run(&resumed, &["--max-cycles", "7", "--session", "run.session"]);
+    run(&resumed, &["--max-cycles", "30", "--resume", "run.session"]);
+
+    for file in ["brain.json", "stream.bin", "results.csv", "heat.csv"] {
+        let a = fs::read(straight.join(file)).unwrap();
+        let b = fs::read(resumed.join(file)).unwrap();
+        assert!(a == b, "{} differs after resume", file);
+    }
+
+    let _ = fs::remove_dir_all(&straight);
+    let _ = fs::remove_dir_all(&resumed);
+}